@@ -0,0 +1,70 @@
+use anyhow::Error;
+use game::network::client::NetworkClient;
+use game::network::transport::TcpTransport;
+
+//Flags that take a value
+const FLAGS: [&str; 2] = ["--connect", "--name"];
+
+pub const USAGE: &str = "Usage: client [options]
+    --connect <address>     Server to join once loaded, like localhost:24680
+    --name <name>           Name to join with (default Player)
+    --help                  Prints this message";
+
+//Command line flags
+#[derive(Clone, Debug, PartialEq)]
+pub struct Arguments {
+    pub connect: Option<String>,
+    pub name: String,
+    pub help: bool,
+}
+
+impl Default for Arguments {
+    fn default() -> Self {
+        return Self {
+            connect: None,
+            name: "Player".to_string(),
+            help: false,
+        };
+    }
+}
+
+impl Arguments {
+    //Parses the arguments after the program name
+    pub fn parse(arguments: impl IntoIterator<Item=String>) -> Result<Self, Error> {
+        let mut output = Self::default();
+        let mut arguments = arguments.into_iter();
+        while let Some(flag) = arguments.next() {
+            if flag == "--help" {
+                output.help = true;
+                continue;
+            }
+            if !FLAGS.contains(&flag.as_str()) {
+                return Err(Error::msg(format!("Unknown flag {}", flag)));
+            }
+            let value = match arguments.next() {
+                Some(value) => value,
+                None => return Err(Error::msg(format!("Missing value for {}", flag)))
+            };
+            match flag.as_str() {
+                "--connect" => output.connect = Some(value),
+                "--name" => output.name = value,
+                _ => unreachable!()
+            }
+        }
+        if output.name.trim().is_empty() {
+            return Err(Error::msg("--name can't be empty"));
+        }
+        return Ok(output);
+    }
+
+    //Connects to the --connect server over TCP, if there is one
+    pub fn network(&self) -> Result<Option<NetworkClient>, Error> {
+        let address = match &self.connect {
+            Some(address) => address,
+            None => return Ok(None)
+        };
+        let transport = TcpTransport::connect(address.as_str())
+            .map_err(|error| Error::msg(format!("Couldn't connect to {}: {}", address, error)))?;
+        return Ok(Some(NetworkClient::connect(Box::new(transport), self.name.clone())?));
+    }
+}
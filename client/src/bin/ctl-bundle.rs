@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+use std::{env, fs, process};
+use client::resources::bundle;
+
+const USAGE: &str = "Usage: ctl-bundle [resources directory] [--output <bundle>]
+Builds the types, textures, models, shaders and translations in the resources directory into one bundle,
+which the client loads instead of the folder.
+The resources directory defaults to ./resources, the bundle defaults to <resources>.ctlbundle.
+Exits with 1 if anything can't be loaded.";
+
+//Builds a bundle of a resources folder, already parsed and decoded so it loads faster
+fn main() {
+    let mut resources = None;
+    let mut output = None;
+    let mut arguments = env::args().skip(1);
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return;
+            }
+            "--output" | "-o" => match arguments.next() {
+                Some(bundle) => output = Some(PathBuf::from(bundle)),
+                None => usage_error("Missing value for --output")
+            },
+            _ if argument.starts_with('-') => usage_error(&format!("Unknown flag {}", argument)),
+            _ if resources.is_none() => resources = Some(PathBuf::from(argument)),
+            _ => usage_error(&format!("Unexpected argument {}", argument))
+        }
+    }
+
+    let resources = resources.unwrap_or_else(|| PathBuf::from("resources"));
+    if !resources.is_dir() {
+        eprintln!("Couldn't find resources directory {}", resources.display());
+        process::exit(2);
+    }
+    let output = output.unwrap_or_else(|| resources.with_extension("ctlbundle"));
+
+    let built = match bundle::build(&resources) {
+        Ok(built) => built,
+        Err(error) => {
+            eprintln!("Failed to build {}:\n{}", output.display(), error);
+            process::exit(1);
+        }
+    };
+    //Written next to it then moved over it, so games with the old bundle mapped keep their copy
+    let temporary = output.with_extension("ctlbundle.tmp");
+    if let Err(error) = fs::write(&temporary, &built).and_then(|_| fs::rename(&temporary, &output)) {
+        eprintln!("Failed to write {}: {}", output.display(), error);
+        process::exit(1);
+    }
+    println!("Wrote {} ({} bytes)", output.display(), built.len());
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    process::exit(2);
+}
@@ -0,0 +1,33 @@
+use std::path::PathBuf;
+use std::{env, process};
+use tokio::task::JoinSet;
+use client::renderer::assets::AssetReferer;
+use client::renderer::renderer::RENDERER_REF;
+use client::resources::schemas;
+use game::resources::resource_manager::ResourceManager;
+
+//Writes JSON Schemas for every JSON file the game loads, for editor autocompletion
+fn main() {
+    let directory = match env::args().nth(1).as_deref() {
+        Some("--help") | Some("-h") => {
+            println!("Usage: ctl-schemas [output directory]\nThe output directory defaults to ./schemas.");
+            return;
+        }
+        Some(directory) => PathBuf::from(directory),
+        None => PathBuf::from("schemas")
+    };
+
+    //Only used for the registered types, nothing is loaded or rendered
+    let resources = ResourceManager::new(JoinSet::new(), Box::new(AssetReferer::new()), RENDERER_REF.clone());
+    match schemas::write_schemas(&directory, &resources) {
+        Ok(written) => {
+            for path in written {
+                println!("Wrote {}", path.display());
+            }
+        }
+        Err(error) => {
+            eprintln!("Failed to write schemas to {}: {}", directory.display(), error);
+            process::exit(1);
+        }
+    }
+}
@@ -1,33 +1,77 @@
+use std::sync::Arc;
 use instant::Instant;
 use wgpu::SurfaceError;
-use winit::event::{ElementState, KeyboardInput, ModifiersState, MouseButton};
+use winit::event::{ElementState, KeyboardInput, ModifiersState, MouseButton, VirtualKeyCode};
 use crate::display::window::GameWindow;
+use crate::input::movement::Movement;
+use crate::renderer::assets::{ASSET_MANAGER, AssetReferer};
+use crate::renderer::entities::EntityRenderer;
 use crate::renderer::renderer::{RENDERER, RENDERER_REF};
+use crate::ui::chat::ChatPanel;
 use crate::ui::manager::UIManager;
+use crate::ui::text::FONT_TEXTURE;
 use game::{error, Game, LoadingStage};
+use game::language::language::LANGUAGE_MANAGER;
+use game::network::client::NetworkClient;
+use game::rendering::assets::AssetReference;
+use game::rendering::mesh::{FrameData, Mesh};
+use game::rendering::texture::ColorTexture;
 use game::util::alloc_handle::AllocHandle;
 use crate::resources::content_pack::ContentPack;
+use crate::resources::content_stack::ContentStack;
 use crate::resources::loading;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::resources::hot_reload::HotReloader;
 
 pub struct Client {
     game: Game,
     window: GameWindow,
     next_update: Instant,
-    ui_manager: UIManager
+    //When the current update started, for how far into it a frame is
+    last_update: Instant,
+    ui_manager: UIManager,
+    chat: ChatPanel,
+    //The chat panel's last mesh and its id in the renderer, pushed again when it changes
+    chat_mesh: Option<(Mesh, u64)>,
+    //Set once connected to a server
+    network: Option<NetworkClient>,
+    //Held movement keys, set by the movement key actions
+    pub(crate) movement: Movement,
+    entities: EntityRenderer,
+    #[cfg(not(target_arch = "wasm32"))]
+    hot_reload: Option<HotReloader>
 }
 
 impl Client {
-    pub fn new(window: GameWindow, mut game: Game, content: Box<dyn ContentPack>) -> Self {
+    pub fn new(window: GameWindow, mut game: Game, mut content: ContentStack) -> Self {
+        content.reorder(&window.settings.content_packs);
+        ASSET_MANAGER.lock().unwrap().set_budget(window.settings.memory_budget.cpu_bytes());
+        RENDERER.lock().unwrap().set_budget(window.settings.memory_budget.gpu_bytes());
+        RENDERER.lock().unwrap().set_atlas_settings(window.settings.atlas.clone());
+        #[cfg(not(target_arch = "wasm32"))]
+        let watched = content.clone();
+        let content: Box<dyn ContentPack> = Box::new(content);
         loading::early_load(&window, &content, &mut game.task_manager);
         game.task_manager.wait(Self::finish_early);
-        loading::load(&window, &content, &game.resource_manager, &mut game.task_manager);
+        let resource_loader = loading::load(&window, &content, &game.resource_manager, &mut game.task_manager);
         game.task_manager.wait(loading::finish_load);
+        #[cfg(not(target_arch = "wasm32"))]
+        let hot_reload = HotReloader::new(watched, resource_loader);
 
         return Self {
             game,
             window,
             next_update: Instant::now(),
-            ui_manager: UIManager::new(&RENDERER_REF)
+            last_update: Instant::now(),
+            ui_manager: UIManager::new(&RENDERER_REF),
+            chat: ChatPanel::new(10),
+            chat_mesh: None,
+            network: None,
+            movement: Movement::new(),
+            //Entities don't have models yet, so they're drawn as red cubes
+            entities: EntityRenderer::new(RENDERER_REF.clone(), Arc::new(ColorTexture::new([200, 50, 50, 255]))),
+            #[cfg(not(target_arch = "wasm32"))]
+            hot_reload
         };
     }
 
@@ -42,6 +86,8 @@ impl Client {
         }
 
         self.ui_manager.update();
+        self.draw_chat();
+        self.draw_entities();
         let result = RENDERER.lock().unwrap().render(&mut self.window);
         return match result {
             Ok(()) => false,
@@ -60,12 +106,86 @@ impl Client {
         };
     }
 
+    fn draw_chat(&mut self) {
+        let mesh = self.chat.mesh(self.window.size);
+        if mesh.as_ref() == self.chat_mesh.as_ref().map(|(last, _)| last) {
+            return;
+        }
+        if let Some((_, id)) = self.chat_mesh.take() {
+            RENDERER_REF.clear(id);
+        }
+        if let Some(mesh) = mesh {
+            let texture = AssetReferer::new().get_texture(&FONT_TEXTURE.to_string());
+            let id = RENDERER_REF.push(Arc::new(mesh.clone()), texture, FrameData::new());
+            self.chat_mesh = Some((mesh, id));
+        }
+    }
+
+    fn draw_entities(&mut self) {
+        let network = match &self.network {
+            Some(network) => network,
+            None => return
+        };
+        let length = self.next_update.saturating_duration_since(self.last_update).as_secs_f64();
+        let alpha = match length > 0.0 {
+            true => (Instant::now().saturating_duration_since(self.last_update).as_secs_f64() / length).min(1.0),
+            false => 1.0
+        };
+        self.entities.update(network, alpha);
+    }
+
     pub fn request_redraw(&self) {
         self.window.inner.request_redraw();
     }
 
     pub async fn update(&mut self) {
+        self.last_update = self.next_update;
         self.next_update += self.game.notify_update().await;
+        self.poll_network();
+        self.send_input();
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(hot_reload) = &mut self.hot_reload {
+            hot_reload.update(&self.window).await;
+        }
+    }
+
+    pub fn connect(&mut self, network: NetworkClient) {
+        self.network = Some(network);
+    }
+
+    fn poll_network(&mut self) {
+        let network = match &mut self.network {
+            Some(network) => network,
+            None => return
+        };
+        if let Err(error) = network.poll() {
+            error!("Error reading from server: {}", error);
+        }
+        let language = LANGUAGE_MANAGER.read().unwrap();
+        for message in network.chat.drain(..) {
+            self.chat.receive(&message, &language);
+        }
+        if let Some(reason) = &network.disconnected {
+            error!("Disconnected: {}", reason);
+            self.network = None;
+            self.entities.clear();
+        }
+    }
+
+    //Moves the local player with the held movement keys, once per update like the server
+    fn send_input(&mut self) {
+        let network = match &mut self.network {
+            Some(network) => network,
+            None => return
+        };
+        //There's no mouse look yet, so the player keeps facing the way the server last put them
+        let rotation = match network.player() {
+            Some(player) => player.rotation,
+            None => return
+        };
+        if let Err(error) = network.send_input(self.movement.direction(), rotation) {
+            error!("Failed to send input: {}", error);
+        }
     }
 
     pub(crate) fn key_modifier_change(&mut self, modifiers: &ModifiersState) {
@@ -77,18 +197,53 @@ impl Client {
     }
 
     pub(crate) fn key_input(&mut self, input: &KeyboardInput) {
+        //While chatting, keys go to the chat box instead of the game
+        if self.chat.open {
+            if input.state == ElementState::Pressed {
+                match input.virtual_keycode {
+                    Some(VirtualKeyCode::Escape) => self.chat.close(),
+                    Some(VirtualKeyCode::PageUp) => self.chat.scroll(self.chat.height as isize),
+                    Some(VirtualKeyCode::PageDown) => self.chat.scroll(-(self.chat.height as isize)),
+                    Some(keycode) => self.chat.input.key(keycode),
+                    None => {}
+                }
+            }
+            return;
+        }
+        if input.state == ElementState::Pressed && input.virtual_keycode == Some(VirtualKeyCode::Return) {
+            self.chat.open = true;
+            //Keys released while typing go to the chat box
+            self.movement.release_all();
+            return;
+        }
+
         match input.virtual_keycode {
             Some(keycode) => match self.window.settings.inputs.map(self.window.modifiers, keycode) {
-                Some(function) => function(&mut self.game, &input.state),
+                Some(function) => function(self, &input.state),
                 None => {}
             },
             None => {}
         }
     }
 
+    pub(crate) fn received_character(&mut self, character: char) {
+        if !self.chat.open {
+            return;
+        }
+        let (channel, text) = match self.chat.receive_character(character) {
+            Some(message) => message,
+            None => return
+        };
+        if let Some(network) = &mut self.network {
+            if let Err(error) = network.send_chat(channel, text) {
+                error!("Failed to send chat message: {}", error);
+            }
+        }
+    }
+
     pub(crate) fn mouse_input(&mut self, button: &MouseButton, state: &ElementState) {
         match self.window.settings.inputs.map_mouse(button) {
-            Some(function) => function(&mut self.game, state),
+            Some(function) => function(self, state),
             None => {}
         }
     }
@@ -1,7 +1,21 @@
+use log::info;
 use winit::event::ElementState;
+use crate::client::Client;
+use crate::renderer::assets::ASSET_MANAGER;
+use crate::renderer::renderer::RENDERER;
 
 fn toggle_console(state: &ElementState) {
     if state != &ElementState::Pressed {
         return;
     }
-}
\ No newline at end of file
+}
+
+//Prints which assets are loaded in memory and on the GPU, and why they're still loaded
+pub fn print_memory(_client: &mut Client, state: &ElementState) {
+    if state != &ElementState::Pressed {
+        return;
+    }
+    let assets = ASSET_MANAGER.lock().unwrap().stats();
+    let textures = RENDERER.lock().unwrap().stats();
+    info!("{}{}", assets, textures);
+}
@@ -8,7 +8,8 @@ use winit::event::{Event, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 use winit::window::{Window, WindowBuilder};
 use game::Game;
-use crate::resources::content_pack::ContentPack;
+use game::network::client::NetworkClient;
+use crate::resources::content_stack::ContentStack;
 use crate::client::Client;
 use crate::renderer::renderer::RENDERER;
 use crate::settings::GameSettings;
@@ -47,7 +48,8 @@ impl GameWindow {
 
         let (device, queue) = adapter.request_device(
             &wgpu::DeviceDescriptor {
-                features: wgpu::Features::empty(),
+                //Block compressed textures are decoded on GPUs without BC support
+                features: adapter.features() & wgpu::Features::TEXTURE_COMPRESSION_BC,
                 // WebGL doesn't support all of wgpu's features, so if
                 // we're building for the web we'll have to disable some.
                 limits: if cfg!(target_arch = "wasm32") {
@@ -83,7 +85,7 @@ impl GameWindow {
         };
     }
 
-    pub fn run(game: Game, content: Box<dyn ContentPack>, runtime: Runtime) {
+    pub fn run(game: Game, content: ContentStack, network: Option<NetworkClient>, runtime: Runtime) {
         let event_loop = EventLoop::new();
 
         let window = WindowBuilder::new().build(&event_loop).unwrap();
@@ -111,6 +113,9 @@ impl GameWindow {
         let window = runtime.block_on(GameWindow::new(window));
         RENDERER.lock().unwrap().init(window.device.clone(), window.queue.clone(), (window.config.width, window.config.height));
         let mut context = Client::new(window, game, content);
+        if let Some(network) = network {
+            context.connect(network);
+        }
         let mut next_frame = context.rendering_time(Instant::now());
         event_loop.run(move |ev, _, control_flow| {
             match ev {
@@ -130,6 +135,9 @@ impl GameWindow {
                                 context.key_input(input)
                             }
                         }
+                        WindowEvent::ReceivedCharacter(character) => {
+                            context.received_character(*character)
+                        }
                         WindowEvent::MouseInput { button, state, .. } => {
                             context.mouse_input(button, state)
                         }
@@ -1 +1,2 @@
 pub mod manager;
+pub mod movement;
\ No newline at end of file
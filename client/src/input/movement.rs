@@ -0,0 +1,74 @@
+use std::collections::HashSet;
+use cgmath::Vector3;
+use winit::event::ElementState;
+use crate::client::Client;
+
+//Ways the player can move, each one a key action bound through the InputManager
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MoveKey {
+    Forwards,
+    Backwards,
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+//The movement keys held down, sent to the server as the player's input every update
+#[derive(Default)]
+pub struct Movement {
+    held: HashSet<MoveKey>,
+}
+
+impl Movement {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn key(&mut self, key: MoveKey, state: &ElementState) {
+        match state {
+            ElementState::Pressed => self.held.insert(key),
+            ElementState::Released => self.held.remove(&key)
+        };
+    }
+
+    //Stops moving, like when the chat box takes the keyboard and the releases never arrive
+    pub fn release_all(&mut self) {
+        self.held.clear();
+    }
+
+    //Which way the keys point, +z is out of the screen so forwards is -z
+    pub fn direction(&self) -> Vector3<f32> {
+        let axis = |positive: MoveKey, negative: MoveKey| {
+            return self.held.contains(&positive) as i32 as f32 - self.held.contains(&negative) as i32 as f32;
+        };
+        return Vector3::new(axis(MoveKey::Right, MoveKey::Left),
+                            axis(MoveKey::Up, MoveKey::Down),
+                            axis(MoveKey::Backwards, MoveKey::Forwards));
+    }
+}
+
+//The key actions, by default W and S forwards and back, A and D sideways, Space and Left Shift up and down
+pub fn forwards(client: &mut Client, state: &ElementState) {
+    client.movement.key(MoveKey::Forwards, state);
+}
+
+pub fn backwards(client: &mut Client, state: &ElementState) {
+    client.movement.key(MoveKey::Backwards, state);
+}
+
+pub fn left(client: &mut Client, state: &ElementState) {
+    client.movement.key(MoveKey::Left, state);
+}
+
+pub fn right(client: &mut Client, state: &ElementState) {
+    client.movement.key(MoveKey::Right, state);
+}
+
+pub fn up(client: &mut Client, state: &ElementState) {
+    client.movement.key(MoveKey::Up, state);
+}
+
+pub fn down(client: &mut Client, state: &ElementState) {
+    client.movement.key(MoveKey::Down, state);
+}
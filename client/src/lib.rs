@@ -1,3 +1,9 @@
+#[cfg(target_arch = "wasm32")]
+use std::collections::HashMap;
+
+#[cfg(target_arch = "wasm32")]
+use std::sync::{Arc, Mutex};
+
 #[cfg(target_arch = "wasm32")]
 use include_dir::{Dir, include_dir};
 
@@ -11,16 +17,28 @@ use tokio::task::JoinSet;
 use game::Game;
 
 #[cfg(target_arch = "wasm32")]
-use crate::client::Client;
+use game::resources::resource_manager::ResourceManager;
+
+#[cfg(target_arch = "wasm32")]
+use game::util::register::{GenericRegister, ThingRegister};
+
+#[cfg(target_arch = "wasm32")]
+use game::util::task_manager::TaskManager;
 
 #[cfg(target_arch = "wasm32")]
 use crate::display::window::GameWindow;
 
 #[cfg(target_arch = "wasm32")]
-use crate::resources::web_loader::WebLoader;
+use crate::renderer::assets::AssetReferer;
+
+#[cfg(target_arch = "wasm32")]
+use crate::renderer::renderer::RENDERER_REF;
 
 #[cfg(target_arch = "wasm32")]
-use game::util::task_manager;
+use crate::resources::content_stack::ContentStack;
+
+#[cfg(target_arch = "wasm32")]
+use crate::resources::web_loader::WebLoader;
 
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
@@ -28,6 +46,7 @@ use wasm_bindgen::prelude::*;
 #[cfg(target_arch = "wasm32")]
 static RESOURCES: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/../resources");
 
+pub mod arguments;
 pub mod debug;
 pub mod display;
 pub mod input;
@@ -58,8 +77,15 @@ pub async fn run() {
         .thread_stack_size(3 * 1024 * 1024)
         .build().unwrap();
 
-    let content = Box::new(WebLoader::new());
-    let game = Game::new(JoinSet::new(), Box::new(WebLoader::new(RESOURCES)),
-              TaskManager::new(cpu_runtime, io_runtime));
-    GameWindow::run(game, content, main_runtime);
+    //Mods can't be loaded on the web, so the embedded resources are all there is
+    let mut content = ContentStack::new();
+    content.push("base", Box::new(WebLoader::new(&RESOURCES)));
+    let task_manager = TaskManager::new(cpu_runtime.handle().clone(), io_runtime.handle().clone());
+    let resource_manager = Arc::new(Mutex::new(
+        ResourceManager::new(JoinSet::new(), Box::new(AssetReferer::new()), RENDERER_REF.clone())));
+    let mut registerer: HashMap<&'static str, Box<dyn ThingRegister>> = HashMap::new();
+    registerer.insert("keyaction", Box::new(GenericRegister::from(vec!())));
+    let game = Game::new(resource_manager, task_manager, registerer);
+    //Joining servers needs TCP, which browsers don't have
+    GameWindow::run(game, content, None, main_runtime);
 }
\ No newline at end of file
@@ -3,18 +3,26 @@
 
 use std::collections::HashMap;
 use std::env;
+use std::path::PathBuf;
+use std::process;
 use std::sync::{Arc, Mutex};
+use log::info;
 use tokio::runtime::Builder;
-use game::Game;
+use game::{error, Game};
 use game::resources::resource_manager::ResourceManager;
 use game::util::register::{GenericRegister, ThingRegister};
 use game::util::task_manager::TaskManager;
+use crate::arguments::{Arguments, USAGE};
 use crate::display::window::GameWindow;
 use crate::mods::mod_loader;
 use crate::renderer::assets::AssetReferer;
 use crate::renderer::renderer::RENDERER_REF;
+use crate::resources::bundle;
+use crate::resources::bundle_pack::BundlePack;
+use crate::resources::content_stack::{self, ContentStack};
 use crate::resources::desktop_loader::DesktopLoader;
 
+pub mod arguments;
 pub mod debug;
 pub mod display;
 pub mod input;
@@ -29,6 +37,25 @@ pub mod settings;
 fn main() {
     env_logger::init();
 
+    let arguments = match Arguments::parse(env::args().skip(1)) {
+        Ok(arguments) => arguments,
+        Err(error) => {
+            eprintln!("{}\n{}", error, USAGE);
+            process::exit(2);
+        }
+    };
+    if arguments.help {
+        println!("{}", USAGE);
+        return;
+    }
+    let network = match arguments.network() {
+        Ok(network) => network,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    };
+
     let cpu_runtime = Builder::new_multi_thread()
         .thread_name("ctl-cpu-worker")
         .thread_stack_size(3 * 1024)
@@ -53,13 +80,46 @@ fn main() {
         }
     }
 
-    let content = Box::new(DesktopLoader::new(directory.clone()));
+    let content = get_content(directory.clone());
     let task_manager = TaskManager::new(cpu_runtime.handle().clone(), io_runtime.handle().clone());
-    let resource_manager = Arc::new(Mutex::new(
-        ResourceManager::new(mod_loader::get_mods(directory, cpu_runtime.handle()),
-                             Box::new(AssetReferer::new()), RENDERER_REF.clone())));
+    let mut resource_manager = ResourceManager::new(mod_loader::get_mods(directory, cpu_runtime.handle()),
+                                                    Box::new(AssetReferer::new()), RENDERER_REF.clone());
+    mod_loader::register_types(&mut resource_manager, cpu_runtime.handle());
+    let resource_manager = Arc::new(Mutex::new(resource_manager));
     let game = Game::new(resource_manager, task_manager, get_registerers());
-    GameWindow::run(game, content, main_runtime);
+    GameWindow::run(game, content, network, main_runtime);
+}
+
+//The base game, then mods after the mods they depend on, then resource packs.
+//The base game is loaded from resources.ctlbundle next to the resources folder if it was built after the folder last changed,
+//so edits to the folder aren't hidden by an old bundle.
+fn get_content(directory: PathBuf) -> ContentStack {
+    let mut content = ContentStack::new();
+    let bundle = directory.with_extension("ctlbundle");
+    if !BundlePack::is_bundle(&bundle) {
+        content.push("base", Box::new(DesktopLoader::new(directory.clone())));
+    } else if !bundle::is_current(&bundle, &directory) {
+        info!("{} is older than {}, loading the folder", bundle.display(), directory.display());
+        content.push("base", Box::new(DesktopLoader::new(directory.clone())));
+    } else {
+        match BundlePack::open(bundle.clone()) {
+            Ok(opened) => {
+                info!("Loading the base game from {}", bundle.display());
+                content.push("base", Box::new(opened));
+            }
+            Err(error) => {
+                error!("{}, loading the resources folder instead", error);
+                content.push("base", Box::new(DesktopLoader::new(directory.clone())));
+            }
+        }
+    }
+    for (name, pack) in mod_loader::get_mod_content(directory.clone()) {
+        content.push(&name, pack);
+    }
+    for (name, pack) in content_stack::resource_packs(&directory) {
+        content.push(&name, pack);
+    }
+    return content;
 }
 
 fn get_registerers() -> HashMap<&'static str, Box<dyn ThingRegister>> {
@@ -1,5 +1,4 @@
-use std::{env, fs};
-use std::fs::DirEntry;
+use std::{env, fs, process};
 use std::path::PathBuf;
 use anyhow::Error;
 use libloading::{Library, Symbol};
@@ -7,9 +6,86 @@ use log::error;
 use tokio::runtime::Handle;
 use tokio::task::JoinSet;
 use game::mods::mod_trait::ModMain;
-use game::mods::mods::{GameMod, ModManifest};
+use game::mods::mods::{GameMod, load_order, ModManifest};
+use game::resources::resource_manager::ResourceManager;
+use interfaces::{json_path, resource};
+use interfaces::json_path::JsonPath;
 use interfaces::loading::JsonLoadable;
 use crate::DesktopLoader;
+use crate::resources::archive_pack::ArchivePack;
+use crate::resources::content_pack::ContentPack;
+
+//Where a mod's files are: a folder, or a .zip or .ctlpak archive laid out the same way
+enum ModSource {
+    Folder(PathBuf),
+    Archive(ArchivePack)
+}
+
+impl ModSource {
+    fn open(path: PathBuf) -> Result<Self, Error> {
+        if path.is_dir() {
+            return Ok(Self::Folder(path));
+        }
+        if ArchivePack::is_archive(&path) {
+            return Ok(Self::Archive(ArchivePack::open(path)?));
+        }
+        return Err(Error::msg(format!("{} isn't a mod folder or archive, ignoring", path.display())));
+    }
+
+    fn path(&self) -> PathBuf {
+        return match self {
+            Self::Folder(folder) => folder.clone(),
+            Self::Archive(archive) => archive.path().to_path_buf()
+        };
+    }
+
+    fn contains(&self, file: &str) -> bool {
+        return match self {
+            Self::Folder(folder) => folder.join(file).exists(),
+            Self::Archive(archive) => archive.contains(file)
+        };
+    }
+
+    fn read_manifest(&self) -> Result<ModManifest, Error> {
+        if !self.contains("manifest.json") {
+            return Err(Error::msg(format!("Mod {} has no manifest, ignoring",
+                                          self.path().file_name().unwrap_or_default().to_string_lossy())));
+        }
+        let path = self.path().join("manifest.json");
+        let (source, manifest) = match self {
+            Self::Folder(_) => resource::read(&path)?,
+            Self::Archive(archive) => resource::parse_file(&path.display().to_string(), archive.read_file("manifest.json")?)?
+        };
+        return JsonLoadable::load(&manifest).map_err(|error|
+            Error::msg(json_path::describe_error(&path.display().to_string(), &source, &JsonPath::new(), &error)));
+    }
+
+    //Libraries can't be loaded from inside archives, so they're copied out to the temp folder first
+    fn get_assembly(&self, manifest: &ModManifest) -> Result<PathBuf, Error> {
+        let assembly = format!("assemblies/{}.rlib", env::consts::ARCH);
+        if !self.contains(&assembly) {
+            return Err(Error::msg(format!("Mod {} doesn't support arch {} (no rlib found)",
+                                          manifest.name, env::consts::ARCH)));
+        }
+        return match self {
+            Self::Folder(folder) => Ok(folder.join(assembly)),
+            Self::Archive(archive) => {
+                let folder = env::temp_dir().join(format!("ctl-mods-{}", process::id()));
+                fs::create_dir_all(&folder)?;
+                let target = folder.join(format!("{}-{}.rlib", manifest.name, env::consts::ARCH));
+                fs::write(&target, archive.read_file(&assembly)?)?;
+                Ok(target)
+            }
+        };
+    }
+
+    fn content(self) -> Box<dyn ContentPack> {
+        return match self {
+            Self::Folder(folder) => Box::new(DesktopLoader::new(folder)),
+            Self::Archive(archive) => Box::new(archive)
+        };
+    }
+}
 
 pub(crate) fn get_mods(path: PathBuf, runtime: &Handle) -> JoinSet<Result<GameMod, Error>> {
     let mod_folder = path.join("mods");
@@ -21,7 +97,7 @@ pub(crate) fn get_mods(path: PathBuf, runtime: &Handle) -> JoinSet<Result<GameMo
     for mod_folder in fs::read_dir(mod_folder).unwrap() {
         match mod_folder {
             Ok(mod_folder) => {
-                output.spawn_on(load_mod(mod_folder), &runtime);
+                output.spawn_on(load_mod(mod_folder.path()), &runtime);
             }
             Err(error) => error!("Error opening mod folder:\n{}", error)
         }
@@ -29,19 +105,64 @@ pub(crate) fn get_mods(path: PathBuf, runtime: &Handle) -> JoinSet<Result<GameMo
     return output;
 }
 
-async fn load_mod(mod_folder: DirEntry) -> Result<GameMod, Error> {
-    let manifest = mod_folder.path().join("manifest.json");
-    if !manifest.exists() {
-        return Err(Error::msg(format!("Mod {} has no manifest, ignoring", mod_folder.file_name().to_str().unwrap())));
+//Waits for the mods to load, then lets each one register its types. Has to run before any types are loaded.
+pub(crate) fn register_types(resources: &mut ResourceManager, runtime: &Handle) {
+    let mut mods = runtime.block_on(resources._mods.take_mods());
+    for found in &mut mods {
+        if let Err(error) = found.register_types(resources) {
+            error!("Mod {} failed to register its types: {}", found.name(), error);
+        }
     }
-    let manifest = json::from(fs::read(manifest).unwrap());
-    let manifest: ModManifest = JsonLoadable::load(&manifest)?;
-    let target = mod_folder.path().join("assemblies")
-        .join(format!("{}.rlib", env::consts::ARCH));
-    if !target.exists() {
-        return Err(Error::msg(format!("Mod {} doesn't support arch {} (no rlib found)",
-                                      manifest.name, env::consts::ARCH)));
+    resources._mods.restore(mods);
+}
+
+//Content of the mods that can be loaded, in load order: after the mods they depend on
+pub(crate) fn get_mod_content(path: PathBuf) -> Vec<(String, Box<dyn ContentPack>)> {
+    let mod_folder = path.join("mods");
+    let folders = match fs::read_dir(mod_folder) {
+        Ok(folders) => folders,
+        Err(_) => return Vec::new()
+    };
+
+    let mut found = Vec::new();
+    for mod_folder in folders {
+        let mod_folder = match mod_folder {
+            Ok(mod_folder) => mod_folder.path(),
+            Err(error) => {
+                error!("Error opening mod folder:\n{}", error);
+                continue;
+            }
+        };
+        //Errors are printed when the mod is loaded
+        let source = match ModSource::open(mod_folder) {
+            Ok(source) => source,
+            Err(_) => continue
+        };
+        if let Ok(manifest) = source.read_manifest() {
+            if source.contains(&format!("assemblies/{}.rlib", env::consts::ARCH)) {
+                found.push((manifest, source));
+            }
+        }
     }
+    found.sort_by(|(first, _), (second, _)| first.name.cmp(&second.name));
+    let (manifests, sources): (Vec<ModManifest>, Vec<ModSource>) = found.into_iter().unzip();
+
+    let order = match load_order(&manifests) {
+        Ok(order) => order,
+        Err(error) => {
+            error!("{}, loading their content by name", error);
+            (0..manifests.len()).collect()
+        }
+    };
+    let mut sources = sources.into_iter().map(Some).collect::<Vec<_>>();
+    return order.into_iter()
+        .map(|index| (manifests[index].name.clone(), sources[index].take().unwrap().content())).collect();
+}
+
+async fn load_mod(path: PathBuf) -> Result<GameMod, Error> {
+    let source = ModSource::open(path)?;
+    let manifest = source.read_manifest()?;
+    let target = source.get_assembly(&manifest)?;
     let library = match unsafe { Library::new(target) } {
         Ok(lib) => lib,
         Err(error) => {
@@ -50,8 +171,7 @@ async fn load_mod(mod_folder: DirEntry) -> Result<GameMod, Error> {
     };
 
     let func: Symbol<unsafe extern fn() -> Box<dyn ModMain + Send>> = unsafe { library.get(manifest.main.as_bytes())? };
-    let _content = Box::new(DesktopLoader::new(mod_folder.path()));
     let found_mod = GameMod::new(manifest, unsafe { func() });
 
     return Ok(found_mod);
-}
\ No newline at end of file
+}
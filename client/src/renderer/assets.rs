@@ -1,28 +1,83 @@
-use std::collections::HashMap;
+use std::collections::HashSet;
+use std::mem::size_of;
 use std::sync::{Arc, Mutex};
 use lazy_static::lazy_static;
 use tokio::runtime::Handle;
 use game::error;
 use game::rendering::{AssetType, GameTexture};
 use game::rendering::assets::AssetReference;
-use game::rendering::mesh::Mesh;
+use game::rendering::texture::MissingTexture;
+use game::rendering::mesh::{Mesh, Vertex};
+use crate::renderer::memory::{AssetCache, CachedAsset, MemoryBudget, MemoryStats};
+use crate::renderer::renderer::RENDERER;
 use crate::resources::content_pack::ContentPack;
+use crate::resources::content_stack::ContentKind;
+use crate::resources::desktop_loader::LoadingAsset;
 use game::util::alloc_handle::AllocHandle;
 
 lazy_static! {
     pub static ref ASSET_MANAGER: Mutex<AssetManager> = Mutex::new(AssetManager::new());
 }
 
+//A texture or model the AssetManager has loaded
+#[derive(Clone)]
+pub enum LoadedAsset {
+    Texture(Arc<dyn GameTexture>),
+    Model(Arc<Mesh>)
+}
+
+impl LoadedAsset {
+    pub fn kind(&self) -> ContentKind {
+        return match self {
+            Self::Texture(_) => ContentKind::Texture,
+            Self::Model(_) => ContentKind::Model
+        };
+    }
+
+    //Bytes of memory it takes
+    pub fn size(&self) -> u64 {
+        return match self {
+            Self::Texture(texture) => match texture.compressed() {
+                Some(image) => image.size() as u64,
+                None => (texture.data().len() * texture.frames()) as u64
+            },
+            Self::Model(model) => {
+                let index_size = if model.needs_u32_indices() { size_of::<u32>() } else { size_of::<u16>() };
+                (model.vertexes.len() * size_of::<Vertex>() + model.indices.len() * index_size) as u64
+            }
+        };
+    }
+}
+
+//Anything holding a texture or model it was given is using it
+impl CachedAsset for LoadedAsset {
+    fn users(&self) -> usize {
+        return match self {
+            Self::Texture(texture) => texture.users(),
+            Self::Model(model) => model.users()
+        };
+    }
+}
+
+//Textures and models, kept within the CPU memory budget. Unused ones are unloaded once it's full,
+//and loaded again from the content they came from when they're asked for.
 pub struct AssetManager {
-    textures: HashMap<String, Arc<dyn GameTexture>>,
-    models: HashMap<String, Arc<Mesh>>
+    assets: AssetCache<LoadedAsset>,
+    //Where unloaded assets are loaded again from
+    content: Option<Box<dyn ContentPack>>,
+    //Drawn instead of textures that are missing or failed to load
+    missing: Arc<dyn GameTexture>,
+    //Names of missing textures that have been logged, so each is only logged once
+    reported: HashSet<String>
 }
 
 impl AssetManager {
-    fn new() -> Self {
+    pub fn new() -> Self {
         return Self {
-            textures: HashMap::new(),
-            models: HashMap::new()
+            assets: AssetCache::new(MemoryBudget::default().cpu_bytes()),
+            content: None,
+            missing: Arc::new(MissingTexture::new()),
+            reported: HashSet::new()
         }
     }
 
@@ -31,39 +86,106 @@ impl AssetManager {
 
         match assets {
             Ok(found) => {
+                let mut loaded_textures = Vec::new();
                 let mut manager = ASSET_MANAGER.lock().unwrap();
                 for asset in found {
                     match asset {
                         AssetType::Texture(textures) => for (name, tex) in textures {
-                            manager.textures.insert(name, tex);
+                            loaded_textures.push(tex.clone());
+                            manager.insert(name, LoadedAsset::Texture(tex));
                         }
                         AssetType::Model(models) => for (name, model) in models {
-                            manager.models.insert(name, model);
+                            manager.insert(name, LoadedAsset::Model(model));
                         }
                     }
                 }
+                manager.content = Some(content);
+                drop(manager);
+
+                //Early textures are drawn before the rest load, so they aren't packed
+                if !load_first {
+                    RENDERER.lock().unwrap().build_atlas(&loaded_textures);
+                }
+                drop(loaded_textures);
+                ASSET_MANAGER.lock().unwrap().assets.trim();
             }
             Err(error) => error!("Internal error loading assets:\n{}", error)
         }
 
         return AllocHandle::empty();
     }
+
+    pub fn set_budget(&mut self, budget: u64) {
+        self.assets.set_budget(budget);
+    }
+
+    pub fn set_content(&mut self, content: Box<dyn ContentPack>) {
+        self.content = Some(content);
+    }
+
+    pub fn stats(&self) -> MemoryStats {
+        return self.assets.stats("Assets in memory");
+    }
+
+    //Gets a texture or model, loading it again if it was unloaded
+    pub fn get(&mut self, kind: ContentKind, name: &str) -> Option<LoadedAsset> {
+        if let Some(found) = self.assets.get(kind, name) {
+            return Some(found.clone());
+        }
+        let loaded = match self.content.as_ref()?.load_asset(kind, name) {
+            Ok(Some(LoadingAsset::Texture((_, texture)))) => LoadedAsset::Texture(texture),
+            Ok(Some(LoadingAsset::Model((_, model)))) => LoadedAsset::Model(model),
+            Ok(None) => return None,
+            Err(error) => {
+                error!("Error loading {} again:\n{}", name, error);
+                return None;
+            }
+        };
+        self.insert(name.to_string(), loaded.clone());
+        self.assets.trim();
+        return Some(loaded);
+    }
+
+    //Gets a texture like get, or the missing texture if there isn't one by that name or it failed to load
+    pub fn get_texture(&mut self, name: &str) -> Arc<dyn GameTexture> {
+        return match self.get(ContentKind::Texture, name) {
+            Some(LoadedAsset::Texture(texture)) => texture,
+            _ => {
+                if self.reported.insert(name.to_string()) {
+                    error!("Missing texture {}, drawing the missing texture instead", name);
+                }
+                self.missing.clone()
+            }
+        };
+    }
+
+    fn insert(&mut self, name: String, asset: LoadedAsset) {
+        let size = asset.size();
+        self.assets.insert(asset.kind(), name, asset, size);
+    }
+
+    //Replaces a texture with a new version of it, including anywhere it's already drawn
+    pub fn reload_texture(texture: Arc<dyn GameTexture>) {
+        {
+            let mut manager = ASSET_MANAGER.lock().unwrap();
+            manager.insert(texture.name(), LoadedAsset::Texture(texture.clone()));
+            manager.assets.trim();
+        }
+        RENDERER.lock().unwrap().reload_texture(&texture);
+    }
 }
 
 pub struct AssetReferer {}
 
 impl AssetReference for AssetReferer {
-    fn get_texture(&self, name: &String) -> Option<Arc<dyn GameTexture>> {
-        return match ASSET_MANAGER.lock().unwrap().textures.get(name) {
-            Some(value) => Some(value.clone()),
-            None => None
-        };
+    fn get_texture(&self, name: &String) -> Arc<dyn GameTexture> {
+        return ASSET_MANAGER.lock().unwrap().get_texture(name);
     }
 
     fn get_model(&self, name: &String) -> Option<Arc<Mesh>> {
-        return match ASSET_MANAGER.lock().unwrap().models.get(name) {
-            Some(value) => Some(value.clone()),
-            None => None
+        return match ASSET_MANAGER.lock().unwrap().get(ContentKind::Model, name) {
+            Some(LoadedAsset::Model(model)) => Some(model),
+            _ => None
         }
     }
 }
@@ -74,4 +196,4 @@ impl AssetReferer {
 
         };
     }
-}
\ No newline at end of file
+}
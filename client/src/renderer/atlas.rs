@@ -0,0 +1,370 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use image::RgbaImage;
+use macros::{JsonLoadable, JsonSaveable};
+use game::rendering::GameTexture;
+use game::rendering::mesh::Mesh;
+
+//Which textures share atlas pages. Textures in different groups never share a page.
+#[derive(JsonLoadable, JsonSaveable, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AtlasGrouping {
+    //Every texture in the same pages
+    #[default]
+    All,
+    //Textures in the same folder, like every texture in blocks/
+    Folder
+}
+
+impl AtlasGrouping {
+    //The group a texture is packed in, by its name
+    pub fn group(&self, name: &str) -> String {
+        return match self {
+            Self::All => String::new(),
+            Self::Folder => name.rsplit_once('/').map(|(folder, _)| folder.to_string()).unwrap_or_default()
+        };
+    }
+}
+
+#[derive(JsonLoadable, JsonSaveable, Clone, Debug, PartialEq)]
+pub struct AtlasSettings {
+    //Textures are drawn on their own if this is off
+    pub enabled: bool,
+    //Largest width and height of a page. Textures too big for one are drawn on their own.
+    pub page_size: u32,
+    //Pixels around each texture, filled with its edges so filtering doesn't blend in its neighbors.
+    //Cells are aligned to it rounded up to a power of two, so it also keeps smaller mip levels apart, see mip_levels.
+    pub padding: u32,
+    pub grouping: AtlasGrouping
+}
+
+impl AtlasSettings {
+    //How many mip levels pages have, counting the full size one. Each level halves the padding,
+    //so textures are kept from blending into each other down to the level where it's one pixel.
+    pub fn mip_levels(&self) -> u32 {
+        return 1 + self.padding.checked_ilog2().unwrap_or(0);
+    }
+}
+
+impl Default for AtlasSettings {
+    fn default() -> Self {
+        return Self {
+            enabled: true,
+            page_size: 2048,
+            padding: 2,
+            grouping: AtlasGrouping::All
+        };
+    }
+}
+
+//Where pack put a texture, by the top left of the texture itself inside its padding
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Placement {
+    pub name: String,
+    pub page: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32
+}
+
+//A row of textures on a page, as tall as the first one in it
+struct Shelf {
+    y: u32,
+    height: u32,
+    //Where the next texture in it goes
+    x: u32
+}
+
+#[derive(Default)]
+struct PageSpace {
+    shelves: Vec<Shelf>,
+    //Height of every shelf so far
+    height: u32
+}
+
+impl PageSpace {
+    //Finds room for a cell, returning where it goes
+    fn place(&mut self, width: u32, height: u32, page_size: u32) -> Option<(u32, u32)> {
+        if let Some(shelf) = self.shelves.iter_mut().find(|shelf| shelf.height >= height && shelf.x + width <= page_size) {
+            let x = shelf.x;
+            shelf.x += width;
+            return Some((x, shelf.y));
+        }
+        if self.height + height > page_size {
+            return None;
+        }
+        self.shelves.push(Shelf {
+            y: self.height,
+            height,
+            x: width
+        });
+        self.height += height;
+        return Some((0, self.height - height));
+    }
+}
+
+//Packs textures, by name and size, into pages of page_size with padding around each one.
+//Tallest textures go first, onto the first shelf with room, so the same textures always pack the same way.
+//Cells are rounded up to the padding's next power of two, so every cell starts on a multiple of it.
+//Returns where each one went, and the ones too big to fit on a page.
+pub fn pack(textures: &[(String, u32, u32)], page_size: u32, padding: u32) -> (Vec<Placement>, Vec<String>) {
+    let align = padding.max(1).next_power_of_two();
+    let mut sorted = textures.iter().collect::<Vec<_>>();
+    sorted.sort_by(|(first, first_width, first_height), (second, second_width, second_height)|
+        (second_height, second_width, first).cmp(&(first_height, first_width, second)));
+
+    let mut pages: Vec<PageSpace> = Vec::new();
+    let mut placed = Vec::new();
+    let mut too_big = Vec::new();
+    for (name, width, height) in sorted {
+        let (cell_width, cell_height) = ((width + 2 * padding).next_multiple_of(align), (height + 2 * padding).next_multiple_of(align));
+        if cell_width > page_size || cell_height > page_size {
+            too_big.push(name.clone());
+            continue;
+        }
+        let mut found = pages.iter_mut().enumerate()
+            .find_map(|(page, space)| space.place(cell_width, cell_height, page_size).map(|(x, y)| (page, x, y)));
+        if found.is_none() {
+            let mut space = PageSpace::default();
+            let (x, y) = space.place(cell_width, cell_height, page_size).unwrap();
+            pages.push(space);
+            found = Some((pages.len() - 1, x, y));
+        }
+        let (page, x, y) = found.unwrap();
+        placed.push(Placement {
+            name: name.clone(),
+            page,
+            x: x + padding,
+            y: y + padding,
+            width: *width,
+            height: *height
+        });
+    }
+    return (placed, too_big);
+}
+
+//Part of a texture, in texture coordinates from 0 to 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UvRect {
+    pub min: [f32; 2],
+    pub max: [f32; 2]
+}
+
+impl UvRect {
+    //Moves coordinates on a whole texture to the same spot in this part of the page
+    pub fn remap(&self, tex_coords: [f32; 2]) -> [f32; 2] {
+        return [self.min[0] + tex_coords[0] * (self.max[0] - self.min[0]),
+            self.min[1] + tex_coords[1] * (self.max[1] - self.min[1])];
+    }
+
+    //The mesh drawn with this part of the page instead of the whole texture
+    pub fn remap_mesh(&self, mesh: &Mesh) -> Mesh {
+        let mut output = mesh.clone();
+        for vertex in &mut output.vertexes {
+            vertex.set_tex_coords(self.remap(vertex.tex_coords()));
+        }
+        return output;
+    }
+}
+
+//Where a texture is in the atlas
+#[derive(Clone, Debug, PartialEq)]
+pub struct AtlasRegion {
+    //Index of its page in Atlas::pages
+    pub page: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub uv: UvRect
+}
+
+//A page of textures, drawn like any other texture
+#[derive(Debug)]
+pub struct AtlasPage {
+    name: String,
+    pub group: String,
+    pub image: RgbaImage,
+    //Smaller levels after image as RGBA8, each half the size of the one before
+    pub mips: Vec<Vec<u8>>,
+    levels: u32
+}
+
+impl AtlasPage {
+    //Size of a mip level, where 0 is the full size image
+    pub fn level_size(&self, level: usize) -> (u32, u32) {
+        return ((self.image.width() >> level).max(1), (self.image.height() >> level).max(1));
+    }
+
+    //Makes each smaller level by averaging 2x2 squares of the one before. Cells are aligned to the padding,
+    //so down to the lowest level the squares never take pixels from two cells.
+    fn make_mips(&mut self) {
+        self.mips.clear();
+        let mut previous = self.image.clone();
+        for level in 1..self.levels as usize {
+            if previous.dimensions() == (1, 1) {
+                break;
+            }
+            let (width, height) = self.level_size(level);
+            let next = RgbaImage::from_fn(width, height, |x, y| {
+                let mut sum = [0u32; 4];
+                for (offset_x, offset_y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let pixel = previous.get_pixel((2 * x + offset_x).min(previous.width() - 1),
+                                                   (2 * y + offset_y).min(previous.height() - 1));
+                    for channel in 0..4 {
+                        sum[channel] += pixel[channel] as u32;
+                    }
+                }
+                image::Rgba(sum.map(|total| ((total + 2) / 4) as u8))
+            });
+            self.mips.push(next.as_raw().clone());
+            previous = next;
+        }
+    }
+}
+
+impl Display for AtlasPage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Atlas page {}", self.name)
+    }
+}
+
+impl GameTexture for AtlasPage {
+    fn dimensions(&self) -> (u32, u32) {
+        return self.image.dimensions();
+    }
+
+    fn name(&self) -> String {
+        return self.name.clone();
+    }
+
+    fn data(&self) -> &[u8] {
+        return &self.image;
+    }
+
+    fn mip_levels(&self) -> &[Vec<u8>] {
+        return &self.mips;
+    }
+}
+
+//Textures packed into large pages, so things drawn with different textures can share one
+#[derive(Default)]
+pub struct Atlas {
+    pages: Vec<AtlasPage>,
+    regions: HashMap<String, AtlasRegion>,
+    padding: u32
+}
+
+impl Atlas {
+    //Packs the textures, grouped like the settings say
+    pub fn build(settings: &AtlasSettings, textures: &[Arc<dyn GameTexture>]) -> Self {
+        return Self::build_grouped(settings, textures.iter()
+            .map(|texture| (settings.grouping.group(&texture.name()), texture.clone())));
+    }
+
+    //Packs each group of textures into its own pages, like to give each shader its own
+    pub fn build_grouped(settings: &AtlasSettings, textures: impl IntoIterator<Item=(String, Arc<dyn GameTexture>)>) -> Self {
+        let mut groups: BTreeMap<String, Vec<Arc<dyn GameTexture>>> = BTreeMap::new();
+        for (group, texture) in textures {
+            //Empty textures have no edges to pad with, animated ones change on their own,
+            //and compressed ones would have to be decoded, taking 4 to 8 times the GPU memory
+            if texture.dimensions().0 == 0 || texture.dimensions().1 == 0 || texture.frames() > 1 || texture.compressed().is_some() {
+                continue;
+            }
+            groups.entry(group).or_default().push(texture);
+        }
+
+        let mut atlas = Self {
+            padding: settings.padding,
+            ..Default::default()
+        };
+        for (group, textures) in groups {
+            let named = textures.iter().map(|texture| (texture.name(), texture)).collect::<HashMap<_, _>>();
+            let sizes = named.iter()
+                .map(|(name, texture)| (name.clone(), texture.dimensions().0, texture.dimensions().1)).collect::<Vec<_>>();
+            let (placed, _) = pack(&sizes, settings.page_size, settings.padding);
+
+            let first = atlas.pages.len();
+            let page_count = placed.iter().map(|placement| placement.page + 1).max().unwrap_or(0);
+            for index in 0..page_count {
+                let on_page = placed.iter().filter(|placement| placement.page == index);
+                let width = on_page.clone().map(|placement| placement.x + placement.width + settings.padding).max().unwrap();
+                let height = on_page.map(|placement| placement.y + placement.height + settings.padding).max().unwrap();
+                atlas.pages.push(AtlasPage {
+                    name: if group.is_empty() { format!("atlas:{}", index) } else { format!("atlas:{}/{}", group, index) },
+                    group: group.clone(),
+                    image: RgbaImage::new(width.next_power_of_two().min(settings.page_size),
+                                          height.next_power_of_two().min(settings.page_size)),
+                    mips: Vec::new(),
+                    levels: settings.mip_levels()
+                });
+            }
+            for placement in placed {
+                let page = &mut atlas.pages[first + placement.page];
+                let (page_width, page_height) = page.image.dimensions();
+                blit(&mut page.image, placement.x, placement.y, named[&placement.name].as_ref(), settings.padding);
+                atlas.regions.insert(placement.name, AtlasRegion {
+                    page: first + placement.page,
+                    x: placement.x,
+                    y: placement.y,
+                    width: placement.width,
+                    height: placement.height,
+                    uv: UvRect {
+                        min: [placement.x as f32 / page_width as f32, placement.y as f32 / page_height as f32],
+                        max: [(placement.x + placement.width) as f32 / page_width as f32,
+                            (placement.y + placement.height) as f32 / page_height as f32]
+                    }
+                });
+            }
+        }
+        for page in &mut atlas.pages {
+            page.make_mips();
+        }
+        return atlas;
+    }
+
+    pub fn pages(&self) -> &[AtlasPage] {
+        return &self.pages;
+    }
+
+    pub fn region(&self, name: &str) -> Option<&AtlasRegion> {
+        return self.regions.get(name);
+    }
+
+    //Where the texture is on its page
+    pub fn uv_rect(&self, name: &str) -> Option<UvRect> {
+        return self.regions.get(name).map(|region| region.uv);
+    }
+
+    //Copies a new version of a texture over the old one, returning the page it's on.
+    //If it changed size or became animated it doesn't fit anymore, so it's taken out and drawn on its own.
+    pub fn replace(&mut self, texture: &dyn GameTexture) -> Option<usize> {
+        let name = texture.name();
+        let region = self.regions.get(&name)?;
+        if (region.width, region.height) != texture.dimensions() || texture.frames() > 1 || texture.compressed().is_some() {
+            self.regions.remove(&name);
+            return None;
+        }
+        let page = &mut self.pages[region.page];
+        blit(&mut page.image, region.x, region.y, texture, self.padding);
+        page.make_mips();
+        return Some(region.page);
+    }
+}
+
+//Draws the texture at x, y on the page, stretching its edge pixels out over the padding around it
+fn blit(page: &mut RgbaImage, x: u32, y: u32, texture: &dyn GameTexture, padding: u32) {
+    let (width, height) = texture.dimensions();
+    let data = texture.data();
+    let padding = padding as i64;
+    for offset_y in -padding..height as i64 + padding {
+        for offset_x in -padding..width as i64 + padding {
+            let source_x = offset_x.clamp(0, width as i64 - 1) as usize;
+            let source_y = offset_y.clamp(0, height as i64 - 1) as usize;
+            let index = (source_y * width as usize + source_x) * 4;
+            let pixel = image::Rgba([data[index], data[index + 1], data[index + 2], data[index + 3]]);
+            page.put_pixel((x as i64 + offset_x) as u32, (y as i64 + offset_y) as u32, pixel);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use cgmath::Vector3;
+use game::network::client::NetworkClient;
+use game::rendering::GameTexture;
+use game::rendering::mesh::{FrameData, Mesh};
+use game::rendering::renderer::Renderer;
+
+//Draws the entities a server sends as cubes. Remote entities are drawn where the Interpolator puts
+//them, the local player where prediction puts it.
+pub struct EntityRenderer {
+    renderer: Arc<dyn Renderer>,
+    texture: Arc<dyn GameTexture>,
+    //Entity ids to their ids in the renderer
+    drawn: HashMap<u64, u64>,
+}
+
+impl EntityRenderer {
+    pub fn new(renderer: Arc<dyn Renderer>, texture: Arc<dyn GameTexture>) -> Self {
+        return Self {
+            renderer,
+            texture,
+            drawn: HashMap::new(),
+        };
+    }
+
+    //Moves every drawn entity, alpha is how far (0 to 1) the client is into the current update.
+    //Entities that left the client's view are cleared.
+    pub fn update(&mut self, network: &NetworkClient, alpha: f64) {
+        let mut positions = HashMap::new();
+        for id in network.world.entities().keys() {
+            if let Some(state) = network.interpolated(*id, alpha) {
+                positions.insert(*id, state.position);
+            }
+        }
+        //The server sends the local player too, but prediction is ahead of it
+        if let Some(player) = network.player() {
+            positions.insert(player.id(), player.position);
+        }
+
+        let renderer = &self.renderer;
+        self.drawn.retain(|id, drawn| {
+            if positions.contains_key(id) {
+                return true;
+            }
+            renderer.clear(*drawn);
+            return false;
+        });
+        for (id, position) in positions {
+            let data = Self::frame(position);
+            match self.drawn.get(&id) {
+                Some(drawn) => self.renderer.update(*drawn, data),
+                None => {
+                    let drawn = self.renderer.push(Arc::new(Mesh::cube("shader".to_string())), self.texture.clone(), data);
+                    self.drawn.insert(id, drawn);
+                }
+            }
+        }
+    }
+
+    //Stops drawing everything, like after disconnecting
+    pub fn clear(&mut self) {
+        for (_, drawn) in self.drawn.drain() {
+            self.renderer.clear(drawn);
+        }
+    }
+
+    pub fn drawn(&self) -> usize {
+        return self.drawn.len();
+    }
+
+    fn frame(position: Vector3<f32>) -> FrameData {
+        let mut data = FrameData::new();
+        data.offset = position;
+        return data;
+    }
+}
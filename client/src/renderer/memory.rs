@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+use instant::Instant;
+use macros::{JsonLoadable, JsonSaveable};
+use crate::resources::content_stack::ContentKind;
+
+const MEGABYTE: u64 = 1024 * 1024;
+
+//How much memory loaded assets can use before unused ones are unloaded.
+//Assets in use are never unloaded, so this can be gone over if they need more.
+#[derive(JsonLoadable, JsonSaveable, Clone, Debug, PartialEq)]
+pub struct MemoryBudget {
+    //Textures and models kept in memory by the AssetManager
+    pub cpu_megabytes: u64,
+    //Textures uploaded to the GPU by the GameRenderer
+    pub gpu_megabytes: u64
+}
+
+impl MemoryBudget {
+    pub fn cpu_bytes(&self) -> u64 {
+        return self.cpu_megabytes * MEGABYTE;
+    }
+
+    pub fn gpu_bytes(&self) -> u64 {
+        return self.gpu_megabytes * MEGABYTE;
+    }
+}
+
+impl Default for MemoryBudget {
+    fn default() -> Self {
+        return Self {
+            cpu_megabytes: 512,
+            gpu_megabytes: 512
+        };
+    }
+}
+
+//Something in an AssetCache that knows how many things are using it
+pub trait CachedAsset {
+    fn users(&self) -> usize;
+}
+
+//Arcs are used by everything holding a clone besides the cache
+impl<T: ?Sized> CachedAsset for Arc<T> {
+    fn users(&self) -> usize {
+        return Arc::strong_count(self) - 1;
+    }
+}
+
+struct Resident<T> {
+    asset: T,
+    size: u64,
+    //When it was last used, for the stats
+    last_used: Instant,
+    //Order it was last used in, so ties in time still unload the oldest first
+    last_tick: u64
+}
+
+//Loaded assets with the memory they take. Once they're over budget, the ones that have gone
+//unused longest are unloaded until they fit, and have to be loaded again to be used.
+pub struct AssetCache<T: CachedAsset> {
+    assets: HashMap<(ContentKind, String), Resident<T>>,
+    budget: u64,
+    used: u64,
+    tick: u64
+}
+
+impl<T: CachedAsset> AssetCache<T> {
+    pub fn new(budget: u64) -> Self {
+        return Self {
+            assets: HashMap::new(),
+            budget,
+            used: 0,
+            tick: 0
+        };
+    }
+
+    //Changes the budget, unloading whatever no longer fits
+    pub fn set_budget(&mut self, budget: u64) -> Vec<(ContentKind, String)> {
+        self.budget = budget;
+        return self.trim();
+    }
+
+    pub fn budget(&self) -> u64 {
+        return self.budget;
+    }
+
+    pub fn used(&self) -> u64 {
+        return self.used;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.assets.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.assets.is_empty();
+    }
+
+    pub fn contains(&self, kind: ContentKind, name: &str) -> bool {
+        return self.assets.contains_key(&(kind, name.to_string()));
+    }
+
+    //Adds or replaces an asset taking size bytes. This doesn't unload anything, so callers can
+    //start using it first, then trim.
+    pub fn insert(&mut self, kind: ContentKind, name: String, asset: T, size: u64) {
+        let tick = self.next_tick();
+        if let Some(old) = self.assets.insert((kind, name), Resident {
+            asset,
+            size,
+            last_used: Instant::now(),
+            last_tick: tick
+        }) {
+            self.used -= old.size;
+        }
+        self.used += size;
+    }
+
+    //Gets an asset, counting it as used
+    pub fn get(&mut self, kind: ContentKind, name: &str) -> Option<&mut T> {
+        let tick = self.next_tick();
+        let found = self.assets.get_mut(&(kind, name.to_string()))?;
+        found.last_used = Instant::now();
+        found.last_tick = tick;
+        return Some(&mut found.asset);
+    }
+
+    //Gets an asset without counting it as used
+    pub fn peek(&self, kind: ContentKind, name: &str) -> Option<&T> {
+        return self.assets.get(&(kind, name.to_string())).map(|found| &found.asset);
+    }
+
+    pub fn remove(&mut self, kind: ContentKind, name: &str) -> Option<T> {
+        let found = self.assets.remove(&(kind, name.to_string()))?;
+        self.used -= found.size;
+        return Some(found.asset);
+    }
+
+    //Unloads unused assets, least recently used first, until they're within budget.
+    //Returns what was unloaded.
+    pub fn trim(&mut self) -> Vec<(ContentKind, String)> {
+        let mut unused = self.assets.iter().filter(|(_, found)| found.asset.users() == 0)
+            .map(|(key, found)| (found.last_tick, key.clone())).collect::<Vec<_>>();
+        unused.sort();
+
+        let mut unloaded = Vec::new();
+        for (_, key) in unused {
+            if self.used <= self.budget {
+                break;
+            }
+            self.used -= self.assets.remove(&key).unwrap().size;
+            unloaded.push(key);
+        }
+        return unloaded;
+    }
+
+    //What's loaded and why, sorted by kind then name
+    pub fn stats(&self, label: &str) -> MemoryStats {
+        let now = Instant::now();
+        let mut assets = self.assets.iter().map(|((kind, name), found)| ResidentAsset {
+            kind: *kind,
+            name: name.clone(),
+            size: found.size,
+            users: found.asset.users(),
+            idle: now.duration_since(found.last_used)
+        }).collect::<Vec<_>>();
+        assets.sort_by(|first, second| (first.kind, &first.name).cmp(&(second.kind, &second.name)));
+        return MemoryStats {
+            label: label.to_string(),
+            used: self.used,
+            budget: self.budget,
+            assets
+        };
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        return self.tick;
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ResidentAsset {
+    pub kind: ContentKind,
+    pub name: String,
+    pub size: u64,
+    pub users: usize,
+    //How long since it was last used
+    pub idle: Duration
+}
+
+impl ResidentAsset {
+    //Why it's still loaded
+    pub fn reason(&self) -> String {
+        return if self.users > 0 {
+            format!("used by {}", self.users)
+        } else {
+            format!("unused for {:.1?}, kept under budget", self.idle)
+        };
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MemoryStats {
+    pub label: String,
+    pub used: u64,
+    pub budget: u64,
+    pub assets: Vec<ResidentAsset>
+}
+
+impl Display for MemoryStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}: {} assets, {:.1} of {:.1} MB", self.label, self.assets.len(),
+                 self.used as f64 / MEGABYTE as f64, self.budget as f64 / MEGABYTE as f64)?;
+        for asset in &self.assets {
+            writeln!(f, "  {:?} {}: {} KB, {}", asset.kind, asset.name, asset.size.div_ceil(1024), asset.reason())?;
+        }
+        return Ok(());
+    }
+}
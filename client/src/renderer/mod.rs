@@ -1,4 +1,7 @@
 pub mod assets;
+pub mod entities;
+pub mod atlas;
+pub mod memory;
 pub mod renderer;
 pub mod rendering_data;
 pub mod shaders;
\ No newline at end of file
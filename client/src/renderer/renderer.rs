@@ -1,16 +1,23 @@
 use std::collections::HashMap;
 use std::ops::Deref;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
+use instant::Instant;
 use lazy_static::lazy_static;
-use wgpu::{Color, CommandEncoderDescriptor, Device, IndexFormat, LoadOp, Operations, Queue, RenderPassColorAttachment, RenderPassDescriptor, SurfaceError, TextureViewDescriptor};
+use wgpu::{Color, CommandEncoderDescriptor, Device, LoadOp, Operations, Queue, RenderPassColorAttachment, RenderPassDescriptor, SurfaceError, TextureViewDescriptor};
 use game::error;
 use game::rendering::GameTexture;
+use game::rendering::assets::AssetReference;
+use game::rendering::texture::Animation;
 use game::rendering::mesh::{FrameData, Mesh};
 use game::rendering::renderer::Renderer;
 use crate::display::camera::Camera;
 use crate::display::window::GameWindow;
-use crate::renderer::rendering_data::RenderingData;
+use crate::renderer::assets::AssetReferer;
+use crate::renderer::atlas::{Atlas, AtlasSettings};
+use crate::renderer::memory::{AssetCache, MemoryBudget, MemoryStats};
+use crate::renderer::rendering_data::{GpuTexture, RenderingData};
 use crate::renderer::shaders::SHADER_MANAGER;
+use crate::resources::content_stack::ContentKind;
 
 lazy_static! {
     pub static ref RENDERER: Mutex<GameRenderer> = Mutex::new(GameRenderer::new());
@@ -20,10 +27,38 @@ pub struct GameRenderer {
     last_id: u64,
     device: Option<Arc<Mutex<Device>>>,
     queue: Option<Arc<Queue>>,
-    rendering: HashMap<u64, RenderingData>,
+    //Everything pushed, as one RenderingData for each of its mesh's parts
+    rendering: HashMap<u64, Vec<RenderingData>>,
+    //Textures on the GPU by name, unloaded once nothing's drawn with them and they're over budget
+    textures: AssetCache<GpuTexture>,
+    //Textures packed into pages, which are drawn with instead of the textures in them
+    atlas: Atlas,
+    atlas_settings: AtlasSettings,
+    //Meshes with their texture coordinates moved onto the atlas, by the mesh they're from and its texture.
+    //The weak reference keeps the mesh's address from being reused until the copy is dropped.
+    remapped: HashMap<(usize, String), (Weak<Mesh>, Arc<Mesh>)>,
+    //Animated textures on the GPU by name
+    animated: HashMap<String, Playing>,
+    //When rendering started, which animations play from
+    started: Instant,
     pub camera: Option<Camera>
 }
 
+//An animated texture, with the frames it was last uploaded showing and how far they were blended
+struct Playing {
+    texture: Arc<dyn GameTexture>,
+    shown: (usize, usize, u8)
+}
+
+impl Playing {
+    fn new(texture: Arc<dyn GameTexture>) -> Self {
+        return Self {
+            texture,
+            shown: (0, 0, 0)
+        };
+    }
+}
+
 impl GameRenderer {
     fn new() -> Self {
         return Self {
@@ -31,6 +66,12 @@ impl GameRenderer {
             device: None,
             queue: None,
             rendering: HashMap::new(),
+            textures: AssetCache::new(MemoryBudget::default().gpu_bytes()),
+            atlas: Atlas::default(),
+            atlas_settings: AtlasSettings::default(),
+            remapped: HashMap::new(),
+            animated: HashMap::new(),
+            started: Instant::now(),
             camera: None
         }
     }
@@ -41,7 +82,9 @@ impl GameRenderer {
         self.queue = Some(queue);
     }
 
-    pub fn render(&self, window: &mut GameWindow) -> Result<(), SurfaceError> {
+    pub fn render(&mut self, window: &mut GameWindow) -> Result<(), SurfaceError> {
+        self.animate();
+        self.prepare_pipelines(window);
         let output = window.surface.get_current_texture()?;
         let view = output.texture.create_view(&TextureViewDescriptor::default());
         let mut encoder = window.device.lock().unwrap().create_command_encoder(&CommandEncoderDescriptor {
@@ -67,23 +110,40 @@ impl GameRenderer {
                 depth_stencil_attachment: None,
             });
 
-            for data in self.rendering.values() {
-                match shaders.shaders.get(&data.shader) {
-                    Some(shader) => {
-                        render_pass.set_pipeline(&shader.pipeline);
-                        render_pass.set_bind_group(0, &data.bind_group, &[]);
-                        if !shader.ui {
-                            render_pass.set_bind_group(1, &self.camera.as_ref().unwrap().camera_bind_group, &[]);
+            //UI is drawn over everything else
+            for ui in [false, true] {
+                for data in self.rendering.values().flatten() {
+                    //Textures are kept while anything's drawn with them
+                    let texture = match self.textures.peek(ContentKind::Texture, &data.texture_name) {
+                        Some(texture) => texture,
+                        None => continue
+                    };
+                    match shaders.shaders.get(&data.shader) {
+                        Some(shader) => {
+                            if shader.ui != ui {
+                                continue;
+                            }
+                            //Meshes the shader can't draw have no pipeline
+                            let pipeline = match shader.pipelines.get(&data.layout) {
+                                Some(pipeline) => pipeline,
+                                None => continue
+                            };
+                            render_pass.set_pipeline(pipeline);
+                            render_pass.set_bind_group(0, &texture.bind_group, &[]);
+                            if !shader.ui {
+                                render_pass.set_bind_group(1, &self.camera.as_ref().unwrap().camera_bind_group, &[]);
+                            }
+                            render_pass.set_vertex_buffer(0, data.vertex_buffer.slice(..));
+                            render_pass.set_index_buffer(data.index_buffer.slice(..), data.index_format);
+                            render_pass.draw_indexed(0..data.index_count, 0, 0..1);
+                        },
+                        None => {
+                            if !ui {
+                                error!("No loaded shader named {}. Loaded: {:?}", data.shader,
+                                    shaders.shaders.keys());
+                            }
+                            continue;
                         }
-                        render_pass.set_vertex_buffer(0, data.vertex_buffer.slice(..));
-                        render_pass.set_index_buffer(data.index_buffer.slice(..), IndexFormat::Uint16);
-                        render_pass.draw_indexed(
-                            0..data.index_buffer.size() as u32/2, 0, 0..1);
-                    },
-                    None => {
-                        error!("No loaded shader named {}. Loaded: {:?}", data.shader,
-                            shaders.shaders.keys());
-                        continue;
                     }
                 }
             }
@@ -96,20 +156,189 @@ impl GameRenderer {
         return Ok(());
     }
 
+    //Builds pipelines for meshes drawn with a shader whose layout differs from theirs for the first time.
+    //Meshes missing attributes their shader reads are logged, then skipped.
+    fn prepare_pipelines(&self, window: &GameWindow) {
+        let mut shaders = SHADER_MANAGER.lock().unwrap();
+        let device = window.device.lock().unwrap();
+        let camera = self.camera.as_ref().map(|camera| &camera.camera_bind_group_layout);
+        for data in self.rendering.values().flatten() {
+            if let Err(error) = shaders.prepare(&data.shader, &data.layout, device.deref(), &window.config, camera) {
+                error!("Can't draw a mesh:\n{}", error);
+            }
+        }
+    }
+
+    //Shows the frame each animated texture is on now, blended into the next one if it interpolates
+    fn animate(&mut self) {
+        let queue = match &self.queue {
+            Some(queue) => queue,
+            None => return
+        };
+        let elapsed = self.started.elapsed();
+        for (name, playing) in &mut self.animated {
+            let texture = &playing.texture;
+            let (frame, next, blend) = match texture.animation() {
+                Some(animation) => animation.frame_at(texture.frames(), elapsed),
+                None => continue
+            };
+            //Blends are uploaded in steps of 1/255, so slow ones aren't uploaded every frame
+            let shown = (frame, next, (blend * 255.0) as u8);
+            //Nothing drawn with it would see the change
+            let uploaded = match self.textures.peek(ContentKind::Texture, name) {
+                Some(uploaded) if uploaded.users > 0 => uploaded,
+                _ => continue
+            };
+            if shown == playing.shown {
+                continue;
+            }
+            playing.shown = shown;
+            if shown.2 == 0 {
+                uploaded.write(queue, texture.dimensions(), texture.frame(frame));
+            } else {
+                uploaded.write(queue, texture.dimensions(), &Animation::blend(texture.frame(frame), texture.frame(next), blend));
+            }
+        }
+    }
+
+    //Unloads unused textures over budget, and stops playing the animated ones unloaded
+    fn trim(&mut self) {
+        for (_, name) in self.textures.trim() {
+            self.animated.remove(&name);
+        }
+        self.remapped.retain(|_, (mesh, _)| mesh.strong_count() > 0);
+    }
+
+    //Textures already on the GPU are shared instead of uploaded again
+    //Textures in the atlas are drawn with their page, with the mesh's texture coordinates moved onto it
     pub fn push(&mut self, mesh: Arc<Mesh>, texture: Arc<dyn GameTexture>, data: FrameData) -> u64 {
+        return self.push_parts(vec!((mesh, texture)), data);
+    }
+
+    //Pushes the parts of a mesh, each with its own texture, as one thing to update and clear
+    pub fn push_parts(&mut self, parts: Vec<(Arc<Mesh>, Arc<dyn GameTexture>)>, data: FrameData) -> u64 {
         let id = self.last_id;
         self.last_id += 1;
-        self.rendering.insert(id, RenderingData::new(
-            self.device.as_ref().unwrap().lock().unwrap().deref(), self.queue.as_ref().unwrap(), mesh, texture, data));
+        let device = self.device.as_ref().unwrap().lock().unwrap();
+        let mut rendering = Vec::new();
+        for (mesh, texture) in parts {
+            //Meshes their shader can't draw aren't uploaded
+            if let Err(error) = SHADER_MANAGER.lock().unwrap().check(&mesh.shader, &mesh.layout) {
+                error!("Can't draw a mesh:\n{}", error);
+                continue;
+            }
+            if texture.frames() > 1 && !self.animated.contains_key(&texture.name()) {
+                self.animated.insert(texture.name(), Playing::new(texture.clone()));
+            }
+            let (mesh, texture): (Arc<Mesh>, &dyn GameTexture) = match self.atlas.region(&texture.name()) {
+                Some(region) => {
+                    let (_, remapped) = self.remapped.entry((Arc::as_ptr(&mesh) as usize, texture.name()))
+                        .or_insert_with(|| (Arc::downgrade(&mesh), Arc::new(region.uv.remap_mesh(&mesh))));
+                    (remapped.clone(), &self.atlas.pages()[region.page])
+                }
+                None => (mesh, texture.as_ref())
+            };
+            let name = texture.name();
+            match self.textures.get(ContentKind::Texture, &name) {
+                Some(uploaded) => uploaded.users += 1,
+                None => {
+                    let mut uploaded = GpuTexture::upload(device.deref(), self.queue.as_ref().unwrap(), texture);
+                    uploaded.users = 1;
+                    let size = uploaded.size();
+                    self.textures.insert(ContentKind::Texture, name.clone(), uploaded, size);
+                }
+            }
+            rendering.push(RenderingData::new(device.deref(), mesh, name, data.clone()));
+        }
+        self.rendering.insert(id, rendering);
+        drop(device);
+        self.trim();
         return id;
     }
 
+    //Uploads the new version of a texture, or the atlas page it's on, if anything's drawn with it or it's still loaded
+    pub fn reload_texture(&mut self, reloaded: &Arc<dyn GameTexture>) {
+        let device = match &self.device {
+            Some(device) => device.lock().unwrap(),
+            None => {
+                self.atlas.replace(reloaded.as_ref());
+                return;
+            }
+        };
+        let texture = match self.atlas.replace(reloaded.as_ref()) {
+            Some(page) => &self.atlas.pages()[page],
+            None => reloaded.as_ref()
+        };
+        let name = texture.name();
+        let users = match self.textures.peek(ContentKind::Texture, &name) {
+            Some(uploaded) => uploaded.users,
+            None => return
+        };
+        let mut uploaded = GpuTexture::upload(device.deref(), self.queue.as_ref().unwrap(), texture);
+        uploaded.users = users;
+        let size = uploaded.size();
+        self.textures.insert(ContentKind::Texture, name.clone(), uploaded, size);
+        drop(device);
+        //It may have started or stopped being animated
+        self.animated.remove(&name);
+        if reloaded.frames() > 1 && reloaded.name() == name {
+            self.animated.insert(name, Playing::new(reloaded.clone()));
+        }
+        self.trim();
+    }
+
     pub fn update(&mut self, id: u64, data: FrameData) {
-        self.rendering.get_mut(&id).unwrap().update(data);
+        for part in self.rendering.get_mut(&id).unwrap() {
+            part.update(self.queue.as_ref().unwrap(), data.clone());
+        }
     }
 
+    //Stops drawing it, letting its texture be unloaded if nothing else is drawn with it
     pub fn clear(&mut self, id: u64) {
-        self.rendering.remove(&id);
+        let data = match self.rendering.remove(&id) {
+            Some(data) => data,
+            None => return
+        };
+        for part in data {
+            if let Some(uploaded) = self.textures.get(ContentKind::Texture, &part.texture_name) {
+                uploaded.users -= 1;
+            }
+        }
+        self.trim();
+    }
+
+    pub fn set_budget(&mut self, budget: u64) {
+        for (_, name) in self.textures.set_budget(budget) {
+            self.animated.remove(&name);
+        }
+    }
+
+    pub fn set_atlas_settings(&mut self, settings: AtlasSettings) {
+        self.atlas_settings = settings;
+    }
+
+    //Packs the textures into the atlas, replacing the old one. Things already drawn keep their textures.
+    pub fn build_atlas(&mut self, textures: &[Arc<dyn GameTexture>]) {
+        for page in self.atlas.pages() {
+            if self.textures.peek(ContentKind::Texture, &page.name()).is_some_and(|uploaded| uploaded.users == 0) {
+                self.textures.remove(ContentKind::Texture, &page.name());
+            }
+        }
+        //Textures can be somewhere else on the new pages
+        self.remapped.clear();
+        self.atlas = if self.atlas_settings.enabled {
+            Atlas::build(&self.atlas_settings, textures)
+        } else {
+            Atlas::default()
+        };
+    }
+
+    pub fn atlas(&self) -> &Atlas {
+        return &self.atlas;
+    }
+
+    pub fn stats(&self) -> MemoryStats {
+        return self.textures.stats("Textures on the GPU");
     }
 }
 
@@ -126,8 +355,17 @@ impl RendererRef {
 }
 
 impl Renderer for RendererRef {
+    //Parts' textures are found before locking the renderer, so it isn't held while they load
     fn push(&self, mesh: Arc<Mesh>, texture: Arc<dyn GameTexture>, data: FrameData) -> u64 {
-        return RENDERER.lock().unwrap().push(mesh, texture, data);
+        if mesh.parts.is_empty() {
+            return RENDERER.lock().unwrap().push(mesh, texture, data);
+        }
+        let assets = AssetReferer::new();
+        let parts = mesh.split().into_iter().map(|(part, name)| {
+            let texture = if name.is_empty() { texture.clone() } else { assets.get_texture(&name) };
+            (Arc::new(part), texture)
+        }).collect();
+        return RENDERER.lock().unwrap().push_parts(parts, data);
     }
 
     fn update(&self, id: u64, data: FrameData) {
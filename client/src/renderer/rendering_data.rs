@@ -2,22 +2,99 @@ use std::mem::size_of;
 use std::ops::Deref;
 use std::sync::Arc;
 use core::num::NonZeroU32;
-use wgpu::{AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor, BindingResource, Buffer, BufferUsages, Device, Extent3d, FilterMode, ImageCopyTexture, ImageDataLayout, Origin3d, Queue, SamplerDescriptor, Texture, TextureAspect, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureViewDescriptor};
+use wgpu::{AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor, BindingResource, Buffer, BufferUsages, Device, Extent3d, Features, FilterMode, ImageCopyTexture, IndexFormat, ImageDataLayout, Origin3d, Queue, SamplerDescriptor, Texture, TextureAspect, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureViewDescriptor};
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
 use game::rendering::GameTexture;
+use game::rendering::block_compression::BlockFormat;
+use game::rendering::image_format::CompressedImage;
 use game::rendering::mesh::{FrameData, Mesh};
+use game::rendering::vertex_layout::VertexLayout;
+use crate::renderer::memory::CachedAsset;
 use crate::renderer::shaders::BIND_LAYOUT;
 
 pub struct RenderingData {
     pub vertex_buffer: Buffer,
+    //Which attributes are in the vertex buffer, for finding the shader's pipeline for it
+    pub layout: VertexLayout,
     pub index_buffer: Buffer,
+    //u16 unless the mesh has too many vertexes for them
+    pub index_format: IndexFormat,
+    pub index_count: u32,
+    //Name of the GpuTexture it's drawn with
+    pub texture_name: String,
+    pub shader: String,
+    //Kept to move the vertexes again when the FrameData's offset changes
+    mesh: Arc<Mesh>
+}
+
+//A texture uploaded to the GPU, shared by everything drawn with it
+pub struct GpuTexture {
     pub texture: Texture,
     pub bind_group: BindGroup,
-    pub shader: String
+    //How many RenderingData are drawn with it
+    pub users: usize,
+    bytes: u64
+}
+
+impl CachedAsset for GpuTexture {
+    fn users(&self) -> usize {
+        return self.users;
+    }
 }
 
 impl RenderingData {
-    pub fn new(device: &Device, queue: &Queue, mesh: Arc<Mesh>, texture: Arc<dyn GameTexture>, _frame_data: FrameData) -> Self {
+    pub fn new(device: &Device, mesh: Arc<Mesh>, texture_name: String, frame_data: FrameData) -> Self {
+        let short_indices: Vec<u16>;
+        let (index_format, indices): (IndexFormat, &[u8]) = if mesh.needs_u32_indices() {
+            (IndexFormat::Uint32, Self::cast(mesh.indices.as_slice()))
+        } else {
+            short_indices = mesh.indices.iter().map(|index| *index as u16).collect();
+            (IndexFormat::Uint16, Self::cast(short_indices.as_slice()))
+        };
+        return Self {
+            vertex_buffer: device.create_buffer_init(
+                &BufferInitDescriptor {
+                    label: Some("Vertex Buffer"),
+                    contents: &mesh.layout.pack(&frame_data.apply(&mesh.vertexes)),
+                    usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                }
+            ),
+            index_buffer: device.create_buffer_init(
+                &BufferInitDescriptor {
+                    label: Some("Index Buffer"),
+                    contents: indices,
+                    usage: BufferUsages::INDEX,
+                }
+            ),
+            layout: mesh.layout.clone(),
+            index_format,
+            index_count: mesh.indices.len() as u32,
+            texture_name,
+            shader: mesh.shader.clone(),
+            mesh
+        };
+    }
+
+    pub fn cast<A, B>(input: &[A]) -> &[B] {
+        let new_len = core::mem::size_of_val(input) / size_of::<u8>();
+        return unsafe { core::slice::from_raw_parts(input.as_ptr() as *const B, new_len) };
+    }
+
+    //Rewrites the vertexes moved by the new offset
+    pub fn update(&mut self, queue: &Queue, data: FrameData) {
+        queue.write_buffer(&self.vertex_buffer, 0, &self.mesh.layout.pack(&data.apply(&self.mesh.vertexes)));
+    }
+}
+
+impl GpuTexture {
+    //Copies the texture to the GPU, with its mip levels and the bind group shaders read it with. Block compressed textures
+    //are copied as they are if the GPU can read them, otherwise they're decoded and have only one level.
+    pub fn upload(device: &Device, queue: &Queue, texture: &dyn GameTexture) -> Self {
+        let compressed = texture.compressed().filter(|_| device.features().contains(Features::TEXTURE_COMPRESSION_BC));
+        let (format, mip_level_count) = match compressed {
+            Some(image) => (Self::block_format(image.format), image.levels.len() as u32),
+            None => (TextureFormat::Rgba8UnormSrgb, 1 + texture.mip_levels().len() as u32)
+        };
         let size = Extent3d {
             width: texture.dimensions().0,
             height: texture.dimensions().1,
@@ -25,29 +102,31 @@ impl RenderingData {
         };
         let loaded_texture = device.create_texture(&TextureDescriptor {
             size,
-            mip_level_count: 1,
+            mip_level_count,
             sample_count: 1,
             dimension: TextureDimension::D2,
-            format: TextureFormat::Rgba8UnormSrgb,
+            format,
             usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
             label: Some(texture.name().as_str()),
-            view_formats: &[TextureFormat::Rgba8UnormSrgb]
+            view_formats: &[format]
         });
-        queue.write_texture(
-            ImageCopyTexture {
-                texture: &loaded_texture,
-                mip_level: 0,
-                origin: Origin3d::ZERO,
-                aspect: TextureAspect::All
-            },
-            texture.data(),
-            ImageDataLayout {
-                offset: 0,
-                bytes_per_row: NonZeroU32::new(4 * texture.dimensions().0),
-                rows_per_image: NonZeroU32::new(texture.dimensions().1)
-            },
-            size
-        );
+        let bytes = match compressed {
+            Some(image) => {
+                for level in 0..image.levels.len() {
+                    Self::write_blocks(queue, &loaded_texture, image, level);
+                }
+                image.size() as u64
+            }
+            None => {
+                let mut bytes = 0;
+                for (level, data) in [texture.data()].into_iter().chain(texture.mip_levels().iter().map(Vec::as_slice)).enumerate() {
+                    let dimensions = ((size.width >> level).max(1), (size.height >> level).max(1));
+                    Self::write_pixels(queue, &loaded_texture, level as u32, dimensions, data);
+                    bytes += data.len() as u64;
+                }
+                bytes
+            }
+        };
 
         let texture_bind_group_layout =
             device.create_bind_group_layout(&BindGroupLayoutDescriptor {
@@ -81,30 +160,77 @@ impl RenderingData {
             }
         );
         return Self {
-            vertex_buffer: device.create_buffer_init(
-                &BufferInitDescriptor {
-                    label: Some("Vertex Buffer"),
-                    contents: Self::cast(mesh.vertexes.as_slice()),
-                    usage: BufferUsages::VERTEX,
-                }
-            ),
-            index_buffer: device.create_buffer_init(
-                &BufferInitDescriptor {
-                    label: Some("Index Buffer"),
-                    contents: Self::cast(mesh.indices.as_slice()),
-                    usage: BufferUsages::INDEX,
-                }
-            ),
-            bind_group,
             texture: loaded_texture,
-            shader: mesh.shader.clone(),
+            bind_group,
+            users: 0,
+            bytes
         };
     }
 
-    pub fn cast<A, B>(input: &[A]) -> &[B] {
-        let new_len = core::mem::size_of_val(input) / size_of::<u8>();
-        return unsafe { core::slice::from_raw_parts(input.as_ptr() as *const B, new_len) };
+    //Replaces the texture's pixels, like with an animated texture's next frame
+    pub fn write(&self, queue: &Queue, dimensions: (u32, u32), data: &[u8]) {
+        Self::write_pixels(queue, &self.texture, 0, dimensions, data);
     }
 
-    pub fn update(&mut self, _data: FrameData) {}
-}
\ No newline at end of file
+    fn write_pixels(queue: &Queue, texture: &Texture, mip_level: u32, dimensions: (u32, u32), data: &[u8]) {
+        queue.write_texture(
+            ImageCopyTexture {
+                texture,
+                mip_level,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All
+            },
+            data,
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(4 * dimensions.0),
+                rows_per_image: NonZeroU32::new(dimensions.1)
+            },
+            Extent3d {
+                width: dimensions.0,
+                height: dimensions.1,
+                depth_or_array_layers: 1
+            }
+        );
+    }
+
+    fn write_blocks(queue: &Queue, texture: &Texture, image: &CompressedImage, level: usize) {
+        //Levels are whole blocks, even ones smaller than a block
+        let (width, height) = image.level_size(level);
+        let (blocks_wide, blocks_high) = (width.div_ceil(4), height.div_ceil(4));
+        queue.write_texture(
+            ImageCopyTexture {
+                texture,
+                mip_level: level as u32,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All
+            },
+            &image.levels[level],
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(blocks_wide * image.format.block_size() as u32),
+                rows_per_image: NonZeroU32::new(blocks_high)
+            },
+            Extent3d {
+                width: blocks_wide * 4,
+                height: blocks_high * 4,
+                depth_or_array_layers: 1
+            }
+        );
+    }
+
+    //Block compressed textures are drawn as sRGB, like every other texture
+    fn block_format(format: BlockFormat) -> TextureFormat {
+        return match format {
+            BlockFormat::Bc1 => TextureFormat::Bc1RgbaUnormSrgb,
+            BlockFormat::Bc2 => TextureFormat::Bc2RgbaUnormSrgb,
+            BlockFormat::Bc3 => TextureFormat::Bc3RgbaUnormSrgb,
+            BlockFormat::Bc7 => TextureFormat::Bc7RgbaUnormSrgb
+        };
+    }
+
+    //Bytes of GPU memory it takes
+    pub fn size(&self) -> u64 {
+        return self.bytes;
+    }
+}
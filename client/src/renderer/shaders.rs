@@ -1,26 +1,31 @@
-use std::collections::HashMap;
-use std::fs;
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use anyhow::Error;
 use lazy_static::lazy_static;
 use tokio::runtime::Handle;
 use tokio::task::JoinSet;
-use wgpu::{BindGroupLayoutEntry, ShaderStages, BindingType, TextureViewDimension, TextureSampleType, SamplerBindingType, BlendState, ColorTargetState, ColorWrites, Device, Face, FragmentState, FrontFace, MultisampleState, PipelineLayoutDescriptor, PolygonMode, PrimitiveState, PrimitiveTopology, RenderPipeline, RenderPipelineDescriptor, ShaderModule, ShaderModuleDescriptor, ShaderSource, SurfaceConfiguration, VertexState, VertexAttribute, vertex_attr_array};
+use wgpu::{BindGroupLayout, BindGroupLayoutEntry, ErrorFilter, ShaderStages, BindingType, TextureViewDimension, TextureSampleType, SamplerBindingType, BlendState, ColorTargetState, ColorWrites, Device, Face, FragmentState, FrontFace, MultisampleState, PipelineLayoutDescriptor, PolygonMode, PrimitiveState, PrimitiveTopology, RenderPipeline, RenderPipelineDescriptor, ShaderModule, ShaderModuleDescriptor, ShaderSource, SurfaceConfiguration, VertexAttribute, VertexBufferLayout, VertexFormat, VertexState, VertexStepMode};
 use game::error;
-use game::rendering::mesh::Vertex;
+use game::rendering::vertex_layout::{self, VertexLayout};
 use game::util::alloc_handle::AllocHandle;
-use crate::renderer::renderer::RENDERER;
+use crate::renderer::renderer::{GameRenderer, RENDERER};
 use crate::resources::content_pack::ContentPack;
 
 pub struct ShaderManager {
     pub shaders: HashMap<String, Shader>,
     pub loaded_ui_shaders: bool,
+    //Shaders and mesh layouts that were rejected, so they're only logged once
+    rejected: HashSet<(String, VertexLayout)>
 }
 
 pub struct Shader {
-    pub pipeline: RenderPipeline,
+    //A pipeline for each layout of mesh drawn with it, all reading the shader's layout
+    pub pipelines: HashMap<VertexLayout, RenderPipeline>,
     pub module: ShaderModule,
+    //The vertex attributes it reads, from its "//layout:" line
+    pub layout: VertexLayout,
     pub ui: bool
 }
 
@@ -53,19 +58,23 @@ impl ShaderManager {
         return Self {
             shaders: HashMap::new(),
             loaded_ui_shaders: false,
+            rejected: HashSet::new()
         };
     }
 
     pub fn get_shaders(first: bool, runtime: Handle, content: Box<dyn ContentPack>) -> JoinSet<(String, String)> {
         let mut loading = JoinSet::new();
-        for shader in content.shaders(first) {
-            loading.spawn_on(Self::load_shader(content.get_relative("shaders/", &shader), shader), &runtime);
+        let content: Arc<dyn ContentPack> = Arc::from(content);
+        for (name, shader) in content.shaders(first) {
+            loading.spawn_on(Self::load_shader(content.clone(), name, shader), &runtime);
         }
         return loading;
     }
 
-    async fn load_shader(relative: String, shader: PathBuf) -> (String, String) {
-        return match fs::read_to_string(shader) {
+    async fn load_shader(content: Arc<dyn ContentPack>, relative: String, shader: PathBuf) -> (String, String) {
+        let read = content.read(&shader)
+            .and_then(|bytes| String::from_utf8(bytes).map_err(Error::from));
+        return match read {
             Ok(shader) => (relative, shader),
             Err(error) => {
                 error!("Error loading shader:\n{}", error);
@@ -84,54 +93,151 @@ impl ShaderManager {
                     continue
                 }
             };
+            let layout = match VertexLayout::from_shader(&source) {
+                Ok(layout) => layout,
+                Err(error) => {
+                    error!("Failed to load shader {}:\n{}", name, error);
+                    continue
+                }
+            };
 
-            let device = device.lock().unwrap();
-            let device = device.deref();
+            //The renderer is locked before the device, like when pushing meshes, and both are unlocked before the shaders
+            let (shader, pipeline) = {
+                let renderer = RENDERER.lock().unwrap();
+                let device = device.lock().unwrap();
+                let shader = device.create_shader_module(ShaderModuleDescriptor {
+                    label: Some(name.as_str()),
+                    source: ShaderSource::Wgsl(source.into()),
+                });
+                let pipeline = Self::get_pipeline(&name, device.deref(), &config, &shader, Self::camera_layout(ui, &renderer), &layout, &layout).unwrap();
+                (shader, pipeline)
+            };
+            SHADER_MANAGER.lock().unwrap().insert(name, Shader::new(pipeline, shader, layout, ui));
+        }
+
+        return AllocHandle::empty();
+    }
 
+    //Rebuilds a shader and its pipeline from new source. If it doesn't compile, the old one is kept.
+    //Shaders that weren't loaded before are UI shaders if they're in load_first, like when loading.
+    pub async fn reload(name: String, source: String, device: Arc<Mutex<Device>>, config: SurfaceConfiguration) -> Result<(), Error> {
+        let ui = match SHADER_MANAGER.lock().unwrap().shaders.get(&name) {
+            Some(shader) => shader.ui,
+            None => name.starts_with("load_first/")
+        };
+        let layout = VertexLayout::from_shader(&source)?;
+
+        let (shader, pipeline, error) = {
+            let renderer = RENDERER.lock().unwrap();
+            let device = device.lock().unwrap();
+            //Catch validation errors instead of letting wgpu panic on them
+            device.push_error_scope(ErrorFilter::Validation);
             let shader = device.create_shader_module(ShaderModuleDescriptor {
                 label: Some(name.as_str()),
                 source: ShaderSource::Wgsl(source.into()),
             });
-            SHADER_MANAGER.lock().unwrap().shaders.insert(name.clone(), Shader::new(
-                Self::get_pipeline(ui, device, &config, &shader), shader, ui));
+            let pipeline = Self::get_pipeline(&name, device.deref(), &config, &shader, Self::camera_layout(ui, &renderer), &layout, &layout)?;
+            (shader, pipeline, device.pop_error_scope())
+        };
+        if let Some(error) = error.await {
+            return Err(Error::msg(error.to_string()));
         }
 
-        return AllocHandle::empty();
+        //Pipelines for other mesh layouts are built again when they're drawn
+        SHADER_MANAGER.lock().unwrap().insert(name, Shader::new(pipeline, shader, layout, ui));
+        return Ok(());
+    }
+
+    fn insert(&mut self, name: String, shader: Shader) {
+        self.rejected.retain(|(rejected, _)| *rejected != name);
+        self.shaders.insert(name, shader);
+    }
+
+    //Non-UI shaders read the camera
+    fn camera_layout(ui: bool, renderer: &GameRenderer) -> Option<&BindGroupLayout> {
+        return match ui {
+            true => None,
+            false => Some(&renderer.camera.as_ref().unwrap().camera_bind_group_layout)
+        };
     }
 
-    pub fn get_pipeline(ui: bool, device: &Device, config: &SurfaceConfiguration, shader: &ShaderModule) -> RenderPipeline {
+    //Checks the shader can draw meshes with the layout. Shaders that aren't loaded yet are checked when they're drawn with.
+    pub fn check(&self, name: &str, layout: &VertexLayout) -> Result<(), Error> {
+        return match self.shaders.get(name) {
+            Some(shader) => layout.check_shader(name, &shader.layout),
+            None => Ok(())
+        };
+    }
+
+    //Builds the shader's pipeline for meshes with the layout, if it's loaded and doesn't have one yet.
+    //Meshes missing attributes the shader reads are an error the first time, and never get a pipeline.
+    pub fn prepare(&mut self, name: &str, layout: &VertexLayout, device: &Device, config: &SurfaceConfiguration,
+                   camera: Option<&BindGroupLayout>) -> Result<(), Error> {
+        let shader = match self.shaders.get_mut(name) {
+            Some(shader) if !shader.pipelines.contains_key(layout) => shader,
+            _ => return Ok(())
+        };
+        if self.rejected.contains(&(name.to_string(), layout.clone())) {
+            return Ok(());
+        }
+        let camera = if shader.ui { None } else { camera };
+        match Self::get_pipeline(name, device, config, &shader.module, camera, &shader.layout, layout) {
+            Ok(pipeline) => {
+                shader.pipelines.insert(layout.clone(), pipeline);
+                return Ok(());
+            }
+            Err(error) => {
+                self.rejected.insert((name.to_string(), layout.clone()));
+                return Err(error);
+            }
+        }
+    }
+
+    //Builds a pipeline for a shader reading its layout from meshes with the mesh layout, which has to have
+    //everything the shader reads. UI shaders have no camera, and blend with what's under them.
+    pub fn get_pipeline(name: &str, device: &Device, config: &SurfaceConfiguration, shader: &ShaderModule,
+                        camera: Option<&BindGroupLayout>, reads: &VertexLayout, mesh: &VertexLayout) -> Result<RenderPipeline, Error> {
+        mesh.check_shader(name, reads)?;
+        let attributes = Self::attributes(reads, mesh);
         let bind_group = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: BIND_LAYOUT.deref(),
             label: Some("Texture Bind Group Layout"),
         });
         let layout;
-        if ui {
+        if let Some(camera) = camera {
             layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
                 label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[&bind_group],
+                bind_group_layouts: &[&bind_group, camera],
                 push_constant_ranges: &[],
             });
         } else {
             layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
                 label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[&bind_group, &RENDERER.lock().unwrap().camera.as_ref().unwrap().camera_bind_group_layout],
+                bind_group_layouts: &[&bind_group],
                 push_constant_ranges: &[],
             });
         }
-        return device.create_render_pipeline(&RenderPipelineDescriptor {
+        return Ok(device.create_render_pipeline(&RenderPipelineDescriptor {
             label: Some("Render Pipeline"),
             layout: Some(&layout),
             vertex: VertexState {
                 module: &shader,
                 entry_point: "vs_main",
-                buffers: &[Self::description()],
+                buffers: &[VertexBufferLayout {
+                    array_stride: mesh.stride() as wgpu::BufferAddress,
+                    step_mode: VertexStepMode::Vertex,
+                    attributes: &attributes,
+                }],
             },
             fragment: Some(FragmentState {
                 module: &shader,
                 entry_point: "fs_main",
                 targets: &[Some(ColorTargetState {
                     format: config.format,
-                    blend: Some(BlendState::REPLACE),
+                    blend: Some(match camera {
+                        Some(_) => BlendState::REPLACE,
+                        None => BlendState::ALPHA_BLENDING
+                    }),
                     write_mask: ColorWrites::ALL,
                 })],
             }),
@@ -151,27 +257,32 @@ impl ShaderManager {
                 alpha_to_coverage_enabled: false,
             },
             multiview: None,
-        });
+        }));
     }
 
-    const ATTRIBS: [VertexAttribute; 2] = vertex_attr_array![0 => Float32x3, 1 => Float32x2];
-
-    fn description<'a>() -> wgpu::VertexBufferLayout<'a> {
-        use std::mem;
-
-        wgpu::VertexBufferLayout {
-            array_stride: mem::size_of::<Vertex>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: &Self::ATTRIBS,
-        }
+    //Where each attribute the shader reads is in the mesh's vertexes
+    fn attributes(reads: &VertexLayout, mesh: &VertexLayout) -> Vec<VertexAttribute> {
+        return reads.attributes().into_iter().map(|attribute| VertexAttribute {
+            format: match attribute {
+                vertex_layout::VertexAttribute::TexCoords | vertex_layout::VertexAttribute::TexCoords2 => VertexFormat::Float32x2,
+                vertex_layout::VertexAttribute::Position | vertex_layout::VertexAttribute::Normal => VertexFormat::Float32x3,
+                vertex_layout::VertexAttribute::Joints => VertexFormat::Uint32x4,
+                vertex_layout::VertexAttribute::Tangent | vertex_layout::VertexAttribute::Color |
+                vertex_layout::VertexAttribute::Weights => VertexFormat::Float32x4
+            },
+            offset: mesh.offset(attribute).unwrap() as wgpu::BufferAddress,
+            shader_location: attribute.location()
+        }).collect();
     }
 }
 
 impl Shader {
-    pub fn new(pipeline: RenderPipeline, module: ShaderModule, ui: bool) -> Self {
+    //The shader with its pipeline for meshes with its own layout
+    pub fn new(pipeline: RenderPipeline, module: ShaderModule, layout: VertexLayout, ui: bool) -> Self {
         return Self {
-            pipeline,
+            pipelines: HashMap::from([(layout.clone(), pipeline)]),
             module,
+            layout,
             ui
         }
     }
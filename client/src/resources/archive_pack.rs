@@ -0,0 +1,196 @@
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use anyhow::Error;
+use tokio::runtime::Handle;
+use tokio::task::{JoinHandle, JoinSet};
+use zip::ZipArchive;
+use game::error;
+use game::language::language::LanguagePack;
+use game::rendering::AssetType;
+use interfaces::resource;
+use crate::resources::content_pack::{self, ContentPack};
+use crate::resources::content_stack::ContentKind;
+use crate::resources::desktop_loader::{DesktopLoader, LoadingAsset};
+
+//Folders content is in, to tell whether an archive has a folder zipped in it instead
+const CONTENT_FOLDERS: [&str; 5] = ["types", "assets", "shaders", "language", "assemblies"];
+
+//Content in a .zip or .ctlpak archive, laid out like the resources folder.
+//A .ctlpak is just a zip, with its own extension so packs can be told apart from other archives.
+//The archive is read into memory once, and files are decompressed as they're loaded.
+#[derive(Clone)]
+pub struct ArchivePack {
+    path: PathBuf,
+    archive: ZipArchive<Cursor<Arc<[u8]>>>,
+    //Folder in the archive the content is in, like "example/" if the folder was zipped instead of its contents
+    root: String,
+}
+
+impl ArchivePack {
+    pub fn is_archive(path: &Path) -> bool {
+        return path.is_file() && matches!(path.extension().and_then(|extension| extension.to_str()), Some("zip" | "ctlpak"));
+    }
+
+    pub fn open(path: PathBuf) -> Result<Self, Error> {
+        let bytes: Arc<[u8]> = Arc::from(fs::read(&path)?);
+        let archive = ZipArchive::new(Cursor::new(bytes))
+            .map_err(|error| Error::msg(format!("Failed to open {}: {}", path.display(), error)))?;
+
+        let mut tops = archive.file_names().map(|name| name.split('/').next().unwrap_or(name)).collect::<Vec<_>>();
+        tops.sort();
+        tops.dedup();
+        let root = match tops.as_slice() {
+            [top] if !CONTENT_FOLDERS.contains(top) && archive.file_names().all(|name| name.starts_with(&format!("{}/", top))) =>
+                format!("{}/", top),
+            _ => String::new()
+        };
+        return Ok(Self {
+            path,
+            archive,
+            root
+        });
+    }
+
+    pub fn path(&self) -> &Path {
+        return &self.path;
+    }
+
+    //The archive's name without its extension
+    pub fn name(&self) -> String {
+        return self.path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    }
+
+    pub fn contains(&self, file: &str) -> bool {
+        let file = format!("{}{}", self.root, file);
+        return self.archive.file_names().any(|name| name == file);
+    }
+
+    //Every file under folder, sorted, with paths through the archive like mods/example.ctlpak/types/items.json.
+    //Folders the content is zipped in aren't in the paths, so they name files the same way read_file does.
+    pub fn list(&self, folder: &str) -> Vec<PathBuf> {
+        return self.files(folder, None).into_iter().map(|file| self.path.join(&file[self.root.len()..])).collect();
+    }
+
+    //Reads a file by its path in the pack, like manifest.json
+    pub fn read_file(&self, file: &str) -> Result<Vec<u8>, Error> {
+        return self.read_entry(&format!("{}{}", self.root, file));
+    }
+
+    fn read_entry(&self, entry: &str) -> Result<Vec<u8>, Error> {
+        let mut archive = self.archive.clone();
+        let mut file = archive.by_name(entry)
+            .map_err(|error| Error::msg(format!("{}/{}: {}", self.path.display(), entry, error)))?;
+        let mut output = Vec::new();
+        file.read_to_end(&mut output)?;
+        return Ok(output);
+    }
+
+    //Files in the folder, sorted, skipping the ignored folder in it
+    fn files(&self, folder: &str, ignoring: Option<&str>) -> Vec<String> {
+        let prefix = format!("{}{}/", self.root, folder);
+        let ignored = ignoring.map(|ignoring| format!("{}{}/", prefix, ignoring));
+        let mut output = self.archive.file_names()
+            .filter(|name| name.starts_with(&prefix) && !name.ends_with('/'))
+            .filter(|name| ignored.as_ref().is_none_or(|ignored| !name.starts_with(ignored)))
+            .map(str::to_string).collect::<Vec<_>>();
+        output.sort();
+        return output;
+    }
+
+    //Files with their names, their path from folder without the extension, and their path through the archive
+    fn named(&self, folder: &str, files: Vec<String>) -> Vec<(String, PathBuf)> {
+        let folder = format!("{}{}", self.root, folder);
+        return files.into_iter().map(|file| (content_pack::asset_name(Path::new(&folder), Path::new(&file)), self.path.join(&file))).collect();
+    }
+
+    fn load_image(&self, folder: &str, entry: String) -> Result<LoadingAsset, Error> {
+        let (name, path) = self.named(folder, vec!(entry.clone())).remove(0);
+        let sidecar = DesktopLoader::animation_path(&entry);
+        let animation = match self.archive.file_names().any(|name| name == sidecar) {
+            true => Some((DesktopLoader::animation_path(&path.display().to_string()), self.read_entry(&sidecar)?)),
+            false => None
+        };
+        return Ok(LoadingAsset::Texture((name.clone(), DesktopLoader::decode_texture(name, &entry, &self.read_entry(&entry)?, animation)?)));
+    }
+}
+
+impl ContentPack for ArchivePack {
+    fn types(&self) -> Vec<(String, PathBuf)> {
+        return self.named("types", self.files("types", None));
+    }
+
+    fn assets(&self, handle: &Handle, load_first: bool) -> JoinHandle<Vec<AssetType>> {
+        let mut join_set = JoinSet::new();
+        if !load_first {
+            for (name, path) in self.named("assets/models", DesktopLoader::models(self.files("assets/models", None))) {
+                let pack = self.clone();
+                join_set.spawn_on(async move { DesktopLoader::load_model(name, &path, &|file| pack.read(Path::new(file))) }, handle);
+            }
+        }
+
+        let textures = if load_first {
+            DesktopLoader::textures(self.files("assets/textures/load_first", None))
+        } else {
+            DesktopLoader::textures(self.files("assets/textures", Some("load_first")))
+        };
+        for texture in textures {
+            let pack = self.clone();
+            join_set.spawn_on(async move { pack.load_image("assets/textures", texture) }, handle);
+        }
+        return handle.spawn(DesktopLoader::join_images(join_set, Vec::new()));
+    }
+
+    fn language(&self) -> Vec<LanguagePack> {
+        let mut values = Vec::new();
+        for (_, path) in self.named("language/translations", self.files("language/translations", None)) {
+            match self.read(&path).and_then(|bytes| resource::parse_file(&path.display().to_string(), bytes)) {
+                Ok((_, value)) => values.push(value),
+                Err(error) => error!("Error loading translations: {}", error)
+            }
+        }
+        return vec!(LanguagePack::Translations(values));
+    }
+
+    fn shaders(&self, early: bool) -> Vec<(String, PathBuf)> {
+        let shaders = if early {
+            self.files("shaders/load_first", None)
+        } else {
+            self.files("shaders", Some("load_first"))
+        };
+        return self.named("shaders", shaders);
+    }
+
+    fn clone_boxed(&self) -> Box<dyn ContentPack> {
+        return Box::new(self.clone());
+    }
+
+    fn load_asset(&self, kind: ContentKind, name: &str) -> Result<Option<LoadingAsset>, Error> {
+        let folder = match kind {
+            ContentKind::Texture => "assets/textures",
+            ContentKind::Model => "assets/models",
+            _ => return Ok(None)
+        };
+        let files = match kind {
+            ContentKind::Texture => DesktopLoader::textures(self.files(folder, None)),
+            _ => DesktopLoader::models(self.files(folder, None))
+        };
+        let entry = match files.into_iter().find(|entry| self.named(folder, vec!(entry.clone()))[0].0 == name) {
+            Some(entry) => entry,
+            None => return Ok(None)
+        };
+        return match kind {
+            ContentKind::Texture => self.load_image(folder, entry),
+            _ => DesktopLoader::load_model(name.to_string(), &self.named(folder, vec!(entry))[0].1, &|file| self.read(Path::new(file)))
+        }.map(Some);
+    }
+
+    //Listed files are under the archive's path, like mods/example.ctlpak/types/items.json
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        let entry = path.strip_prefix(&self.path)
+            .map_err(|_| Error::msg(format!("{} isn't in {}", path.display(), self.path.display())))?;
+        let entry = entry.components().map(|component| component.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+        return self.read_entry(&entry);
+    }
+}
@@ -0,0 +1,224 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use anyhow::Error;
+use macros::{JsonLoadable, JsonSaveable};
+use xxhash_rust::xxh3::xxh3_64;
+use game::rendering::block_compression::BlockFormat;
+use game::rendering::image_format::{self, ReadTexture};
+use game::rendering::mesh::Mesh;
+use game::rendering::model_import::{self, ModelFormat};
+use interfaces::format::{Binary, DataFormat};
+use interfaces::loading::JsonLoadable;
+use interfaces::{json_path, resource};
+use interfaces::json_path::JsonPath;
+use interfaces::saving::JsonSaveable;
+use crate::resources::content_pack;
+use crate::resources::content_stack::ContentKind;
+use crate::resources::desktop_loader::DesktopLoader;
+
+//Bundles are a whole resources folder in one file, already parsed and decoded:
+//  "CTLBUNDL", the format version and the index's length as little endian u32s,
+//  the index: every BundleEntry in Binary, sorted by kind then name,
+//  then the entries' data, which each entry has the offset of from the end of the index.
+//Types, translations and models are their JSON in Binary, with imported models saved as JSON meshes, shaders are their source,
+//and textures are their pixels as RGBA8, with the text of their animation file in their entry,
+//or their blocks with each mip level after the one before if they're block compressed.
+pub const MAGIC: &[u8; 8] = b"CTLBUNDL";
+pub const VERSION: u32 = 1;
+const HEADER_LENGTH: usize = 16;
+
+#[derive(JsonLoadable, JsonSaveable, Clone, Debug, Default, PartialEq)]
+pub struct BundleEntry {
+    pub kind: ContentKind,
+    //Named like any other pack's resources, by their path from their folder without the extension
+    pub name: String,
+    //The file it was built from, from the resources folder
+    pub path: String,
+    pub offset: u64,
+    pub length: u64,
+    //xxh3 of the data, in hex
+    pub hash: String,
+    #[json(default, skip_serializing_if_default)]
+    pub width: u32,
+    #[json(default, skip_serializing_if_default)]
+    pub height: u32,
+    //Text of a texture's animation file, if it has one
+    #[json(default, skip_serializing_if_default)]
+    pub animation: String,
+    //The format of block compressed textures, which keep their blocks instead of being decoded
+    #[json(default, skip_serializing_if_default)]
+    pub compressed: Option<BlockFormat>,
+}
+
+pub fn hash(data: &[u8]) -> String {
+    return format!("{:016x}", xxh3_64(data));
+}
+
+//Builds a bundle of the resources folder at root, failing if any file in it can't be loaded
+pub fn build(root: &Path) -> Result<Vec<u8>, Error> {
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    let folders = [
+        (ContentKind::Type, "types"),
+        (ContentKind::Texture, "assets/textures"),
+        (ContentKind::Model, "assets/models"),
+        (ContentKind::Shader, "shaders"),
+        (ContentKind::Translation, "language/translations")
+    ];
+    for (kind, folder) in folders {
+        let mut files = Vec::new();
+        find_files(&root.join(folder), &mut files)?;
+        for file in files {
+            //Animation files and anything else that isn't an image are skipped, like files models point to
+            if kind == ContentKind::Texture && !image_format::is_texture(&file.to_string_lossy()) ||
+                kind == ContentKind::Model && !model_import::is_model(&file.to_string_lossy()) {
+                continue;
+            }
+            match build_entry(kind, root, folder, &file) {
+                Ok(entry) => entries.push(entry),
+                Err(error) => errors.push(format!("{}", error))
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(Error::msg(errors.join("\n")));
+    }
+    entries.sort_by(|(first, _), (second, _)| (first.kind, &first.name).cmp(&(second.kind, &second.name)));
+
+    let mut index = Vec::new();
+    let mut data = Vec::new();
+    for (mut entry, bytes) in entries {
+        entry.offset = data.len() as u64;
+        entry.length = bytes.len() as u64;
+        entry.hash = hash(&bytes);
+        data.extend(bytes);
+        index.push(entry);
+    }
+    let index = index.save_to::<Binary>();
+
+    let mut output = Vec::with_capacity(HEADER_LENGTH + index.len() + data.len());
+    output.extend(MAGIC);
+    output.extend(VERSION.to_le_bytes());
+    output.extend((index.len() as u32).to_le_bytes());
+    output.extend(index);
+    output.extend(data);
+    return Ok(output);
+}
+
+//If the bundle was built after anything in the resources folder at root last changed.
+//Bundles that can't be checked aren't current, so the folder gets loaded.
+pub fn is_current(bundle: &Path, root: &Path) -> bool {
+    return match (fs::metadata(bundle).and_then(|metadata| metadata.modified()), last_changed(root)) {
+        (Ok(built), Ok(changed)) => changed <= built,
+        _ => false
+    };
+}
+
+//When the file, or anything in the folder including the folders themselves, last changed
+fn last_changed(path: &Path) -> Result<SystemTime, Error> {
+    let mut newest = fs::metadata(path)?.modified()?;
+    if path.is_dir() {
+        for file in fs::read_dir(path)? {
+            newest = newest.max(last_changed(&file?.path())?);
+        }
+    }
+    return Ok(newest);
+}
+
+//Reads a bundle's index, returning it with where the entries' data starts
+pub fn read_index(bundle: &[u8]) -> Result<(Vec<BundleEntry>, usize), Error> {
+    if bundle.len() < HEADER_LENGTH || &bundle[..8] != MAGIC {
+        return Err(Error::msg("Not a bundle"));
+    }
+    let version = u32::from_le_bytes(bundle[8..12].try_into()?);
+    if version != VERSION {
+        return Err(Error::msg(format!("Bundle is version {}, expected {}. Build it again", version, VERSION)));
+    }
+    let length = u32::from_le_bytes(bundle[12..16].try_into()?) as usize;
+    let data = HEADER_LENGTH + length;
+    if bundle.len() < data {
+        return Err(Error::msg("Bundle's index is cut off"));
+    }
+    let entries = Vec::<BundleEntry>::load_from::<Binary>(&bundle[HEADER_LENGTH..data])?;
+    return Ok((entries, data));
+}
+
+//An entry's data in the bundle, checked against its hash
+pub fn entry_data<'a>(bundle: &'a [u8], data: usize, entry: &BundleEntry) -> Result<&'a [u8], Error> {
+    //Corrupt offsets and lengths can be anything, so they're checked before they're added
+    let outside = || Error::msg(format!("{} is outside the bundle", entry.path));
+    let start = usize::try_from(entry.offset).ok().and_then(|offset| data.checked_add(offset)).ok_or_else(outside)?;
+    let end = usize::try_from(entry.length).ok().and_then(|length| start.checked_add(length)).ok_or_else(outside)?;
+    let found = bundle.get(start..end).ok_or_else(outside)?;
+    if hash(found) != entry.hash {
+        return Err(Error::msg(format!("{} doesn't match its hash, the bundle is corrupt", entry.path)));
+    }
+    return Ok(found);
+}
+
+fn build_entry(kind: ContentKind, root: &Path, folder: &str, file: &Path) -> Result<(BundleEntry, Vec<u8>), Error> {
+    let relative = file.strip_prefix(root)?.components()
+        .map(|component| component.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+    let name = content_pack::asset_name(&root.join(folder), file);
+    let mut entry = BundleEntry {
+        kind,
+        name,
+        path: relative,
+        ..Default::default()
+    };
+    let data = match kind {
+        ContentKind::Texture => {
+            let texture = image_format::read(&file.display().to_string(), &fs::read(file)?)
+                .map_err(|error| Error::msg(format!("{} → {}", file.display(), error)))?;
+            let sidecar = DesktopLoader::animation_path(&file.display().to_string());
+            let animated = Path::new(&sidecar).exists();
+            //Animated textures are cut into frames, so they're decoded like the folder's are
+            if let (ReadTexture::Compressed(image), false) = (&texture, animated) {
+                entry.width = image.width;
+                entry.height = image.height;
+                entry.compressed = Some(image.format);
+                return Ok((entry, image.levels.concat()));
+            }
+            let image = texture.into_pixels();
+            entry.width = image.width();
+            entry.height = image.height();
+            if animated {
+                let animation = fs::read_to_string(&sidecar)?;
+                DesktopLoader::make_texture(entry.name.clone(), image.clone(), Some((sidecar, animation.clone().into_bytes())))?;
+                entry.animation = animation;
+            }
+            image.into_raw()
+        }
+        ContentKind::Shader => fs::read_to_string(file)
+            .map_err(|error| Error::msg(format!("{} → {}", file.display(), error)))?.into_bytes(),
+        //Imported models are stored like JSON meshes, so they aren't imported again
+        ContentKind::Model if ModelFormat::from_path(&file.display().to_string()).is_some() =>
+            Binary::encode(&DesktopLoader::read_model(file, &|file| Ok(fs::read(file)?))?.save()),
+        ContentKind::Model => {
+            let (source, value) = resource::read(file)?;
+            Mesh::load(&value).map_err(|error|
+                Error::msg(json_path::describe_error(&file.display().to_string(), &source, &JsonPath::new(), &error)))?;
+            Binary::encode(&value)
+        }
+        ContentKind::Type | ContentKind::Translation => Binary::encode(&resource::read(file)?.1)
+    };
+    return Ok((entry, data));
+}
+
+//Every file under directory, sorted. Missing folders are empty.
+fn find_files(directory: &Path, output: &mut Vec<PathBuf>) -> Result<(), Error> {
+    if !directory.exists() {
+        return Ok(());
+    }
+    let mut found = fs::read_dir(directory)?.map(|file| file.map(|file| file.path())).collect::<Result<Vec<_>, _>>()?;
+    found.sort();
+    for file in found {
+        if file.is_dir() {
+            find_files(&file, output)?;
+        } else {
+            output.push(file);
+        }
+    }
+    return Ok(());
+}
@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use anyhow::Error;
+use image::RgbaImage;
+use json::JsonValue;
+use memmap2::Mmap;
+use tokio::runtime::Handle;
+use tokio::task::{JoinHandle, JoinSet};
+use game::error;
+use game::language::language::LanguagePack;
+use game::rendering::AssetType;
+use game::rendering::block_compression::BlockFormat;
+use game::rendering::image_format::CompressedImage;
+use game::rendering::mesh::Mesh;
+use interfaces::format::{Binary, DataFormat};
+use interfaces::loading::JsonLoadable;
+use crate::resources::bundle::{self, BundleEntry};
+use crate::resources::content_pack::ContentPack;
+use crate::resources::content_stack::ContentKind;
+use crate::resources::desktop_loader::{CompressedTexture, DesktopLoader, LoadingAsset};
+
+//Content in a bundle built by ctl-bundle, memory mapped so only what's loaded is read.
+//Files are listed with paths through the bundle, like resources.ctlbundle/types/cubes.json.
+#[derive(Clone)]
+pub struct BundlePack {
+    path: PathBuf,
+    bundle: Arc<Mmap>,
+    entries: Arc<Vec<BundleEntry>>,
+    //Indices of entries by the path they were built from
+    paths: Arc<HashMap<String, usize>>,
+    //Where the entries' data starts
+    data: usize,
+}
+
+impl BundlePack {
+    pub fn is_bundle(path: &Path) -> bool {
+        return path.is_file() && path.extension().and_then(|extension| extension.to_str()) == Some("ctlbundle");
+    }
+
+    pub fn open(path: PathBuf) -> Result<Self, Error> {
+        //Safety: bundles aren't changed while the game runs, rebuilding one replaces the file
+        let bundle = unsafe { Mmap::map(&File::open(&path)?)? };
+        let (entries, data) = bundle::read_index(&bundle)
+            .map_err(|error| Error::msg(format!("Failed to open {}: {}", path.display(), error)))?;
+        let paths = entries.iter().enumerate().map(|(index, entry)| (entry.path.clone(), index)).collect();
+        return Ok(Self {
+            path,
+            bundle: Arc::new(bundle),
+            entries: Arc::new(entries),
+            paths: Arc::new(paths),
+            data
+        });
+    }
+
+    //The bundle's name without its extension
+    pub fn name(&self) -> String {
+        return self.path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    }
+
+    pub fn entries(&self) -> &[BundleEntry] {
+        return &self.entries;
+    }
+
+    fn data(&self, entry: &BundleEntry) -> Result<&[u8], Error> {
+        return bundle::entry_data(&self.bundle, self.data, entry)
+            .map_err(|error| Error::msg(format!("{}: {}", self.path.display(), error)));
+    }
+
+    fn entry(&self, path: &Path) -> Result<&BundleEntry, Error> {
+        let relative = path.strip_prefix(&self.path).ok()
+            .map(|relative| relative.components().map(|component| component.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"));
+        return relative.and_then(|relative| self.paths.get(&relative)).map(|index| &self.entries[*index])
+            .ok_or_else(|| Error::msg(format!("{} isn't in {}", path.display(), self.path.display())));
+    }
+
+    //Entries of the kind, with their index
+    fn of_kind(&self, kind: ContentKind) -> impl Iterator<Item=(usize, &BundleEntry)> {
+        return self.entries.iter().enumerate().filter(move |(_, entry)| entry.kind == kind);
+    }
+
+    fn listed(&self, kind: ContentKind, early: Option<bool>) -> Vec<(String, PathBuf)> {
+        return self.of_kind(kind)
+            .filter(|(_, entry)| early.is_none_or(|early| entry.name.starts_with("load_first/") == early))
+            .map(|(_, entry)| (entry.name.clone(), self.path.join(&entry.path))).collect();
+    }
+
+    fn load_image(&self, index: usize) -> Result<LoadingAsset, Error> {
+        let entry = &self.entries[index];
+        if let Some(format) = entry.compressed {
+            let image = Self::compressed_image(format, entry, self.data(entry)?)?;
+            return Ok(LoadingAsset::Texture((entry.name.clone(), Arc::new(CompressedTexture::new(entry.name.clone(), image)))));
+        }
+        let texture = RgbaImage::from_raw(entry.width, entry.height, self.data(entry)?.to_vec())
+            .ok_or_else(|| Error::msg(format!("{} is the wrong size for {}x{}", entry.path, entry.width, entry.height)))?;
+        let animation = match entry.animation.is_empty() {
+            true => None,
+            false => Some((DesktopLoader::animation_path(&self.path.join(&entry.path).display().to_string()),
+                           entry.animation.clone().into_bytes()))
+        };
+        return Ok(LoadingAsset::Texture((entry.name.clone(), DesktopLoader::make_texture(entry.name.clone(), texture, animation)?)));
+    }
+
+    //Splits a compressed texture's data back into its levels, which are each half the size of the one before
+    fn compressed_image(format: BlockFormat, entry: &BundleEntry, mut data: &[u8]) -> Result<CompressedImage, Error> {
+        let mut levels = Vec::new();
+        while !data.is_empty() {
+            let (width, height) = ((entry.width >> levels.len()).max(1), (entry.height >> levels.len()).max(1));
+            let length = format.image_size(width, height).min(data.len());
+            levels.push(data[..length].to_vec());
+            data = &data[length..];
+        }
+        return CompressedImage::new(format, entry.width, entry.height, levels)
+            .map_err(|error| Error::msg(format!("{} → {}", entry.path, error)));
+    }
+
+    fn load_model(&self, index: usize) -> Result<LoadingAsset, Error> {
+        let entry = &self.entries[index];
+        let mesh = Mesh::load(&Binary::decode(self.data(entry)?)?)?;
+        return Ok(LoadingAsset::Model((entry.name.clone(), Arc::new(mesh))));
+    }
+}
+
+impl ContentPack for BundlePack {
+    fn types(&self) -> Vec<(String, PathBuf)> {
+        return self.listed(ContentKind::Type, None);
+    }
+
+    fn assets(&self, handle: &Handle, load_first: bool) -> JoinHandle<Vec<AssetType>> {
+        let mut join_set = JoinSet::new();
+        if !load_first {
+            for (index, _) in self.of_kind(ContentKind::Model) {
+                let pack = self.clone();
+                join_set.spawn_on(async move { pack.load_model(index) }, handle);
+            }
+        }
+        for (index, entry) in self.of_kind(ContentKind::Texture) {
+            if entry.name.starts_with("load_first/") == load_first {
+                let pack = self.clone();
+                join_set.spawn_on(async move { pack.load_image(index) }, handle);
+            }
+        }
+        return handle.spawn(DesktopLoader::join_images(join_set, Vec::new()));
+    }
+
+    fn language(&self) -> Vec<LanguagePack> {
+        let mut values = Vec::new();
+        for (_, entry) in self.of_kind(ContentKind::Translation) {
+            match self.data(entry).and_then(Binary::decode) {
+                Ok(value) => values.push(value),
+                Err(error) => error!("Error loading translations: {}", error)
+            }
+        }
+        return vec!(LanguagePack::Translations(values));
+    }
+
+    fn shaders(&self, early: bool) -> Vec<(String, PathBuf)> {
+        return self.listed(ContentKind::Shader, Some(early));
+    }
+
+    fn clone_boxed(&self) -> Box<dyn ContentPack> {
+        return Box::new(self.clone());
+    }
+
+    fn load_asset(&self, kind: ContentKind, name: &str) -> Result<Option<LoadingAsset>, Error> {
+        return match self.of_kind(kind).find(|(_, entry)| entry.name == name) {
+            Some((index, _)) if kind == ContentKind::Texture => self.load_image(index).map(Some),
+            Some((index, _)) if kind == ContentKind::Model => self.load_model(index).map(Some),
+            _ => Ok(None)
+        };
+    }
+
+    //Types are read back as JSON, since they're stored parsed
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        let entry = self.entry(path)?;
+        return match entry.kind {
+            ContentKind::Type | ContentKind::Translation | ContentKind::Model =>
+                Ok(Binary::decode(self.data(entry)?)?.dump().into_bytes()),
+            _ => Ok(self.data(entry)?.to_vec())
+        };
+    }
+
+    //Types don't have their text, so errors in them say the path to the value without a line
+    fn read_json(&self, path: &Path) -> Result<(String, JsonValue), Error> {
+        let entry = self.entry(path)?;
+        return Ok((String::new(), Binary::decode(self.data(entry)?)?));
+    }
+}
@@ -1,19 +1,56 @@
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Error;
+use json::JsonValue;
 use tokio::runtime::Handle;
 use tokio::task::JoinHandle;
 use game::language::language::LanguagePack;
 use game::rendering::AssetType;
+use interfaces::{json_path, resource};
+use interfaces::json_path::JsonPath;
+use crate::resources::content_stack::ContentKind;
+use crate::resources::desktop_loader::LoadingAsset;
 
+//Types, shaders and assets are named by their path from their folder, without the extension.
+//Packs in a ContentStack override each other's files with the same name.
 pub trait ContentPack: Send + Sync {
-    fn types(&self) -> Vec<PathBuf>;
-
-    fn get_relative(&self, subpath: &str, path: &PathBuf) -> String;
+    fn types(&self) -> Vec<(String, PathBuf)>;
 
     fn assets(&self, handle: &Handle, load_first: bool) -> JoinHandle<Vec<AssetType>>;
 
     fn language(&self) -> Vec<LanguagePack>;
 
-    fn shaders(&self, early: bool) -> Vec<PathBuf>;
+    fn shaders(&self, early: bool) -> Vec<(String, PathBuf)>;
 
     fn clone_boxed(&self) -> Box<dyn ContentPack>;
+
+    //Loads one texture or model by name, like when it was unloaded and is needed again. None if the pack doesn't have it.
+    fn load_asset(&self, kind: ContentKind, name: &str) -> Result<Option<LoadingAsset>, Error>;
+
+    //Reads a file listed by types or shaders. Packs that aren't folders list paths inside themselves.
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        return Ok(fs::read(path)?);
+    }
+
+    //Reads and parses a types file, returning its text too for locating errors. Errors start with the file.
+    //Packs with types that are already parsed return them with no text.
+    fn read_json(&self, path: &Path) -> Result<(String, JsonValue), Error> {
+        let file = path.display().to_string();
+        let bytes = self.read(path)
+            .map_err(|error| Error::msg(json_path::describe(&file, None, &JsonPath::new(), &error.to_string())))?;
+        return resource::parse_file(&file, bytes);
+    }
+
+    //Folders to watch for changed files to hot reload, empty if the pack can't change
+    fn watched(&self) -> Vec<PathBuf> {
+        return Vec::new();
+    }
+}
+
+//The name of a file in folder: its path under folder with '/' separators, up to the first '.'.
+//Every pack and ctl-validate name files with this, so they agree on what overrides what.
+pub fn asset_name(folder: &Path, file: &Path) -> String {
+    let relative = file.strip_prefix(folder).unwrap_or(file).components()
+        .map(|component| component.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+    return relative.split('.').next().unwrap().to_string();
 }
\ No newline at end of file
@@ -0,0 +1,293 @@
+use std::collections::{BTreeMap, HashMap};
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use anyhow::Error;
+use json::JsonValue;
+use macros::{JsonLoadable, JsonSaveable};
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+use game::error;
+use game::language::language::LanguagePack;
+use game::rendering::AssetType;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::resources::archive_pack::ArchivePack;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::resources::bundle_pack::BundlePack;
+use crate::resources::content_pack::ContentPack;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::resources::desktop_loader::DesktopLoader;
+use crate::resources::desktop_loader::LoadingAsset;
+
+#[derive(JsonLoadable, JsonSaveable, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ContentKind {
+    #[default]
+    Type,
+    Texture,
+    Model,
+    Shader,
+    Translation
+}
+
+//Content packs on top of each other: the base game, then mods in dependency order, then resource packs.
+//Later packs override the types, shaders, textures and models with the same name in earlier ones.
+//Translations are overridden key by key instead, since their files are named after their language.
+pub struct ContentStack {
+    packs: Vec<StackedPack>,
+    //The pack each resource came from, by kind and name. Filled in as they're listed and loaded.
+    suppliers: Arc<Mutex<HashMap<(ContentKind, String), String>>>,
+    //The pack each listed file is read from
+    files: Arc<Mutex<HashMap<PathBuf, String>>>,
+}
+
+struct StackedPack {
+    name: String,
+    pack: Box<dyn ContentPack>,
+}
+
+impl Clone for ContentStack {
+    fn clone(&self) -> Self {
+        return Self {
+            packs: self.packs.iter().map(|found| StackedPack {
+                name: found.name.clone(),
+                pack: found.pack.clone_boxed()
+            }).collect(),
+            suppliers: self.suppliers.clone(),
+            files: self.files.clone()
+        };
+    }
+}
+
+impl Default for ContentStack {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl ContentStack {
+    pub fn new() -> Self {
+        return Self {
+            packs: Vec::new(),
+            suppliers: Arc::new(Mutex::new(HashMap::new())),
+            files: Arc::new(Mutex::new(HashMap::new()))
+        };
+    }
+
+    //Adds a pack on top, overriding everything before it
+    pub fn push(&mut self, name: &str, pack: Box<dyn ContentPack>) {
+        self.packs.push(StackedPack {
+            name: name.to_string(),
+            pack
+        });
+    }
+
+    //Names of the packs, bottom first
+    pub fn names(&self) -> Vec<String> {
+        return self.packs.iter().map(|found| found.name.clone()).collect();
+    }
+
+    //Moves the packs named in order to the top, in that order, like GameSettings::content_packs.
+    //Unlisted packs stay below them as they were, and unknown names are ignored.
+    pub fn reorder(&mut self, order: &[String]) {
+        let mut moved = Vec::new();
+        for name in order {
+            if let Some(index) = self.packs.iter().position(|found| &found.name == name) {
+                moved.push(self.packs.remove(index));
+            }
+        }
+        self.packs.extend(moved);
+    }
+
+    //Name of the pack a resource came from, once it's been listed or loaded
+    pub fn supplier(&self, kind: ContentKind, name: &str) -> Option<String> {
+        return self.suppliers.lock().unwrap().get(&(kind, name.to_string())).cloned();
+    }
+
+    //The pack above the given one supplying a resource, if one overrides the given pack's version of it
+    pub fn overridden_by(&self, kind: ContentKind, name: &str, pack: &str) -> Option<String> {
+        let supplier = self.supplier(kind, name)?;
+        return (self.position(&supplier) > self.position(pack)).then_some(supplier);
+    }
+
+    //Records the pack a resource came from, like once it's been reloaded from another one
+    pub fn set_supplier(&self, kind: ContentKind, name: &str, pack: &str) {
+        self.suppliers.lock().unwrap().insert((kind, name.to_string()), pack.to_string());
+    }
+
+    //Name of the pack watching the folder, like the resources folder or a mod's folder in it
+    pub fn pack_in(&self, folder: &Path) -> Option<String> {
+        return self.packs.iter().find(|stacked| stacked.pack.watched().iter().any(|watched| watched == folder))
+            .map(|stacked| stacked.name.clone());
+    }
+
+    fn position(&self, pack: &str) -> Option<usize> {
+        return self.packs.iter().position(|stacked| stacked.name == pack);
+    }
+
+    //Every resource listed or loaded so far with the pack it came from, sorted by kind then name
+    pub fn suppliers(&self) -> Vec<(ContentKind, String, String)> {
+        let mut output = self.suppliers.lock().unwrap().iter()
+            .map(|((kind, name), pack)| (*kind, name.clone(), pack.clone())).collect::<Vec<_>>();
+        output.sort();
+        return output;
+    }
+
+    //The pack that listed the file, which it has to be read from
+    fn listed_by(&self, path: &Path) -> Result<&dyn ContentPack, Error> {
+        let pack = self.files.lock().unwrap().get(path).cloned();
+        return match self.packs.iter().find(|stacked| Some(&stacked.name) == pack.as_ref()) {
+            Some(stacked) => Ok(stacked.pack.as_ref()),
+            None => Err(Error::msg(format!("{} isn't in any content pack", path.display())))
+        };
+    }
+
+    //Files from every pack, with later ones replacing earlier ones of the same name. Sorted by name.
+    fn overridden(&self, kind: ContentKind, list: impl Fn(&dyn ContentPack) -> Vec<(String, PathBuf)>) -> Vec<(String, PathBuf)> {
+        let mut found = BTreeMap::new();
+        for stacked in &self.packs {
+            for (name, path) in list(stacked.pack.as_ref()) {
+                found.insert(name, (path, &stacked.name));
+            }
+        }
+        let mut suppliers = self.suppliers.lock().unwrap();
+        let mut files = self.files.lock().unwrap();
+        return found.into_iter().map(|(name, (path, pack))| {
+            suppliers.insert((kind, name.clone()), pack.clone());
+            files.insert(path.clone(), pack.clone());
+            (name, path)
+        }).collect();
+    }
+}
+
+//Resource packs in root/resource_packs: folders laid out like root, archives or bundles.
+//They're named by their folder, or their file name without its extension.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn resource_packs(root: &Path) -> Vec<(String, Box<dyn ContentPack>)> {
+    let found = match fs::read_dir(root.join("resource_packs")) {
+        Ok(found) => found,
+        Err(_) => return Vec::new()
+    };
+    let mut output: Vec<(String, Box<dyn ContentPack>)> = Vec::new();
+    for pack in found {
+        let path = match pack {
+            Ok(pack) => pack.path(),
+            Err(error) => {
+                error!("Error opening resource pack:\n{}", error);
+                continue;
+            }
+        };
+        if path.is_dir() {
+            output.push((path.file_name().unwrap_or_default().to_string_lossy().to_string(), Box::new(DesktopLoader::new(path))));
+        } else if ArchivePack::is_archive(&path) {
+            match ArchivePack::open(path) {
+                Ok(archive) => output.push((archive.name(), Box::new(archive))),
+                Err(error) => error!("Error opening resource pack:\n{}", error)
+            }
+        } else if BundlePack::is_bundle(&path) {
+            match BundlePack::open(path) {
+                Ok(bundle) => output.push((bundle.name(), Box::new(bundle))),
+                Err(error) => error!("Error opening resource pack:\n{}", error)
+            }
+        }
+    }
+    output.sort_by(|(first, _), (second, _)| first.cmp(second));
+    return output;
+}
+
+impl ContentPack for ContentStack {
+    fn types(&self) -> Vec<(String, PathBuf)> {
+        return self.overridden(ContentKind::Type, |pack| pack.types());
+    }
+
+    fn assets(&self, handle: &Handle, load_first: bool) -> JoinHandle<Vec<AssetType>> {
+        let loading = self.packs.iter()
+            .map(|stacked| (stacked.name.clone(), stacked.pack.assets(handle, load_first))).collect::<Vec<_>>();
+        let suppliers = self.suppliers.clone();
+        return handle.spawn(async move {
+            let mut textures = HashMap::new();
+            let mut models = HashMap::new();
+            //Waited on in order, so later packs replace earlier ones
+            for (pack, assets) in loading {
+                let assets = match assets.await {
+                    Ok(assets) => assets,
+                    Err(error) => {
+                        error!("Internal error loading assets from {}:\n{}", pack, error);
+                        continue;
+                    }
+                };
+                let mut suppliers = suppliers.lock().unwrap();
+                for asset in assets {
+                    match asset {
+                        AssetType::Texture(found) => for (name, texture) in found {
+                            suppliers.insert((ContentKind::Texture, name.clone()), pack.clone());
+                            textures.insert(name, texture);
+                        }
+                        AssetType::Model(found) => for (name, model) in found {
+                            suppliers.insert((ContentKind::Model, name.clone()), pack.clone());
+                            models.insert(name, model);
+                        }
+                    }
+                }
+            }
+            return vec!(AssetType::Texture(textures), AssetType::Model(models));
+        });
+    }
+
+    //Every pack's translations, bottom first, so later ones replace the keys they share
+    fn language(&self) -> Vec<LanguagePack> {
+        let mut output = Vec::new();
+        let mut suppliers = self.suppliers.lock().unwrap();
+        for stacked in &self.packs {
+            for pack in stacked.pack.language() {
+                match &pack {
+                    LanguagePack::Translations(values) => for value in values {
+                        for (key, _) in value.entries() {
+                            suppliers.insert((ContentKind::Translation, key.to_string()), stacked.name.clone());
+                        }
+                    }
+                }
+                output.push(pack);
+            }
+        }
+        return output;
+    }
+
+    fn shaders(&self, early: bool) -> Vec<(String, PathBuf)> {
+        return self.overridden(ContentKind::Shader, |pack| pack.shaders(early));
+    }
+
+    fn clone_boxed(&self) -> Box<dyn ContentPack> {
+        return Box::new(self.clone());
+    }
+
+    //From the top pack that has it, like it was loaded
+    fn load_asset(&self, kind: ContentKind, name: &str) -> Result<Option<LoadingAsset>, Error> {
+        for stacked in self.packs.iter().rev() {
+            if let Some(found) = stacked.pack.load_asset(kind, name)? {
+                return Ok(Some(found));
+            }
+        }
+        return Ok(None);
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        return self.listed_by(path)?.read(path);
+    }
+
+    fn read_json(&self, path: &Path) -> Result<(String, JsonValue), Error> {
+        return self.listed_by(path)?.read_json(path);
+    }
+
+    //Folders inside another watched folder, like mods in the resources folder, are already watched
+    fn watched(&self) -> Vec<PathBuf> {
+        let folders = self.packs.iter().flat_map(|stacked| stacked.pack.watched()).collect::<Vec<_>>();
+        let mut output: Vec<PathBuf> = Vec::new();
+        for folder in &folders {
+            if !folders.iter().any(|other| other != folder && folder.starts_with(other)) && !output.contains(folder) {
+                output.push(folder.clone());
+            }
+        }
+        return output;
+    }
+}
@@ -1,22 +1,25 @@
 use std::collections::HashMap;
-use std::{fs, path};
+use std::fs;
 use std::fmt::{Display, Formatter};
-use std::fs::File;
-use std::io::BufReader;
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 use anyhow::Error;
-use image::{ImageFormat, RgbaImage};
+use image::{imageops, RgbaImage};
 use json::JsonValue;
 use tokio::runtime::Handle;
 use tokio::task::{JoinHandle, JoinSet};
 use game::error;
 use game::language::language::LanguagePack;
 use game::rendering::{AssetType, GameTexture};
+use game::rendering::image_format::{self, CompressedImage, ReadTexture};
 use game::rendering::mesh::Mesh;
+use game::rendering::model_import::{self, ModelFormat, ReadFile};
+use game::rendering::texture::Animation;
 use interfaces::loading::JsonLoadable;
-use crate::resources::content_pack::ContentPack;
-use crate::resources::loading::load_json;
+use interfaces::{json_path, resource};
+use interfaces::json_path::JsonPath;
+use crate::resources::content_pack::{self, ContentPack};
+use crate::resources::content_stack::ContentKind;
 
 #[derive(Clone)]
 pub struct DesktopLoader {
@@ -24,7 +27,7 @@ pub struct DesktopLoader {
 }
 
 impl ContentPack for DesktopLoader {
-    fn types(&self) -> Vec<PathBuf> {
+    fn types(&self) -> Vec<(String, PathBuf)> {
         let path = self.root.join("types");
 
         if !path.exists() {
@@ -32,8 +35,8 @@ impl ContentPack for DesktopLoader {
         }
 
         let mut loading = Vec::new();
-        return match DesktopLoader::find_files(path, &mut loading, vec!()) {
-            Ok(_) => loading,
+        return match DesktopLoader::find_files(path.clone(), &mut loading, vec!()) {
+            Ok(_) => Self::named(path, loading),
             Err(error) => {
                 error!("Error loading JSON types: {}", error);
                 return Vec::new();
@@ -41,13 +44,6 @@ impl ContentPack for DesktopLoader {
         };
     }
 
-    fn get_relative(&self, subpath: &str, path: &PathBuf) -> String {
-        return path.to_str().unwrap().replace(&self.root.to_str().unwrap().to_string(), "")
-            .replace(path::MAIN_SEPARATOR, "/")
-            .replace(&("/".to_string() + subpath), "")
-            .split('.').nth(0).unwrap().to_string();
-    }
-
     fn assets(&self, handle: &Handle, load_first: bool) -> JoinHandle<Vec<AssetType>> {
         let mut join_set = JoinSet::new();
         let mut output = Vec::new();
@@ -56,8 +52,9 @@ impl ContentPack for DesktopLoader {
                 Ok(_) => {},
                 Err(error) => error!("Error finding models: {}", error)
             }
-            for asset in output {
-                join_set.spawn_on(Self::load_model(self.root.join("assets/models"), asset), handle);
+            for model in Self::models(output) {
+                let name = content_pack::asset_name(&self.root.join("assets/models"), &model);
+                join_set.spawn_on(async move { Self::load_model(name, &model, &|file| Ok(fs::read(file)?)) }, handle);
             }
         }
 
@@ -75,7 +72,7 @@ impl ContentPack for DesktopLoader {
                 error!("Error finding textures: {}", error)
             }
         }
-        for texture in temp {
+        for texture in Self::textures(temp) {
             join_set.spawn_on(Self::load_image(self.root.join("assets/textures"), texture), handle);
         }
         let output = Vec::new();
@@ -97,7 +94,7 @@ impl ContentPack for DesktopLoader {
         return output;
     }
 
-    fn shaders(&self, early: bool) -> Vec<PathBuf> {
+    fn shaders(&self, early: bool) -> Vec<(String, PathBuf)> {
         let shaders;
         let mut loading = Vec::new();
         if early {
@@ -106,7 +103,7 @@ impl ContentPack for DesktopLoader {
             shaders = DesktopLoader::find_files(self.root.join("shaders"), &mut loading, vec!("load_first"));
         }
         return match shaders {
-            Ok(_) => loading,
+            Ok(_) => Self::named(self.root.join("shaders"), loading),
             Err(error) => {
                 error!("Error loading shaders: {}", error);
                 return Vec::new();
@@ -117,6 +114,33 @@ impl ContentPack for DesktopLoader {
     fn clone_boxed(&self) -> Box<dyn ContentPack> {
         return Box::new(self.clone());
     }
+
+    fn load_asset(&self, kind: ContentKind, name: &str) -> Result<Option<LoadingAsset>, Error> {
+        let folder = match kind {
+            ContentKind::Texture => self.root.join("assets/textures"),
+            ContentKind::Model => self.root.join("assets/models"),
+            _ => return Ok(None)
+        };
+        let mut files = Vec::new();
+        DesktopLoader::find_files(folder.clone(), &mut files, Vec::new())?;
+        let files = match kind {
+            ContentKind::Texture => Self::textures(files),
+            _ => Self::models(files)
+        };
+        let file = match files.into_iter().find(|file| content_pack::asset_name(&folder, file) == name) {
+            Some(file) => file,
+            None => return Ok(None)
+        };
+        return Ok(Some(match kind {
+            ContentKind::Texture => LoadingAsset::Texture(Self::read_texture(folder, file)?),
+            _ => Self::load_model(name.to_string(), &file, &|file| Ok(fs::read(file)?))?
+        }));
+    }
+
+    //Mods are in the root's mods folder, so they're watched too
+    fn watched(&self) -> Vec<PathBuf> {
+        return vec!(self.root.clone());
+    }
 }
 
 #[derive(Debug)]
@@ -125,6 +149,88 @@ pub struct TextureWrapper {
     name: String,
 }
 
+impl TextureWrapper {
+    pub(crate) fn new(name: String, texture: RgbaImage) -> Self {
+        return Self {
+            texture,
+            name
+        };
+    }
+}
+
+//A block compressed texture, only decoded if its pixels are needed
+#[derive(Debug)]
+pub struct CompressedTexture {
+    image: CompressedImage,
+    decoded: OnceLock<RgbaImage>,
+    name: String
+}
+
+impl CompressedTexture {
+    pub(crate) fn new(name: String, image: CompressedImage) -> Self {
+        return Self {
+            image,
+            decoded: OnceLock::new(),
+            name
+        };
+    }
+}
+
+//A texture cut into frames, which the renderer plays through
+#[derive(Debug)]
+pub struct AnimatedTexture {
+    frames: Vec<RgbaImage>,
+    name: String,
+    animation: Animation
+}
+
+impl AnimatedTexture {
+    pub fn new(name: String, texture: &RgbaImage, animation: Animation) -> Result<Self, Error> {
+        let count = animation.frame_count(texture.width(), texture.height())?;
+        let (width, height) = animation.frame_size(texture.width());
+        let columns = texture.width() / width;
+        let frames = (0..count as u32).map(|frame|
+            imageops::crop_imm(texture, frame % columns * width, frame / columns * height, width, height).to_image()).collect();
+        return Ok(Self {
+            frames,
+            name,
+            animation
+        });
+    }
+}
+
+impl Display for AnimatedTexture {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Animated texture {}", self.name)
+    }
+}
+
+impl GameTexture for AnimatedTexture {
+    fn dimensions(&self) -> (u32, u32) {
+        return self.frames[0].dimensions();
+    }
+
+    fn name(&self) -> String {
+        return self.name.clone();
+    }
+
+    fn data(&self) -> &[u8] {
+        return &self.frames[0];
+    }
+
+    fn frames(&self) -> usize {
+        return self.frames.len();
+    }
+
+    fn frame(&self, frame: usize) -> &[u8] {
+        return &self.frames[frame];
+    }
+
+    fn animation(&self) -> Option<&Animation> {
+        return Some(&self.animation);
+    }
+}
+
 impl Display for TextureWrapper {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "Texture {}", self.name)
@@ -145,6 +251,30 @@ impl GameTexture for TextureWrapper {
     }
 }
 
+impl Display for CompressedTexture {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Compressed texture {}", self.name)
+    }
+}
+
+impl GameTexture for CompressedTexture {
+    fn dimensions(&self) -> (u32, u32) {
+        return (self.image.width, self.image.height);
+    }
+
+    fn name(&self) -> String {
+        return self.name.clone();
+    }
+
+    fn data(&self) -> &[u8] {
+        return self.decoded.get_or_init(|| self.image.decode());
+    }
+
+    fn compressed(&self) -> Option<&CompressedImage> {
+        return Some(&self.image);
+    }
+}
+
 impl DesktopLoader {
     pub fn new(root: PathBuf) -> Self {
         return Self {
@@ -152,7 +282,7 @@ impl DesktopLoader {
         };
     }
 
-    async fn join_images(mut loading: JoinSet<Result<LoadingAsset, Error>>, mut input: Vec<AssetType>) -> Vec<AssetType> {
+    pub(crate) async fn join_images(mut loading: JoinSet<Result<LoadingAsset, Error>>, mut input: Vec<AssetType>) -> Vec<AssetType> {
         let mut textures = HashMap::new();
         let mut models = HashMap::new();
 
@@ -177,31 +307,91 @@ impl DesktopLoader {
         return input;
     }
 
-    async fn load_model(base: PathBuf, location: PathBuf) -> Result<LoadingAsset, Error> {
-        return Ok(LoadingAsset::Model((Self::get_relative_path(base, location.clone()),
-                                       Arc::new(Mesh::load(&load_json(location).await?)?))));
+    //Reads the model at file with read, named name
+    pub(crate) fn load_model(name: String, file: &Path, read: ReadFile) -> Result<LoadingAsset, Error> {
+        return Ok(LoadingAsset::Model((name, Arc::new(Self::read_model(file, read)?))));
+    }
+
+    //Loads a JSON mesh, or imports a glTF or OBJ model with the files it points to next to it, reading them all with read
+    pub fn read_model(file: &Path, read: ReadFile) -> Result<Mesh, Error> {
+        let file = file.display().to_string();
+        let bytes = read(&file)?;
+        if ModelFormat::from_path(&file).is_some() {
+            return model_import::import(&file, &bytes, &|uri| read(&model_import::relative_to(&file, uri)));
+        }
+        let (_, value) = resource::parse_file(&file, bytes)?;
+        return Mesh::load(&value);
     }
 
     async fn load_image(base: PathBuf, texture: PathBuf) -> Result<LoadingAsset, Error> {
-        let loaded = image::load(BufReader::new(File::open(texture.clone())?),
-                                 ImageFormat::Png).unwrap().to_rgba8();
-        let name = Self::get_relative_path(base, texture);
-        return Ok(LoadingAsset::Texture((name.clone(), Arc::new(TextureWrapper {
-            texture: loaded,
-            name,
-        }))));
+        return Ok(LoadingAsset::Texture(Self::read_texture(base, texture)?));
+    }
+
+    //Reads the image at texture, and its animation if it has one, named by its path from base
+    pub(crate) fn read_texture(base: PathBuf, texture: PathBuf) -> Result<(String, Arc<dyn GameTexture>), Error> {
+        let file = texture.display().to_string();
+        let sidecar = Self::animation_path(&file);
+        let animation = match Path::new(&sidecar).exists() {
+            true => Some((sidecar.clone(), fs::read(&sidecar)?)),
+            false => None
+        };
+        let bytes = fs::read(&texture)?;
+        let name = content_pack::asset_name(&base, &texture);
+        return Ok((name.clone(), Self::decode_texture(name, &file, &bytes, animation)?));
+    }
+
+    //Decodes an image that's already been read from file, with the animation file next to it if it has one
+    pub(crate) fn decode_texture(name: String, file: &str, bytes: &[u8], animation: Option<(String, Vec<u8>)>) -> Result<Arc<dyn GameTexture>, Error> {
+        let texture = image_format::read(file, bytes).map_err(|error| Error::msg(format!("{} → {}", file, error)))?;
+        return match texture {
+            //Animated textures are cut into frames, so they're decoded
+            ReadTexture::Compressed(image) if animation.is_none() => Ok(Arc::new(CompressedTexture::new(name, image))),
+            texture => Self::make_texture(name, texture.into_pixels(), animation)
+        };
     }
 
-    fn get_relative_path(base: PathBuf, path: PathBuf) -> String {
-        let mut name = path.to_str().unwrap().replace(base.to_str().unwrap(), "")
-            .replace(path::MAIN_SEPARATOR, "/").split('.').nth(0).unwrap().to_string();
-        name.remove(0);
-        return name;
+    //The texture, cut into frames if it has an animation file, given with its path for errors
+    pub(crate) fn make_texture(name: String, texture: RgbaImage, animation: Option<(String, Vec<u8>)>) -> Result<Arc<dyn GameTexture>, Error> {
+        let (file, bytes) = match animation {
+            Some(animation) => animation,
+            None => return Ok(Arc::new(TextureWrapper::new(name, texture)))
+        };
+        let (source, value) = resource::parse_file(&file, bytes)?;
+        let animation = Animation::load(&value).map_err(|error|
+            Error::msg(json_path::describe_error(&file, &source, &JsonPath::new(), &error)))?;
+        return Ok(Arc::new(AnimatedTexture::new(name, &texture, animation)
+            .map_err(|error| Error::msg(format!("{} → {}", file, error)))?));
+    }
+
+    //Where a texture's animation file is, next to it
+    pub(crate) fn animation_path(texture: &str) -> String {
+        return format!("{}.json", texture);
+    }
+
+    //Images among the files, without the animation files next to them
+    pub(crate) fn textures<T: AsRef<Path>>(files: Vec<T>) -> Vec<T> {
+        return files.into_iter().filter(|file| image_format::is_texture(&file.as_ref().to_string_lossy())).collect();
+    }
+
+    //Models among the files, without the files they point to
+    pub(crate) fn models<T: AsRef<Path>>(files: Vec<T>) -> Vec<T> {
+        return files.into_iter().filter(|file| model_import::is_model(&file.as_ref().to_string_lossy())).collect();
+    }
+
+    //Files with their names, their path from base
+    fn named(base: PathBuf, files: Vec<PathBuf>) -> Vec<(String, PathBuf)> {
+        return files.into_iter().map(|file| (content_pack::asset_name(&base, &file), file)).collect();
     }
 
     fn find_files(directory: PathBuf, output: &mut Vec<PathBuf>, ignoring: Vec<&str>) -> Result<(), Error> {
-        for file in fs::read_dir(directory.clone())? {
-            let file = file?;
+        //Mods and resource packs only have the folders they need
+        if !directory.exists() {
+            return Ok(());
+        }
+        //Sorted so files are listed in the same order on every file system, like in bundles
+        let mut files = fs::read_dir(directory.clone())?.collect::<Result<Vec<_>, _>>()?;
+        files.sort_by_key(|file| file.file_name());
+        for file in files {
             if file.file_type()?.is_file() {
                 output.push(file.path());
             } else {
@@ -223,14 +413,14 @@ impl DesktopLoader {
         let mut temp = Vec::new();
         DesktopLoader::find_files(directory.clone(), &mut temp, Vec::new())?;
         for file in temp {
-            output.push((Self::get_relative_path(directory.clone(), file.clone()),
-                         json::parse(String::from_utf8(fs::read(file)?)?.as_str())?));
+            output.push((content_pack::asset_name(&directory, &file),
+                         resource::read(&file)?.1));
         }
         return Ok(output);
     }
 }
 
-enum LoadingAsset {
+pub enum LoadingAsset {
     Texture((String, Arc<dyn GameTexture>)),
     Model((String, Arc<Mesh>))
 }
\ No newline at end of file
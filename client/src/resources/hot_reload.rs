@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+use anyhow::Error;
+use log::info;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use game::error;
+use game::language::language::LANGUAGE_MANAGER;
+use game::rendering::image_format;
+use game::resources::resource_loader::ResourceLoader;
+use interfaces::{json_path, resource};
+use interfaces::json_path::JsonPath;
+use crate::display::window::GameWindow;
+use crate::renderer::assets::AssetManager;
+use crate::renderer::shaders::ShaderManager;
+use crate::resources::content_pack::{self, ContentPack};
+use crate::resources::content_stack::{ContentKind, ContentStack};
+use crate::resources::desktop_loader::DesktopLoader;
+use crate::resources::loading::get_types;
+
+//How long a file has to stop changing before it's reloaded, so half written files aren't read
+const SETTLE_TIME: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReloadKind {
+    Texture,
+    Shader,
+    Translation,
+    Types
+}
+
+//A changed file that can be reloaded
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChangedFile {
+    pub kind: ReloadKind,
+    //Folder of the content pack the file's in, the watched folder or a mod or resource pack in it
+    pub pack: PathBuf,
+    pub path: PathBuf,
+}
+
+impl ChangedFile {
+    //What the file at path in the watched folder root is, if it can be reloaded.
+    //Mods in root/mods/<mod> and resource packs in root/resource_packs/<pack> are laid out like root.
+    pub fn find(root: &Path, path: &Path) -> Option<Self> {
+        let mut relative = path.strip_prefix(root).ok()?;
+        let mut pack = root.to_path_buf();
+        for packs in ["mods", "resource_packs"] {
+            if let Ok(inside) = relative.strip_prefix(packs) {
+                let pack_folder = inside.components().next()?;
+                pack = root.join(packs).join(pack_folder);
+                relative = inside.strip_prefix(pack_folder).ok()?;
+                break;
+            }
+        }
+
+        let mut path = path.to_path_buf();
+        let kind = match path.extension()?.to_str()? {
+            _ if relative.starts_with("assets/textures") && image_format::is_texture(&path.to_string_lossy()) => ReloadKind::Texture,
+            //Animation files reload the texture they're next to
+            "json" if relative.starts_with("assets/textures") => {
+                path = path.with_extension("");
+                ReloadKind::Texture
+            }
+            "wgsl" if relative.starts_with("shaders") => ReloadKind::Shader,
+            "json" if relative.starts_with("language/translations") => ReloadKind::Translation,
+            "json" if relative.starts_with("types") => ReloadKind::Types,
+            _ => return None
+        };
+        return Some(Self {
+            kind,
+            pack,
+            path
+        });
+    }
+
+    //What the file's content is listed as, with the folder it's in
+    pub fn content(&self) -> (ContentKind, &'static str) {
+        return match self.kind {
+            ReloadKind::Texture => (ContentKind::Texture, "assets/textures"),
+            ReloadKind::Shader => (ContentKind::Shader, "shaders"),
+            ReloadKind::Translation => (ContentKind::Translation, "language/translations"),
+            ReloadKind::Types => (ContentKind::Type, "types")
+        };
+    }
+
+    //Name of the texture, shader, translation file or types file, by its path in its folder like when it's loaded
+    pub fn name(&self) -> String {
+        return content_pack::asset_name(&self.pack.join(self.content().1), &self.path);
+    }
+}
+
+//Watches folders for files being created or changed
+pub struct FileWatcher {
+    //Stops watching when dropped
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    //Changed files and when they last changed
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl FileWatcher {
+    pub fn new(folders: &[PathBuf]) -> Result<Self, Error> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        for folder in folders {
+            watcher.watch(folder, RecursiveMode::Recursive)?;
+        }
+        return Ok(Self {
+            _watcher: watcher,
+            events,
+            pending: HashMap::new()
+        });
+    }
+
+    //Files that changed and haven't changed again for SETTLE_TIME, sorted
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        let now = Instant::now();
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                Ok(event) => if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                    for path in event.paths {
+                        self.pending.insert(path, now);
+                    }
+                },
+                Err(error) => error!("Error watching files: {}", error)
+            }
+        }
+
+        let mut output = Vec::new();
+        self.pending.retain(|path, changed| {
+            if now.duration_since(*changed) < SETTLE_TIME {
+                return true;
+            }
+            output.push(path.clone());
+            return false;
+        });
+        output.sort();
+        return output;
+    }
+}
+
+//Reloads textures, shaders, translations and types when their files change.
+//Files overridden by a later pack are skipped, since the later pack's version is the one in use.
+//Anything that fails to reload keeps its old version, and the error is printed.
+pub struct HotReloader {
+    roots: Vec<PathBuf>,
+    watcher: FileWatcher,
+    loader: Arc<Mutex<ResourceLoader>>,
+    content: ContentStack,
+}
+
+impl HotReloader {
+    //None if the content can't change or can't be watched
+    pub fn new(content: ContentStack, loader: Arc<Mutex<ResourceLoader>>) -> Option<Self> {
+        let roots = content.watched();
+        if roots.is_empty() {
+            return None;
+        }
+        return match FileWatcher::new(&roots) {
+            Ok(watcher) => Some(Self {
+                roots,
+                watcher,
+                loader,
+                content
+            }),
+            Err(error) => {
+                error!("Failed to watch resources, hot reloading is off: {}", error);
+                None
+            }
+        };
+    }
+
+    pub async fn update(&mut self, window: &GameWindow) {
+        for path in self.watcher.changed() {
+            //Deleted files keep what they loaded
+            if !path.is_file() {
+                continue;
+            }
+            let changed = match self.roots.iter().find_map(|root| ChangedFile::find(root, &path)) {
+                Some(changed) => changed,
+                None => continue
+            };
+            let pack = match self.content.pack_in(&changed.pack) {
+                Some(pack) => pack,
+                None => continue
+            };
+            //Translations are overridden key by key, so every pack's are merged again instead
+            let (kind, _) = changed.content();
+            let whole_file = kind != ContentKind::Translation;
+            if let Some(supplier) = self.content.overridden_by(kind, &changed.name(), &pack).filter(|_| whole_file) {
+                info!("Not reloading {}, {} overrides it", path.display(), supplier);
+                continue;
+            }
+            match self.reload(&changed, window).await {
+                Ok(()) => {
+                    if whole_file {
+                        self.content.set_supplier(kind, &changed.name(), &pack);
+                    }
+                    info!("Reloaded {}", path.display())
+                }
+                Err(error) => error!("Failed to reload {}, keeping the old version:\n{}", path.display(), error)
+            }
+        }
+    }
+
+    async fn reload(&self, changed: &ChangedFile, window: &GameWindow) -> Result<(), Error> {
+        match changed.kind {
+            ReloadKind::Texture => {
+                let (_, texture) = DesktopLoader::read_texture(changed.pack.join("assets/textures"), changed.path.clone())?;
+                AssetManager::reload_texture(texture);
+            }
+            ReloadKind::Shader => {
+                let source = fs::read_to_string(&changed.path)?;
+                let name = content_pack::asset_name(&changed.pack.join("shaders"), &changed.path);
+                ShaderManager::reload(name, source, window.device.clone(), window.config.clone()).await?;
+            }
+            ReloadKind::Translation => {
+                let (_, value) = resource::read(&changed.path)?;
+                if !value.is_object() {
+                    return Err(Error::msg("Translations must be an object"));
+                }
+                //In stack order, so keys later packs override stay overridden
+                LANGUAGE_MANAGER.write().unwrap().load_packs(self.content.language());
+            }
+            ReloadKind::Types => self.reload_types(&changed.path)?
+        }
+        return Ok(());
+    }
+
+    //Reloads every type in the file, and the types inheriting from them
+    fn reload_types(&self, path: &Path) -> Result<(), Error> {
+        let file = path.display().to_string();
+        let (source, found) = resource::read(path)?;
+        let top_level = found.is_array();
+        let mut errors = Vec::new();
+        let mut loader = self.loader.lock().unwrap();
+        for (index, object) in get_types(&file, found) {
+            let name = object.get("$name").and_then(|name| name.as_str()).map(str::to_string);
+            for (reloaded, result) in loader.reload(object) {
+                let error = match result {
+                    Ok(()) => continue,
+                    Err(error) => error
+                };
+                //Children are in other files, so their errors can't be located in this one
+                if name.as_deref() == Some(reloaded.as_str()) || reloaded.is_empty() {
+                    let parent = if top_level { JsonPath::new().index(index) } else { JsonPath::new() };
+                    errors.push(json_path::describe_error(&file, &source, &parent, &error));
+                } else {
+                    errors.push(format!("{} → {}", reloaded, error));
+                }
+            }
+        }
+        if errors.is_empty() {
+            return Ok(());
+        }
+        return Err(Error::msg(errors.join("\n")));
+    }
+}
@@ -1,4 +1,3 @@
-use std::fs;
 use std::future::Future;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
@@ -14,17 +13,22 @@ use game::resources::resource_loader::ResourceLoader;
 use game::resources::resource_manager::ResourceManager;
 use game::util::alloc_handle::AllocHandle;
 use game::util::task_manager::TaskManager;
+use interfaces::{json_path, resource};
+use interfaces::json_path::JsonPath;
 use crate::display::window::GameWindow;
 use crate::renderer::assets::AssetManager;
 use crate::renderer::shaders::ShaderManager;
 
-pub fn load(window: &GameWindow, content: &Box<dyn ContentPack>, resource_manager: &Arc<Mutex<ResourceManager>>, task_manager: &mut TaskManager) {
+//Returns the ResourceLoader loading the types, which can reload them afterwards
+pub fn load(window: &GameWindow, content: &Box<dyn ContentPack>, resource_manager: &Arc<Mutex<ResourceManager>>,
+            task_manager: &mut TaskManager) -> Arc<Mutex<ResourceLoader>> {
     let resource_loader = Arc::new(Mutex::new(ResourceLoader::new(resource_manager.clone())));
 
-    for json in content.types() {
-        let loader = task_manager.get_runtime(true).spawn(load_json(json.clone()));
+    for (_, json) in content.types() {
+        resource_loader.lock().unwrap().reserve();
+        let loader = task_manager.get_runtime(true).spawn(load_json_source(content.clone_boxed(), json.clone()));
         task_manager.queue(false,
-                           load_types(loader, content.get_relative("", &json), resource_loader.clone(),
+                           load_types(loader, json.to_string_lossy().to_string(), resource_loader.clone(),
                                                task_manager.get_runtime(false).clone()));
     }
 
@@ -33,6 +37,7 @@ pub fn load(window: &GameWindow, content: &Box<dyn ContentPack>, resource_manage
     task_manager.queue(false, ShaderManager::load(false, window.device.clone(), window.config.clone(),
                                                   ShaderManager::get_shaders(false, runtime.clone(), content.clone_boxed())));
     task_manager.queue(false, AssetManager::load(runtime.clone(), content.clone_boxed(), false));
+    return resource_loader;
 }
 
 pub fn finish_load(game: &mut Game, _: AllocHandle) {
@@ -59,35 +64,50 @@ pub async fn load_language(_handle: Handle, content: Box<dyn ContentPack>) -> Al
 }
 
 pub async fn load_json(path: PathBuf) -> Result<JsonValue, Error> {
-    return Ok(json::parse(String::from_utf8(fs::read(path)?)?.as_str())?);
+    return Ok(resource::read(&path)?.1);
 }
 
-pub async fn load_types(loading: impl Future<Output=Result<Result<JsonValue, Error>, JoinError>>,
+//Reads a JSON5 file from the content, keeping the text for finding where errors are
+pub async fn load_json_source(content: Box<dyn ContentPack>, path: PathBuf) -> Result<(String, JsonValue), Error> {
+    return content.read_json(&path);
+}
+
+pub async fn load_types(loading: impl Future<Output=Result<Result<(String, JsonValue), Error>, JoinError>>,
                     name: String, loader: Arc<Mutex<ResourceLoader>>, runtime: Handle) -> AllocHandle {
-    let found = match loading.await {
+    let (source, found) = match loading.await {
         Ok(value) => match value {
             Ok(value) => value,
             Err(error) => {
-                error!("Error loading JSON {}: {}", name, error);
+                //Read errors already start with the file
+                error!("Error loading JSON {}", error);
+                loader.lock().unwrap().release();
                 return AllocHandle::empty();
             }
         },
         Err(error) => {
             error!("Error joining thread: {}", error);
+            loader.lock().unwrap().release();
             return AllocHandle::empty();
         }
     };
 
     let mut join_set = JoinSet::new();
-    for found in get_types(found) {
-        join_set.spawn_on(ResourceLoader::spawn(loader.clone(), found), &runtime);
+    let top_level = found.is_array();
+    for (index, found) in get_types(&name, found) {
+        let task = ResourceLoader::spawn(loader.clone(), found);
+        join_set.spawn_on(async move { (index, task.await) }, &runtime);
     }
+    //Every type in this file is spawned, they can be waited on now
+    loader.lock().unwrap().release();
 
     while let Some(value) = join_set.join_next().await {
         match value {
-            Ok(result) => match result {
+            Ok((index, result)) => match result {
                 Ok(_) => {},
-                Err(error) => error!("Error loading JSON resource {}:\n{}", name, error)
+                Err(error) => {
+                    let parent = if top_level { JsonPath::new().index(index) } else { JsonPath::new() };
+                    error!("Error loading JSON resource {}", json_path::describe_error(&name, &source, &parent, &error))
+                }
             }
             Err(error) => error!("Error joining resource loading thread:\n{}", error)
         }
@@ -96,25 +116,26 @@ pub async fn load_types(loading: impl Future<Output=Result<Result<JsonValue, Err
     return AllocHandle::empty();
 }
 
-pub fn get_types(found: JsonValue) -> Vec<Object> {
+//The types in a types file with their index, it can be one type or an array of them
+pub fn get_types(name: &str, found: JsonValue) -> Vec<(usize, Object)> {
     let mut types = Vec::new();
     match found {
         JsonValue::Array(values) => {
-            for value in values {
+            for (index, value) in values.into_iter().enumerate() {
                 match value {
-                    JsonValue::Object(object) => types.push(object),
+                    JsonValue::Object(object) => types.push((index, object)),
                     _ => {
-                        error!("Tried to load JSON with unknown top level object: {}", value);
+                        error!("Error loading JSON resource {} → [{}] → Expected object, found {}", name, index, value);
                         continue;
                     }
                 }
             }
         }
-        JsonValue::Object(object) => types.push(object),
+        JsonValue::Object(object) => types.push((0, object)),
         _ => {
-            error!("Tried to load JSON with unknown top level object: {}", found);
+            error!("Error loading JSON resource {} → Expected array or object, found {}", name, found);
             return Vec::new();
         }
     }
     return types;
-}
\ No newline at end of file
+}
@@ -1,4 +1,14 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub mod archive_pack;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod bundle;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod bundle_pack;
 pub mod desktop_loader;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod hot_reload;
 pub mod loading;
 pub mod web_loader;
 pub mod content_pack;
+pub mod content_stack;
+pub mod schemas;
@@ -0,0 +1,48 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Error;
+use json::JsonValue;
+use game::mods::mods::ModManifest;
+use game::rendering::mesh::Mesh;
+use game::rendering::texture::Animation;
+use game::resources::resource_manager::ResourceManager;
+use game::settings::Settings;
+use interfaces::loading::JsonLoadable;
+use crate::settings::GameSettings;
+
+const SCHEMA_VERSION: &str = "http://json-schema.org/draft-07/schema#";
+
+//Every schema written by write_schemas, by file name without ".schema.json"
+pub fn schemas(resources: &ResourceManager) -> Vec<(String, JsonValue)> {
+    let mut output = vec!(
+        ("mesh".to_string(), Mesh::schema()),
+        ("animation".to_string(), Animation::schema()),
+        ("mod_manifest".to_string(), ModManifest::schema()),
+        ("settings".to_string(), Settings::schema()),
+        ("game_settings".to_string(), GameSettings::schema()),
+        ("types".to_string(), resources.types_schema())
+    );
+    for name in resources.registered_types() {
+        if let Some(schema) = resources.type_schema(&name) {
+            //Mods prefix types with the mod name, which isn't allowed in file names everywhere
+            output.push((format!("types/{}", name.replace(':', "_")), schema));
+        }
+    }
+    return output;
+}
+
+//Writes a JSON Schema for every JSON file the game loads into directory, returning the written files
+pub fn write_schemas(directory: &Path, resources: &ResourceManager) -> Result<Vec<PathBuf>, Error> {
+    let mut written = Vec::new();
+    for (name, schema) in schemas(resources) {
+        let mut output = json::object! { "$schema": SCHEMA_VERSION, "title": name.as_str() };
+        for (key, value) in schema.entries() {
+            output[key] = value.clone();
+        }
+        let path = directory.join(format!("{}.schema.json", name));
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(&path, json::stringify_pretty(output, 4))?;
+        written.push(path);
+    }
+    return Ok(written);
+}
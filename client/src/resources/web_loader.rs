@@ -1,48 +1,134 @@
-use std::path::PathBuf;
-#[cfg(target_arch = "wasm32")]
+use std::path::{Path, PathBuf};
+use anyhow::Error;
 use include_dir::Dir;
 use tokio::runtime::Handle;
-use tokio::task::JoinHandle;
+use tokio::task::{JoinHandle, JoinSet};
+use game::error;
 use game::language::language::LanguagePack;
 use game::rendering::AssetType;
-use crate::resources::content_pack::ContentPack;
+use crate::resources::content_pack::{self, ContentPack};
+use crate::resources::content_stack::ContentKind;
+use crate::resources::desktop_loader::{DesktopLoader, LoadingAsset};
 
+//Content embedded in the binary with include_dir, laid out like the resources folder.
+//The web client has no file system, so it loads the resources it was built with from here.
+//Files are listed by their path in the embedded folder, like types/cubes.json.
 #[derive(Clone)]
 pub struct WebLoader {
-
+    resources: &'static Dir<'static>,
 }
 
 impl ContentPack for WebLoader {
-    fn types(&self) -> Vec<PathBuf> {
-        todo!()
+    fn types(&self) -> Vec<(String, PathBuf)> {
+        return self.named("types", self.files("types", None));
     }
 
-    fn get_relative(&self, _subpath: &str, _path: &PathBuf) -> String {
-        todo!()
-    }
+    fn assets(&self, handle: &Handle, load_first: bool) -> JoinHandle<Vec<AssetType>> {
+        let mut join_set = JoinSet::new();
+        if !load_first {
+            for (name, path) in self.named("assets/models", DesktopLoader::models(self.files("assets/models", None))) {
+                let pack = self.clone();
+                join_set.spawn_on(async move { DesktopLoader::load_model(name, &path, &|file| pack.read(Path::new(file))) }, handle);
+            }
+        }
 
-    fn assets(&self, _handle: &Handle, _load_first: bool) -> JoinHandle<Vec<AssetType>> {
-        todo!()
+        let textures = if load_first {
+            DesktopLoader::textures(self.files("assets/textures/load_first", None))
+        } else {
+            DesktopLoader::textures(self.files("assets/textures", Some("load_first")))
+        };
+        for texture in textures {
+            let pack = self.clone();
+            join_set.spawn_on(async move { pack.load_image(texture) }, handle);
+        }
+        return handle.spawn(DesktopLoader::join_images(join_set, Vec::new()));
     }
 
     fn language(&self) -> Vec<LanguagePack> {
-        todo!()
+        let mut values = Vec::new();
+        for file in self.files("language/translations", None) {
+            match self.read_json(&file) {
+                Ok((_, value)) => values.push(value),
+                Err(error) => error!("Error loading translations: {}", error)
+            }
+        }
+        return vec!(LanguagePack::Translations(values));
     }
 
-    fn shaders(&self, _early: bool) -> Vec<PathBuf> {
-        todo!()
+    fn shaders(&self, early: bool) -> Vec<(String, PathBuf)> {
+        let shaders = if early {
+            self.files("shaders/load_first", None)
+        } else {
+            self.files("shaders", Some("load_first"))
+        };
+        return self.named("shaders", shaders);
     }
 
     fn clone_boxed(&self) -> Box<dyn ContentPack> {
-        todo!()
+        return Box::new(self.clone());
+    }
+
+    fn load_asset(&self, kind: ContentKind, name: &str) -> Result<Option<LoadingAsset>, Error> {
+        let folder = match kind {
+            ContentKind::Texture => "assets/textures",
+            ContentKind::Model => "assets/models",
+            _ => return Ok(None)
+        };
+        let files = match kind {
+            ContentKind::Texture => DesktopLoader::textures(self.files(folder, None)),
+            _ => DesktopLoader::models(self.files(folder, None))
+        };
+        return match files.into_iter().find(|file| content_pack::asset_name(Path::new(folder), file) == name) {
+            Some(file) if kind == ContentKind::Texture => self.load_image(file).map(Some),
+            Some(file) => DesktopLoader::load_model(name.to_string(), &file, &|file| self.read(Path::new(file))).map(Some),
+            None => Ok(None)
+        };
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        return match self.resources.get_file(path) {
+            Some(file) => Ok(file.contents().to_vec()),
+            None => Err(Error::msg(format!("{} isn't in the embedded resources", path.display())))
+        };
     }
 }
 
 impl WebLoader {
-    #[cfg(target_arch = "wasm32")]
-    pub fn new(resources: Dir<'_>) -> Self {
+    pub fn new(resources: &'static Dir<'static>) -> Self {
         return Self {
+            resources
+        };
+    }
 
+    //Files in the folder and the folders in it, sorted, skipping the ignored folder in it
+    fn files(&self, folder: &str, ignoring: Option<&str>) -> Vec<PathBuf> {
+        let mut output = Vec::new();
+        if let Some(found) = self.resources.get_dir(folder) {
+            let ignored = ignoring.map(|ignoring| Path::new(folder).join(ignoring));
+            Self::find_files(found, ignored.as_deref(), &mut output);
         }
+        output.sort();
+        return output;
     }
-}
\ No newline at end of file
+
+    fn find_files(directory: &Dir<'static>, ignored: Option<&Path>, output: &mut Vec<PathBuf>) {
+        output.extend(directory.files().map(|file| file.path().to_path_buf()));
+        for inner in directory.dirs() {
+            if Some(inner.path()) != ignored {
+                Self::find_files(inner, ignored, output);
+            }
+        }
+    }
+
+    //Files with their names, their path from folder without the extension
+    fn named(&self, folder: &str, files: Vec<PathBuf>) -> Vec<(String, PathBuf)> {
+        return files.into_iter().map(|file| (content_pack::asset_name(Path::new(folder), &file), file)).collect();
+    }
+
+    fn load_image(&self, texture: PathBuf) -> Result<LoadingAsset, Error> {
+        let name = content_pack::asset_name(Path::new("assets/textures"), &texture);
+        let sidecar = DesktopLoader::animation_path(&texture.display().to_string());
+        let animation = self.resources.get_file(&sidecar).map(|file| (sidecar.clone(), file.contents().to_vec()));
+        return Ok(LoadingAsset::Texture((name.clone(), DesktopLoader::decode_texture(name, &texture.display().to_string(), &self.read(&texture)?, animation)?)));
+    }
+}
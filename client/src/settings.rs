@@ -1,18 +1,27 @@
 use std::time::Duration;
 use macros::{JsonLoadable, JsonSaveable};
 use crate::input::manager::InputManager;
+use crate::renderer::atlas::AtlasSettings;
+use crate::renderer::memory::MemoryBudget;
 
 #[derive(JsonLoadable, JsonSaveable)]
 pub struct GameSettings {
     pub frames_per_second: Duration,
-    pub inputs: InputManager
+    pub inputs: InputManager,
+    //Content packs to override the others, lowest first. Mods and resource packs not listed keep their order below them.
+    pub content_packs: Vec<String>,
+    pub memory_budget: MemoryBudget,
+    pub atlas: AtlasSettings
 }
 
 impl Default for GameSettings {
     fn default() -> Self {
         return Self {
             frames_per_second: Duration::from_nanos(1000000000 / 60),
-            inputs: InputManager::default()
+            inputs: InputManager::default(),
+            content_packs: Vec::new(),
+            memory_budget: MemoryBudget::default(),
+            atlas: AtlasSettings::default()
         };
     }
 }
\ No newline at end of file
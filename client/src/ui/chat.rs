@@ -0,0 +1,123 @@
+use std::collections::VecDeque;
+use game::language::language::LanguageManager;
+use game::network::chat::{ChatChannel, ChatMessage};
+use game::rendering::mesh::Mesh;
+use crate::ui::components::text_input::TextInput;
+use crate::ui::text::{CHARACTER_SIZE, TextMesh};
+
+//Rendered lines kept for scrolling back
+const MAX_SCROLLBACK: usize = 200;
+//Pixels between the panel and the screen's edges
+const MARGIN: f32 = 8.0;
+//Widest the panel gets, in characters
+const MAX_COLUMNS: usize = 80;
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.5];
+
+//Chat box with scrollback. Messages are translated when they arrive, so changing
+//language only affects new messages.
+pub struct ChatPanel {
+    pub input: TextInput,
+    pub open: bool,
+    //Lines shown at once
+    pub height: usize,
+    lines: VecDeque<String>,
+    //Lines scrolled up from the newest message
+    scroll: usize,
+}
+
+impl ChatPanel {
+    pub fn new(height: usize) -> Self {
+        return Self {
+            input: TextInput::new(),
+            open: false,
+            height,
+            lines: VecDeque::new(),
+            scroll: 0,
+        };
+    }
+
+    //Returns the line the message was rendered as
+    pub fn receive(&mut self, message: &ChatMessage, language: &LanguageManager) -> &String {
+        if self.lines.len() == MAX_SCROLLBACK {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(message.render(language));
+        //Keep the same lines in view while scrolled back
+        if self.scroll > 0 {
+            self.scroll = (self.scroll + 1).min(self.max_scroll());
+        }
+        return self.lines.back().unwrap();
+    }
+
+    //Positive amounts scroll towards older messages
+    pub fn scroll(&mut self, amount: isize) {
+        self.scroll = (self.scroll as isize + amount).clamp(0, self.max_scroll() as isize) as usize;
+    }
+
+    //The lines in view, oldest first
+    pub fn visible(&self) -> impl Iterator<Item=&String> {
+        let end = self.lines.len() - self.scroll;
+        return self.lines.range(end.saturating_sub(self.height)..end);
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+        self.input.clear();
+    }
+
+    //Handles a typed character while open. Returns a message to send once one is submitted.
+    //"/w <player> <message>" whispers, "/room <message>" talks to the room, anything else is global.
+    pub fn receive_character(&mut self, character: char) -> Option<(ChatChannel, String)> {
+        let line = self.input.receive_character(character)?;
+        self.open = false;
+        self.scroll = 0;
+        return Self::parse(&line);
+    }
+
+    pub fn parse(line: &str) -> Option<(ChatChannel, String)> {
+        if let Some(rest) = line.strip_prefix("/w ") {
+            let rest = rest.trim_start();
+            let (player, text) = rest.split_once(char::is_whitespace)?;
+            return Some((ChatChannel::Whisper(player.to_string()), text.trim().to_string()));
+        }
+        if let Some(text) = line.strip_prefix("/room ") {
+            return Some((ChatChannel::Room, text.trim().to_string()));
+        }
+        return Some((ChatChannel::Global, line.trim().to_string()));
+    }
+
+    //The panel in the bottom left of the screen: the lines in view, and while open a background and the input line.
+    //Lines too wide for the panel are cut off. None when there's nothing to draw.
+    pub fn mesh(&self, screen: (u32, u32)) -> Option<Mesh> {
+        let columns = (((screen.0 as f32 - MARGIN * 2.0) / CHARACTER_SIZE.0).max(0.0) as usize).min(MAX_COLUMNS);
+        let bottom = screen.1 as f32 - MARGIN;
+        let mut mesh = TextMesh::new(screen);
+        if self.open {
+            let rows = self.height + 1;
+            mesh.rectangle((MARGIN, bottom - rows as f32 * CHARACTER_SIZE.1),
+                           (columns as f32 * CHARACTER_SIZE.0, rows as f32 * CHARACTER_SIZE.1), BACKGROUND_COLOR);
+            //Scrolled along so the cursor stays in view
+            let start = (self.input.cursor + 1).saturating_sub(columns);
+            let shown: String = self.input.text.chars().skip(start).take(columns).collect();
+            let top = bottom - CHARACTER_SIZE.1;
+            mesh.text(&shown, (MARGIN, top), TEXT_COLOR);
+            mesh.rectangle((MARGIN + (self.input.cursor - start) as f32 * CHARACTER_SIZE.0, top), (2.0, CHARACTER_SIZE.1), TEXT_COLOR);
+        }
+
+        let lines: Vec<&String> = self.visible().collect();
+        let bottom = if self.open { bottom - CHARACTER_SIZE.1 } else { bottom };
+        for (i, line) in lines.iter().enumerate() {
+            let line: String = line.chars().take(columns).collect();
+            mesh.text(&line, (MARGIN, bottom - (lines.len() - i) as f32 * CHARACTER_SIZE.1), TEXT_COLOR);
+        }
+        if mesh.is_empty() {
+            return None;
+        }
+        return Some(mesh.build());
+    }
+
+    fn max_scroll(&self) -> usize {
+        return self.lines.len().saturating_sub(self.height);
+    }
+}
@@ -1 +1,2 @@
-pub mod component;
\ No newline at end of file
+pub mod component;
+pub mod text_input;
\ No newline at end of file
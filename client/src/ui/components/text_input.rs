@@ -0,0 +1,109 @@
+use winit::event::VirtualKeyCode;
+
+//Previous lines kept for the up and down arrows
+const MAX_HISTORY: usize = 50;
+
+//Single line text box with a cursor and history, for the chat panel and the debug console.
+//The cursor is a character index, not a byte index.
+#[derive(Default)]
+pub struct TextInput {
+    pub text: String,
+    pub cursor: usize,
+    history: Vec<String>,
+    //Which history line is shown, None while editing a new line
+    history_index: Option<usize>,
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    //Handles a typed character, returns the line if it was submitted
+    pub fn receive_character(&mut self, character: char) -> Option<String> {
+        match character {
+            '\r' | '\n' => return self.submit(),
+            '\u{8}' => self.backspace(),
+            '\u{7f}' => self.delete(),
+            _ if character.is_control() => {}
+            _ => {
+                let index = self.byte_index(self.cursor);
+                self.text.insert(index, character);
+                self.cursor += 1;
+            }
+        }
+        return None;
+    }
+
+    //Handles keys that don't type anything, like the arrows
+    pub fn key(&mut self, key: VirtualKeyCode) {
+        match key {
+            VirtualKeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            VirtualKeyCode::Right => self.cursor = (self.cursor + 1).min(self.text.chars().count()),
+            VirtualKeyCode::Home => self.cursor = 0,
+            VirtualKeyCode::End => self.cursor = self.text.chars().count(),
+            VirtualKeyCode::Up => self.browse_history(true),
+            VirtualKeyCode::Down => self.browse_history(false),
+            _ => {}
+        }
+    }
+
+    //Clears the input and returns what was typed, unless it's empty
+    pub fn submit(&mut self) -> Option<String> {
+        let line = std::mem::take(&mut self.text);
+        self.cursor = 0;
+        self.history_index = None;
+        if line.trim().is_empty() {
+            return None;
+        }
+        if self.history.len() == MAX_HISTORY {
+            self.history.remove(0);
+        }
+        self.history.push(line.clone());
+        return Some(line);
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+        self.history_index = None;
+    }
+
+    fn backspace(&mut self) {
+        if self.cursor == 0 {
+            return;
+        }
+        self.cursor -= 1;
+        let index = self.byte_index(self.cursor);
+        self.text.remove(index);
+    }
+
+    fn delete(&mut self) {
+        if self.cursor < self.text.chars().count() {
+            let index = self.byte_index(self.cursor);
+            self.text.remove(index);
+        }
+    }
+
+    fn browse_history(&mut self, older: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+        self.history_index = match (self.history_index, older) {
+            (None, true) => Some(self.history.len() - 1),
+            (None, false) => return,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index + 1 < self.history.len() => Some(index + 1),
+            (Some(_), false) => None
+        };
+        self.text = match self.history_index {
+            Some(index) => self.history[index].clone(),
+            None => String::new()
+        };
+        self.cursor = self.text.chars().count();
+    }
+
+    fn byte_index(&self, cursor: usize) -> usize {
+        return self.text.char_indices().nth(cursor).map_or(self.text.len(), |(index, _)| index);
+    }
+}
@@ -1,5 +1,7 @@
+pub mod chat;
 pub mod components;
 pub mod constraints;
 pub mod layout;
 pub mod manager;
+pub mod text;
 pub mod window;
\ No newline at end of file
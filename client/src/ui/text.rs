@@ -0,0 +1,78 @@
+use game::rendering::mesh::{Mesh, Vertex};
+use game::rendering::vertex_layout::{VertexAttribute, VertexLayout};
+
+//Monospace font with ASCII from space to ~ in rows of 16, then a solid cell for backgrounds
+pub const FONT_TEXTURE: &str = "load_first/font";
+pub const TEXT_SHADER: &str = "load_first/text";
+//Pixels each character takes
+pub const CHARACTER_SIZE: (f32, f32) = (10.0, 20.0);
+
+const COLUMNS: u32 = 16;
+const ROWS: u32 = 6;
+const SOLID: u32 = 95;
+
+//Builds a UI mesh of text and rectangles, positioned in pixels from the top left of the screen
+pub struct TextMesh {
+    mesh: Mesh,
+    screen: (f32, f32),
+}
+
+impl TextMesh {
+    pub fn new(screen: (u32, u32)) -> Self {
+        let mut mesh = Mesh::new(TEXT_SHADER.to_string());
+        mesh.layout = VertexLayout::new(vec!(VertexAttribute::Position, VertexAttribute::TexCoords, VertexAttribute::Color));
+        return Self {
+            mesh,
+            screen: (screen.0.max(1) as f32, screen.1.max(1) as f32),
+        };
+    }
+
+    pub fn rectangle(&mut self, position: (f32, f32), size: (f32, f32), color: [f32; 4]) {
+        self.quad(position, size, SOLID, color);
+    }
+
+    //Draws a line of text, characters the font doesn't have are drawn as ?
+    pub fn text(&mut self, text: &str, position: (f32, f32), color: [f32; 4]) {
+        for (i, character) in text.chars().enumerate() {
+            if character == ' ' {
+                continue;
+            }
+            let cell = match character {
+                ' '..='~' => character as u32 - ' ' as u32,
+                _ => '?' as u32 - ' ' as u32
+            };
+            self.quad((position.0 + i as f32 * CHARACTER_SIZE.0, position.1), CHARACTER_SIZE, cell, color);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.mesh.indices.is_empty();
+    }
+
+    pub fn build(self) -> Mesh {
+        return self.mesh;
+    }
+
+    fn quad(&mut self, position: (f32, f32), size: (f32, f32), cell: u32, color: [f32; 4]) {
+        let (left, top) = self.clip(position);
+        let (right, bottom) = self.clip((position.0 + size.0, position.1 + size.1));
+        let u = (cell % COLUMNS) as f32 / COLUMNS as f32;
+        let v = (cell / COLUMNS) as f32 / ROWS as f32;
+        let (width, height) = (1.0 / COLUMNS as f32, 1.0 / ROWS as f32);
+
+        let start = self.mesh.vertexes.len() as u32;
+        for (position, tex_coords) in [([left, top], [u, v]), ([left, bottom], [u, v + height]),
+            ([right, bottom], [u + width, v + height]), ([right, top], [u + width, v])] {
+            let mut vertex = Vertex::new([position[0], position[1], 0.0], tex_coords);
+            vertex.set_color(color);
+            self.mesh.vertexes.push(vertex);
+        }
+        //Top left, bottom left, bottom right, then top left, bottom right, top right, which is counter-clockwise
+        self.mesh.indices.extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+    }
+
+    //Pixels from the top left to clip space
+    fn clip(&self, position: (f32, f32)) -> (f32, f32) {
+        return (position.0 / self.screen.0 * 2.0 - 1.0, 1.0 - position.1 / self.screen.1 * 2.0);
+    }
+}
@@ -0,0 +1,149 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use image::{Rgba, RgbaImage};
+use tokio::runtime::Builder;
+use game::rendering::{AssetType, GameTexture};
+use game::rendering::texture::Animation;
+use interfaces::loading::JsonLoadable;
+use client::resources::bundle;
+use client::resources::bundle_pack::BundlePack;
+use client::resources::content_pack::ContentPack;
+use client::resources::content_stack::ContentKind;
+use client::resources::desktop_loader::{DesktopLoader, LoadingAsset};
+
+mod common;
+
+fn directory(test: &str) -> PathBuf {
+    let directory = common::directory(&format!("animated-{}", test));
+    fs::create_dir_all(directory.join("assets/textures")).unwrap();
+    return directory;
+}
+
+//A 2x4 strip of two frames, red on top of blue, playing like animation says
+fn write_strip(root: &Path, animation: &str) {
+    let mut strip = RgbaImage::from_pixel(2, 4, Rgba([255, 0, 0, 255]));
+    for y in 2..4 {
+        for x in 0..2 {
+            strip.put_pixel(x, y, Rgba([0, 0, 255, 255]));
+        }
+    }
+    strip.save(root.join("assets/textures/water.png")).unwrap();
+    fs::write(root.join("assets/textures/water.png.json"), animation).unwrap();
+}
+
+fn texture(pack: &dyn ContentPack, name: &str) -> Arc<dyn GameTexture> {
+    return match pack.load_asset(ContentKind::Texture, name).unwrap() {
+        Some(LoadingAsset::Texture((_, texture))) => texture,
+        _ => panic!("Expected the texture {}", name)
+    };
+}
+
+fn texture_names(pack: &dyn ContentPack) -> Vec<String> {
+    let runtime = Builder::new_multi_thread().build().unwrap();
+    let mut names = Vec::new();
+    for asset in runtime.block_on(pack.assets(runtime.handle(), false)).unwrap() {
+        if let AssetType::Texture(textures) = asset {
+            names.extend(textures.into_keys());
+        }
+    }
+    return names;
+}
+
+#[test]
+fn missing_fields_make_a_strip_of_squares() {
+    let animation = Animation::load(&json::object! {}).unwrap();
+    assert_eq!(animation, Animation::default());
+    assert_eq!(animation.frame_size(16), (16, 16));
+    assert_eq!(animation.frame_count(16, 64).unwrap(), 4);
+
+    let grid = Animation::load(&json::object! { "frame_width": 8, "frame_height": 4 }).unwrap();
+    assert_eq!(grid.frame_count(16, 8).unwrap(), 4);
+}
+
+#[test]
+fn frames_that_dont_fit_are_errors() {
+    assert_eq!(Animation::default().frame_count(16, 20).unwrap_err().to_string(),
+               "A 16x20 image can't be cut into 16x16 frames");
+    let animation = Animation {
+        durations: vec!(100, 100, 100),
+        ..Default::default()
+    };
+    assert_eq!(animation.frame_count(4, 8).unwrap_err().to_string(), "There are 3 durations for 2 frames");
+}
+
+#[test]
+fn frames_play_for_their_durations_and_loop() {
+    let animation = Animation {
+        frame_time: 50,
+        durations: vec!(200),
+        ..Default::default()
+    };
+    let frame_at = |time| animation.frame_at(3, Duration::from_millis(time));
+    assert_eq!(frame_at(0), (0, 1, 0.0));
+    assert_eq!(frame_at(199), (0, 1, 0.0));
+    assert_eq!(frame_at(200), (1, 2, 0.0));
+    assert_eq!(frame_at(260), (2, 0, 0.0));
+    assert_eq!(frame_at(300), (0, 1, 0.0));
+}
+
+#[test]
+fn interpolated_frames_blend_into_the_next() {
+    let animation = Animation {
+        interpolate: true,
+        ..Default::default()
+    };
+    assert_eq!(animation.frame_at(2, Duration::from_millis(125)), (1, 0, 0.25));
+    assert_eq!(Animation::blend(&[0, 255, 100], &[255, 0, 100], 0.25), vec!(64, 191, 100));
+}
+
+#[test]
+fn textures_are_cut_into_frames() {
+    let root = directory("frames");
+    write_strip(&root, "{frame_time: 50, interpolate: true}");
+    let pack = DesktopLoader::new(root.clone());
+
+    let water = texture(&pack, "water");
+    assert_eq!(water.frames(), 2);
+    assert_eq!(water.dimensions(), (2, 2));
+    assert_eq!(&water.frame(0)[..4], &[255, 0, 0, 255]);
+    assert_eq!(&water.frame(1)[..4], &[0, 0, 255, 255]);
+    assert_eq!(water.data(), water.frame(0));
+    let animation = water.animation().unwrap();
+    assert_eq!((animation.frame_time, animation.interpolate), (50, true));
+
+    //The animation file isn't a texture of its own
+    assert_eq!(texture_names(&pack), vec!("water"));
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn bundles_keep_animations() {
+    let root = directory("bundle");
+    write_strip(&root, "{durations: [100, 300]}");
+    let path = root.join("resources.ctlbundle");
+    fs::write(&path, bundle::build(&root).unwrap()).unwrap();
+    let pack = BundlePack::open(path).unwrap();
+
+    let water = texture(&pack, "water");
+    assert_eq!(water.frames(), 2);
+    assert_eq!(water.animation().unwrap().durations, vec!(100, 300));
+    assert_eq!(texture_names(&pack), vec!("water"));
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn broken_animations_are_errors() {
+    let root = directory("broken");
+    write_strip(&root, "{frame_width: 3}");
+    let pack = DesktopLoader::new(root.clone());
+    let error = pack.load_asset(ContentKind::Texture, "water").err().unwrap().to_string();
+    assert!(error.ends_with("water.png.json → A 2x4 image can't be cut into 3x3 frames"), "{}", error);
+    assert!(bundle::build(&root).is_err());
+
+    write_strip(&root, "{frame_time: 'fast'}");
+    let error = pack.load_asset(ContentKind::Texture, "water").err().unwrap().to_string();
+    assert!(error.contains("water.png.json"), "{}", error);
+    fs::remove_dir_all(root).unwrap();
+}
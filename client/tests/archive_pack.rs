@@ -0,0 +1,132 @@
+use std::fs::{self, File};
+use std::io::{Cursor, Write};
+use std::path::Path;
+use image::{ImageOutputFormat, RgbaImage};
+use tokio::runtime::Builder;
+use zip::ZipWriter;
+use zip::write::FileOptions;
+use game::language::language::LanguageManager;
+use game::rendering::AssetType;
+use client::resources::archive_pack::ArchivePack;
+use client::resources::content_pack::ContentPack;
+use client::resources::content_stack::ContentStack;
+use client::resources::desktop_loader::DesktopLoader;
+
+mod common;
+
+fn png(width: u32) -> Vec<u8> {
+    let mut output = Cursor::new(Vec::new());
+    RgbaImage::new(width, 1).write_to(&mut output, ImageOutputFormat::Png).unwrap();
+    return output.into_inner();
+}
+
+fn archive(path: &Path, files: &[(&str, Vec<u8>)]) {
+    let mut writer = ZipWriter::new(File::create(path).unwrap());
+    for (name, contents) in files {
+        writer.start_file(*name, FileOptions::default()).unwrap();
+        writer.write_all(contents).unwrap();
+    }
+    writer.finish().unwrap();
+}
+
+fn textures(assets: Vec<AssetType>) -> Vec<(String, (u32, u32))> {
+    let mut output = Vec::new();
+    for asset in assets {
+        if let AssetType::Texture(textures) = asset {
+            output.extend(textures.into_iter().map(|(name, texture)| (name, texture.dimensions())));
+        }
+    }
+    output.sort();
+    return output;
+}
+
+#[test]
+fn archives_are_laid_out_like_folders() {
+    let directory = common::directory("archive-pack-layout");
+    let path = directory.join("example.ctlpak");
+    archive(&path, &[
+        ("manifest.json", b"{}".to_vec()),
+        ("types/items.json", b"[{$name: 'example:stick'}]".to_vec()),
+        ("types/tools/axes.json", b"[]".to_vec()),
+        ("shaders/cube.wgsl", b"//Cube".to_vec()),
+        ("shaders/load_first/ui.wgsl", b"//UI".to_vec()),
+        ("language/translations/en_us.json", b"{stick: 'Stick'}".to_vec()),
+        ("assets/textures/stick.png", png(2)),
+        ("assets/textures/load_first/logo.png", png(3)),
+    ]);
+    assert!(ArchivePack::is_archive(&path));
+    let pack = ArchivePack::open(path.clone()).unwrap();
+    assert_eq!(pack.name(), "example");
+    assert!(pack.contains("manifest.json"));
+
+    assert_eq!(pack.types(), vec!((String::from("items"), path.join("types/items.json")),
+                                  (String::from("tools/axes"), path.join("types/tools/axes.json"))));
+    assert_eq!(pack.read(&path.join("types/items.json")).unwrap(), b"[{$name: 'example:stick'}]");
+    assert_eq!(pack.shaders(true), vec!((String::from("load_first/ui"), path.join("shaders/load_first/ui.wgsl"))));
+    assert_eq!(pack.shaders(false), vec!((String::from("cube"), path.join("shaders/cube.wgsl"))));
+
+    let mut language = LanguageManager::new();
+    language.load_packs(pack.language());
+    assert_eq!(language.translate(&String::from("stick")), "Stick");
+
+    //Textures are decoded on the runtime they're loaded with, load_first ones separately
+    let runtime = Builder::new_multi_thread().build().unwrap();
+    assert_eq!(textures(runtime.block_on(pack.assets(runtime.handle(), true)).unwrap()), vec!((String::from("load_first/logo"), (3, 1))));
+    assert_eq!(textures(runtime.block_on(pack.assets(runtime.handle(), false)).unwrap()), vec!((String::from("stick"), (2, 1))));
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn zipped_folders_are_read_from_inside_the_folder() {
+    let directory = common::directory("archive-pack-folder");
+    let path = directory.join("faithful.zip");
+    archive(&path, &[
+        ("faithful/types/cubes.json", b"[]".to_vec()),
+        ("faithful/shaders/cube.wgsl", b"//Cube".to_vec()),
+    ]);
+    let pack = ArchivePack::open(path.clone()).unwrap();
+    assert_eq!(pack.types(), vec!((String::from("cubes"), path.join("faithful/types/cubes.json"))));
+    assert_eq!(pack.read(&pack.shaders(false)[0].1).unwrap(), b"//Cube");
+
+    //Archives without a single folder at the top are read from the top
+    let path = directory.join("flat.zip");
+    archive(&path, &[("types/cubes.json", b"[]".to_vec())]);
+    assert_eq!(ArchivePack::open(path.clone()).unwrap().types(), vec!((String::from("cubes"), path.join("types/cubes.json"))));
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn stacks_read_from_the_pack_that_listed_the_file() {
+    let directory = common::directory("archive-pack-stack");
+    let base = directory.join("base");
+    fs::create_dir_all(base.join("shaders")).unwrap();
+    fs::write(base.join("shaders/cube.wgsl"), "//Base cube").unwrap();
+    fs::write(base.join("shaders/sky.wgsl"), "//Base sky").unwrap();
+    let path = directory.join("faithful.ctlpak");
+    archive(&path, &[("shaders/cube.wgsl", b"//Faithful cube".to_vec())]);
+
+    let mut stack = ContentStack::new();
+    stack.push("base", Box::new(DesktopLoader::new(base)));
+    stack.push("faithful", Box::new(ArchivePack::open(path).unwrap()));
+    let shaders = stack.shaders(false).into_iter()
+        .map(|(name, path)| (name, String::from_utf8(stack.read(&path).unwrap()).unwrap())).collect::<Vec<_>>();
+    assert_eq!(shaders, vec!((String::from("cube"), String::from("//Faithful cube")), (String::from("sky"), String::from("//Base sky"))));
+    //Files that weren't listed aren't read
+    assert!(stack.read(&directory.join("base/shaders/other.wgsl")).is_err());
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn broken_archives_are_errors() {
+    let directory = common::directory("archive-pack-broken");
+    let path = directory.join("broken.zip");
+    fs::write(&path, "not a zip").unwrap();
+    match ArchivePack::open(path) {
+        Ok(_) => panic!("Opened a broken archive"),
+        Err(error) => assert!(error.to_string().starts_with("Failed to open"))
+    }
+    fs::remove_dir_all(directory).unwrap();
+}
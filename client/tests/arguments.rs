@@ -0,0 +1,42 @@
+use std::io::Read;
+use std::net::TcpListener;
+use client::arguments::Arguments;
+use game::network::packets::ClientPacket;
+
+fn parse(arguments: &[&str]) -> Result<Arguments, anyhow::Error> {
+    return Arguments::parse(arguments.iter().map(|argument| argument.to_string()));
+}
+
+#[test]
+fn arguments_are_parsed() {
+    assert_eq!(parse(&[]).unwrap(), Arguments::default());
+    let arguments = parse(&["--connect", "localhost:24680", "--name", "alice"]).unwrap();
+    assert_eq!(arguments.connect.as_deref(), Some("localhost:24680"));
+    assert_eq!(arguments.name, "alice");
+    assert!(parse(&["--help"]).unwrap().help);
+
+    assert!(parse(&["--connect"]).is_err());
+    assert!(parse(&["--name", " "]).is_err());
+    assert!(parse(&["--unknown", "1"]).is_err());
+}
+
+#[test]
+fn connect_joins_the_server() {
+    assert!(Arguments::default().network().unwrap().is_none());
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let network = parse(&["--connect", &address, "--name", "alice"]).unwrap().network().unwrap();
+    assert!(network.is_some());
+
+    let (mut stream, _) = listener.accept().unwrap();
+    let mut length = [0; 4];
+    stream.read_exact(&mut length).unwrap();
+    let mut packet = vec!(0; u32::from_le_bytes(length) as usize);
+    stream.read_exact(&mut packet).unwrap();
+    assert_eq!(ClientPacket::decode(&packet).unwrap(), ClientPacket::Join { name: "alice".to_string() });
+
+    //Nothing listens here once the listener is dropped
+    drop(listener);
+    assert!(parse(&["--connect", &address]).unwrap().network().is_err());
+}
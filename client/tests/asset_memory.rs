@@ -0,0 +1,118 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use client::renderer::assets::{AssetManager, LoadedAsset};
+use client::renderer::memory::AssetCache;
+use client::resources::bundle;
+use client::resources::bundle_pack::BundlePack;
+use client::resources::content_pack::ContentPack;
+use client::resources::content_stack::ContentKind;
+use client::resources::desktop_loader::{DesktopLoader, LoadingAsset};
+use game::rendering::GameTexture;
+
+mod common;
+
+fn resources() -> std::path::PathBuf {
+    return Path::new(env!("CARGO_MANIFEST_DIR")).join("../resources");
+}
+
+fn texture(manager: &mut AssetManager, name: &str) -> Arc<dyn GameTexture> {
+    return match manager.get(ContentKind::Texture, name) {
+        Some(LoadedAsset::Texture(texture)) => texture,
+        _ => panic!("Expected the {} texture", name)
+    };
+}
+
+fn names(unloaded: Vec<(ContentKind, String)>) -> Vec<String> {
+    return unloaded.into_iter().map(|(_, name)| name).collect();
+}
+
+#[test]
+fn least_recently_used_are_unloaded_first() {
+    let mut cache = AssetCache::new(100);
+    for name in ["first", "second", "third"] {
+        cache.insert(ContentKind::Texture, name.to_string(), Arc::new(()), 40);
+    }
+    assert_eq!(cache.used(), 120);
+    assert_eq!(names(cache.trim()), vec!("first"));
+
+    cache.get(ContentKind::Texture, "second");
+    cache.insert(ContentKind::Model, "fourth".to_string(), Arc::new(()), 40);
+    assert_eq!(names(cache.trim()), vec!("third"));
+    assert!(cache.contains(ContentKind::Texture, "second"));
+    assert!(cache.contains(ContentKind::Model, "fourth"));
+    assert_eq!(cache.used(), 80);
+}
+
+#[test]
+fn assets_in_use_stay_over_budget() {
+    let mut cache = AssetCache::new(100);
+    let used = Arc::new(());
+    cache.insert(ContentKind::Texture, "used".to_string(), used.clone(), 80);
+    cache.insert(ContentKind::Texture, "unused".to_string(), Arc::new(()), 80);
+
+    assert_eq!(names(cache.set_budget(0)), vec!("unused"));
+    assert_eq!(cache.used(), 80);
+    let stats = cache.stats("Test");
+    assert_eq!(stats.assets.len(), 1);
+    assert_eq!(stats.assets[0].reason(), "used by 1");
+    assert!(stats.to_string().starts_with("Test: 1 assets"));
+
+    drop(used);
+    assert_eq!(names(cache.trim()), vec!("used"));
+    assert!(cache.is_empty());
+    assert_eq!(cache.used(), 0);
+}
+
+#[test]
+fn replacing_an_asset_replaces_its_size() {
+    let mut cache = AssetCache::new(100);
+    cache.insert(ContentKind::Texture, "texture".to_string(), Arc::new(()), 40);
+    cache.insert(ContentKind::Texture, "texture".to_string(), Arc::new(()), 60);
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.used(), 60);
+    assert_eq!(cache.remove(ContentKind::Texture, "texture").map(|_| ()), Some(()));
+    assert_eq!(cache.used(), 0);
+}
+
+#[test]
+fn unloaded_textures_load_again_when_asked_for() {
+    let mut manager = AssetManager::new();
+    manager.set_content(Box::new(DesktopLoader::new(resources())));
+    manager.set_budget(0);
+    let dirt = texture(&mut manager, "testing/dirt");
+    assert_eq!(dirt.name(), "testing/dirt");
+    assert_eq!(manager.stats().assets[0].users, 1);
+
+    //Nothing's using it, so getting another texture unloads it
+    drop(dirt);
+    let grass = texture(&mut manager, "testing/grass_block_top");
+    let stats = manager.stats();
+    assert_eq!(stats.assets.iter().map(|asset| asset.name.as_str()).collect::<Vec<_>>(), vec!("testing/grass_block_top"));
+
+    let dirt = texture(&mut manager, "testing/dirt");
+    assert_eq!(dirt.dimensions(), (16, 16));
+    drop((dirt, grass));
+}
+
+#[test]
+fn bundles_load_single_assets_like_their_folder() {
+    let directory = common::directory("asset-memory");
+    let path = directory.join("resources.ctlbundle");
+    fs::write(&path, bundle::build(&resources()).unwrap()).unwrap();
+    let bundled = BundlePack::open(path).unwrap();
+    let folder = DesktopLoader::new(resources());
+
+    for pack in [&bundled as &dyn ContentPack, &folder] {
+        match pack.load_asset(ContentKind::Texture, "testing/dirt").unwrap() {
+            Some(LoadingAsset::Texture((name, texture))) => {
+                assert_eq!(name, "testing/dirt");
+                assert_eq!(texture.dimensions(), (16, 16));
+            }
+            _ => panic!("Expected the dirt texture")
+        }
+        assert!(pack.load_asset(ContentKind::Texture, "testing/missing").unwrap().is_none());
+        assert!(pack.load_asset(ContentKind::Type, "cubes").unwrap().is_none());
+    }
+    fs::remove_dir_all(directory).unwrap();
+}
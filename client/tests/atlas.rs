@@ -0,0 +1,194 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use image::RgbaImage;
+use client::renderer::atlas::{self, Atlas, AtlasGrouping, AtlasSettings, Placement, UvRect};
+use game::rendering::GameTexture;
+use game::rendering::mesh::Mesh;
+
+//A texture filled with one color
+#[derive(Debug)]
+struct Solid {
+    name: String,
+    size: (u32, u32),
+    data: Vec<u8>
+}
+
+impl Solid {
+    fn texture(name: &str, width: u32, height: u32, color: [u8; 4]) -> Arc<dyn GameTexture> {
+        return Arc::new(Self {
+            name: name.to_string(),
+            size: (width, height),
+            data: color.repeat((width * height) as usize)
+        });
+    }
+}
+
+impl Display for Solid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl GameTexture for Solid {
+    fn dimensions(&self) -> (u32, u32) {
+        return self.size;
+    }
+
+    fn name(&self) -> String {
+        return self.name.clone();
+    }
+
+    fn data(&self) -> &[u8] {
+        return &self.data;
+    }
+}
+
+fn sizes(textures: &[(&str, u32, u32)]) -> Vec<(String, u32, u32)> {
+    return textures.iter().map(|(name, width, height)| (name.to_string(), *width, *height)).collect();
+}
+
+fn overlaps(first: &Placement, second: &Placement, padding: u32) -> bool {
+    return first.page == second.page
+        && first.x < second.x + second.width + 2 * padding && second.x < first.x + first.width + 2 * padding
+        && first.y < second.y + second.height + 2 * padding && second.y < first.y + first.height + 2 * padding;
+}
+
+#[test]
+fn packing_is_the_same_in_any_order() {
+    let textures = sizes(&[("a", 16, 16), ("b", 32, 8), ("c", 16, 16), ("d", 8, 32), ("e", 64, 64), ("f", 16, 16)]);
+    let (placed, too_big) = atlas::pack(&textures, 128, 2);
+    assert!(too_big.is_empty());
+    let mut reversed = textures.clone();
+    reversed.reverse();
+    assert_eq!(atlas::pack(&reversed, 128, 2).0, placed);
+
+    //Tallest first, then widest, then by name
+    assert_eq!(placed.iter().map(|placement| placement.name.as_str()).collect::<Vec<_>>(), vec!("e", "d", "a", "c", "f", "b"));
+    assert_eq!((placed[0].x, placed[0].y), (2, 2));
+    for (index, first) in placed.iter().enumerate() {
+        assert!(first.x + first.width + 2 <= 128 && first.y + first.height + 2 <= 128);
+        for second in &placed[index + 1..] {
+            assert!(!overlaps(first, second, 2), "{} overlaps {}", first.name, second.name);
+        }
+    }
+}
+
+#[test]
+fn full_pages_start_new_ones() {
+    let textures = sizes(&[("a", 28, 28), ("b", 28, 28), ("c", 28, 28), ("d", 28, 28), ("e", 28, 28), ("huge", 80, 8)]);
+    let (placed, too_big) = atlas::pack(&textures, 64, 2);
+    assert_eq!(too_big, vec!("huge"));
+    assert_eq!(placed.iter().map(|placement| placement.page).collect::<Vec<_>>(), vec!(0, 0, 0, 0, 1));
+    assert_eq!(placed[4], Placement {
+        name: "e".to_string(),
+        page: 1,
+        x: 2,
+        y: 2,
+        width: 28,
+        height: 28
+    });
+}
+
+#[test]
+fn cells_are_aligned_for_mip_levels() {
+    let textures = sizes(&[("a", 5, 5), ("b", 7, 3), ("c", 16, 9), ("d", 1, 1)]);
+    let (placed, _) = atlas::pack(&textures, 64, 4);
+    for placement in &placed {
+        //Each cell starts on a multiple of 4, 4 pixels before its texture
+        assert_eq!(((placement.x - 4) % 4, (placement.y - 4) % 4), (0, 0), "{:?}", placement);
+    }
+    assert!(placed.iter().any(|placement| placement.x > 4));
+
+    let levels = |padding: u32| AtlasSettings { padding, ..Default::default() }.mip_levels();
+    assert_eq!([levels(0), levels(1), levels(2), levels(3), levels(4), levels(8)], [1, 1, 2, 2, 3, 4]);
+}
+
+#[test]
+fn atlases_pad_textures_with_their_edges() {
+    let settings = AtlasSettings {
+        padding: 1,
+        ..Default::default()
+    };
+    let built = Atlas::build(&settings, &[Solid::texture("blocks/red", 2, 2, [255, 0, 0, 255]),
+        Solid::texture("blocks/blue", 2, 2, [0, 0, 255, 255])]);
+    assert_eq!(built.pages().len(), 1);
+    let page = &built.pages()[0];
+    assert_eq!(page.dimensions(), (8, 4));
+    assert_eq!(page.name(), "atlas:0");
+
+    let blue = built.region("blocks/blue").unwrap();
+    assert_eq!((blue.x, blue.y), (1, 1));
+    assert_eq!(blue.uv, UvRect {
+        min: [0.125, 0.25],
+        max: [0.375, 0.75]
+    });
+    for (x, y) in [(0, 0), (3, 3), (1, 2), (0, 3)] {
+        assert_eq!(page.image.get_pixel(x, y).0, [0, 0, 255, 255]);
+    }
+    assert_eq!(page.image.get_pixel(4, 0).0, [255, 0, 0, 255]);
+    assert!(built.uv_rect("blocks/green").is_none());
+}
+
+#[test]
+fn mip_levels_keep_textures_apart() {
+    let settings = AtlasSettings {
+        padding: 4,
+        ..Default::default()
+    };
+    let colors = [("red", [255, 0, 0, 255]), ("green", [0, 255, 0, 255]), ("blue", [0, 0, 255, 255]), ("white", [255; 4])];
+    let built = Atlas::build(&settings, &colors.map(|(name, color)| Solid::texture(name, 5, 5, color)));
+    let page = &built.pages()[0];
+    assert_eq!(page.mips.len() as u32, settings.mip_levels() - 1);
+    assert_eq!(page.mip_levels().len(), 2);
+
+    //At the lowest level, every pixel of each cell is still only its own texture's color
+    let level = page.mips.len();
+    let (width, height) = page.level_size(level);
+    let lowest = RgbaImage::from_raw(width, height, page.mips[level - 1].clone()).unwrap();
+    let scale = 1 << level;
+    for (name, color) in colors {
+        let region = built.region(name).unwrap();
+        for y in (region.y - 4) / scale..(region.y + region.height + 4) / scale {
+            for x in (region.x - 4) / scale..(region.x + region.width + 4) / scale {
+                assert_eq!(lowest.get_pixel(x, y).0, color, "{} at {}, {}", name, x, y);
+            }
+        }
+    }
+}
+
+#[test]
+fn groups_get_their_own_pages() {
+    let settings = AtlasSettings {
+        grouping: AtlasGrouping::Folder,
+        ..Default::default()
+    };
+    let built = Atlas::build(&settings, &[Solid::texture("blocks/dirt", 4, 4, [0; 4]),
+        Solid::texture("items/stick", 4, 4, [0; 4]), Solid::texture("blocks/stone", 4, 4, [0; 4])]);
+    assert_eq!(built.pages().iter().map(|page| page.name()).collect::<Vec<_>>(), vec!("atlas:blocks/0", "atlas:items/0"));
+    assert_eq!(built.region("blocks/dirt").unwrap().page, built.region("blocks/stone").unwrap().page);
+    assert_eq!(built.region("items/stick").unwrap().page, 1);
+}
+
+#[test]
+fn meshes_are_moved_onto_their_region() {
+    let rect = UvRect {
+        min: [0.5, 0.25],
+        max: [1.0, 0.5]
+    };
+    let mesh = rect.remap_mesh(&Mesh::cube("shader".to_string()));
+    assert_eq!(mesh.vertexes.iter().map(|vertex| vertex.tex_coords()).collect::<Vec<_>>(),
+               vec!([0.5, 0.25], [1.0, 0.25], [0.5, 0.5], [1.0, 0.5]));
+    assert_eq!(mesh.vertexes[3].position(), [1.0, 1.0, 0.0]);
+}
+
+#[test]
+fn replaced_textures_are_drawn_over_the_old_ones() {
+    let mut built = Atlas::build(&AtlasSettings::default(), &[Solid::texture("dirt", 2, 2, [0; 4])]);
+    assert_eq!(built.replace(Solid::texture("dirt", 2, 2, [9; 4]).as_ref()), Some(0));
+    assert_eq!(built.pages()[0].image.get_pixel(0, 0).0, [9; 4]);
+
+    //Resized textures don't fit anymore
+    assert_eq!(built.replace(Solid::texture("dirt", 4, 4, [9; 4]).as_ref()), None);
+    assert!(built.region("dirt").is_none());
+    assert_eq!(built.replace(Solid::texture("stone", 2, 2, [9; 4]).as_ref()), None);
+}
@@ -0,0 +1,161 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use image::RgbaImage;
+use json::JsonValue;
+use tokio::runtime::Builder;
+use game::language::language::LanguagePack;
+use game::rendering::AssetType;
+use client::resources::bundle;
+use client::resources::bundle_pack::BundlePack;
+use client::resources::content_pack::ContentPack;
+use client::resources::content_stack::ContentKind;
+use client::resources::desktop_loader::DesktopLoader;
+
+mod common;
+
+//Textures and models sorted by name, with what they loaded as
+fn assets(pack: &dyn ContentPack, load_first: bool) -> Vec<String> {
+    let runtime = Builder::new_multi_thread().build().unwrap();
+    let mut output = Vec::new();
+    for asset in runtime.block_on(pack.assets(runtime.handle(), load_first)).unwrap() {
+        match asset {
+            AssetType::Texture(textures) => output.extend(textures.into_iter()
+                .map(|(name, texture)| format!("{} {:?} {}", name, texture.dimensions(), bundle::hash(texture.data())))),
+            AssetType::Model(models) => output.extend(models.into_iter()
+                .map(|(name, model)| format!("{} {} {}", name, model.shader, model.vertexes.len())))
+        }
+    }
+    output.sort();
+    return output;
+}
+
+fn translations(pack: &dyn ContentPack) -> Vec<JsonValue> {
+    return pack.language().into_iter().flat_map(|pack| match pack {
+        LanguagePack::Translations(values) => values
+    }).collect();
+}
+
+fn bundled(directory: &Path, resources: &Path) -> BundlePack {
+    let path = directory.join("resources.ctlbundle");
+    fs::write(&path, bundle::build(resources).unwrap()).unwrap();
+    return BundlePack::open(path).unwrap();
+}
+
+#[test]
+fn bundles_load_like_their_folder() {
+    let directory = common::directory("bundle-folder");
+    let resources = Path::new(env!("CARGO_MANIFEST_DIR")).join("../resources");
+    let folder = DesktopLoader::new(resources.clone());
+    let bundle = bundled(&directory, &resources);
+
+    let names = |listed: Vec<(String, PathBuf)>| listed.into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+    assert_eq!(names(bundle.types()), names(folder.types()));
+    for early in [true, false] {
+        assert_eq!(names(bundle.shaders(early)), names(folder.shaders(early)));
+        assert_eq!(assets(&bundle, early), assets(&folder, early));
+    }
+    for ((_, bundled), (_, file)) in bundle.types().into_iter().zip(folder.types()) {
+        assert_eq!(bundle.read_json(&bundled).unwrap().1, folder.read_json(&file).unwrap().1);
+    }
+    for ((_, bundled), (_, file)) in bundle.shaders(false).into_iter().zip(folder.shaders(false)) {
+        assert_eq!(bundle.read(&bundled).unwrap(), folder.read(&file).unwrap());
+    }
+    assert_eq!(translations(&bundle), translations(&folder));
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn entries_are_indexed_and_hashed() {
+    let directory = common::directory("bundle-index");
+    let resources = directory.join("resources");
+    common::write(&resources, "types/cubes.json", "[{$name: 'test:dirt', $type: 'Cube'}]");
+    common::write(&resources, "shaders/load_first/ui.wgsl", "//UI");
+    let texture = resources.join("assets/textures/dirt.png");
+    fs::create_dir_all(texture.parent().unwrap()).unwrap();
+    RgbaImage::new(4, 2).save(&texture).unwrap();
+
+    let built = bundle::build(&resources).unwrap();
+    let (entries, data) = bundle::read_index(&built).unwrap();
+    assert_eq!(entries.iter().map(|entry| (entry.kind, entry.name.as_str(), entry.path.as_str())).collect::<Vec<_>>(), vec!(
+        (ContentKind::Type, "cubes", "types/cubes.json"),
+        (ContentKind::Texture, "dirt", "assets/textures/dirt.png"),
+        (ContentKind::Shader, "load_first/ui", "shaders/load_first/ui.wgsl")));
+    assert_eq!((entries[1].width, entries[1].height, entries[1].length), (4, 2, 32));
+    assert_eq!(bundle::entry_data(&built, data, &entries[2]).unwrap(), b"//UI");
+
+    //Changed data doesn't match its hash
+    let mut corrupt = built.clone();
+    let last = corrupt.len() - 1;
+    corrupt[last] = b'!';
+    assert_eq!(bundle::entry_data(&corrupt, data, &entries[2]).unwrap_err().to_string(),
+               "shaders/load_first/ui.wgsl doesn't match its hash, the bundle is corrupt");
+    //Offsets and lengths past the end are errors, even ones that would overflow
+    for (offset, length) in [(0, built.len() as u64), (u64::MAX, 1), (1, u64::MAX)] {
+        let broken = bundle::BundleEntry { offset, length, ..entries[2].clone() };
+        assert_eq!(bundle::entry_data(&built, data, &broken).unwrap_err().to_string(),
+                   "shaders/load_first/ui.wgsl is outside the bundle");
+    }
+
+    let path = directory.join("resources.ctlbundle");
+    fs::write(&path, &built).unwrap();
+    let pack = BundlePack::open(path.clone()).unwrap();
+    assert_eq!(pack.types(), vec!((String::from("cubes"), path.join("types/cubes.json"))));
+    assert_eq!(pack.shaders(true), vec!((String::from("load_first/ui"), path.join("shaders/load_first/ui.wgsl"))));
+    assert!(pack.shaders(false).is_empty());
+    //Types are already parsed, so they have no text
+    let (source, value) = pack.read_json(&path.join("types/cubes.json")).unwrap();
+    assert_eq!((source.as_str(), value[0]["$name"].as_str()), ("", Some("test:dirt")));
+    assert!(pack.read(&path.join("types/missing.json")).is_err());
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn broken_files_fail_the_build() {
+    let directory = common::directory("bundle-broken");
+    common::write(&directory, "types/cubes.json", "[{$name: 'test:dirt',");
+    common::write(&directory, "assets/models/cube.json", "{vertexes: []}");
+    common::write(&directory, "assets/textures/dirt.png", "not a png");
+    let error = bundle::build(&directory).unwrap_err().to_string();
+    assert_eq!(error.lines().count(), 3, "{}", error);
+    assert!(error.contains("cubes.json:1:"), "{}", error);
+    assert!(error.contains("cube.json"), "{}", error);
+    assert!(error.contains("dirt.png"), "{}", error);
+
+    fs::write(directory.join("old.ctlbundle"), b"CTLBUNDL\x00\x00\x00\x00\x00\x00\x00\x00").unwrap();
+    match BundlePack::open(directory.join("old.ctlbundle")) {
+        Ok(_) => panic!("Opened a bundle from another version"),
+        Err(error) => assert!(error.to_string().ends_with("Bundle is version 0, expected 1. Build it again"), "{}", error)
+    }
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn bundles_older_than_their_folder_are_skipped() {
+    let directory = common::directory("bundle-current");
+    let resources = directory.join("resources");
+    common::write(&resources, "shaders/load_first/ui.wgsl", "");
+    let path = directory.join("resources.ctlbundle");
+    assert!(!bundle::is_current(&path, &resources));
+    let set_changed = |path: &Path, seconds: u64| fs::File::open(path).unwrap()
+        .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)).unwrap();
+
+    fs::write(&path, bundle::build(&resources).unwrap()).unwrap();
+    for folder in ["", "shaders", "shaders/load_first", "shaders/load_first/ui.wgsl"] {
+        set_changed(&resources.join(folder), 100);
+    }
+    set_changed(&path, 200);
+    assert!(bundle::is_current(&path, &resources));
+
+    //Editing a file, or adding or removing one, makes it out of date
+    set_changed(&resources.join("shaders/load_first/ui.wgsl"), 300);
+    assert!(!bundle::is_current(&path, &resources));
+    set_changed(&resources.join("shaders/load_first/ui.wgsl"), 100);
+    set_changed(&resources.join("shaders/load_first"), 300);
+    assert!(!bundle::is_current(&path, &resources));
+
+    fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,125 @@
+use client::ui::chat::ChatPanel;
+use client::ui::components::text_input::TextInput;
+use client::ui::text::{TEXT_SHADER, TextMesh};
+use game::language::language::LanguageManager;
+use game::network::chat::{ChatChannel, ChatContent, ChatMessage};
+use game::rendering::mesh::Mesh;
+use winit::event::VirtualKeyCode;
+
+fn message(text: &str) -> ChatMessage {
+    return ChatMessage {
+        channel: ChatChannel::Global,
+        sender: Some("alice".to_string()),
+        content: ChatContent::Text(text.to_string()),
+    };
+}
+
+#[test]
+fn text_input_edits_at_the_cursor() {
+    let mut input = TextInput::new();
+    for character in "héllo".chars() {
+        input.receive_character(character);
+    }
+    input.key(VirtualKeyCode::Left);
+    input.key(VirtualKeyCode::Left);
+    input.receive_character('\u{8}');
+    input.receive_character('y');
+    assert_eq!(input.text, "héylo");
+
+    assert_eq!(input.receive_character('\r'), Some("héylo".to_string()));
+    assert_eq!(input.text, "");
+    input.key(VirtualKeyCode::Up);
+    assert_eq!(input.text, "héylo");
+    input.key(VirtualKeyCode::Down);
+    assert_eq!(input.text, "");
+}
+
+#[test]
+fn chat_commands_pick_the_channel() {
+    assert_eq!(ChatPanel::parse("hi"), Some((ChatChannel::Global, "hi".to_string())));
+    assert_eq!(ChatPanel::parse("/room hi all"), Some((ChatChannel::Room, "hi all".to_string())));
+    assert_eq!(ChatPanel::parse("/w bob  hi there"), Some((ChatChannel::Whisper("bob".to_string()), "hi there".to_string())));
+    assert_eq!(ChatPanel::parse("/w bob"), None);
+}
+
+#[test]
+fn scrollback_keeps_view_while_scrolled() {
+    let language = LanguageManager::new();
+    let mut panel = ChatPanel::new(2);
+    for i in 0..5 {
+        panel.receive(&message(&i.to_string()), &language);
+    }
+    assert_eq!(panel.visible().cloned().collect::<Vec<_>>(), vec!("<alice> 3", "<alice> 4"));
+
+    panel.scroll(2);
+    assert_eq!(panel.visible().cloned().collect::<Vec<_>>(), vec!("<alice> 1", "<alice> 2"));
+    panel.receive(&message("5"), &language);
+    assert_eq!(panel.visible().cloned().collect::<Vec<_>>(), vec!("<alice> 1", "<alice> 2"));
+
+    panel.scroll(100);
+    assert_eq!(panel.visible().cloned().collect::<Vec<_>>(), vec!("<alice> 0", "<alice> 1"));
+}
+
+//Quads whose vertexes are all inside the rectangle, in clip space
+fn quads_in(mesh: &Mesh, left: f32, top: f32, right: f32, bottom: f32) -> usize {
+    return mesh.vertexes.chunks(4).filter(|quad| quad.iter().all(|vertex| {
+        let [x, y, _] = vertex.position();
+        return x >= left && x <= right && y <= top && y >= bottom;
+    })).count();
+}
+
+#[test]
+fn closed_panel_draws_its_lines() {
+    let language = LanguageManager::new();
+    let mut panel = ChatPanel::new(2);
+    assert!(panel.mesh((800, 600)).is_none());
+
+    panel.receive(&message("hi"), &language);
+    let mesh = panel.mesh((800, 600)).unwrap();
+    assert_eq!(mesh.shader, TEXT_SHADER);
+    //A quad for each character that isn't a space
+    let characters = panel.visible().next().unwrap().chars().filter(|character| *character != ' ').count();
+    assert_eq!(mesh.vertexes.len(), characters * 4);
+    assert_eq!(mesh.indices.len(), characters * 6);
+    //All in the bottom left
+    assert_eq!(quads_in(&mesh, -1.0, 0.0, 0.0, -1.0), characters);
+}
+
+#[test]
+fn open_panel_draws_the_input_and_cursor() {
+    let mut panel = ChatPanel::new(2);
+    panel.open = true;
+    for character in "ab".chars() {
+        panel.receive_character(character);
+    }
+    let mesh = panel.mesh((800, 600)).unwrap();
+    //Background, two characters and the cursor
+    assert_eq!(mesh.vertexes.len(), 4 * 4);
+    for vertex in &mesh.vertexes {
+        let [x, y, _] = vertex.position();
+        assert!((-1.0..=1.0).contains(&x) && (-1.0..=1.0).contains(&y));
+    }
+}
+
+#[test]
+fn long_lines_are_cut_off() {
+    let language = LanguageManager::new();
+    let mut panel = ChatPanel::new(2);
+    panel.receive(&message(&"x".repeat(500)), &language);
+    let mesh = panel.mesh((200, 600)).unwrap();
+    //Every quad stays on screen
+    assert_eq!(quads_in(&mesh, -1.0, 1.0, 1.0, -1.0), mesh.vertexes.len() / 4);
+    assert!(mesh.vertexes.len() / 4 <= (200 - 16) / 10);
+}
+
+#[test]
+fn unknown_characters_are_drawn() {
+    let mut mesh = TextMesh::new((100, 100));
+    mesh.text("é", (0.0, 0.0), [1.0; 4]);
+    let question = {
+        let mut mesh = TextMesh::new((100, 100));
+        mesh.text("?", (0.0, 0.0), [1.0; 4]);
+        mesh.build()
+    };
+    assert_eq!(mesh.build().vertexes, question.vertexes);
+}
@@ -0,0 +1,24 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+//Only std is used here, so the server and ctl-validate tests can include this file too
+
+static NEXT_DIRECTORY: AtomicUsize = AtomicUsize::new(0);
+
+//An empty folder for a test's files. Each call gets its own, so tests running at once don't share one.
+pub fn directory(test: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("ctl-{}-{}-{}", test, std::process::id(),
+                                                     NEXT_DIRECTORY.fetch_add(1, Ordering::Relaxed)));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    return directory;
+}
+
+//Writes a file under root, making the folders it's in
+#[allow(dead_code)]
+pub fn write(root: &Path, path: &str, contents: impl AsRef<[u8]>) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
@@ -0,0 +1,140 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use image::RgbaImage;
+use tokio::runtime::Builder;
+use game::language::language::LanguageManager;
+use game::rendering::AssetType;
+use client::resources::content_pack::ContentPack;
+use client::resources::content_stack::{ContentKind, ContentStack};
+use client::resources::desktop_loader::DesktopLoader;
+use client::resources::hot_reload::ChangedFile;
+
+mod common;
+
+fn texture(root: &Path, path: &str, width: u32) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    RgbaImage::new(width, 1).save(path).unwrap();
+}
+
+//A base game, a mod and a resource pack, each overriding some of the ones before
+fn packs(test: &str) -> (PathBuf, ContentStack) {
+    let directory = common::directory(&format!("content-stack-{}", test));
+    let base = directory.join("base");
+    common::write(&base, "types/cubes.json", "[]");
+    common::write(&base, "types/items.json", "[]");
+    common::write(&base, "shaders/cube.wgsl", "");
+    common::write(&base, "shaders/load_first/ui.wgsl", "");
+    common::write(&base, "language/translations/en_us.json", "{hello: 'Hello', bye: 'Bye'}");
+    texture(&base, "assets/textures/dirt.png", 1);
+    texture(&base, "assets/textures/stone.png", 1);
+
+    let example = directory.join("example");
+    common::write(&example, "types/items.json", "[]");
+    common::write(&example, "types/example/tools.json", "[]");
+    texture(&example, "assets/textures/dirt.png", 2);
+
+    let faithful = directory.join("faithful");
+    common::write(&faithful, "shaders/load_first/ui.wgsl", "");
+    common::write(&faithful, "language/translations/en_us.json", "{hello: 'Howdy'}");
+    texture(&faithful, "assets/textures/dirt.png", 3);
+
+    let mut stack = ContentStack::new();
+    stack.push("base", Box::new(DesktopLoader::new(base)));
+    stack.push("example", Box::new(DesktopLoader::new(example)));
+    stack.push("faithful", Box::new(DesktopLoader::new(faithful)));
+    return (directory, stack);
+}
+
+#[test]
+fn later_packs_override_earlier_ones() {
+    let (directory, stack) = packs("override");
+    let types = stack.types();
+    assert_eq!(types.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), vec!("cubes", "example/tools", "items"));
+    assert_eq!(types[2].1, directory.join("example/types/items.json"));
+    assert_eq!(stack.supplier(ContentKind::Type, "cubes"), Some(String::from("base")));
+    assert_eq!(stack.supplier(ContentKind::Type, "items"), Some(String::from("example")));
+
+    assert_eq!(stack.shaders(true), vec!((String::from("load_first/ui"), directory.join("faithful/shaders/load_first/ui.wgsl"))));
+    assert_eq!(stack.shaders(false), vec!((String::from("cube"), directory.join("base/shaders/cube.wgsl"))));
+    assert_eq!(stack.supplier(ContentKind::Shader, "load_first/ui"), Some(String::from("faithful")));
+
+    let runtime = Builder::new_multi_thread().build().unwrap();
+    let assets = runtime.block_on(stack.assets(runtime.handle(), false)).unwrap();
+    let textures = assets.iter().find_map(|asset| match asset {
+        AssetType::Texture(textures) => Some(textures),
+        _ => None
+    }).unwrap();
+    assert_eq!(textures["dirt"].dimensions(), (3, 1));
+    assert_eq!(textures["stone"].dimensions(), (1, 1));
+    assert_eq!(stack.supplier(ContentKind::Texture, "dirt"), Some(String::from("faithful")));
+
+    //Translations override key by key, so the base game's other keys are kept
+    let mut language = LanguageManager::new();
+    language.load_packs(stack.language());
+    assert_eq!(language.translate(&String::from("hello")), "Howdy");
+    assert_eq!(language.translate(&String::from("bye")), "Bye");
+    assert_eq!(stack.supplier(ContentKind::Translation, "bye"), Some(String::from("base")));
+    assert!(stack.suppliers().contains(&(ContentKind::Translation, String::from("hello"), String::from("faithful"))));
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn packs_can_be_reordered() {
+    let (directory, mut stack) = packs("reorder");
+    //Listed packs go on top in order, unknown ones are ignored
+    stack.reorder(&[String::from("faithful"), String::from("missing"), String::from("example")]);
+    assert_eq!(stack.names(), vec!("base", "faithful", "example"));
+    let texture_pack = stack.clone();
+    assert_eq!(stack.types()[2].1, directory.join("example/types/items.json"));
+
+    stack.reorder(&[String::from("example"), String::from("faithful")]);
+    assert_eq!(stack.names(), vec!("base", "example", "faithful"));
+    //Clones keep their own order
+    assert_eq!(texture_pack.names(), vec!("base", "faithful", "example"));
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn nested_folders_are_watched_once() {
+    let mut stack = ContentStack::new();
+    stack.push("base", Box::new(DesktopLoader::new(PathBuf::from("/resources"))));
+    stack.push("example", Box::new(DesktopLoader::new(PathBuf::from("/resources/mods/example"))));
+    stack.push("outside", Box::new(DesktopLoader::new(PathBuf::from("/packs/outside"))));
+    assert_eq!(stack.watched(), vec!(PathBuf::from("/resources"), PathBuf::from("/packs/outside")));
+}
+
+#[test]
+fn overridden_files_are_found_for_hot_reloading() {
+    let (directory, stack) = packs("reloading");
+    stack.types();
+    let runtime = Builder::new_multi_thread().build().unwrap();
+    runtime.block_on(stack.assets(runtime.handle(), false)).unwrap();
+
+    //Each pack's folder is watched on its own here
+    let changed = |path: &str| {
+        let root = directory.join(path.split('/').next().unwrap());
+        let changed = ChangedFile::find(&root, &directory.join(path)).unwrap();
+        return (changed.content().0, changed.name(), stack.pack_in(&changed.pack).unwrap());
+    };
+    //The base game's dirt is drawn with the resource pack's, so editing it changes nothing
+    let (kind, name, pack) = changed("base/assets/textures/dirt.png");
+    assert_eq!((kind, name.as_str(), pack.as_str()), (ContentKind::Texture, "dirt", "base"));
+    assert_eq!(stack.overridden_by(kind, &name, &pack), Some(String::from("faithful")));
+    let (kind, name, pack) = changed("faithful/assets/textures/dirt.png");
+    assert_eq!(stack.overridden_by(kind, &name, &pack), None);
+    let (kind, name, pack) = changed("example/types/items.json");
+    assert_eq!(stack.overridden_by(kind, &name, &pack), None);
+    let (kind, name, pack) = changed("base/types/items.json");
+    assert_eq!(stack.overridden_by(kind, &name, &pack), Some(String::from("example")));
+
+    //New files in a higher pack override the lower one's from then on
+    let (kind, name, pack) = changed("example/assets/textures/stone.png");
+    assert_eq!(stack.overridden_by(kind, &name, &pack), None);
+    stack.set_supplier(kind, &name, &pack);
+    assert_eq!(stack.overridden_by(ContentKind::Texture, "stone", "base"), Some(String::from("example")));
+
+    fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,59 @@
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use client::resources::hot_reload::{ChangedFile, FileWatcher, ReloadKind};
+
+mod common;
+
+fn kind(path: &str) -> Option<(ReloadKind, String)> {
+    let root = Path::new("/resources");
+    return ChangedFile::find(root, &root.join(path))
+        .map(|changed| (changed.kind, changed.pack.to_str().unwrap().to_string()));
+}
+
+#[test]
+fn changed_files_are_sorted_by_kind() {
+    assert_eq!(kind("assets/textures/testing/dirt.png"), Some((ReloadKind::Texture, String::from("/resources"))));
+    assert_eq!(kind("assets/textures/testing/stone.ktx2"), Some((ReloadKind::Texture, String::from("/resources"))));
+    assert_eq!(kind("shaders/load_first/ui.wgsl"), Some((ReloadKind::Shader, String::from("/resources"))));
+    assert_eq!(kind("language/translations/en_us.json"), Some((ReloadKind::Translation, String::from("/resources"))));
+    assert_eq!(kind("types/cubes.json"), Some((ReloadKind::Types, String::from("/resources"))));
+    //Mods are laid out like the resources folder
+    assert_eq!(kind("mods/example/types/items.json"), Some((ReloadKind::Types, String::from("/resources/mods/example"))));
+    assert_eq!(kind("mods/example/assets/textures/item.png"), Some((ReloadKind::Texture, String::from("/resources/mods/example"))));
+    assert_eq!(kind("resource_packs/faithful/shaders/ui.wgsl"), Some((ReloadKind::Shader, String::from("/resources/resource_packs/faithful"))));
+    //Animation files reload the texture they're next to
+    assert_eq!(ChangedFile::find(Path::new("/resources"), Path::new("/resources/assets/textures/water.png.json")).unwrap().path,
+               Path::new("/resources/assets/textures/water.png"));
+
+    //Editor backups, files in the wrong folder and files outside the root aren't reloaded
+    assert_eq!(kind("types/cubes.json~"), None);
+    assert_eq!(kind("assets/textures/notes.txt"), None);
+    assert_eq!(kind("mods/example/manifest.json"), None);
+    assert_eq!(kind("types.json"), None);
+    assert_eq!(ChangedFile::find(Path::new("/resources"), Path::new("/other/types/cubes.json")), None);
+}
+
+#[test]
+fn files_are_reported_once_they_settle() {
+    let directory = common::directory("hot-reload");
+    fs::create_dir_all(directory.join("types")).unwrap();
+    let mut watcher = FileWatcher::new(std::slice::from_ref(&directory)).unwrap();
+
+    let file = directory.join("types/cubes.json");
+    fs::write(&file, "[]").unwrap();
+    fs::write(&file, "[{}]").unwrap();
+    let start = Instant::now();
+    let mut changed = Vec::new();
+    while changed.is_empty() && start.elapsed() < Duration::from_secs(5) {
+        thread::sleep(Duration::from_millis(20));
+        changed = watcher.changed();
+    }
+    //Both writes come out as one change, after it's stopped changing for a bit
+    assert_eq!(changed, vec!(file.clone()));
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    thread::sleep(Duration::from_millis(200));
+    assert!(watcher.changed().is_empty());
+    fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,276 @@
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+use image::{ImageOutputFormat, Rgba, RgbaImage};
+use client::renderer::assets::AssetManager;
+use client::renderer::atlas::{Atlas, AtlasSettings};
+use client::resources::bundle;
+use client::resources::bundle_pack::BundlePack;
+use client::resources::content_pack::ContentPack;
+use client::resources::content_stack::ContentKind;
+use client::resources::desktop_loader::{DesktopLoader, LoadingAsset};
+use game::rendering::GameTexture;
+use game::rendering::block_compression::{self, BlockFormat};
+use game::rendering::image_format::{self, ReadTexture, TextureFormat};
+use game::rendering::texture::MissingTexture;
+
+mod common;
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+
+fn image() -> RgbaImage {
+    let mut image = RgbaImage::from_pixel(2, 2, Rgba(RED));
+    image.put_pixel(1, 1, Rgba([0, 0, 255, 255]));
+    return image;
+}
+
+fn encode(format: ImageOutputFormat) -> Vec<u8> {
+    let mut bytes = Cursor::new(Vec::new());
+    image().write_to(&mut bytes, format).unwrap();
+    return bytes.into_inner();
+}
+
+//A KTX2 of one 2x2 level in the Vulkan format, with its data supercompressed by the scheme
+fn ktx2(format: u32, scheme: u32, data: &[u8]) -> Vec<u8> {
+    return ktx2_levels(format, scheme, (2, 2), &[data]);
+}
+
+//A KTX2 with the levels, largest first
+fn ktx2_levels(format: u32, scheme: u32, (width, height): (u32, u32), levels: &[&[u8]]) -> Vec<u8> {
+    let mut bytes = vec!(0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n');
+    //Format, type size, width, height, depth, layers, faces, levels and supercompression
+    for value in [format, 1, width, height, 0, 0, 1, levels.len() as u32, scheme] {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    //No data format descriptor, key/values or supercompression data
+    bytes.extend_from_slice(&[0; 32]);
+    //Each level's offset and length, then its uncompressed length
+    let mut offset = bytes.len() as u64 + 24 * levels.len() as u64;
+    for level in levels {
+        for value in [offset, level.len() as u64, level.len() as u64] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        offset += level.len() as u64;
+    }
+    for level in levels {
+        bytes.extend_from_slice(level);
+    }
+    return bytes;
+}
+
+//A BC1 block of red and blue, with the top row going from red to blue and the rest red
+fn bc1_block() -> Vec<u8> {
+    let mut block = Vec::new();
+    block.extend_from_slice(&0xF800u16.to_le_bytes());
+    block.extend_from_slice(&0x001Fu16.to_le_bytes());
+    block.extend_from_slice(&0b11_10_01_00u32.to_le_bytes());
+    return block;
+}
+
+//Writes BC7 fields from the lowest bit up
+fn bc7_block(fields: &[(u32, u128)]) -> Vec<u8> {
+    let (mut value, mut position) = (0u128, 0);
+    for (bits, field) in fields {
+        value |= field << position;
+        position += bits;
+    }
+    assert_eq!(position, 128);
+    return value.to_le_bytes().to_vec();
+}
+
+//A zstd frame holding data as one uncompressed block
+fn zstd(data: &[u8]) -> Vec<u8> {
+    let mut bytes = vec!(0x28, 0xB5, 0x2F, 0xFD, 0x20, data.len() as u8);
+    bytes.extend_from_slice(&(1 | (data.len() as u32) << 3).to_le_bytes()[..3]);
+    bytes.extend_from_slice(data);
+    return bytes;
+}
+
+fn directory(test: &str) -> PathBuf {
+    let directory = common::directory(&format!("image-formats-{}", test));
+    fs::create_dir_all(directory.join("assets/textures")).unwrap();
+    return directory;
+}
+
+#[test]
+fn formats_are_found_by_their_contents_first() {
+    let png = encode(ImageOutputFormat::Png);
+    assert_eq!(TextureFormat::detect("dirt.tga", &png).unwrap(), TextureFormat::Png);
+    assert_eq!(TextureFormat::detect("dirt.png", &encode(ImageOutputFormat::Jpeg(90))).unwrap(), TextureFormat::Jpeg);
+    assert_eq!(TextureFormat::detect("dirt.png", &ktx2(37, 0, &[0; 16])).unwrap(), TextureFormat::Ktx2);
+    //TGAs have no signature
+    assert_eq!(TextureFormat::detect("dirt.TGA", b"anything").unwrap(), TextureFormat::Tga);
+    assert_eq!(TextureFormat::detect("dirt.txt", b"anything").unwrap_err().to_string(),
+               "Unknown image format, use PNG, JPEG, TGA or KTX2");
+    assert_eq!(TextureFormat::detect("dirt.png", b"GIF89a").unwrap_err().to_string(),
+               "Gif textures aren't supported, use PNG, JPEG, TGA or KTX2");
+
+    assert!(image_format::is_texture("blocks/dirt.jpeg"));
+    assert!(!image_format::is_texture("blocks/dirt.png.json"));
+}
+
+#[test]
+fn every_format_decodes_to_rgba() {
+    for (file, bytes) in [("dirt.png", encode(ImageOutputFormat::Png)), ("dirt.tga", encode(ImageOutputFormat::Tga))] {
+        assert_eq!(image_format::decode(file, &bytes).unwrap(), image(), "{}", file);
+    }
+    let jpeg = image_format::decode("dirt.jpg", &encode(ImageOutputFormat::Jpeg(90))).unwrap();
+    assert_eq!(jpeg.dimensions(), (2, 2));
+
+    let rgba = image().into_raw();
+    let bgra = rgba.chunks(4).flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]]).collect::<Vec<_>>();
+    assert_eq!(image_format::decode("dirt.ktx2", &ktx2(37, 0, &rgba)).unwrap(), image());
+    assert_eq!(image_format::decode("dirt.ktx2", &ktx2(50, 0, &bgra)).unwrap(), image());
+    assert_eq!(image_format::decode("dirt.ktx2", &ktx2(43, 2, &zstd(&rgba))).unwrap(), image());
+}
+
+#[test]
+fn unsupported_ktx2s_are_errors() {
+    let error = |bytes: &[u8]| image_format::decode("dirt.ktx2", bytes).unwrap_err().to_string();
+    assert_eq!(error(&ktx2(141, 0, &[0; 16])),
+               "KTX2 format BC5_UNORM_BLOCK isn't supported, use R8G8B8A8, B8G8R8A8, BC1_RGBA, BC2, BC3 or BC7");
+    assert_eq!(error(&ktx2(0, 1, &[0; 16])), "KTX2 textures need a format, Basis Universal isn't supported");
+    assert_eq!(error(&ktx2(37, 0, &[0; 8])), "KTX2 image data is too short for 2x2 RGBA8");
+    assert!(error(&ktx2(37, 0, &[0; 16])[..90]).starts_with("Invalid KTX2"));
+}
+
+#[test]
+fn block_compressed_ktx2s_stay_compressed() {
+    let (block, small) = (bc1_block(), bc1_block());
+    let bytes = ktx2_levels(134, 0, (4, 4), &[&block, &small]);
+    let image = match image_format::read("dirt.ktx2", &bytes).unwrap() {
+        ReadTexture::Compressed(image) => image,
+        ReadTexture::Pixels(_) => panic!("Expected the KTX2 to stay compressed")
+    };
+    assert_eq!((image.format, image.width, image.height), (BlockFormat::Bc1, 4, 4));
+    assert_eq!(image.levels, vec!(block.clone(), small));
+    assert_eq!((image.level_size(1), image.level_size(2), image.size()), ((2, 2), (1, 1), 16));
+
+    //Decoding them gives the largest level's pixels
+    let decoded = image_format::decode("dirt.ktx2", &bytes).unwrap();
+    assert_eq!(decoded.dimensions(), (4, 4));
+    let row = (0..4).map(|x| decoded.get_pixel(x, 0).0).collect::<Vec<_>>();
+    assert_eq!(row, vec!(RED, [0, 0, 255, 255], [170, 0, 85, 255], [85, 0, 170, 255]));
+    assert_eq!(decoded.get_pixel(3, 3).0, RED);
+
+    //Zstd works on every level
+    let bytes = ktx2_levels(146, 2, (4, 4), &[&zstd(&[0; 16]), &zstd(&[0; 16])]);
+    assert!(matches!(image_format::read("dirt.ktx2", &bytes).unwrap(), ReadTexture::Compressed(image) if image.levels.len() == 2));
+
+    let error = |bytes: &[u8]| image_format::read("dirt.ktx2", bytes).unwrap_err().to_string();
+    assert_eq!(error(&ktx2(134, 0, &block)),
+               "Invalid KTX2: Block compressed textures have to be a multiple of 4 pixels wide and high, not 2x2");
+    assert_eq!(error(&ktx2_levels(138, 0, (4, 4), &[&block])), "Invalid KTX2: Level 0 is 8 bytes, Bc3 at 4x4 is 16");
+}
+
+#[test]
+fn blocks_decode_like_the_gpu_would() {
+    //BC1 blocks with the first color not above the second have transparent black instead of a fourth color
+    let mut block = bc1_block();
+    block.swap(0, 2);
+    block.swap(1, 3);
+    let pixels = block_compression::decode_block(BlockFormat::Bc1, &block);
+    assert_eq!(pixels[..4], [[0, 0, 255, 255], RED, [127, 0, 127, 255], [0, 0, 0, 0]]);
+
+    //BC2 has 4 bits of alpha for each pixel, then a BC1 block that always has four colors
+    let mut block = vec!(0x0F, 0x07, 0, 0, 0, 0, 0, 0);
+    block.extend(bc1_block());
+    let pixels = block_compression::decode_block(BlockFormat::Bc2, &block);
+    assert_eq!(pixels.map(|pixel| pixel[3])[..4], [255, 0, 119, 0]);
+
+    //BC3 interpolates alpha between two values, with 3 bit indices
+    let mut block = vec!(255, 0);
+    block.extend_from_slice(&(1u64 << 3 | 2 << 6 | 7 << 9).to_le_bytes()[..6]);
+    block.extend(bc1_block());
+    let pixels = block_compression::decode_block(BlockFormat::Bc3, &block);
+    assert_eq!(pixels.map(|pixel| pixel[3])[..5], [255, 0, 218, 36, 255]);
+    assert_eq!(pixels[1], [0, 0, 255, 0]);
+
+    //BC7 mode 6: a 7 bit RGBA endpoint pair with a p-bit each and 4 bit indices, the first one bit shorter
+    let block = bc7_block(&[(7, 1 << 6), (7, 127), (7, 0), (7, 0), (7, 127), (7, 0), (7, 0), (7, 127), (7, 127), (1, 1), (1, 0),
+        (3, 0), (4, 15), (4, 8), (52, 0)]);
+    let pixels = block_compression::decode_block(BlockFormat::Bc7, &block);
+    assert_eq!(pixels[..4], [[255, 1, 1, 255], [0, 254, 0, 254], [120, 135, 0, 254], [255, 1, 1, 255]]);
+    //Blocks without a mode are transparent black
+    assert_eq!(block_compression::decode_block(BlockFormat::Bc7, &[0; 16]), [[0; 4]; 16]);
+
+    //Images decode block by block, left to right then top to bottom
+    let image = block_compression::decode_image(BlockFormat::Bc1, 8, 4, &[bc1_block(), vec!(0; 8)].concat());
+    assert_eq!(&image[4 * 4..4 * 4 + 4], &[0, 0, 0, 255]);
+    assert_eq!(&image[4..8], &[0, 0, 255, 255]);
+}
+
+#[test]
+fn compressed_textures_are_only_decoded_when_needed() {
+    let root = directory("compressed");
+    let textures = root.join("assets/textures");
+    let bytes = ktx2_levels(134, 0, (4, 4), &[&bc1_block(), &bc1_block()]);
+    fs::write(textures.join("ore.ktx2"), &bytes).unwrap();
+    fs::write(textures.join("lava.ktx2"), &bytes).unwrap();
+    fs::write(textures.join("lava.ktx2.json"), "{frame_width: 4, frame_height: 2}").unwrap();
+    let pack = DesktopLoader::new(root.clone());
+
+    let texture = match pack.load_asset(ContentKind::Texture, "ore").unwrap() {
+        Some(LoadingAsset::Texture((_, texture))) => texture,
+        _ => panic!("Expected the ore texture")
+    };
+    assert_eq!(texture.compressed().map(|image| image.levels.len()), Some(2));
+    assert_eq!(&texture.data()[..4], &RED);
+    //They aren't packed into the atlas, so they stay compressed on the GPU
+    assert!(Atlas::build(&AtlasSettings::default(), std::slice::from_ref(&texture)).region("ore").is_none());
+
+    //Animated ones are cut into frames
+    match pack.load_asset(ContentKind::Texture, "lava").unwrap() {
+        Some(LoadingAsset::Texture((_, texture))) => assert_eq!((texture.frames(), texture.compressed().is_none()), (2, true)),
+        _ => panic!("Expected the lava texture")
+    }
+
+    //Bundles keep every level
+    let bundled = root.with_extension("ctlbundle");
+    fs::write(&bundled, bundle::build(&root).unwrap()).unwrap();
+    let bundle = BundlePack::open(bundled.clone()).unwrap();
+    match bundle.load_asset(ContentKind::Texture, "ore").unwrap() {
+        Some(LoadingAsset::Texture((_, bundled))) => assert_eq!(bundled.compressed(), texture.compressed()),
+        _ => panic!("Expected the bundled ore texture")
+    }
+    fs::remove_file(bundled).unwrap();
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn broken_textures_are_errors() {
+    let root = directory("broken");
+    let textures = root.join("assets/textures");
+    fs::write(textures.join("broken.png"), b"not a png").unwrap();
+    fs::write(textures.join("stone.jpg"), encode(ImageOutputFormat::Jpeg(90))).unwrap();
+    fs::write(textures.join("README.txt"), b"Not a texture").unwrap();
+    let pack = DesktopLoader::new(root.clone());
+
+    let error = pack.load_asset(ContentKind::Texture, "broken").err().unwrap().to_string();
+    assert!(error.ends_with("broken.png → Format error decoding Png: Invalid PNG signature."), "{}", error);
+    match pack.load_asset(ContentKind::Texture, "stone").unwrap() {
+        Some(LoadingAsset::Texture((_, texture))) => assert_eq!(texture.dimensions(), (2, 2)),
+        _ => panic!("Expected the stone texture")
+    }
+    assert!(pack.load_asset(ContentKind::Texture, "README").unwrap().is_none());
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn missing_textures_are_drawn_as_a_checkerboard() {
+    let missing = MissingTexture::new();
+    assert_eq!(missing.dimensions(), (16, 16));
+    let pixel = |x: usize, y: usize| &missing.data()[(y * 16 + x) * 4..(y * 16 + x) * 4 + 4];
+    assert_eq!(pixel(0, 0), &[255, 0, 255, 255]);
+    assert_eq!(pixel(8, 0), &[0, 0, 0, 255]);
+    assert_eq!(pixel(15, 15), &[255, 0, 255, 255]);
+
+    let root = directory("missing");
+    fs::write(root.join("assets/textures/broken.png"), b"not a png").unwrap();
+    let mut manager = AssetManager::new();
+    manager.set_content(Box::new(DesktopLoader::new(root.clone())));
+    for name in ["nothing", "broken"] {
+        assert_eq!(manager.get_texture(name).name(), MissingTexture::NAME);
+    }
+    fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,37 @@
+use winit::event::VirtualKeyCode;
+use client::input::manager::InputManager;
+use interfaces::loading::JsonLoadable;
+use interfaces::saving::JsonSaveable;
+
+#[test]
+fn actions_are_bound_by_default() {
+    let inputs = InputManager::default();
+    let saved = inputs.save();
+    assert_eq!(saved["KEYS"]["W:0"], "move_forwards");
+    assert_eq!(saved["KEYS"]["Left Shift:0"], "move_down");
+    assert_eq!(saved["KEYS"]["Function 3:0"], "print_memory");
+    assert!(inputs.map(0, VirtualKeyCode::W).is_some());
+    //Holding a modifier falls back to the unmodified binding
+    assert!(inputs.map(4, VirtualKeyCode::W).is_some());
+    assert!(inputs.map(0, VirtualKeyCode::Q).is_none());
+}
+
+#[test]
+fn loaded_bindings_replace_the_defaults() {
+    let inputs = InputManager::load(&json::object! {
+        "KEYS": {
+            "Up Arrow:0": "move_forwards",
+            "W:0": "move_up",
+            "Q:0": "not_an_action"
+        }
+    }).unwrap();
+    let saved = inputs.save();
+    assert_eq!(saved["KEYS"]["Up Arrow:0"], "move_forwards");
+    assert_eq!(saved["KEYS"]["W:0"], "move_up");
+    assert!(!saved["KEYS"].has_key("Space:0"));
+    assert!(!saved["KEYS"].has_key("Q:0"));
+    assert_eq!(saved["KEYS"]["S:0"], "move_backwards");
+
+    //Saved bindings load back the same
+    assert_eq!(InputManager::load(&saved).unwrap().save(), saved);
+}
@@ -0,0 +1,264 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use tokio::runtime::Builder;
+use game::rendering::AssetType;
+use game::rendering::mesh::{Mesh, MeshPart, Vertex};
+use game::rendering::model_import::{self, ModelFormat};
+use game::rendering::vertex_layout::{VertexAttribute, VertexLayout};
+use client::resources::bundle;
+use client::resources::bundle_pack::BundlePack;
+use client::resources::content_pack::ContentPack;
+use client::resources::content_stack::ContentKind;
+use client::resources::desktop_loader::{DesktopLoader, LoadingAsset};
+
+mod common;
+
+//One triangle's positions, texture coordinates and u16 indices
+fn triangle_buffer() -> Vec<u8> {
+    let mut buffer = Vec::new();
+    for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0] {
+        buffer.extend_from_slice(&value.to_le_bytes());
+    }
+    for index in [0u16, 1, 2] {
+        buffer.extend_from_slice(&index.to_le_bytes());
+    }
+    buffer.extend_from_slice(&[0; 2]);
+    return buffer;
+}
+
+//A glTF with a node moved one to the right, drawing the triangle twice: once with a texture, once with a named material
+fn gltf(buffer: &str) -> String {
+    return format!(r#"{{
+        "asset": {{"version": "2.0"}},
+        "scene": 0,
+        "scenes": [{{"nodes": [0]}}],
+        "nodes": [{{"mesh": 0, "translation": [1, 0, 0]}}],
+        "meshes": [{{"primitives": [
+            {{"attributes": {{"POSITION": 0, "TEXCOORD_0": 1}}, "indices": 2, "material": 0}},
+            {{"attributes": {{"POSITION": 0}}, "indices": 2, "material": 1}}
+        ]}}],
+        "materials": [{{"pbrMetallicRoughness": {{"baseColorTexture": {{"index": 0}}}}}}, {{"name": "stone"}}],
+        "textures": [{{"source": 0}}],
+        "images": [{{"uri": "../../textures/blocks/dirt.png"}}],
+        "buffers": [{{"byteLength": 68{}}}],
+        "bufferViews": [
+            {{"buffer": 0, "byteOffset": 0, "byteLength": 36}},
+            {{"buffer": 0, "byteOffset": 36, "byteLength": 24}},
+            {{"buffer": 0, "byteOffset": 60, "byteLength": 6}}
+        ],
+        "accessors": [
+            {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0]}},
+            {{"bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC2"}},
+            {{"bufferView": 2, "componentType": 5123, "count": 3, "type": "SCALAR"}}
+        ]
+    }}"#, buffer);
+}
+
+fn data_uri() -> String {
+    return format!(r#", "uri": "data:application/octet-stream;base64,{}""#, STANDARD.encode(triangle_buffer()));
+}
+
+//The glTF as a GLB, with the buffer in its binary chunk
+fn glb() -> Vec<u8> {
+    let mut json = gltf("").into_bytes();
+    json.resize(json.len().next_multiple_of(4), b' ');
+    let buffer = triangle_buffer();
+    let mut bytes = b"glTF".to_vec();
+    for value in [2, 12 + 8 + json.len() as u32 + 8 + buffer.len() as u32, json.len() as u32, 0x4E4F534A] {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes.extend_from_slice(&json);
+    for value in [buffer.len() as u32, 0x004E4942] {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes.extend_from_slice(&buffer);
+    return bytes;
+}
+
+const OBJ: &str = "mtllib crate.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+usemtl wood
+f 1/1/1 2/2/1 3/3/1
+usemtl metal
+f 1/1/1 3/3/1 4/4/1
+";
+
+const MTL: &str = "newmtl wood
+map_Kd textures/blocks/planks.png
+newmtl metal
+Kd 0.5 0.5 0.5
+";
+
+fn no_files(uri: &str) -> Result<Vec<u8>, anyhow::Error> {
+    return Err(anyhow::Error::msg(format!("No file {}", uri)));
+}
+
+fn directory(test: &str) -> PathBuf {
+    let directory = common::directory(&format!("model-import-{}", test));
+    fs::create_dir_all(directory.join("assets/models/props")).unwrap();
+    fs::write(directory.join("assets/models/props/crate.obj"), OBJ).unwrap();
+    fs::write(directory.join("assets/models/props/crate.mtl"), MTL).unwrap();
+    fs::write(directory.join("assets/models/props/triangle.gltf"), gltf(r#", "uri": "triangle.bin""#)).unwrap();
+    fs::write(directory.join("assets/models/props/triangle.bin"), triangle_buffer()).unwrap();
+    return directory;
+}
+
+fn model(pack: &dyn ContentPack, name: &str) -> Arc<Mesh> {
+    return match pack.load_asset(ContentKind::Model, name).unwrap() {
+        Some(LoadingAsset::Model((_, model))) => model,
+        _ => panic!("Expected the model {}", name)
+    };
+}
+
+fn model_names(pack: &dyn ContentPack) -> Vec<String> {
+    let runtime = Builder::new_multi_thread().build().unwrap();
+    let mut names = Vec::new();
+    for asset in runtime.block_on(pack.assets(runtime.handle(), false)).unwrap() {
+        if let AssetType::Model(models) = asset {
+            names.extend(models.into_keys());
+        }
+    }
+    names.sort();
+    return names;
+}
+
+fn check_crate(mesh: &Mesh) {
+    assert_eq!(mesh.shader, model_import::DEFAULT_SHADER);
+    assert_eq!(mesh.parts, vec!(
+        MeshPart { start: 0, count: 3, texture: "blocks/planks".to_string() },
+        MeshPart { start: 3, count: 3, texture: "metal".to_string() }
+    ));
+    assert_eq!(mesh.indices.len(), 6);
+    assert_eq!(mesh.layout, VertexLayout::new(vec!(VertexAttribute::Position, VertexAttribute::TexCoords, VertexAttribute::Normal)));
+    //Texture coordinates are flipped, since OBJ's start at the bottom
+    assert!(mesh.vertexes.iter().any(|vertex| vertex.position() == [1.0, 1.0, 0.0] && vertex.tex_coords() == [1.0, 0.0]));
+    assert!(mesh.vertexes.iter().all(|vertex| vertex.normal() == [0.0, 0.0, 1.0]));
+}
+
+#[test]
+fn models_are_found_by_their_extension() {
+    assert_eq!(ModelFormat::from_path("props/crate.OBJ"), Some(ModelFormat::Obj));
+    assert_eq!(ModelFormat::from_path("props/robot.glb"), Some(ModelFormat::Gltf));
+    assert_eq!(ModelFormat::from_path("props/robot.json"), None);
+    assert!(model_import::is_model("props/robot.json"));
+    assert!(!model_import::is_model("props/crate.mtl"));
+    assert!(!model_import::is_model("props/robot.bin"));
+
+    assert_eq!(model_import::texture_name("../../textures/blocks/dirt.png"), "blocks/dirt");
+    assert_eq!(model_import::texture_name("C:\\art\\textures\\stone.tga"), "stone");
+    assert_eq!(model_import::texture_name("images/wood.jpg"), "wood");
+}
+
+#[test]
+fn gltf_primitives_become_parts() {
+    for (file, bytes) in [("triangle.gltf", gltf(&data_uri()).into_bytes()), ("triangle.glb", glb())] {
+        let mesh = model_import::import(file, &bytes, &no_files).unwrap();
+        assert_eq!(mesh.parts, vec!(
+            MeshPart { start: 0, count: 3, texture: "blocks/dirt".to_string() },
+            MeshPart { start: 3, count: 3, texture: "stone".to_string() }
+        ), "{}", file);
+        assert_eq!(mesh.indices, vec!(0, 1, 2, 3, 4, 5));
+        assert_eq!(mesh.layout, VertexLayout::default());
+        //Moved by the node
+        assert_eq!(mesh.vertexes[1].position(), [2.0, 0.0, 0.0]);
+        assert_eq!(mesh.vertexes[2].tex_coords(), [0.0, 1.0]);
+        //The second primitive has no texture coordinates, so they're zero
+        assert_eq!(mesh.vertexes[5].tex_coords(), [0.0, 0.0]);
+    }
+}
+
+#[test]
+fn obj_materials_become_parts() {
+    let read = |uri: &str| match uri {
+        "crate.mtl" => Ok(MTL.as_bytes().to_vec()),
+        _ => no_files(uri)
+    };
+    check_crate(&model_import::import("crate.obj", OBJ.as_bytes(), &read).unwrap());
+}
+
+#[test]
+fn packs_import_models_next_to_their_files() {
+    let root = directory("desktop");
+    let pack = DesktopLoader::new(root.clone());
+    check_crate(&model(&pack, "props/crate"));
+    let triangle = model(&pack, "props/triangle");
+    assert_eq!(triangle.parts.len(), 2);
+    assert_eq!(triangle.vertexes[1].position(), [2.0, 0.0, 0.0]);
+    //Materials and buffers aren't models of their own
+    assert_eq!(model_names(&pack), vec!("props/crate", "props/triangle"));
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn bundles_keep_imported_models() {
+    let root = directory("bundle");
+    let path = root.join("resources.ctlbundle");
+    fs::write(&path, bundle::build(&root).unwrap()).unwrap();
+    let pack = BundlePack::open(path).unwrap();
+    check_crate(&model(&pack, "props/crate"));
+    assert_eq!(model(&pack, "props/triangle").vertexes, model(&DesktopLoader::new(root.clone()), "props/triangle").vertexes);
+    assert_eq!(model_names(&pack), vec!("props/crate", "props/triangle"));
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn broken_models_are_errors() {
+    let root = directory("broken");
+    fs::remove_file(root.join("assets/models/props/triangle.bin")).unwrap();
+    let lines = gltf(&data_uri()).replace(r#""indices": 2, "material": 1"#, r#""mode": 1"#);
+    fs::write(root.join("assets/models/props/lines.gltf"), lines).unwrap();
+    let pack = DesktopLoader::new(root.clone());
+
+    let error = pack.load_asset(ContentKind::Model, "props/triangle").err().unwrap().to_string();
+    assert!(error.contains("triangle.gltf → "), "{}", error);
+    let error = pack.load_asset(ContentKind::Model, "props/lines").err().unwrap().to_string();
+    assert!(error.ends_with("lines.gltf → Mesh unnamed has Lines, only triangles are supported"), "{}", error);
+    assert!(bundle::build(&root).is_err());
+
+    let error = model_import::import("crate.obj", b"v 0 0 0\nf 1 2 3\n", &no_files).unwrap_err().to_string();
+    assert!(error.starts_with("crate.obj → "), "{}", error);
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn parts_split_into_their_own_meshes() {
+    let mut mesh = Mesh::new("shader".to_string());
+    for x in 0..4 {
+        mesh.vertexes.push(Vertex::new([x as f32, 0.0, 0.0], [0.0, 0.0]));
+    }
+    mesh.indices = vec!(0, 1, 2, 2, 3, 0, 9);
+    assert_eq!(mesh.split().len(), 1);
+    assert_eq!(mesh.split()[0].1, "");
+
+    mesh.parts = vec!(
+        MeshPart { start: 0, count: 3, texture: String::new() },
+        MeshPart { start: 3, count: 4, texture: "stone".to_string() }
+    );
+    let parts = mesh.split();
+    assert_eq!(parts[0].0.vertexes, mesh.vertexes[0..3].to_vec());
+    assert_eq!(parts[0].1, "");
+    //Only the vertexes each part uses are kept, and indices past them are left out
+    assert_eq!(parts[1].0.indices, vec!(0, 1, 2));
+    assert_eq!(parts[1].0.vertexes, vec!(mesh.vertexes[2], mesh.vertexes[3], mesh.vertexes[0]));
+    assert_eq!(parts[1].1, "stone");
+}
+
+#[test]
+fn large_meshes_need_u32_indices() {
+    let mut mesh = Mesh::new("shader".to_string());
+    mesh.vertexes = vec!(Vertex::default(); 65536);
+    assert!(!mesh.needs_u32_indices());
+    mesh.vertexes.push(Vertex::default());
+    assert!(mesh.needs_u32_indices());
+}
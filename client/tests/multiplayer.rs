@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use cgmath::Vector3;
+use client::input::movement::{Movement, MoveKey};
+use client::renderer::entities::EntityRenderer;
+use game::network::client::NetworkClient;
+use game::network::replication::ReplicationSettings;
+use game::network::server::NetworkServer;
+use game::rendering::GameTexture;
+use game::rendering::mesh::{FrameData, Mesh};
+use game::rendering::renderer::Renderer;
+use game::rendering::texture::ColorTexture;
+use game::world::entities::entity::Entity;
+use game::world::world::WorldData;
+use winit::event::ElementState;
+
+#[path = "../../game/tests/common/network.rs"]
+mod network;
+
+//Keeps where each pushed mesh is drawn
+#[derive(Default)]
+struct Recorder {
+    drawn: Mutex<HashMap<u64, Vector3<f32>>>,
+    next: Mutex<u64>,
+}
+
+impl Renderer for Recorder {
+    fn push(&self, _mesh: Arc<Mesh>, _texture: Arc<dyn GameTexture>, data: FrameData) -> u64 {
+        let mut next = self.next.lock().unwrap();
+        *next += 1;
+        self.drawn.lock().unwrap().insert(*next, data.offset);
+        return *next;
+    }
+
+    fn update(&self, id: u64, data: FrameData) {
+        self.drawn.lock().unwrap().insert(id, data.offset);
+    }
+
+    fn clear(&self, id: u64) {
+        self.drawn.lock().unwrap().remove(&id);
+    }
+}
+
+fn step(world: &mut WorldData, server: &mut NetworkServer, client: &mut NetworkClient) {
+    network::step(world, server, &mut [client]);
+    thread::sleep(Duration::from_millis(1));
+}
+
+#[test]
+fn movement_keys_point_the_player() {
+    let mut movement = Movement::new();
+    movement.key(MoveKey::Forwards, &ElementState::Pressed);
+    movement.key(MoveKey::Right, &ElementState::Pressed);
+    assert_eq!(movement.direction(), Vector3::new(1.0, 0.0, -1.0));
+
+    movement.key(MoveKey::Forwards, &ElementState::Released);
+    assert_eq!(movement.direction(), Vector3::new(1.0, 0.0, 0.0));
+    movement.release_all();
+    assert_eq!(movement.direction(), Vector3::new(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn entities_are_drawn_where_the_server_puts_them() {
+    let mut world = network::create_world(1);
+    let mut server = NetworkServer::new(ReplicationSettings::default());
+    let mut network = network::connect(&mut server, "player", Duration::ZERO);
+    Entity::new("testing".to_string(), 0, Vector3::new(5.0, 0.0, 0.0)).spawn(&mut world);
+
+    let recorder = Arc::new(Recorder::default());
+    let mut entities = EntityRenderer::new(recorder.clone(), Arc::new(ColorTexture::new([255; 4])));
+    for _ in 0..500 {
+        step(&mut world, &mut server, &mut network);
+        if network.player().is_some() && network.world.entities().len() == 2 {
+            break;
+        }
+    }
+    //Enough snapshots to render behind the newest one
+    for _ in 0..5 {
+        step(&mut world, &mut server, &mut network);
+    }
+
+    entities.update(&network, 0.0);
+    assert_eq!(entities.drawn(), 2);
+    let drawn: Vec<Vector3<f32>> = recorder.drawn.lock().unwrap().values().copied().collect();
+    assert!(drawn.iter().any(|position| (position.x - 5.0).abs() < 0.01));
+
+    //The local player is drawn where prediction moved it, before the server answers
+    network.send_input(Vector3::new(0.0, 0.0, 1.0), 0.0).unwrap();
+    let predicted = network.player().unwrap().position;
+    entities.update(&network, 0.0);
+    assert!(recorder.drawn.lock().unwrap().values().any(|position| *position == predicted));
+
+    entities.clear();
+    assert_eq!(entities.drawn(), 0);
+    assert!(recorder.drawn.lock().unwrap().is_empty());
+}
@@ -0,0 +1,29 @@
+use std::fs;
+use tokio::task::JoinSet;
+use client::renderer::assets::AssetReferer;
+use client::renderer::renderer::RENDERER_REF;
+use client::resources::schemas;
+use game::resources::resource_manager::ResourceManager;
+
+mod common;
+
+#[test]
+fn schemas_are_written_for_every_file() {
+    let directory = common::directory("schemas");
+    let resources = ResourceManager::new(JoinSet::new(), Box::new(AssetReferer::new()), RENDERER_REF.clone());
+    let written = schemas::write_schemas(&directory, &resources).unwrap();
+    let names = written.iter().map(|path| path.strip_prefix(&directory).unwrap().to_str().unwrap().replace('\\', "/"))
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!("mesh.schema.json", "animation.schema.json", "mod_manifest.schema.json", "settings.schema.json",
+                           "game_settings.schema.json", "types.schema.json", "types/cube.schema.json"));
+
+    let manifest = json::parse(&fs::read_to_string(directory.join("mod_manifest.schema.json")).unwrap()).unwrap();
+    assert_eq!(manifest["$schema"], "http://json-schema.org/draft-07/schema#");
+    assert_eq!(manifest["required"], json::array!["name", "main", "platforms"]);
+    assert_eq!(manifest["properties"]["platforms"]["additionalProperties"], json::object! { "type": "string" });
+
+    let settings = json::parse(&fs::read_to_string(directory.join("game_settings.schema.json")).unwrap()).unwrap();
+    assert_eq!(settings["properties"]["frames_per_second"], json::object! { "type": "integer", "minimum": 0 });
+    assert!(settings["properties"]["inputs"]["properties"].has_key("KEYS"));
+    fs::remove_dir_all(directory).unwrap();
+}
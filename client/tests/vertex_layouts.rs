@@ -0,0 +1,81 @@
+use std::collections::HashSet;
+use game::rendering::mesh::{Mesh, Vertex};
+use game::rendering::vertex_layout::{VertexAttribute, VertexLayout};
+use interfaces::loading::JsonLoadable;
+use interfaces::saving::JsonSaveable;
+
+fn floats(bytes: &[u8]) -> Vec<f32> {
+    return bytes.chunks(4).map(|value| f32::from_le_bytes(value.try_into().unwrap())).collect();
+}
+
+#[test]
+fn layouts_are_in_attribute_order() {
+    let layout = VertexLayout::new(vec!(VertexAttribute::Color, VertexAttribute::Position, VertexAttribute::Joints));
+    assert_eq!(layout.attributes(), vec!(VertexAttribute::Position, VertexAttribute::Color, VertexAttribute::Joints));
+    assert_eq!(layout, VertexLayout::new(vec!(VertexAttribute::Joints, VertexAttribute::Color, VertexAttribute::Position)));
+    assert_eq!(HashSet::from([layout.clone(), VertexLayout::new(layout.attributes())]).len(), 1);
+
+    assert_eq!(layout.stride(), 12 + 16 + 16);
+    assert_eq!(layout.offset(VertexAttribute::Color), Some(12));
+    assert_eq!(layout.offset(VertexAttribute::Joints), Some(28));
+    assert_eq!(layout.offset(VertexAttribute::Normal), None);
+    assert_eq!(VertexAttribute::Weights.location(), 7);
+    assert_eq!(VertexLayout::default().attributes(), vec!(VertexAttribute::Position, VertexAttribute::TexCoords));
+}
+
+#[test]
+fn vertexes_are_packed_with_only_their_layout() {
+    let mut vertex = Vertex::with_normal([1.0, 2.0, 3.0], [0.5, 0.25], [0.0, 1.0, 0.0]);
+    vertex.set_color([1.0, 0.0, 0.0, 1.0]);
+    vertex.set_skin([3, 0, 0, 0], [1.0, 0.0, 0.0, 0.0]);
+
+    let layout = VertexLayout::new(vec!(VertexAttribute::Position, VertexAttribute::Normal, VertexAttribute::Color));
+    let packed = layout.pack(&[vertex, vertex]);
+    assert_eq!(packed.len(), 2 * layout.stride());
+    assert_eq!(floats(&packed[..layout.stride()]), vec!(1.0, 2.0, 3.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0));
+
+    let skinned = VertexLayout::new(vec!(VertexAttribute::Joints, VertexAttribute::Weights)).pack(&[vertex]);
+    assert_eq!(&skinned[..4], &3u32.to_le_bytes());
+    assert_eq!(floats(&skinned[16..]), vec!(1.0, 0.0, 0.0, 0.0));
+}
+
+#[test]
+fn shaders_declare_what_they_read() {
+    let source = "// Vertex shader\n//layout: Position, Normal,TexCoords2\nstruct VertexInput {}";
+    assert_eq!(VertexLayout::from_shader(source).unwrap(),
+               VertexLayout::new(vec!(VertexAttribute::Position, VertexAttribute::Normal, VertexAttribute::TexCoords2)));
+    assert_eq!(VertexLayout::from_shader("struct VertexInput {}").unwrap(), VertexLayout::default());
+    assert!(VertexLayout::from_shader("//layout: Position, Normals").unwrap_err().to_string()
+        .starts_with("Invalid shader layout: Unknown variant Normals"));
+}
+
+#[test]
+fn meshes_need_everything_their_shader_reads() {
+    let reads = VertexLayout::new(vec!(VertexAttribute::Position, VertexAttribute::Normal, VertexAttribute::Color));
+    assert!(reads.check_shader("lit", &VertexLayout::default()).is_err());
+    let mut mesh = VertexLayout::new(vec!(VertexAttribute::Position, VertexAttribute::TexCoords, VertexAttribute::Normal));
+    assert_eq!(mesh.check_shader("lit", &reads).unwrap_err().to_string(),
+               "Shader lit reads [Color], which the mesh's [Position, TexCoords, Normal] vertexes don't have");
+    //Attributes the shader doesn't read are skipped
+    mesh.add(VertexAttribute::Color);
+    assert!(mesh.check_shader("lit", &reads).is_ok());
+}
+
+#[test]
+fn meshes_load_their_layout() {
+    let mesh = Mesh::load(&json::object! {
+        "shader": "lit",
+        "layout": ["Position", "Color"],
+        "vertexes": [{"position": [0, 1, 0], "tex_coords": [0, 0], "color": [1, 0, 0, 1]}],
+        "indices": [0, 0, 0]
+    }).unwrap();
+    assert_eq!(mesh.layout, VertexLayout::new(vec!(VertexAttribute::Position, VertexAttribute::Color)));
+    assert_eq!(mesh.vertexes[0].color(), [1.0, 0.0, 0.0, 1.0]);
+    assert_eq!(mesh.vertexes[0].normal(), [0.0; 3]);
+    assert!(!mesh.vertexes[0].save().has_key("normal"));
+    assert_eq!(Mesh::load(&mesh.save()).unwrap().vertexes, mesh.vertexes);
+
+    //Meshes from before layouts have positions and texture coordinates
+    let old = Mesh::load(&json::object! { "shader": "shader", "vertexes": [], "indices": [] }).unwrap();
+    assert_eq!(old.layout, VertexLayout::default());
+}
@@ -0,0 +1,87 @@
+use std::path::{Path, PathBuf};
+use include_dir::{Dir, DirEntry, File, include_dir};
+use json::JsonValue;
+use tokio::runtime::Builder;
+use game::language::language::LanguagePack;
+use game::rendering::AssetType;
+use client::resources::content_pack::ContentPack;
+use client::resources::desktop_loader::DesktopLoader;
+use client::resources::web_loader::WebLoader;
+
+static RESOURCES: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/../resources");
+
+static SMALL: Dir<'_> = Dir::new("", &[
+    DirEntry::Dir(Dir::new("types", &[
+        DirEntry::File(File::new("types/cubes.json", b"[{$name: 'test:dirt'}]")),
+        DirEntry::Dir(Dir::new("types/tools", &[DirEntry::File(File::new("types/tools/axes.json", b"[]"))]))
+    ])),
+    DirEntry::Dir(Dir::new("shaders", &[
+        DirEntry::File(File::new("shaders/cube.wgsl", b"//Cube")),
+        DirEntry::Dir(Dir::new("shaders/load_first", &[DirEntry::File(File::new("shaders/load_first/ui.wgsl", b"//UI"))]))
+    ])),
+    DirEntry::Dir(Dir::new("language", &[
+        DirEntry::Dir(Dir::new("language/translations", &[DirEntry::File(File::new("language/translations/en_us.json", b"{dirt: 'Dirt'}"))]))
+    ]))
+]);
+
+fn names(listed: Vec<(String, PathBuf)>) -> Vec<String> {
+    return listed.into_iter().map(|(name, _)| name).collect();
+}
+
+fn translations(pack: &dyn ContentPack) -> Vec<JsonValue> {
+    return pack.language().into_iter().flat_map(|pack| match pack {
+        LanguagePack::Translations(values) => values
+    }).collect();
+}
+
+//Textures and models sorted by name, with what they loaded as
+fn assets(pack: &dyn ContentPack, load_first: bool) -> Vec<String> {
+    let runtime = Builder::new_multi_thread().build().unwrap();
+    let mut output = Vec::new();
+    for asset in runtime.block_on(pack.assets(runtime.handle(), load_first)).unwrap() {
+        match asset {
+            AssetType::Texture(textures) => output.extend(textures.into_iter()
+                .map(|(name, texture)| format!("{} {:?} {:?}", name, texture.dimensions(), texture.data().iter().map(|byte| *byte as u64).sum::<u64>()))),
+            AssetType::Model(models) => output.extend(models.into_iter()
+                .map(|(name, model)| format!("{} {} {}", name, model.shader, model.vertexes.len())))
+        }
+    }
+    output.sort();
+    return output;
+}
+
+#[test]
+fn embedded_resources_load_like_the_folder() {
+    let embedded = WebLoader::new(&RESOURCES);
+    let folder = DesktopLoader::new(Path::new(env!("CARGO_MANIFEST_DIR")).join("../resources"));
+
+    assert!(!embedded.types().is_empty());
+    assert_eq!(names(embedded.types()), names(folder.types()));
+    for early in [true, false] {
+        assert_eq!(names(embedded.shaders(early)), names(folder.shaders(early)));
+        assert_eq!(assets(&embedded, early), assets(&folder, early));
+    }
+    for ((_, embedded_file), (_, file)) in embedded.types().into_iter().zip(folder.types()) {
+        assert_eq!(embedded.read_json(&embedded_file).unwrap().1, folder.read_json(&file).unwrap().1);
+    }
+    for ((_, embedded_file), (_, file)) in embedded.shaders(true).into_iter().zip(folder.shaders(true)) {
+        assert_eq!(embedded.read(&embedded_file).unwrap(), folder.read(&file).unwrap());
+    }
+    assert_eq!(translations(&embedded), translations(&folder));
+}
+
+#[test]
+fn files_are_listed_by_their_embedded_path() {
+    let embedded = WebLoader::new(&SMALL);
+    assert_eq!(embedded.types(), vec!((String::from("cubes"), PathBuf::from("types/cubes.json")),
+                                      (String::from("tools/axes"), PathBuf::from("types/tools/axes.json"))));
+    assert_eq!(embedded.shaders(true), vec!((String::from("load_first/ui"), PathBuf::from("shaders/load_first/ui.wgsl"))));
+    assert_eq!(embedded.shaders(false), vec!((String::from("cube"), PathBuf::from("shaders/cube.wgsl"))));
+    assert_eq!(embedded.read_json(Path::new("types/cubes.json")).unwrap().1[0]["$name"].as_str(), Some("test:dirt"));
+    assert_eq!(translations(&embedded), vec!(json::object! {dirt: "Dirt"}));
+    //Missing folders are empty, missing files are errors
+    assert!(assets(&embedded, false).is_empty());
+    assert_eq!(embedded.read(Path::new("types/missing.json")).unwrap_err().to_string(),
+               "types/missing.json isn't in the embedded resources");
+    assert!(embedded.read_json(Path::new("types/missing.json")).unwrap_err().to_string().starts_with("types/missing.json → "));
+}
@@ -15,8 +15,14 @@ lazy_static! {
     pub static ref LANGUAGE_MANAGER: Arc<RwLock<LanguageManager>> = Arc::new(RwLock::new(LanguageManager::new()));
 }
 
+impl Default for LanguageManager {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
 impl LanguageManager {
-    fn new() -> Self {
+    pub fn new() -> Self {
         return Self {
             translations: Box::new(HashMap::new())
         };
@@ -29,6 +35,33 @@ impl LanguageManager {
         };
     }
 
+    //Translates input, replacing {0}, {1}, ... with the matching argument.
+    //Arguments are inserted as is, so a player name containing {1} isn't replaced again.
+    pub fn translate_with(&self, input: &String, arguments: &[String]) -> String {
+        let translated = self.translate(input);
+        let mut output = String::new();
+        let mut rest = translated.as_str();
+        while let Some(start) = rest.find('{') {
+            output += &rest[..start];
+            rest = &rest[start..];
+            let argument = rest.find('}')
+                .and_then(|end| rest[1..end].parse::<usize>().ok().map(|index| (end, index)))
+                .and_then(|(end, index)| arguments.get(index).map(|argument| (end, argument)));
+            match argument {
+                Some((end, argument)) => {
+                    output += argument;
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    output.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        output += rest;
+        return output;
+    }
+
     pub fn load_packs(&mut self, packs: Vec<LanguagePack>) {
         for pack in packs {
             match pack {
@@ -1,5 +1,8 @@
 #![feature(stmt_expr_attributes)]
 
+//Lets derive macros refer to game:: from inside the game crate
+extern crate self as game;
+
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
@@ -15,6 +18,7 @@ use crate::world::world::World;
 
 pub mod language;
 pub mod mods;
+pub mod network;
 #[cfg(feature = "renderer")]
 pub mod rendering;
 pub mod resources;
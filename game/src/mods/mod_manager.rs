@@ -1,3 +1,4 @@
+use std::mem;
 use anyhow::Error;
 use tokio::task::JoinSet;
 use crate::{error, GameMod};
@@ -33,4 +34,15 @@ impl ModManager {
 
         return &self.loaded_mods;
     }
+
+    //Waits for the mods to load and takes them out, so they can be handed the ResourceManager holding this.
+    //Give them back with restore.
+    pub async fn take_mods(&mut self) -> Vec<GameMod> {
+        self.get_mods().await;
+        return mem::take(&mut self.loaded_mods);
+    }
+
+    pub fn restore(&mut self, mods: Vec<GameMod>) {
+        self.loaded_mods.extend(mods);
+    }
 }
\ No newline at end of file
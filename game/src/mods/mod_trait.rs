@@ -1,7 +1,15 @@
 use std::sync::Arc;
+use anyhow::Error;
+use crate::resources::resource_manager::ResourceManager;
 use crate::util::register::ThingRegister;
 
 pub trait ModMain {
+    //Registers the mod's types with ResourceManager::register_type. Called once every mod is loaded,
+    //before any types are, in no particular order.
+    fn register_types(&mut self, _resources: &mut ResourceManager) -> Result<(), Error> {
+        return Ok(());
+    }
+
     fn finish_loading(&mut self);
 
     fn handle_event(&mut self, event: Arc<dyn ThingRegister>);
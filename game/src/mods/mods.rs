@@ -1,21 +1,31 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::string::String;
+use anyhow::Error;
 use macros::JsonLoadable;
 use crate::mods::mod_trait::ModMain;
+use crate::resources::resource_manager::ResourceManager;
 
 //A loaded mod
 pub struct GameMod {
-    _manifest: ModManifest,
-    _main: Box<dyn ModMain + Send>
+    manifest: ModManifest,
+    main: Box<dyn ModMain + Send>
 }
 
 impl GameMod {
     pub fn new(manifest: ModManifest, main: Box<dyn ModMain + Send>) -> Self {
         return Self {
-            _manifest: manifest,
-            _main: main
+            manifest,
+            main
         }
     }
+
+    pub fn name(&self) -> &str {
+        return &self.manifest.name;
+    }
+
+    pub fn register_types(&mut self, resources: &mut ResourceManager) -> Result<(), Error> {
+        return self.main.register_types(resources);
+    }
 }
 
 #[derive(JsonLoadable, Default)]
@@ -28,4 +38,35 @@ pub struct ModManifest {
     pub platforms: HashMap<String, String>,
     pub hard_dependencies: Vec<String>,
     pub soft_dependencies: Vec<String>
-}
\ No newline at end of file
+}
+
+//Order to load mods in, as indices into manifests: every mod comes after the mods it depends on,
+//hard or soft, that are there. Otherwise they're sorted by name. Fails if mods depend on each other in a cycle.
+pub fn load_order(manifests: &[ModManifest]) -> Result<Vec<usize>, Error> {
+    let indices: HashMap<&str, usize> = manifests.iter().enumerate()
+        .map(|(index, manifest)| (manifest.name.as_str(), index)).collect();
+    //Dependencies of each mod that haven't been ordered yet
+    let mut waiting: Vec<BTreeSet<usize>> = manifests.iter().map(|manifest|
+        manifest.hard_dependencies.iter().chain(&manifest.soft_dependencies)
+            .filter_map(|dependency| indices.get(dependency.as_str()).copied()).collect()).collect();
+
+    let mut ready: BTreeSet<(&str, usize)> = waiting.iter().enumerate().filter(|(_, dependencies)| dependencies.is_empty())
+        .map(|(index, _)| (manifests[index].name.as_str(), index)).collect();
+    let mut output = Vec::new();
+    while let Some((_, index)) = ready.pop_first() {
+        output.push(index);
+        for (other, dependencies) in waiting.iter_mut().enumerate() {
+            if dependencies.remove(&index) && dependencies.is_empty() {
+                ready.insert((manifests[other].name.as_str(), other));
+            }
+        }
+    }
+
+    if output.len() < manifests.len() {
+        let mut cycle = (0..manifests.len()).filter(|index| !waiting[*index].is_empty())
+            .map(|index| manifests[index].name.clone()).collect::<Vec<_>>();
+        cycle.sort();
+        return Err(Error::msg(format!("Mods depend on each other in a cycle: {}", cycle.join(", "))));
+    }
+    return Ok(output);
+}
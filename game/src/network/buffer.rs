@@ -0,0 +1,135 @@
+use anyhow::Error;
+
+//Little-endian writer for packets
+#[derive(Default)]
+pub struct ByteWriter {
+    bytes: Vec<u8>,
+}
+
+impl ByteWriter {
+    pub fn new() -> Self {
+        return Self {
+            bytes: Vec::new()
+        };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.bytes.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.bytes.is_empty();
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        return self.bytes;
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_i16(&mut self, value: i16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_i32(&mut self, value: i32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_bytes(&mut self, value: &[u8]) {
+        self.bytes.extend_from_slice(value);
+    }
+
+    //LEB128, most ids and counts fit in one or two bytes
+    pub fn write_varint(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.bytes.push(byte);
+                return;
+            }
+            self.bytes.push(byte | 0x80);
+        }
+    }
+
+    pub fn write_string(&mut self, value: &str) {
+        self.write_varint(value.len() as u64);
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+}
+
+pub struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        return Self {
+            bytes,
+            position: 0
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.position >= self.bytes.len();
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        //Compared to what's left so huge lengths from a peer can't overflow
+        if length > self.bytes.len() - self.position {
+            return Err(Error::msg(format!("Packet too short, wanted {} bytes at {} of {}",
+                                          length, self.position, self.bytes.len())));
+        }
+        let found = &self.bytes[self.position..self.position + length];
+        self.position += length;
+        return Ok(found);
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, Error> {
+        return Ok(self.take(1)?[0]);
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, Error> {
+        return Ok(u16::from_le_bytes(self.take(2)?.try_into()?));
+    }
+
+    pub fn read_i16(&mut self) -> Result<i16, Error> {
+        return Ok(i16::from_le_bytes(self.take(2)?.try_into()?));
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32, Error> {
+        return Ok(i32::from_le_bytes(self.take(4)?.try_into()?));
+    }
+
+    pub fn read_bytes(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        return self.take(length);
+    }
+
+    pub fn read_varint(&mut self) -> Result<u64, Error> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_u8()?;
+            if shift >= 64 {
+                return Err(Error::msg("Varint too long"));
+            }
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    pub fn read_string(&mut self) -> Result<String, Error> {
+        let length = self.read_varint()? as usize;
+        return Ok(String::from_utf8(self.take(length)?.to_vec())?);
+    }
+}
@@ -0,0 +1,169 @@
+use anyhow::Error;
+use crate::language::language::LanguageManager;
+use crate::network::buffer::{ByteReader, ByteWriter};
+
+//Longest message a client can send, in characters
+pub const MAX_MESSAGE_LENGTH: usize = 256;
+
+//Who a message goes to. From clients, Whisper holds who it's for. From the server, who it's from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChatChannel {
+    //Everyone on the server
+    Global,
+    //Everyone in the sender's room
+    Room,
+    Whisper(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChatContent {
+    //Typed by a player, shown as is
+    Text(String),
+    //Sent by the server, translated by each client. Arguments replace {0}, {1}, ... in the translation.
+    Translated { key: String, arguments: Vec<String> },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChatMessage {
+    pub channel: ChatChannel,
+    //None for system messages
+    pub sender: Option<String>,
+    pub content: ChatContent,
+}
+
+impl ChatChannel {
+    pub fn write(&self, writer: &mut ByteWriter) {
+        match self {
+            ChatChannel::Global => writer.write_u8(0),
+            ChatChannel::Room => writer.write_u8(1),
+            ChatChannel::Whisper(player) => {
+                writer.write_u8(2);
+                writer.write_string(player);
+            }
+        }
+    }
+
+    pub fn read(reader: &mut ByteReader) -> Result<Self, Error> {
+        return match reader.read_u8()? {
+            0 => Ok(ChatChannel::Global),
+            1 => Ok(ChatChannel::Room),
+            2 => Ok(ChatChannel::Whisper(reader.read_string()?)),
+            found => Err(Error::msg(format!("Unknown chat channel {}", found)))
+        };
+    }
+}
+
+impl ChatMessage {
+    //A message from the server, like someone joining
+    pub fn system(key: &str, arguments: Vec<String>) -> Self {
+        return Self {
+            channel: ChatChannel::Global,
+            sender: None,
+            content: ChatContent::Translated { key: key.to_string(), arguments },
+        };
+    }
+
+    //The message as it's shown in the chat panel
+    pub fn render(&self, language: &LanguageManager) -> String {
+        let content = match &self.content {
+            ChatContent::Text(text) => text.clone(),
+            ChatContent::Translated { key, arguments } => language.translate_with(key, arguments)
+        };
+        return match (&self.sender, &self.channel) {
+            (None, _) => content,
+            (Some(sender), ChatChannel::Global) => format!("<{}> {}", sender, content),
+            (Some(sender), ChatChannel::Room) => format!("[Room] <{}> {}", sender, content),
+            (Some(sender), ChatChannel::Whisper(_)) => format!("{} whispers: {}", sender, content),
+        };
+    }
+
+    pub fn write(&self, writer: &mut ByteWriter) {
+        self.channel.write(writer);
+        match &self.sender {
+            Some(sender) => {
+                writer.write_u8(1);
+                writer.write_string(sender);
+            }
+            None => writer.write_u8(0)
+        }
+        match &self.content {
+            ChatContent::Text(text) => {
+                writer.write_u8(0);
+                writer.write_string(text);
+            }
+            ChatContent::Translated { key, arguments } => {
+                writer.write_u8(1);
+                writer.write_string(key);
+                writer.write_varint(arguments.len() as u64);
+                for argument in arguments {
+                    writer.write_string(argument);
+                }
+            }
+        }
+    }
+
+    pub fn read(reader: &mut ByteReader) -> Result<Self, Error> {
+        let channel = ChatChannel::read(reader)?;
+        let sender = match reader.read_u8()? {
+            0 => None,
+            _ => Some(reader.read_string()?)
+        };
+        let content = match reader.read_u8()? {
+            0 => ChatContent::Text(reader.read_string()?),
+            1 => {
+                let key = reader.read_string()?;
+                let mut arguments = Vec::new();
+                for _ in 0..reader.read_varint()? {
+                    arguments.push(reader.read_string()?);
+                }
+                ChatContent::Translated { key, arguments }
+            }
+            found => return Err(Error::msg(format!("Unknown chat content {}", found)))
+        };
+        return Ok(Self {
+            channel,
+            sender,
+            content
+        });
+    }
+}
+
+//Token bucket limiting how fast a client can chat. Counted in world updates so it doesn't
+//depend on the server's tick rate.
+#[derive(Clone, Debug)]
+pub struct ChatLimiter {
+    //Messages that can be sent at once
+    pub burst: u32,
+    //Updates it takes to earn another message
+    pub refill_ticks: u64,
+    tokens: u32,
+    last_refill: u64,
+}
+
+impl ChatLimiter {
+    pub fn new(burst: u32, refill_ticks: u64) -> Self {
+        return Self {
+            burst,
+            refill_ticks,
+            tokens: burst,
+            last_refill: 0,
+        };
+    }
+
+    //Takes a token if there's one left at this tick
+    pub fn try_send(&mut self, tick: u64) -> bool {
+        if self.tokens == self.burst {
+            self.last_refill = tick;
+        }
+        let earned = tick.saturating_sub(self.last_refill) / self.refill_ticks.max(1);
+        if earned > 0 {
+            self.tokens = (self.tokens as u64 + earned).min(self.burst as u64) as u32;
+            self.last_refill += earned * self.refill_ticks.max(1);
+        }
+        if self.tokens == 0 {
+            return false;
+        }
+        self.tokens -= 1;
+        return true;
+    }
+}
@@ -0,0 +1,111 @@
+use anyhow::Error;
+use cgmath::Vector3;
+use crate::network::chat::{ChatChannel, ChatMessage};
+use crate::network::interpolation::{InterpolatedState, Interpolator};
+use crate::network::packets::{ClientPacket, ServerPacket};
+use crate::network::prediction::PredictedPlayer;
+use crate::network::replication::ReplicatedWorld;
+use crate::network::transport::Transport;
+use crate::world::entities::entity::Entity;
+
+//Updates remote entities are rendered behind the newest snapshot
+const INTERPOLATION_DELAY: f64 = 2.0;
+
+//Client end of a connection to a server
+pub struct NetworkClient {
+    transport: Box<dyn Transport>,
+    pub client_id: Option<u64>,
+    pub world: ReplicatedWorld,
+    //The local player, set once the server welcomes us
+    pub prediction: Option<PredictedPlayer>,
+    pub interpolation: Interpolator,
+    //The server's message of the day, sent when joining
+    pub motd: Option<String>,
+    //Chat messages received since the UI last took them
+    pub chat: Vec<ChatMessage>,
+    //Set when the server kicks us or the connection drops
+    pub disconnected: Option<String>,
+}
+
+impl NetworkClient {
+    pub fn connect(mut transport: Box<dyn Transport>, name: String) -> Result<Self, Error> {
+        transport.send(ClientPacket::Join { name }.encode())?;
+        return Ok(Self {
+            transport,
+            client_id: None,
+            world: ReplicatedWorld::new(),
+            prediction: None,
+            interpolation: Interpolator::new(INTERPOLATION_DELAY),
+            motd: None,
+            chat: Vec::new(),
+            disconnected: None,
+        });
+    }
+
+    pub fn send(&mut self, packet: &ClientPacket) -> Result<(), Error> {
+        return self.transport.send(packet.encode());
+    }
+
+    //Called once per client update with the player's input. Moves the local player
+    //right away and sends the input to the server.
+    pub fn send_input(&mut self, movement: Vector3<f32>, rotation: f32) -> Result<(), Error> {
+        let input = match &mut self.prediction {
+            Some(prediction) => prediction.predict(movement, rotation),
+            None => return Ok(())
+        };
+        return self.send(&ClientPacket::Input(input));
+    }
+
+    pub fn send_chat(&mut self, channel: ChatChannel, text: String) -> Result<(), Error> {
+        return self.send(&ClientPacket::Chat { channel, text });
+    }
+
+    pub fn player(&self) -> Option<&Entity> {
+        return self.prediction.as_ref().map(|prediction| &prediction.entity);
+    }
+
+    //Where to draw a remote entity, alpha is how far (0 to 1) the client is into the current update
+    pub fn interpolated(&self, id: u64, alpha: f64) -> Option<InterpolatedState> {
+        return self.interpolation.sample(id, self.interpolation.render_tick(alpha));
+    }
+
+    //Handles every packet that arrived since the last poll
+    pub fn poll(&mut self) -> Result<(), Error> {
+        if self.disconnected.is_some() {
+            return Ok(());
+        }
+        loop {
+            let packet = match self.transport.receive() {
+                Ok(Some(packet)) => packet,
+                Ok(None) => return Ok(()),
+                Err(error) => {
+                    self.disconnected = Some(error.to_string());
+                    return Ok(());
+                }
+            };
+            match ServerPacket::decode(&packet)? {
+                ServerPacket::Welcome { client_id, tick, player, room, motd } => {
+                    self.client_id = Some(client_id);
+                    self.motd = Some(motd);
+                    self.world.tick = tick;
+                    self.prediction = Some(PredictedPlayer::new(Entity::replica(player, "player".to_string(), room)));
+                }
+                ServerPacket::Snapshot(snapshot) => {
+                    self.world.apply(&snapshot)?;
+                    let player = self.player().map(|player| player.id());
+                    self.interpolation.record(snapshot.tick, &self.world, player);
+                }
+                ServerPacket::PlayerState { acked_input, state, .. } => {
+                    if let Some(prediction) = &mut self.prediction {
+                        prediction.reconcile(acked_input, &state);
+                    }
+                }
+                ServerPacket::Chat(message) => self.chat.push(message),
+                ServerPacket::Disconnect { reason } => {
+                    self.disconnected = Some(reason);
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::{HashMap, VecDeque};
+use std::f32::consts::{PI, TAU};
+use cgmath::{Vector3, VectorSpace};
+use crate::network::quantize::QuantizedState;
+use crate::network::replication::ReplicatedWorld;
+
+//Snapshots kept per entity, older ones are never rendered
+const MAX_SAMPLES: usize = 32;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct InterpolatedState {
+    pub position: Vector3<f32>,
+    pub rotation: f32,
+}
+
+//Smooths remote entities by rendering them slightly in the past, between the two
+//snapshots around the render time.
+pub struct Interpolator {
+    //Updates behind the newest snapshot that are rendered, so there's usually a snapshot on both sides
+    pub delay: f64,
+    latest: u64,
+    history: HashMap<u64, VecDeque<(u64, QuantizedState)>>,
+}
+
+impl Interpolator {
+    pub fn new(delay: f64) -> Self {
+        return Self {
+            delay,
+            latest: 0,
+            history: HashMap::new(),
+        };
+    }
+
+    //Records every entity's state after a snapshot, skipping the locally predicted player
+    pub fn record(&mut self, tick: u64, world: &ReplicatedWorld, skip: Option<u64>) {
+        self.latest = self.latest.max(tick);
+        self.history.retain(|id, _| world.get(*id).is_some());
+        for (id, entity) in world.entities() {
+            if Some(*id) == skip {
+                continue;
+            }
+            let samples = self.history.entry(*id).or_default();
+            if samples.back().is_some_and(|(last, _)| *last >= tick) {
+                continue;
+            }
+            if samples.len() == MAX_SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back((tick, entity.state));
+        }
+    }
+
+    //The time to render at, alpha is how far (0 to 1) the client is into the current update
+    pub fn render_tick(&self, alpha: f64) -> f64 {
+        return self.latest as f64 + alpha - self.delay;
+    }
+
+    pub fn sample(&self, id: u64, tick: f64) -> Option<InterpolatedState> {
+        let samples = self.history.get(&id)?;
+        let (first_tick, first) = samples.front()?;
+        if tick <= *first_tick as f64 {
+            return Some(Self::state(first));
+        }
+
+        for i in 1..samples.len() {
+            let (to_tick, to) = &samples[i];
+            if tick > *to_tick as f64 {
+                continue;
+            }
+            let (from_tick, from) = &samples[i - 1];
+            let alpha = ((tick - *from_tick as f64) / (*to_tick - *from_tick) as f64) as f32;
+            return Some(InterpolatedState {
+                position: from.position().lerp(to.position(), alpha),
+                rotation: Self::lerp_angle(from.rotation(), to.rotation(), alpha),
+            });
+        }
+
+        //Past the newest snapshot, hold the last known state instead of guessing
+        return Some(Self::state(&samples.back()?.1));
+    }
+
+    fn state(state: &QuantizedState) -> InterpolatedState {
+        return InterpolatedState {
+            position: state.position(),
+            rotation: state.rotation(),
+        };
+    }
+
+    //Turns the short way around
+    fn lerp_angle(from: f32, to: f32, alpha: f32) -> f32 {
+        let difference = (to - from + PI).rem_euclid(TAU) - PI;
+        return (from + difference * alpha).rem_euclid(TAU);
+    }
+}
@@ -0,0 +1,10 @@
+pub mod buffer;
+pub mod chat;
+pub mod client;
+pub mod interpolation;
+pub mod packets;
+pub mod prediction;
+pub mod quantize;
+pub mod replication;
+pub mod server;
+pub mod transport;
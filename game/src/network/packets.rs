@@ -0,0 +1,310 @@
+use anyhow::Error;
+use crate::network::buffer::{ByteReader, ByteWriter};
+use crate::network::chat::{ChatChannel, ChatMessage};
+use crate::network::quantize::{POSITION, QuantizedState, ROTATION, VELOCITY};
+use crate::world::entities::player::PlayerInput;
+
+//Packets have their own codec instead of the Binary data format: deltas only write the components in their mask,
+//values are fixed-width without tags, and replication budgets with the exact written size.
+
+//Packets sent from a client to the server
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClientPacket {
+    Join { name: String },
+    Leave,
+    //Sent once per client update, the server applies every queued one on its next update
+    Input(PlayerInput),
+    Chat { channel: ChatChannel, text: String },
+}
+
+//Packets sent from the server to a client
+#[derive(Clone, Debug, PartialEq)]
+pub enum ServerPacket {
+    //player is the id of the entity this client controls and room the room it spawned in,
+    //motd is shown once on joining
+    Welcome { client_id: u64, tick: u64, player: u64, room: usize, motd: String },
+    Snapshot(Snapshot),
+    Disconnect { reason: String },
+    //Authoritative state of the client's player after applying acked_input, used for reconciliation
+    PlayerState { tick: u64, acked_input: u32, state: QuantizedState },
+    Chat(ChatMessage),
+}
+
+//Everything that changed for one client in one server update
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    pub tick: u64,
+    pub despawns: Vec<u64>,
+    pub spawns: Vec<EntitySpawn>,
+    pub deltas: Vec<EntityDelta>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntitySpawn {
+    pub id: u64,
+    pub entity_type: String,
+    pub state: QuantizedState,
+}
+
+//Only the components flagged in mask are sent, the rest of state is ignored
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntityDelta {
+    pub id: u64,
+    pub mask: u8,
+    pub state: QuantizedState,
+}
+
+impl ClientPacket {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = ByteWriter::new();
+        match self {
+            ClientPacket::Join { name } => {
+                writer.write_u8(0);
+                writer.write_string(name);
+            }
+            ClientPacket::Leave => writer.write_u8(1),
+            ClientPacket::Input(input) => {
+                writer.write_u8(2);
+                writer.write_varint(input.sequence as u64);
+                for axis in input.movement {
+                    writer.write_u8(axis as u8);
+                }
+                writer.write_u16(input.rotation);
+            }
+            ClientPacket::Chat { channel, text } => {
+                writer.write_u8(3);
+                channel.write(&mut writer);
+                writer.write_string(text);
+            }
+        }
+        return writer.finish();
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = ByteReader::new(bytes);
+        return match reader.read_u8()? {
+            0 => Ok(ClientPacket::Join { name: reader.read_string()? }),
+            1 => Ok(ClientPacket::Leave),
+            2 => {
+                let sequence = reader.read_varint()? as u32;
+                let mut movement = [0; 3];
+                for axis in &mut movement {
+                    *axis = reader.read_u8()? as i8;
+                }
+                Ok(ClientPacket::Input(PlayerInput {
+                    sequence,
+                    movement,
+                    rotation: reader.read_u16()?
+                }))
+            }
+            3 => Ok(ClientPacket::Chat {
+                channel: ChatChannel::read(&mut reader)?,
+                text: reader.read_string()?
+            }),
+            found => Err(Error::msg(format!("Unknown client packet {}", found)))
+        };
+    }
+}
+
+impl ServerPacket {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = ByteWriter::new();
+        match self {
+            ServerPacket::Welcome { client_id, tick, player, room, motd } => {
+                writer.write_u8(0);
+                writer.write_varint(*client_id);
+                writer.write_varint(*tick);
+                writer.write_varint(*player);
+                writer.write_varint(*room as u64);
+                writer.write_string(motd);
+            }
+            ServerPacket::Snapshot(snapshot) => {
+                writer.write_u8(1);
+                snapshot.write(&mut writer);
+            }
+            ServerPacket::Disconnect { reason } => {
+                writer.write_u8(2);
+                writer.write_string(reason);
+            }
+            ServerPacket::PlayerState { tick, acked_input, state } => {
+                writer.write_u8(3);
+                writer.write_varint(*tick);
+                writer.write_varint(*acked_input as u64);
+                write_state(&mut writer, POSITION | VELOCITY | ROTATION, state);
+            }
+            ServerPacket::Chat(message) => {
+                writer.write_u8(4);
+                message.write(&mut writer);
+            }
+        }
+        return writer.finish();
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = ByteReader::new(bytes);
+        return match reader.read_u8()? {
+            0 => Ok(ServerPacket::Welcome {
+                client_id: reader.read_varint()?,
+                tick: reader.read_varint()?,
+                player: reader.read_varint()?,
+                room: reader.read_varint()? as usize,
+                motd: reader.read_string()?
+            }),
+            1 => Ok(ServerPacket::Snapshot(Snapshot::read(&mut reader)?)),
+            2 => Ok(ServerPacket::Disconnect { reason: reader.read_string()? }),
+            3 => {
+                let tick = reader.read_varint()?;
+                let acked_input = reader.read_varint()? as u32;
+                let mut state = QuantizedState::default();
+                read_state(&mut reader, POSITION | VELOCITY | ROTATION, &mut state)?;
+                Ok(ServerPacket::PlayerState { tick, acked_input, state })
+            }
+            4 => Ok(ServerPacket::Chat(ChatMessage::read(&mut reader)?)),
+            found => Err(Error::msg(format!("Unknown server packet {}", found)))
+        };
+    }
+}
+
+impl Snapshot {
+    pub fn new(tick: u64) -> Self {
+        return Self {
+            tick,
+            despawns: Vec::new(),
+            spawns: Vec::new(),
+            deltas: Vec::new(),
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.despawns.is_empty() && self.spawns.is_empty() && self.deltas.is_empty();
+    }
+
+    pub fn write(&self, writer: &mut ByteWriter) {
+        writer.write_varint(self.tick);
+        writer.write_varint(self.despawns.len() as u64);
+        for id in &self.despawns {
+            writer.write_varint(*id);
+        }
+        writer.write_varint(self.spawns.len() as u64);
+        for spawn in &self.spawns {
+            spawn.write(writer);
+        }
+        writer.write_varint(self.deltas.len() as u64);
+        for delta in &self.deltas {
+            delta.write(writer);
+        }
+    }
+
+    pub fn read(reader: &mut ByteReader) -> Result<Self, Error> {
+        let mut snapshot = Snapshot::new(reader.read_varint()?);
+        for _ in 0..reader.read_varint()? {
+            snapshot.despawns.push(reader.read_varint()?);
+        }
+        for _ in 0..reader.read_varint()? {
+            snapshot.spawns.push(EntitySpawn::read(reader)?);
+        }
+        for _ in 0..reader.read_varint()? {
+            snapshot.deltas.push(EntityDelta::read(reader)?);
+        }
+        return Ok(snapshot);
+    }
+}
+
+impl EntitySpawn {
+    pub fn write(&self, writer: &mut ByteWriter) {
+        writer.write_varint(self.id);
+        writer.write_string(&self.entity_type);
+        write_state(writer, POSITION | VELOCITY | ROTATION, &self.state);
+    }
+
+    pub fn read(reader: &mut ByteReader) -> Result<Self, Error> {
+        let id = reader.read_varint()?;
+        let entity_type = reader.read_string()?;
+        let mut state = QuantizedState::default();
+        read_state(reader, POSITION | VELOCITY | ROTATION, &mut state)?;
+        return Ok(Self {
+            id,
+            entity_type,
+            state
+        });
+    }
+
+    //Size of this message once written, used for bandwidth budgeting
+    pub fn size(&self) -> usize {
+        let mut writer = ByteWriter::new();
+        self.write(&mut writer);
+        return writer.len();
+    }
+}
+
+impl EntityDelta {
+    pub fn write(&self, writer: &mut ByteWriter) {
+        writer.write_varint(self.id);
+        writer.write_u8(self.mask);
+        write_state(writer, self.mask, &self.state);
+    }
+
+    pub fn read(reader: &mut ByteReader) -> Result<Self, Error> {
+        let id = reader.read_varint()?;
+        let mask = reader.read_u8()?;
+        let mut state = QuantizedState::default();
+        read_state(reader, mask, &mut state)?;
+        return Ok(Self {
+            id,
+            mask,
+            state
+        });
+    }
+
+    //Copies the changed components onto the given state
+    pub fn apply(&self, state: &mut QuantizedState) {
+        if self.mask & POSITION != 0 {
+            state.position = self.state.position;
+        }
+        if self.mask & VELOCITY != 0 {
+            state.velocity = self.state.velocity;
+        }
+        if self.mask & ROTATION != 0 {
+            state.rotation = self.state.rotation;
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        let mut writer = ByteWriter::new();
+        self.write(&mut writer);
+        return writer.len();
+    }
+}
+
+fn write_state(writer: &mut ByteWriter, mask: u8, state: &QuantizedState) {
+    if mask & POSITION != 0 {
+        for value in state.position {
+            writer.write_i32(value);
+        }
+    }
+    if mask & VELOCITY != 0 {
+        for value in state.velocity {
+            writer.write_i16(value);
+        }
+    }
+    if mask & ROTATION != 0 {
+        writer.write_u16(state.rotation);
+    }
+}
+
+fn read_state(reader: &mut ByteReader, mask: u8, state: &mut QuantizedState) -> Result<(), Error> {
+    if mask & POSITION != 0 {
+        for value in &mut state.position {
+            *value = reader.read_i32()?;
+        }
+    }
+    if mask & VELOCITY != 0 {
+        for value in &mut state.velocity {
+            *value = reader.read_i16()?;
+        }
+    }
+    if mask & ROTATION != 0 {
+        state.rotation = reader.read_u16()?;
+    }
+    return Ok(());
+}
@@ -0,0 +1,71 @@
+use std::collections::VecDeque;
+use cgmath::{InnerSpace, Vector3};
+use crate::network::quantize::QuantizedState;
+use crate::world::entities::entity::Entity;
+use crate::world::entities::player::PlayerInput;
+
+//Inputs kept while waiting for the server. If the server stops acking, the oldest are dropped.
+const MAX_HISTORY: usize = 256;
+
+//The local player, simulated ahead of the server using the client's own inputs.
+//Each update runs the same steps as the server: the entity update, then the input.
+pub struct PredictedPlayer {
+    pub entity: Entity,
+    next_sequence: u32,
+    history: VecDeque<PlayerInput>,
+    last_acked: Option<u32>,
+    //How far the last reconciliation moved the player, zero when prediction was right
+    pub last_correction: f32,
+}
+
+impl PredictedPlayer {
+    pub fn new(entity: Entity) -> Self {
+        return Self {
+            entity,
+            next_sequence: 0,
+            history: VecDeque::new(),
+            last_acked: None,
+            last_correction: 0.0,
+        };
+    }
+
+    //Runs one update locally and returns the input to send to the server
+    pub fn predict(&mut self, movement: Vector3<f32>, rotation: f32) -> PlayerInput {
+        let input = PlayerInput::new(self.next_sequence, movement, rotation);
+        self.next_sequence += 1;
+        Self::simulate(&mut self.entity, &input);
+        if self.history.len() == MAX_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(input);
+        return input;
+    }
+
+    //Rewinds to the server's state after acked, then replays every input it hasn't processed yet
+    pub fn reconcile(&mut self, acked: u32, state: &QuantizedState) {
+        if self.last_acked.is_some_and(|last| acked <= last) {
+            return;
+        }
+        self.last_acked = Some(acked);
+
+        while self.history.front().is_some_and(|input| input.sequence <= acked) {
+            self.history.pop_front();
+        }
+
+        let predicted = self.entity.position;
+        state.restore(&mut self.entity);
+        for input in &self.history {
+            Self::simulate(&mut self.entity, input);
+        }
+        self.last_correction = (self.entity.position - predicted).magnitude();
+    }
+
+    pub fn unacknowledged(&self) -> usize {
+        return self.history.len();
+    }
+
+    fn simulate(entity: &mut Entity, input: &PlayerInput) {
+        entity.update();
+        input.apply(entity);
+    }
+}
@@ -0,0 +1,89 @@
+use std::f32::consts::TAU;
+use cgmath::Vector3;
+use crate::world::entities::entity::Entity;
+
+//Positions are sent in 1/1024ths of a unit
+pub const POSITION_SCALE: f32 = 1024.0;
+//Velocities are sent in 1/1024ths of a unit per update, clamped to about 32 units per update
+pub const VELOCITY_SCALE: f32 = 1024.0;
+
+//Changed component flags for deltas
+pub const POSITION: u8 = 1;
+pub const VELOCITY: u8 = 1 << 1;
+pub const ROTATION: u8 = 1 << 2;
+
+//The replicated components of an entity, in the precision they're sent at.
+//The server compares these (not the raw floats) to decide what changed, so
+//tiny float jitter doesn't cost bandwidth.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct QuantizedState {
+    pub position: [i32; 3],
+    pub velocity: [i16; 3],
+    pub rotation: u16,
+}
+
+impl QuantizedState {
+    pub fn of(entity: &Entity) -> Self {
+        return Self {
+            position: quantize_position(&entity.position),
+            velocity: quantize_velocity(&entity.velocity),
+            rotation: quantize_rotation(entity.rotation),
+        };
+    }
+
+    //Sets the entity's components to this state
+    pub fn restore(&self, entity: &mut Entity) {
+        entity.position = self.position();
+        entity.velocity = self.velocity();
+        entity.rotation = self.rotation();
+    }
+
+    //Flags of every component that differs from the other state
+    pub fn changes(&self, other: &QuantizedState) -> u8 {
+        let mut mask = 0;
+        if self.position != other.position {
+            mask |= POSITION;
+        }
+        if self.velocity != other.velocity {
+            mask |= VELOCITY;
+        }
+        if self.rotation != other.rotation {
+            mask |= ROTATION;
+        }
+        return mask;
+    }
+
+    pub fn position(&self) -> Vector3<f32> {
+        return Vector3::new(self.position[0] as f32 / POSITION_SCALE,
+                            self.position[1] as f32 / POSITION_SCALE,
+                            self.position[2] as f32 / POSITION_SCALE);
+    }
+
+    pub fn velocity(&self) -> Vector3<f32> {
+        return Vector3::new(self.velocity[0] as f32 / VELOCITY_SCALE,
+                            self.velocity[1] as f32 / VELOCITY_SCALE,
+                            self.velocity[2] as f32 / VELOCITY_SCALE);
+    }
+
+    pub fn rotation(&self) -> f32 {
+        return self.rotation as f32 / (u16::MAX as f32 + 1.0) * TAU;
+    }
+}
+
+pub fn quantize_position(position: &Vector3<f32>) -> [i32; 3] {
+    return [(position.x * POSITION_SCALE).round() as i32,
+        (position.y * POSITION_SCALE).round() as i32,
+        (position.z * POSITION_SCALE).round() as i32];
+}
+
+pub fn quantize_velocity(velocity: &Vector3<f32>) -> [i16; 3] {
+    //Float to int casts saturate, so huge velocities clamp instead of wrapping
+    return [(velocity.x * VELOCITY_SCALE).round() as i16,
+        (velocity.y * VELOCITY_SCALE).round() as i16,
+        (velocity.z * VELOCITY_SCALE).round() as i16];
+}
+
+pub fn quantize_rotation(rotation: f32) -> u16 {
+    let turns = rotation.rem_euclid(TAU) / TAU;
+    return ((turns * (u16::MAX as f32 + 1.0)).round() as u32 % (u16::MAX as u32 + 1)) as u16;
+}
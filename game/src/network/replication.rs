@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use anyhow::Error;
+use crate::network::packets::{EntityDelta, EntitySpawn, Snapshot};
+use crate::network::quantize::QuantizedState;
+use crate::world::world::WorldData;
+
+//Rough upper bound of a snapshot's header (packet id, tick, three counts)
+const SNAPSHOT_HEADER: usize = 16;
+
+pub struct ReplicationSettings {
+    //Maximum snapshot size sent to each client per update.
+    //Anything that doesn't fit is sent on a later update, oldest changes first.
+    pub bytes_per_update: usize,
+}
+
+impl Default for ReplicationSettings {
+    fn default() -> Self {
+        return Self {
+            bytes_per_update: 1200
+        };
+    }
+}
+
+//What one client can currently see, and the last state sent to it for each entity
+pub struct ReplicationView {
+    pub room: usize,
+    known: HashMap<u64, KnownEntity>,
+}
+
+struct KnownEntity {
+    state: QuantizedState,
+    last_sent: u64,
+}
+
+//Server side of replication. Works out the spawns, despawns and deltas each client needs.
+pub struct ReplicationServer {
+    settings: ReplicationSettings,
+    views: HashMap<u64, ReplicationView>,
+}
+
+impl ReplicationServer {
+    pub fn new(settings: ReplicationSettings) -> Self {
+        return Self {
+            settings,
+            views: HashMap::new(),
+        };
+    }
+
+    pub fn add_client(&mut self, client: u64, room: usize) {
+        self.views.insert(client, ReplicationView {
+            room,
+            known: HashMap::new(),
+        });
+    }
+
+    pub fn remove_client(&mut self, client: u64) {
+        self.views.remove(&client);
+    }
+
+    //Moves the client's interest to another room. Entities from the old room
+    //are despawned and the new room's are spawned over the next updates.
+    //Only moves the view, NetworkServer::set_room moves the client's player with it.
+    pub fn set_room(&mut self, client: u64, room: usize) {
+        if let Some(view) = self.views.get_mut(&client) {
+            view.room = room;
+        }
+    }
+
+    pub fn get_view(&self, client: u64) -> Option<&ReplicationView> {
+        return self.views.get(&client);
+    }
+
+    //Builds the next snapshot for the client, or None if nothing changed
+    pub fn snapshot(&mut self, client: u64, world: &WorldData) -> Option<Snapshot> {
+        let view = self.views.get_mut(&client)?;
+        let tick = world.tick();
+        let mut snapshot = Snapshot::new(tick);
+        let mut budget = Budget::new(self.settings.bytes_per_update.saturating_sub(SNAPSHOT_HEADER));
+
+        let mut visible = HashMap::new();
+        if let Some(room) = world.rooms().get(view.room) {
+            for entity in room.entities() {
+                visible.insert(entity.id(), entity);
+            }
+        }
+
+        //Despawns are tiny and free up client memory, so they go first
+        let mut despawns: Vec<u64> = view.known.keys().filter(|id| !visible.contains_key(id)).copied().collect();
+        despawns.sort();
+        for id in despawns {
+            if budget.take(varint_size(id)) {
+                view.known.remove(&id);
+                snapshot.despawns.push(id);
+            }
+        }
+
+        let mut spawns: Vec<u64> = visible.keys().filter(|id| !view.known.contains_key(id)).copied().collect();
+        spawns.sort();
+        for id in spawns {
+            let entity = visible[&id];
+            let spawn = EntitySpawn {
+                id,
+                entity_type: entity.entity_type.clone(),
+                state: QuantizedState::of(entity),
+            };
+            if budget.take(spawn.size()) {
+                view.known.insert(id, KnownEntity {
+                    state: spawn.state,
+                    last_sent: tick,
+                });
+                snapshot.spawns.push(spawn);
+            }
+        }
+
+        //Stalest entities first so a tight budget still updates everyone eventually
+        let mut deltas = Vec::new();
+        for (id, known) in &view.known {
+            let entity = match visible.get(id) {
+                Some(entity) => entity,
+                None => continue
+            };
+            if known.last_sent == tick {
+                continue;
+            }
+            let state = QuantizedState::of(entity);
+            let mask = state.changes(&known.state);
+            if mask != 0 {
+                deltas.push((known.last_sent, EntityDelta {
+                    id: *id,
+                    mask,
+                    state
+                }));
+            }
+        }
+        deltas.sort_by(|first, second| first.0.cmp(&second.0).then(first.1.id.cmp(&second.1.id)));
+        for (_, delta) in deltas {
+            if budget.take(delta.size()) {
+                let known = view.known.get_mut(&delta.id).unwrap();
+                delta.apply(&mut known.state);
+                known.last_sent = tick;
+                snapshot.deltas.push(delta);
+            }
+        }
+
+        if snapshot.is_empty() {
+            return None;
+        }
+        return Some(snapshot);
+    }
+}
+
+struct Budget {
+    remaining: usize,
+    used: bool,
+}
+
+impl Budget {
+    fn new(remaining: usize) -> Self {
+        return Self {
+            remaining,
+            used: false,
+        };
+    }
+
+    //The first message always fits, so a tiny budget can't stall replication forever
+    fn take(&mut self, size: usize) -> bool {
+        if size > self.remaining && self.used {
+            return false;
+        }
+        self.remaining = self.remaining.saturating_sub(size);
+        self.used = true;
+        return true;
+    }
+}
+
+fn varint_size(mut value: u64) -> usize {
+    let mut size = 1;
+    while value >= 0x80 {
+        value >>= 7;
+        size += 1;
+    }
+    return size;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplicatedEntity {
+    pub entity_type: String,
+    pub state: QuantizedState,
+}
+
+//Client side of replication, the client's copy of the entities it can see
+#[derive(Debug, Default, PartialEq)]
+pub struct ReplicatedWorld {
+    pub tick: u64,
+    entities: HashMap<u64, ReplicatedEntity>,
+}
+
+impl ReplicatedWorld {
+    pub fn new() -> Self {
+        return Self {
+            tick: 0,
+            entities: HashMap::new(),
+        };
+    }
+
+    pub fn apply(&mut self, snapshot: &Snapshot) -> Result<(), Error> {
+        self.tick = snapshot.tick;
+        for id in &snapshot.despawns {
+            self.entities.remove(id);
+        }
+        for spawn in &snapshot.spawns {
+            self.entities.insert(spawn.id, ReplicatedEntity {
+                entity_type: spawn.entity_type.clone(),
+                state: spawn.state,
+            });
+        }
+        for delta in &snapshot.deltas {
+            match self.entities.get_mut(&delta.id) {
+                Some(entity) => delta.apply(&mut entity.state),
+                None => return Err(Error::msg(format!("Delta for unknown entity {}", delta.id)))
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn get(&self, id: u64) -> Option<&ReplicatedEntity> {
+        return self.entities.get(&id);
+    }
+
+    pub fn entities(&self) -> &HashMap<u64, ReplicatedEntity> {
+        return &self.entities;
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::VecDeque;
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, Sender};
+use cgmath::Vector3;
+use crate::error;
+use crate::network::chat::{ChatChannel, ChatContent, ChatLimiter, ChatMessage, MAX_MESSAGE_LENGTH};
+use crate::network::packets::{ClientPacket, ServerPacket};
+use crate::network::quantize::QuantizedState;
+use crate::network::replication::{ReplicationServer, ReplicationSettings};
+use crate::network::transport::Transport;
+use crate::world::attachments::WorldAttachment;
+use crate::world::entities::entity::Entity;
+use crate::world::entities::player::PlayerInput;
+use crate::world::world::WorldData;
+
+//Inputs past this are rejected until the next update, so a client flooding inputs can't build up latency
+const MAX_QUEUED_INPUTS: usize = 8;
+
+//Decides who can join, checked when a client sends Join.
+//players is how many clients have already joined.
+pub trait JoinFilter: Send {
+    fn allow(&mut self, name: &str, players: usize) -> Result<(), String>;
+}
+
+pub struct Connection {
+    pub id: u64,
+    //Set once the client sends Join
+    pub name: Option<String>,
+    //The entity this client controls, spawned on join
+    pub player: Option<u64>,
+    transport: Box<dyn Transport>,
+    inputs: VecDeque<PlayerInput>,
+    last_input: Option<u32>,
+    //Inputs rejected since the last update, logged once per update
+    dropped_inputs: u32,
+    chat: ChatLimiter,
+    closed: bool,
+}
+
+//Server end of every connection. Attached to the world so it runs once per world update,
+//after the rooms have updated.
+pub struct NetworkServer {
+    next_id: u64,
+    spawn_room: usize,
+    connections: Vec<Connection>,
+    incoming: Receiver<Box<dyn Transport>>,
+    connector: Sender<Box<dyn Transport>>,
+    filter: Option<Box<dyn JoinFilter>>,
+    pub replication: ReplicationServer,
+    //Sent to every client when they join
+    pub motd: String,
+    //Copied for each new connection, by default 5 messages at once then one every 30 updates
+    pub chat_limit: ChatLimiter,
+    //Global chat starting with / from joined players, without the /. They aren't broadcast,
+    //whatever runs the server takes and runs them.
+    pub commands: Vec<(u64, String)>,
+}
+
+impl NetworkServer {
+    pub fn new(settings: ReplicationSettings) -> Self {
+        let (connector, incoming) = mpsc::channel();
+        return Self {
+            next_id: 0,
+            spawn_room: 0,
+            connections: Vec::new(),
+            incoming,
+            connector,
+            filter: None,
+            replication: ReplicationServer::new(settings),
+            motd: String::new(),
+            chat_limit: ChatLimiter::new(5, 30),
+            commands: Vec::new(),
+        };
+    }
+
+    //Listener threads send new connections through this, they're accepted on the next update
+    pub fn connector(&self) -> Sender<Box<dyn Transport>> {
+        return self.connector.clone();
+    }
+
+    pub fn connect(&mut self, transport: Box<dyn Transport>) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.connections.push(Connection {
+            id,
+            name: None,
+            player: None,
+            transport,
+            inputs: VecDeque::new(),
+            last_input: None,
+            dropped_inputs: 0,
+            chat: self.chat_limit.clone(),
+            closed: false,
+        });
+        return id;
+    }
+
+    pub fn set_filter(&mut self, filter: Box<dyn JoinFilter>) {
+        self.filter = Some(filter);
+    }
+
+    //The room players spawn in when they join, room 0 by default
+    pub fn set_spawn_room(&mut self, room: usize) {
+        self.spawn_room = room;
+    }
+
+    pub fn connections(&self) -> &Vec<Connection> {
+        return &self.connections;
+    }
+
+    //Finds the client that joined with this name
+    pub fn get_client(&self, name: &str) -> Option<u64> {
+        return self.connections.iter()
+            .find(|connection| !connection.closed && connection.name.as_deref() == Some(name))
+            .map(|connection| connection.id);
+    }
+
+    //Moves the client's player to another room, and what the client sees along with it
+    pub fn set_room(&mut self, client: u64, room: usize, world: &mut WorldData) {
+        if room >= world.rooms().len() {
+            error!("Tried to move client {} to missing room {}", client, room);
+            return;
+        }
+        let player = self.connections.iter().find(|connection| connection.id == client)
+            .and_then(|connection| connection.player);
+        if let Some(mut player) = player.and_then(|player| world.remove_entity(player)) {
+            player.room = room;
+            player.spawn(world);
+        }
+        self.replication.set_room(client, room);
+    }
+
+    pub fn send(&mut self, client: u64, packet: &ServerPacket) {
+        if let Some(connection) = self.connections.iter_mut().find(|connection| connection.id == client) {
+            Self::send_to(connection, packet);
+        }
+    }
+
+    pub fn disconnect(&mut self, client: u64, reason: &str) {
+        self.send(client, &ServerPacket::Disconnect { reason: reason.to_string() });
+        if let Some(connection) = self.connections.iter_mut().find(|connection| connection.id == client) {
+            connection.closed = true;
+        }
+    }
+
+    //Sends a chat message to every client that has joined
+    pub fn broadcast(&mut self, message: &ChatMessage) {
+        let packet = ServerPacket::Chat(message.clone());
+        for connection in &mut self.connections {
+            if !connection.closed && connection.name.is_some() {
+                Self::send_to(connection, &packet);
+            }
+        }
+    }
+
+    //Kicks everyone, their players are removed on the next tick
+    pub fn disconnect_all(&mut self, reason: &str) {
+        let clients: Vec<u64> = self.connections.iter().map(|connection| connection.id).collect();
+        for client in clients {
+            self.disconnect(client, reason);
+        }
+    }
+
+    pub fn tick(&mut self, world: &mut WorldData) {
+        while let Ok(transport) = self.incoming.try_recv() {
+            self.connect(transport);
+        }
+
+        for i in 0..self.connections.len() {
+            loop {
+                let connection = &mut self.connections[i];
+                if connection.closed {
+                    break;
+                }
+                let packet = match connection.transport.receive() {
+                    Ok(Some(packet)) => packet,
+                    Ok(None) => break,
+                    Err(_) => {
+                        connection.closed = true;
+                        break;
+                    }
+                };
+                match ClientPacket::decode(&packet) {
+                    Ok(packet) => self.handle_packet(i, packet, world),
+                    Err(error) => {
+                        error!("Bad packet from client {}: {}", connection.id, error);
+                        let id = connection.id;
+                        self.disconnect(id, "Malformed packet");
+                    }
+                }
+            }
+        }
+
+        //Inputs are applied after the world update, the same order clients predict in.
+        //Clients can update faster than the server, so the whole queue is applied,
+        //each input after the first getting its own entity update like it had on the client.
+        for connection in &mut self.connections {
+            if connection.dropped_inputs > 0 {
+                error!("Dropped {} inputs from client {}, it sent more than {} per update",
+                    connection.dropped_inputs, connection.id, MAX_QUEUED_INPUTS);
+                connection.dropped_inputs = 0;
+            }
+            if connection.inputs.is_empty() {
+                continue;
+            }
+            let player = match connection.player.and_then(|player| world.get_entity_mut(player)) {
+                Some(player) => player,
+                None => {
+                    connection.inputs.clear();
+                    continue;
+                }
+            };
+            for (i, input) in connection.inputs.drain(..).enumerate() {
+                if i > 0 {
+                    player.update();
+                }
+                input.apply(player);
+                connection.last_input = Some(input.sequence);
+            }
+            let state = QuantizedState::of(player);
+            Self::send_to(connection, &ServerPacket::PlayerState {
+                tick: world.tick(),
+                acked_input: connection.last_input.unwrap(),
+                state
+            });
+        }
+
+        for connection in &mut self.connections {
+            if connection.closed || connection.name.is_none() {
+                continue;
+            }
+            if let Some(snapshot) = self.replication.snapshot(connection.id, world) {
+                Self::send_to(connection, &ServerPacket::Snapshot(snapshot));
+            }
+        }
+
+        let replication = &mut self.replication;
+        let mut left = Vec::new();
+        self.connections.retain(|connection| {
+            if connection.closed {
+                replication.remove_client(connection.id);
+                if let Some(player) = connection.player {
+                    world.remove_entity(player);
+                }
+                if let Some(name) = &connection.name {
+                    left.push(name.clone());
+                }
+            }
+            return !connection.closed;
+        });
+        for name in left {
+            self.broadcast(&ChatMessage::system("chat.left", vec!(name)));
+        }
+    }
+
+    fn handle_packet(&mut self, index: usize, packet: ClientPacket, world: &mut WorldData) {
+        match packet {
+            ClientPacket::Join { name } => {
+                if self.connections[index].name.is_some() {
+                    return;
+                }
+                let id = self.connections[index].id;
+                if self.get_client(&name).is_some() {
+                    self.disconnect(id, "Name already in use");
+                    return;
+                }
+                let players = self.connections.iter().filter(|connection| connection.name.is_some()).count();
+                if let Some(filter) = &mut self.filter {
+                    if let Err(reason) = filter.allow(&name, players) {
+                        self.disconnect(id, &reason);
+                        return;
+                    }
+                }
+
+                let connection = &mut self.connections[index];
+                let joined = ChatMessage::system("chat.joined", vec!(name.clone()));
+                let player = Entity::new("player".to_string(), self.spawn_room, Vector3::new(0.0, 0.0, 0.0));
+                let player_id = player.id();
+                player.spawn(world);
+                connection.name = Some(name);
+                connection.player = Some(player_id);
+                self.replication.add_client(connection.id, self.spawn_room);
+                Self::send_to(connection, &ServerPacket::Welcome {
+                    client_id: connection.id,
+                    tick: world.tick(),
+                    player: player_id,
+                    room: self.spawn_room,
+                    motd: self.motd.clone()
+                });
+                self.broadcast(&joined);
+            }
+            ClientPacket::Leave => self.connections[index].closed = true,
+            ClientPacket::Input(input) => {
+                let connection = &mut self.connections[index];
+                if connection.player.is_none() || connection.last_input.is_some_and(|last| input.sequence <= last) {
+                    return;
+                }
+                if connection.inputs.len() == MAX_QUEUED_INPUTS {
+                    connection.dropped_inputs += 1;
+                    return;
+                }
+                connection.inputs.push_back(input);
+            }
+            ClientPacket::Chat { channel, text } => self.chat(index, channel, text, world.tick()),
+        }
+    }
+
+    fn chat(&mut self, index: usize, channel: ChatChannel, text: String, tick: u64) {
+        let connection = &mut self.connections[index];
+        let sender = match &connection.name {
+            Some(name) => name.clone(),
+            None => return
+        };
+        let id = connection.id;
+        let text = text.trim().to_string();
+        if text.is_empty() {
+            return;
+        }
+        if text.chars().count() > MAX_MESSAGE_LENGTH {
+            let reply = ChatMessage::system("chat.too_long", vec!(MAX_MESSAGE_LENGTH.to_string()));
+            Self::send_to(connection, &ServerPacket::Chat(reply));
+            return;
+        }
+        if !connection.chat.try_send(tick) {
+            Self::send_to(connection, &ServerPacket::Chat(ChatMessage::system("chat.rate_limited", Vec::new())));
+            return;
+        }
+
+        match channel {
+            ChatChannel::Global if text.starts_with('/') => self.commands.push((id, text[1..].to_string())),
+            ChatChannel::Global => self.broadcast(&ChatMessage {
+                channel: ChatChannel::Global,
+                sender: Some(sender),
+                content: ChatContent::Text(text)
+            }),
+            ChatChannel::Room => {
+                let room = self.replication.get_view(id).map(|view| view.room);
+                let packet = ServerPacket::Chat(ChatMessage {
+                    channel: ChatChannel::Room,
+                    sender: Some(sender),
+                    content: ChatContent::Text(text)
+                });
+                for connection in &mut self.connections {
+                    if !connection.closed && room.is_some()
+                        && self.replication.get_view(connection.id).map(|view| view.room) == room {
+                        Self::send_to(connection, &packet);
+                    }
+                }
+            }
+            ChatChannel::Whisper(target) => {
+                let reply = match self.get_client(&target) {
+                    Some(client) => {
+                        self.send(client, &ServerPacket::Chat(ChatMessage {
+                            channel: ChatChannel::Whisper(sender.clone()),
+                            sender: Some(sender),
+                            content: ChatContent::Text(text.clone())
+                        }));
+                        ChatMessage::system("chat.whisper_sent", vec!(target, text))
+                    }
+                    None => ChatMessage::system("chat.player_not_found", vec!(target))
+                };
+                self.send(id, &ServerPacket::Chat(reply));
+            }
+        }
+    }
+
+    fn send_to(connection: &mut Connection, packet: &ServerPacket) {
+        if connection.transport.send(packet.encode()).is_err() {
+            connection.closed = true;
+        }
+    }
+}
+
+impl WorldAttachment for NetworkServer {
+    fn update(&mut self, world: &mut WorldData) {
+        self.tick(world);
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+use std::time::{Duration, Instant};
+use anyhow::Error;
+
+//A reliable, ordered, message-based connection to the other side.
+//Receiving never blocks, it returns None if nothing has arrived yet.
+pub trait Transport: Send {
+    fn send(&mut self, packet: Vec<u8>) -> Result<(), Error>;
+
+    fn receive(&mut self) -> Result<Option<Vec<u8>>, Error>;
+}
+
+//Largest packet a TCP peer may send, anything bigger is treated as a broken connection
+const MAX_TCP_PACKET: usize = 1 << 20;
+
+//In-process transport, used for singleplayer and tests
+pub struct LoopbackTransport {
+    sender: Sender<(Instant, Vec<u8>)>,
+    receiver: Receiver<(Instant, Vec<u8>)>,
+    //Artificial one-way delay for testing prediction and interpolation
+    latency: Duration,
+    //Packets that arrived but are still being delayed
+    delayed: VecDeque<(Instant, Vec<u8>)>,
+    closed: bool,
+}
+
+impl LoopbackTransport {
+    //Returns both ends of a connection
+    pub fn pair() -> (Self, Self) {
+        return Self::pair_with_latency(Duration::ZERO);
+    }
+
+    //Returns both ends of a connection where every packet takes latency to arrive
+    pub fn pair_with_latency(latency: Duration) -> (Self, Self) {
+        let (first_sender, second_receiver) = mpsc::channel();
+        let (second_sender, first_receiver) = mpsc::channel();
+        return (Self::new(first_sender, first_receiver, latency),
+                Self::new(second_sender, second_receiver, latency));
+    }
+
+    fn new(sender: Sender<(Instant, Vec<u8>)>, receiver: Receiver<(Instant, Vec<u8>)>, latency: Duration) -> Self {
+        return Self {
+            sender,
+            receiver,
+            latency,
+            delayed: VecDeque::new(),
+            closed: false,
+        };
+    }
+}
+
+impl Transport for LoopbackTransport {
+    fn send(&mut self, packet: Vec<u8>) -> Result<(), Error> {
+        self.sender.send((Instant::now() + self.latency, packet))
+            .map_err(|_| Error::msg("Loopback connection closed"))?;
+        return Ok(());
+    }
+
+    fn receive(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(packet) => self.delayed.push_back(packet),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.closed = true;
+                    break;
+                }
+            }
+        }
+
+        //Every packet has the same delay, so they become ready in order
+        if let Some((ready, _)) = self.delayed.front() {
+            if *ready <= Instant::now() {
+                return Ok(self.delayed.pop_front().map(|(_, packet)| packet));
+            }
+            return Ok(None);
+        }
+        if self.closed {
+            return Err(Error::msg("Loopback connection closed"));
+        }
+        return Ok(None);
+    }
+}
+
+//Transport over a non-blocking TCP stream. Each packet is prefixed with its length as a u32.
+pub struct TcpTransport {
+    stream: TcpStream,
+    //Bytes read that aren't a full packet yet
+    incoming: Vec<u8>,
+    //Bytes the socket wasn't ready to take yet
+    outgoing: Vec<u8>,
+    //Set once the other side hangs up, packets that already arrived are still returned
+    closed: bool,
+}
+
+impl TcpTransport {
+    pub fn new(stream: TcpStream) -> Result<Self, Error> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        return Ok(Self {
+            stream,
+            incoming: Vec::new(),
+            outgoing: Vec::new(),
+            closed: false,
+        });
+    }
+
+    pub fn connect(address: impl ToSocketAddrs) -> Result<Self, Error> {
+        return Self::new(TcpStream::connect(address)?);
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err(Error::msg("TCP connection closed")),
+                Ok(written) => {
+                    self.outgoing.drain(..written);
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error.into())
+            }
+        }
+        return Ok(());
+    }
+}
+
+impl Transport for TcpTransport {
+    fn send(&mut self, packet: Vec<u8>) -> Result<(), Error> {
+        self.outgoing.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+        self.outgoing.extend_from_slice(&packet);
+        return self.flush();
+    }
+
+    fn receive(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        if !self.closed {
+            self.flush()?;
+        }
+        let mut buffer = [0; 4096];
+        while !self.closed {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => self.closed = true,
+                Ok(read) => self.incoming.extend_from_slice(&buffer[..read]),
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error.into())
+            }
+        }
+
+        //Wait until both the length and the whole packet have arrived
+        let complete = match self.incoming.get(..4) {
+            Some(prefix) => {
+                let length = u32::from_le_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as usize;
+                if length > MAX_TCP_PACKET {
+                    return Err(Error::msg(format!("TCP packet too large: {} bytes", length)));
+                }
+                if self.incoming.len() >= 4 + length { Some(length) } else { None }
+            }
+            None => None
+        };
+        let length = match complete {
+            Some(length) => length,
+            None if self.closed => return Err(Error::msg("TCP connection closed")),
+            None => return Ok(None)
+        };
+        let packet = self.incoming[4..4 + length].to_vec();
+        self.incoming.drain(..4 + length);
+        return Ok(Some(packet));
+    }
+}
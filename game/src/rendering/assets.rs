@@ -3,7 +3,8 @@ use crate::rendering::GameTexture;
 use crate::rendering::mesh::Mesh;
 
 pub trait AssetReference: Send {
-    fn get_texture(&self, name: &String) -> Option<Arc<dyn GameTexture>>;
+    //Textures that are missing or failed to load are a MissingTexture, so they still draw something
+    fn get_texture(&self, name: &String) -> Arc<dyn GameTexture>;
 
     fn get_model(&self, name: &String) -> Option<Arc<Mesh>>;
 }
\ No newline at end of file
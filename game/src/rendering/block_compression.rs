@@ -0,0 +1,333 @@
+use macros::{JsonLoadable, JsonSaveable};
+
+//Block compressed formats textures can be in, which store each 4x4 block of pixels in a fixed number of bytes.
+//GPUs with BC support read them as they are, anything else decodes them to RGBA8 with decode_image.
+#[derive(JsonLoadable, JsonSaveable, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlockFormat {
+    //Color with 1 bit alpha, in 8 bytes
+    #[default]
+    Bc1,
+    //Color with 4 bit alpha
+    Bc2,
+    //Color with smoothly interpolated alpha
+    Bc3,
+    //Color and alpha, with a mode chosen for each block
+    Bc7
+}
+
+impl BlockFormat {
+    //Bytes each block takes
+    pub fn block_size(&self) -> usize {
+        return match self {
+            Self::Bc1 => 8,
+            Self::Bc2 | Self::Bc3 | Self::Bc7 => 16
+        };
+    }
+
+    //Bytes an image this size takes, counting partial blocks at the edges as whole ones
+    pub fn image_size(&self, width: u32, height: u32) -> usize {
+        return width.div_ceil(4) as usize * height.div_ceil(4) as usize * self.block_size();
+    }
+}
+
+//Decodes a whole image of blocks to RGBA8. The data has to be at least image_size long.
+pub fn decode_image(format: BlockFormat, width: u32, height: u32, data: &[u8]) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let blocks_wide = width.div_ceil(4);
+    let mut output = vec!(0; width * height * 4);
+    for (index, block) in data.chunks_exact(format.block_size()).take(blocks_wide * height.div_ceil(4)).enumerate() {
+        let pixels = decode_block(format, block);
+        let (block_x, block_y) = (index % blocks_wide * 4, index / blocks_wide * 4);
+        for (pixel, color) in pixels.iter().enumerate() {
+            let (x, y) = (block_x + pixel % 4, block_y + pixel / 4);
+            //Blocks at the edges can go past the image
+            if x < width && y < height {
+                output[(y * width + x) * 4..(y * width + x) * 4 + 4].copy_from_slice(color);
+            }
+        }
+    }
+    return output;
+}
+
+//The 16 pixels of a block, left to right then top to bottom
+pub fn decode_block(format: BlockFormat, block: &[u8]) -> [[u8; 4]; 16] {
+    return match format {
+        BlockFormat::Bc1 => color_block(block, true),
+        BlockFormat::Bc2 => {
+            let mut pixels = color_block(&block[8..], false);
+            let alpha = u64::from_le_bytes(block[..8].try_into().unwrap());
+            for (pixel, color) in pixels.iter_mut().enumerate() {
+                color[3] = ((alpha >> (pixel * 4)) & 0xF) as u8 * 17;
+            }
+            pixels
+        }
+        BlockFormat::Bc3 => {
+            let mut pixels = color_block(&block[8..], false);
+            for (pixel, alpha) in alpha_block(&block[..8]).into_iter().enumerate() {
+                pixels[pixel][3] = alpha;
+            }
+            pixels
+        }
+        BlockFormat::Bc7 => bc7_block(block)
+    };
+}
+
+fn rgb565(color: u16) -> [u32; 3] {
+    let (red, green, blue) = ((color >> 11) as u32 & 31, (color >> 5) as u32 & 63, color as u32 & 31);
+    return [red << 3 | red >> 2, green << 2 | green >> 4, blue << 3 | blue >> 2];
+}
+
+//Two RGB565 colors, then two bits per pixel picking one of them or a color between them.
+//BC1 blocks with the first color not above the second have a transparent black instead of a third color between them.
+fn color_block(block: &[u8], transparency: bool) -> [[u8; 4]; 16] {
+    let (first, second) = (u16::from_le_bytes([block[0], block[1]]), u16::from_le_bytes([block[2], block[3]]));
+    let (start, end) = (rgb565(first), rgb565(second));
+    let mut palette = [[0u8; 4]; 4];
+    for channel in 0..3 {
+        palette[0][channel] = start[channel] as u8;
+        palette[1][channel] = end[channel] as u8;
+        if first > second || !transparency {
+            palette[2][channel] = ((2 * start[channel] + end[channel]) / 3) as u8;
+            palette[3][channel] = ((start[channel] + 2 * end[channel]) / 3) as u8;
+        } else {
+            palette[2][channel] = ((start[channel] + end[channel]) / 2) as u8;
+        }
+    }
+    palette[0][3] = 255;
+    palette[1][3] = 255;
+    palette[2][3] = 255;
+    palette[3][3] = if first > second || !transparency { 255 } else { 0 };
+
+    let indices = u32::from_le_bytes(block[4..8].try_into().unwrap());
+    return std::array::from_fn(|pixel| palette[(indices >> (pixel * 2)) as usize & 3]);
+}
+
+//Two alphas, then three bits per pixel picking one of 8 alphas between them, or 6 and fully transparent and opaque
+fn alpha_block(block: &[u8]) -> [u8; 16] {
+    let (first, second) = (block[0] as u32, block[1] as u32);
+    let mut palette = [first, second, 0, 0, 0, 0, 0, 255];
+    if first > second {
+        for step in 1..7 {
+            palette[step + 1] = ((7 - step as u32) * first + step as u32 * second) / 7;
+        }
+    } else {
+        for step in 1..5 {
+            palette[step + 1] = ((5 - step as u32) * first + step as u32 * second) / 5;
+        }
+    }
+    let mut indices = [0; 8];
+    indices[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(indices);
+    return std::array::from_fn(|pixel| palette[(indices >> (pixel * 3)) as usize & 7] as u8);
+}
+
+//Reads a BC7 block's fields from its lowest bit up
+struct Bits {
+    value: u128,
+    position: u32
+}
+
+impl Bits {
+    fn read(&mut self, count: u32) -> u32 {
+        let value = (self.value >> self.position) as u32 & ((1 << count) - 1);
+        self.position += count;
+        return value;
+    }
+}
+
+//How each of BC7's 8 modes splits up its block
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    //A p-bit for each endpoint, or for each subset's two endpoints
+    endpoint_pbits: bool,
+    shared_pbits: bool,
+    index_bits: u32,
+    //Modes 4 and 5 have a second set of indices, for color or alpha
+    index_bits2: u32
+}
+
+const BC7_MODES: [Bc7Mode; 8] = [
+    Bc7Mode { subsets: 3, partition_bits: 4, rotation_bits: 0, selection_bits: 0, color_bits: 4, alpha_bits: 0,
+        endpoint_pbits: true, shared_pbits: false, index_bits: 3, index_bits2: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, selection_bits: 0, color_bits: 6, alpha_bits: 0,
+        endpoint_pbits: false, shared_pbits: true, index_bits: 3, index_bits2: 0 },
+    Bc7Mode { subsets: 3, partition_bits: 6, rotation_bits: 0, selection_bits: 0, color_bits: 5, alpha_bits: 0,
+        endpoint_pbits: false, shared_pbits: false, index_bits: 2, index_bits2: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, selection_bits: 0, color_bits: 7, alpha_bits: 0,
+        endpoint_pbits: true, shared_pbits: false, index_bits: 2, index_bits2: 0 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, selection_bits: 1, color_bits: 5, alpha_bits: 6,
+        endpoint_pbits: false, shared_pbits: false, index_bits: 2, index_bits2: 3 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, selection_bits: 0, color_bits: 7, alpha_bits: 8,
+        endpoint_pbits: false, shared_pbits: false, index_bits: 2, index_bits2: 2 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 0, selection_bits: 0, color_bits: 7, alpha_bits: 7,
+        endpoint_pbits: true, shared_pbits: false, index_bits: 4, index_bits2: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, selection_bits: 0, color_bits: 5, alpha_bits: 5,
+        endpoint_pbits: true, shared_pbits: false, index_bits: 2, index_bits2: 0 }
+];
+
+//Which subset each pixel of the two subset partitions is in, a bit for each pixel
+const PARTITIONS_2: [u16; 64] = [
+    0xCCCC, 0x8888, 0xEEEE, 0xECC8, 0xC880, 0xFEEC, 0xFEC8, 0xEC80, 0xC800, 0xFFEC, 0xFE80, 0xE800, 0xFFE8, 0xFF00, 0xFFF0, 0xF000,
+    0xF710, 0x008E, 0x7100, 0x08CE, 0x008C, 0x7310, 0x3100, 0x8CCE, 0x088C, 0x3110, 0x6666, 0x366C, 0x17E8, 0x0FF0, 0x718E, 0x399C,
+    0xAAAA, 0xF0F0, 0x5A5A, 0x33CC, 0x3C3C, 0x55AA, 0x9696, 0xA55A, 0x73CE, 0x13C8, 0x324C, 0x3BDC, 0x6996, 0xC33C, 0x9966, 0x0660,
+    0x0272, 0x04E4, 0x4E40, 0x2720, 0xC936, 0x936C, 0x39C6, 0x639C, 0x9336, 0x9CC6, 0x817E, 0xE718, 0xCCF0, 0x0FCC, 0x7744, 0xEE22
+];
+
+//Which subset each pixel of the three subset partitions is in
+const PARTITIONS_3: [[u8; 16]; 64] = [
+    [0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 1, 2, 2, 2, 2], [0, 0, 0, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 2, 0, 0, 1, 2, 2, 1, 1, 2, 2, 1, 1], [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 1, 0, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2], [0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 2, 2],
+    [0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1], [0, 0, 1, 1, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1],
+    [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2], [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2], [0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2],
+    [0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2, 0, 1, 1, 2], [0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2, 0, 1, 2, 2],
+    [0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2, 1, 2, 2, 2], [0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0, 2, 2, 2, 0],
+    [0, 0, 0, 1, 0, 0, 1, 1, 0, 1, 1, 2, 1, 1, 2, 2], [0, 1, 1, 1, 0, 0, 1, 1, 2, 0, 0, 1, 2, 2, 0, 0],
+    [0, 0, 0, 0, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2], [0, 0, 2, 2, 0, 0, 2, 2, 0, 0, 2, 2, 1, 1, 1, 1],
+    [0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2, 0, 2, 2, 2], [0, 0, 0, 1, 0, 0, 0, 1, 2, 2, 2, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2], [0, 0, 0, 0, 1, 1, 0, 0, 2, 2, 1, 0, 2, 2, 1, 0],
+    [0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1, 0, 0, 0, 0], [0, 0, 1, 2, 0, 0, 1, 2, 1, 1, 2, 2, 2, 2, 2, 2],
+    [0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1, 0, 1, 1, 0], [0, 0, 0, 0, 0, 1, 1, 0, 1, 2, 2, 1, 1, 2, 2, 1],
+    [0, 0, 2, 2, 1, 1, 0, 2, 1, 1, 0, 2, 0, 0, 2, 2], [0, 1, 1, 0, 0, 1, 1, 0, 2, 0, 0, 2, 2, 2, 2, 2],
+    [0, 0, 1, 1, 0, 1, 2, 2, 0, 1, 2, 2, 0, 0, 1, 1], [0, 0, 0, 0, 2, 0, 0, 0, 2, 2, 1, 1, 2, 2, 2, 1],
+    [0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 2, 2, 2], [0, 2, 2, 2, 0, 0, 2, 2, 0, 0, 1, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 0, 0, 1, 2, 0, 0, 2, 2, 0, 2, 2, 2], [0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0, 0, 1, 2, 0],
+    [0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0], [0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0],
+    [0, 1, 2, 0, 2, 0, 1, 2, 1, 2, 0, 1, 0, 1, 2, 0], [0, 0, 1, 1, 2, 2, 0, 0, 1, 1, 2, 2, 0, 0, 1, 1],
+    [0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 0, 0, 0, 0, 1, 1], [0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2],
+    [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1], [0, 0, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2, 1, 1, 2, 2],
+    [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 2, 2, 0, 0, 1, 1], [0, 2, 2, 0, 1, 2, 2, 1, 0, 2, 2, 0, 1, 2, 2, 1],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 0, 1, 0, 1], [0, 0, 0, 0, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1, 2, 1],
+    [0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 2, 2, 2, 2], [0, 2, 2, 2, 0, 1, 1, 1, 0, 2, 2, 2, 0, 1, 1, 1],
+    [0, 0, 0, 2, 1, 1, 1, 2, 0, 0, 0, 2, 1, 1, 1, 2], [0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 2, 2, 2, 0, 1, 1, 1, 0, 1, 1, 1, 0, 2, 2, 2], [0, 0, 0, 2, 1, 1, 1, 2, 1, 1, 1, 2, 0, 0, 0, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2], [0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2, 2, 1, 1, 2],
+    [0, 1, 1, 0, 0, 1, 1, 0, 2, 2, 2, 2, 2, 2, 2, 2], [0, 0, 2, 2, 0, 0, 1, 1, 0, 0, 1, 1, 0, 0, 2, 2],
+    [0, 0, 2, 2, 1, 1, 2, 2, 1, 1, 2, 2, 0, 0, 2, 2], [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 1, 1, 2],
+    [0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 1], [0, 2, 2, 2, 1, 2, 2, 2, 0, 2, 2, 2, 1, 2, 2, 2],
+    [0, 1, 0, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2], [0, 1, 1, 1, 2, 0, 1, 1, 2, 2, 0, 1, 2, 2, 2, 0]
+];
+
+//The pixel whose index has one less bit, for the second subset of two subset partitions
+const ANCHORS_2: [usize; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15,
+    15, 2, 8, 2, 2, 8, 8, 15, 2, 8, 2, 2, 8, 8, 2, 2,
+    15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6,
+    6, 2, 6, 8, 15, 15, 2, 2, 15, 15, 15, 15, 15, 2, 2, 15
+];
+
+//The same for the second and third subsets of three subset partitions
+const ANCHORS_3: [[usize; 2]; 64] = [
+    [3, 15], [3, 8], [15, 8], [15, 3], [8, 15], [3, 15], [15, 3], [15, 8],
+    [8, 15], [8, 15], [6, 15], [6, 15], [6, 15], [5, 15], [3, 15], [3, 8],
+    [3, 15], [3, 8], [8, 15], [15, 3], [3, 15], [3, 8], [6, 15], [10, 8],
+    [5, 3], [8, 15], [8, 6], [6, 10], [8, 15], [5, 15], [15, 10], [15, 8],
+    [8, 15], [15, 3], [3, 15], [5, 10], [6, 10], [10, 8], [8, 9], [15, 10],
+    [15, 6], [3, 15], [15, 8], [5, 15], [15, 3], [15, 6], [15, 6], [15, 8],
+    [3, 15], [15, 3], [5, 15], [5, 15], [5, 15], [8, 15], [5, 15], [10, 15],
+    [5, 15], [10, 15], [8, 15], [13, 15], [15, 3], [12, 15], [3, 15], [3, 8]
+];
+
+const WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+fn interpolate(start: u32, end: u32, index: u32, bits: u32) -> u8 {
+    let weight = match bits {
+        2 => WEIGHTS_2[index as usize],
+        3 => WEIGHTS_3[index as usize],
+        _ => WEIGHTS_4[index as usize]
+    };
+    return (((64 - weight) * start + weight * end + 32) >> 6) as u8;
+}
+
+//The block's mode is the number of zero bits before its first one. Blocks without one are transparent black.
+fn bc7_block(block: &[u8]) -> [[u8; 4]; 16] {
+    let value = u128::from_le_bytes(block[..16].try_into().unwrap());
+    let mode = match BC7_MODES.get(value.trailing_zeros() as usize) {
+        Some(mode) => mode,
+        None => return [[0; 4]; 16]
+    };
+    let mut bits = Bits { value, position: value.trailing_zeros() + 1 };
+    let partition = bits.read(mode.partition_bits) as usize;
+    let rotation = bits.read(mode.rotation_bits);
+    let selection = bits.read(mode.selection_bits);
+
+    //Each subset's two endpoints, red then green, blue and alpha for every endpoint
+    let endpoints = mode.subsets * 2;
+    let mut colors = [[0u32; 4]; 6];
+    for channel in 0..3 {
+        for color in colors.iter_mut().take(endpoints) {
+            color[channel] = bits.read(mode.color_bits);
+        }
+    }
+    for color in colors.iter_mut().take(endpoints) {
+        color[3] = bits.read(mode.alpha_bits);
+    }
+    let mut precision = [mode.color_bits, mode.color_bits, mode.color_bits, mode.alpha_bits];
+    if mode.endpoint_pbits || mode.shared_pbits {
+        let pbits = if mode.endpoint_pbits { endpoints } else { mode.subsets };
+        let read = (0..pbits).map(|_| bits.read(1)).collect::<Vec<_>>();
+        for (endpoint, color) in colors.iter_mut().take(endpoints).enumerate() {
+            let pbit = if mode.endpoint_pbits { read[endpoint] } else { read[endpoint / 2] };
+            for channel in color.iter_mut() {
+                *channel = *channel << 1 | pbit;
+            }
+        }
+        for channel in precision.iter_mut() {
+            *channel += 1;
+        }
+    }
+    //Endpoints are stretched to 8 bits by repeating their top bits
+    for color in colors.iter_mut().take(endpoints) {
+        for channel in 0..4 {
+            color[channel] = match mode.alpha_bits == 0 && channel == 3 {
+                true => 255,
+                false => (color[channel] << (8 - precision[channel])) | (color[channel] << (8 - precision[channel]) >> precision[channel])
+            };
+        }
+    }
+
+    let subset = |pixel: usize| match mode.subsets {
+        1 => 0,
+        2 => (PARTITIONS_2[partition] >> pixel) as usize & 1,
+        _ => PARTITIONS_3[partition][pixel] as usize
+    };
+    //The first pixel of each subset has one less index bit, since its top bit is always 0
+    let anchor = |pixel: usize| pixel == 0 || match mode.subsets {
+        2 => pixel == ANCHORS_2[partition],
+        3 => ANCHORS_3[partition].contains(&pixel),
+        _ => false
+    };
+    let indices = std::array::from_fn::<_, 16, _>(|pixel| bits.read(mode.index_bits - anchor(pixel) as u32));
+    let indices2 = match mode.index_bits2 {
+        0 => indices,
+        index_bits => std::array::from_fn(|pixel| bits.read(index_bits - (pixel == 0) as u32))
+    };
+    //Mode 4 and 5's second indices are for alpha, or for color if mode 4's selection bit is set
+    let (color_indices, color_bits, alpha_indices, alpha_bits) = match (mode.index_bits2, selection) {
+        (0, _) => (indices, mode.index_bits, indices, mode.index_bits),
+        (_, 1) => (indices2, mode.index_bits2, indices, mode.index_bits),
+        _ => (indices, mode.index_bits, indices2, mode.index_bits2)
+    };
+
+    return std::array::from_fn(|pixel| {
+        let (start, end) = (colors[subset(pixel) * 2], colors[subset(pixel) * 2 + 1]);
+        let mut color = [0u8; 4];
+        for channel in 0..3 {
+            color[channel] = interpolate(start[channel], end[channel], color_indices[pixel], color_bits);
+        }
+        color[3] = interpolate(start[3], end[3], alpha_indices[pixel], alpha_bits);
+        //Modes 4 and 5 can swap alpha with a color channel, so it gets the more precise endpoints
+        if rotation > 0 {
+            color.swap(rotation as usize - 1, 3);
+        }
+        return color;
+    });
+}
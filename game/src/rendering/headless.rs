@@ -0,0 +1,31 @@
+use std::sync::Arc;
+use crate::rendering::assets::AssetReference;
+use crate::rendering::GameTexture;
+use crate::rendering::mesh::{FrameData, Mesh};
+use crate::rendering::renderer::Renderer;
+use crate::rendering::texture::MissingTexture;
+
+//For a ResourceManager without a window, like ctl-validate's and tests'. Types are only loaded, so nothing is rendered.
+pub struct NoAssets;
+
+impl AssetReference for NoAssets {
+    fn get_texture(&self, _name: &String) -> Arc<dyn GameTexture> {
+        return Arc::new(MissingTexture::new());
+    }
+
+    fn get_model(&self, _name: &String) -> Option<Arc<Mesh>> {
+        return None;
+    }
+}
+
+pub struct NoRenderer;
+
+impl Renderer for NoRenderer {
+    fn push(&self, _mesh: Arc<Mesh>, _texture: Arc<dyn GameTexture>, _data: FrameData) -> u64 {
+        return 0;
+    }
+
+    fn update(&self, _id: u64, _data: FrameData) {}
+
+    fn clear(&self, _id: u64) {}
+}
@@ -0,0 +1,184 @@
+use std::io::Read;
+use anyhow::Error;
+use image::{ImageFormat, RgbaImage};
+use ktx2::{Format, SupercompressionScheme};
+use crate::rendering::block_compression::{self, BlockFormat};
+
+const KTX2_MAGIC: &[u8] = &[0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n'];
+
+//Image formats textures can be in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureFormat {
+    Png,
+    Jpeg,
+    Tga,
+    //Pixels or compressed blocks ready for the GPU, optionally zstd compressed
+    Ktx2
+}
+
+impl TextureFormat {
+    //The format a file's extension says it is, if it's a texture
+    pub fn from_path(file: &str) -> Option<Self> {
+        let extension = file.rsplit_once('.')?.1.to_lowercase();
+        return match extension.as_str() {
+            "png" => Some(Self::Png),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "tga" => Some(Self::Tga),
+            "ktx2" => Some(Self::Ktx2),
+            _ => None
+        };
+    }
+
+    //The format the bytes start like, or the file's extension if they don't start like any.
+    //TGA files have no signature, so they're only found by their extension.
+    pub fn detect(file: &str, bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.starts_with(KTX2_MAGIC) {
+            return Ok(Self::Ktx2);
+        }
+        return match image::guess_format(bytes) {
+            Ok(ImageFormat::Png) => Ok(Self::Png),
+            Ok(ImageFormat::Jpeg) => Ok(Self::Jpeg),
+            Ok(format) => Err(Error::msg(format!("{:?} textures aren't supported, use PNG, JPEG, TGA or KTX2", format))),
+            Err(_) => Self::from_path(file)
+                .ok_or_else(|| Error::msg("Unknown image format, use PNG, JPEG, TGA or KTX2"))
+        };
+    }
+}
+
+//Whether the file is a texture, by its extension
+pub fn is_texture(file: &str) -> bool {
+    return TextureFormat::from_path(file).is_some();
+}
+
+//A texture as it was read, with block compressed ones left compressed
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReadTexture {
+    Pixels(RgbaImage),
+    Compressed(CompressedImage)
+}
+
+impl ReadTexture {
+    //Its pixels as RGBA8, decoding it if it's compressed
+    pub fn into_pixels(self) -> RgbaImage {
+        return match self {
+            Self::Pixels(image) => image,
+            Self::Compressed(image) => image.decode()
+        };
+    }
+}
+
+//A block compressed image with its mip levels, largest first
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompressedImage {
+    pub format: BlockFormat,
+    pub width: u32,
+    pub height: u32,
+    pub levels: Vec<Vec<u8>>
+}
+
+impl CompressedImage {
+    //Checks the image is whole blocks, which GPUs need, and each level is as long as its size needs
+    pub fn new(format: BlockFormat, width: u32, height: u32, levels: Vec<Vec<u8>>) -> Result<Self, Error> {
+        if width == 0 || height == 0 || !width.is_multiple_of(4) || !height.is_multiple_of(4) {
+            return Err(Error::msg(format!("Block compressed textures have to be a multiple of 4 pixels wide and high, not {}x{}",
+                                          width, height)));
+        }
+        if levels.is_empty() {
+            return Err(Error::msg("Block compressed textures need at least one level"));
+        }
+        let image = Self { format, width, height, levels };
+        for (level, data) in image.levels.iter().enumerate() {
+            let (width, height) = image.level_size(level);
+            if data.len() != format.image_size(width, height) {
+                return Err(Error::msg(format!("Level {} is {} bytes, {:?} at {}x{} is {}",
+                                              level, data.len(), format, width, height, format.image_size(width, height))));
+            }
+        }
+        return Ok(image);
+    }
+
+    //Size of the level, each half the one before
+    pub fn level_size(&self, level: usize) -> (u32, u32) {
+        return ((self.width >> level).max(1), (self.height >> level).max(1));
+    }
+
+    //The largest level decoded to RGBA8, for GPUs that can't read the format
+    pub fn decode(&self) -> RgbaImage {
+        return RgbaImage::from_raw(self.width, self.height,
+                                   block_compression::decode_image(self.format, self.width, self.height, &self.levels[0])).unwrap();
+    }
+
+    //Bytes every level takes together
+    pub fn size(&self) -> usize {
+        return self.levels.iter().map(Vec::len).sum();
+    }
+}
+
+//Reads a texture in any supported format, named file for finding its format. Block compressed KTX2s stay compressed.
+pub fn read(file: &str, bytes: &[u8]) -> Result<ReadTexture, Error> {
+    return Ok(match TextureFormat::detect(file, bytes)? {
+        TextureFormat::Png => ReadTexture::Pixels(image::load_from_memory_with_format(bytes, ImageFormat::Png)?.to_rgba8()),
+        TextureFormat::Jpeg => ReadTexture::Pixels(image::load_from_memory_with_format(bytes, ImageFormat::Jpeg)?.to_rgba8()),
+        TextureFormat::Tga => ReadTexture::Pixels(image::load_from_memory_with_format(bytes, ImageFormat::Tga)?.to_rgba8()),
+        TextureFormat::Ktx2 => read_ktx2(bytes)?
+    });
+}
+
+//Decodes a texture in any supported format to RGBA8, named file for finding its format
+pub fn decode(file: &str, bytes: &[u8]) -> Result<RgbaImage, Error> {
+    return Ok(read(file, bytes)?.into_pixels());
+}
+
+//KTX2s are 8 bit RGBA or BGRA, of which only the largest level is read, or BC1, BC2, BC3 or BC7 with all their levels.
+//Textures are drawn as sRGB, so UNORM and SRGB formats are read the same.
+fn read_ktx2(bytes: &[u8]) -> Result<ReadTexture, Error> {
+    let reader = ktx2::Reader::new(bytes).map_err(|error| Error::msg(format!("Invalid KTX2: {:?}", error)))?;
+    let header = reader.header();
+    if header.pixel_depth > 1 || header.layer_count > 1 || header.face_count > 1 {
+        return Err(Error::msg("KTX2 textures have to be a single 2D image, not a volume, array or cube map"));
+    }
+    let (width, height) = (header.pixel_width, header.pixel_height.max(1));
+    let compressed = match header.format {
+        Some(Format::R8G8B8A8_UNORM) | Some(Format::R8G8B8A8_SRGB) | Some(Format::B8G8R8A8_UNORM) | Some(Format::B8G8R8A8_SRGB) => None,
+        Some(Format::BC1_RGBA_UNORM_BLOCK) | Some(Format::BC1_RGBA_SRGB_BLOCK) => Some(BlockFormat::Bc1),
+        Some(Format::BC2_UNORM_BLOCK) | Some(Format::BC2_SRGB_BLOCK) => Some(BlockFormat::Bc2),
+        Some(Format::BC3_UNORM_BLOCK) | Some(Format::BC3_SRGB_BLOCK) => Some(BlockFormat::Bc3),
+        Some(Format::BC7_UNORM_BLOCK) | Some(Format::BC7_SRGB_BLOCK) => Some(BlockFormat::Bc7),
+        Some(format) => return Err(Error::msg(format!(
+            "KTX2 format {:?} isn't supported, use R8G8B8A8, B8G8R8A8, BC1_RGBA, BC2, BC3 or BC7", format))),
+        None => return Err(Error::msg("KTX2 textures need a format, Basis Universal isn't supported"))
+    };
+    if reader.levels().len() == 0 {
+        return Err(Error::msg("KTX2 has no images"));
+    }
+    if let Some(format) = compressed {
+        let levels = reader.levels().map(|level| ktx2_level(header.supercompression_scheme, level)).collect::<Result<Vec<_>, _>>()?;
+        return Ok(ReadTexture::Compressed(CompressedImage::new(format, width, height, levels)
+            .map_err(|error| Error::msg(format!("Invalid KTX2: {}", error)))?));
+    }
+
+    let mut data = ktx2_level(header.supercompression_scheme, reader.levels().next().unwrap())?;
+    if matches!(header.format, Some(Format::B8G8R8A8_UNORM) | Some(Format::B8G8R8A8_SRGB)) {
+        for pixel in data.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+    return Ok(ReadTexture::Pixels(RgbaImage::from_raw(width, height, data).ok_or_else(||
+        Error::msg(format!("KTX2 image data is too short for {}x{} RGBA8", width, height)))?));
+}
+
+//A level's data, without its supercompression
+fn ktx2_level(scheme: Option<SupercompressionScheme>, level: &[u8]) -> Result<Vec<u8>, Error> {
+    return match scheme {
+        None => Ok(level.to_vec()),
+        Some(SupercompressionScheme::Zstandard) => {
+            let mut data = Vec::new();
+            let mut level = level;
+            ruzstd::StreamingDecoder::new(&mut level)
+                .map_err(|error| Error::msg(format!("Invalid zstd in KTX2: {}", error)))?
+                .read_to_end(&mut data)?;
+            Ok(data)
+        }
+        Some(scheme) => Err(Error::msg(format!("KTX2 supercompression {:?} isn't supported, use zstd", scheme)))
+    };
+}
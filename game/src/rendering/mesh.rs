@@ -1,32 +1,53 @@
+use std::collections::HashMap;
 use std::string::ToString;
 use cgmath::Vector3;
 use lazy_static::lazy_static;
-use macros::JsonLoadable;
+use macros::{JsonLoadable, JsonSaveable};
+use crate::rendering::vertex_layout::VertexLayout;
 
-#[derive(JsonLoadable, Clone, Debug, Default)]
+#[derive(JsonLoadable, JsonSaveable, Clone, Debug, Default, PartialEq)]
 pub struct Mesh {
     #[require_field]
     pub shader: String,
+    //Which of the vertexes' attributes are uploaded, which has to have everything the shader reads
+    pub layout: VertexLayout,
     pub vertexes: Vec<Vertex>,
-    pub indices: Vec<u16>,
+    //Meshes with few enough vertexes are uploaded with u16 indices instead
+    pub indices: Vec<u32>,
+    //Ranges of indices drawn with their own textures, like an imported model's materials.
+    //Without any, the whole mesh is drawn with the texture it's pushed with.
+    pub parts: Vec<MeshPart>
+}
+
+//Indices from start to start + count, drawn with a texture
+#[derive(JsonLoadable, JsonSaveable, Clone, Debug, Default, PartialEq)]
+pub struct MeshPart {
+    pub start: u32,
+    pub count: u32,
+    //Texture name, or empty for the texture the mesh is pushed with
+    pub texture: String
 }
 
 lazy_static! {
     pub static ref CUBE: Mesh = Mesh {
         shader: "shader".to_string(),
+        layout: VertexLayout::default(),
         vertexes: vec!(
             Vertex::new([0.0, 0.0, 0.0], [0.0, 0.0]), Vertex::new([1.0, 0.0, 0.0], [1.0, 0.0]),
             Vertex::new([0.0, 1.0, 0.0], [0.0, 1.0]), Vertex::new([1.0, 1.0, 0.0], [1.0, 1.0])),
-            indices: vec!(0, 1, 2, 1, 3, 2)
+            indices: vec!(0, 1, 2, 1, 3, 2),
+            parts: Vec::new()
     };
 }
 
 impl Mesh {
-    pub const fn new(shader: String) -> Self {
+    pub fn new(shader: String) -> Self {
         return Self {
             shader,
+            layout: VertexLayout::default(),
             vertexes: Vec::new(),
             indices: Vec::new(),
+            parts: Vec::new()
         };
     }
 
@@ -35,8 +56,36 @@ impl Mesh {
         temp.shader = shader;
         return temp;
     }
+
+    //Whether it has too many vertexes to index with u16s
+    pub fn needs_u32_indices(&self) -> bool {
+        return self.vertexes.len() > u16::MAX as usize + 1;
+    }
+
+    //Each part as its own mesh with only the vertexes it uses, and the texture it's drawn with.
+    //Indices outside the mesh are left out.
+    pub fn split(&self) -> Vec<(Mesh, String)> {
+        if self.parts.is_empty() {
+            return vec!((self.clone(), String::new()));
+        }
+        return self.parts.iter().map(|part| {
+            let mut mesh = Mesh::new(self.shader.clone());
+            mesh.layout = self.layout.clone();
+            let mut moved = HashMap::new();
+            let indices = self.indices.get(part.start as usize..part.start as usize + part.count as usize).unwrap_or_default();
+            for index in indices.iter().filter(|index| (**index as usize) < self.vertexes.len()) {
+                let new_index = *moved.entry(*index).or_insert_with(|| {
+                    mesh.vertexes.push(self.vertexes[*index as usize]);
+                    (mesh.vertexes.len() - 1) as u32
+                });
+                mesh.indices.push(new_index);
+            }
+            (mesh, part.texture.clone())
+        }).collect();
+    }
 }
 
+#[derive(Clone)]
 pub struct FrameData {
     pub offset: Vector3<f32>,
 }
@@ -47,13 +96,36 @@ impl FrameData {
             offset: Vector3::new(0.0, 0.0, 0.0)
         };
     }
+
+    //The vertexes moved by the offset
+    pub fn apply(&self, vertexes: &[Vertex]) -> Vec<Vertex> {
+        return vertexes.iter().map(|vertex| {
+            let mut vertex = *vertex;
+            let [x, y, z] = vertex.position;
+            vertex.position = [x + self.offset.x, y + self.offset.y, z + self.offset.z];
+            return vertex;
+        }).collect();
+    }
 }
 
+//Every attribute a vertex can have. Only the ones in its mesh's layout are uploaded, the rest are zero.
 #[repr(C)]
-#[derive(Copy, Clone, Debug, JsonLoadable, Default)]
+#[derive(Copy, Clone, Debug, JsonLoadable, JsonSaveable, Default, PartialEq)]
 pub struct Vertex {
     position: [f32; 3],
-    tex_coords: [f32; 2]
+    tex_coords: [f32; 2],
+    #[json(default, skip_serializing_if_default)]
+    normal: [f32; 3],
+    #[json(default, skip_serializing_if_default)]
+    tangent: [f32; 4],
+    #[json(default, skip_serializing_if_default)]
+    color: [f32; 4],
+    #[json(default, skip_serializing_if_default)]
+    tex_coords2: [f32; 2],
+    #[json(default, skip_serializing_if_default)]
+    joints: [u32; 4],
+    #[json(default, skip_serializing_if_default)]
+    weights: [f32; 4]
 }
 
 impl Vertex {
@@ -61,6 +133,74 @@ impl Vertex {
         return Self {
             position,
             tex_coords,
+            ..Default::default()
+        };
+    }
+
+    pub fn with_normal(position: [f32; 3], tex_coords: [f32; 2], normal: [f32; 3]) -> Self {
+        return Self {
+            position,
+            tex_coords,
+            normal,
+            ..Default::default()
         };
     }
-}
\ No newline at end of file
+
+    pub fn normal(&self) -> [f32; 3] {
+        return self.normal;
+    }
+
+    pub fn position(&self) -> [f32; 3] {
+        return self.position;
+    }
+
+    pub fn set_position(&mut self, position: [f32; 3]) {
+        self.position = position;
+    }
+
+    pub fn tex_coords(&self) -> [f32; 2] {
+        return self.tex_coords;
+    }
+
+    pub fn set_tex_coords(&mut self, tex_coords: [f32; 2]) {
+        self.tex_coords = tex_coords;
+    }
+
+    pub fn tangent(&self) -> [f32; 4] {
+        return self.tangent;
+    }
+
+    pub fn set_tangent(&mut self, tangent: [f32; 4]) {
+        self.tangent = tangent;
+    }
+
+    pub fn color(&self) -> [f32; 4] {
+        return self.color;
+    }
+
+    pub fn set_color(&mut self, color: [f32; 4]) {
+        self.color = color;
+    }
+
+    pub fn tex_coords2(&self) -> [f32; 2] {
+        return self.tex_coords2;
+    }
+
+    pub fn set_tex_coords2(&mut self, tex_coords: [f32; 2]) {
+        self.tex_coords2 = tex_coords;
+    }
+
+    pub fn joints(&self) -> [u32; 4] {
+        return self.joints;
+    }
+
+    pub fn weights(&self) -> [f32; 4] {
+        return self.weights;
+    }
+
+    //The bones moving it, and how much each does
+    pub fn set_skin(&mut self, joints: [u32; 4], weights: [f32; 4]) {
+        self.joints = joints;
+        self.weights = weights;
+    }
+}
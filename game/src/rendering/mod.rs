@@ -1,12 +1,20 @@
 pub mod assets;
+pub mod block_compression;
+#[cfg(feature = "renderer")]
+pub mod headless;
+pub mod image_format;
 pub mod mesh;
+pub mod model_import;
 pub mod renderer;
 pub mod texture;
+pub mod vertex_layout;
 
 use std::collections::HashMap;
 use std::fmt::{Debug, Display};
 use std::sync::Arc;
+use crate::rendering::image_format::CompressedImage;
 use crate::rendering::mesh::Mesh;
+use crate::rendering::texture::Animation;
 
 #[cfg(feature = "renderer")]
 #[derive(Debug)]
@@ -20,5 +28,32 @@ pub trait GameTexture: Send + Sync + Display + Debug {
 
     fn name(&self) -> String;
 
+    //The first frame's pixels, as RGBA8
     fn data(&self) -> &[u8];
+
+    //Animated textures have more than one frame, each the size of dimensions
+    fn frames(&self) -> usize {
+        return 1;
+    }
+
+    fn frame(&self, _frame: usize) -> &[u8] {
+        return self.data();
+    }
+
+    //How the frames play, if it's animated
+    fn animation(&self) -> Option<&Animation> {
+        return None;
+    }
+
+    //The blocks the texture was read as, if it's block compressed. Its data is them decoded,
+    //for GPUs that can't read them and anything that needs its pixels.
+    fn compressed(&self) -> Option<&CompressedImage> {
+        return None;
+    }
+
+    //Smaller mip levels after the full size one, as RGBA8, each half the size of the one before.
+    //Empty if the texture only has the full size one.
+    fn mip_levels(&self) -> &[Vec<u8>] {
+        return &[];
+    }
 }
\ No newline at end of file
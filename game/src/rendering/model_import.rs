@@ -0,0 +1,224 @@
+use std::io::{BufReader, Cursor};
+use std::path::Path;
+use anyhow::Error;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use cgmath::{InnerSpace, Matrix4, SquareMatrix, Transform, Vector4};
+use gltf::Gltf;
+use gltf::mesh::Mode;
+use crate::rendering::mesh::{Mesh, MeshPart, Vertex};
+use crate::rendering::vertex_layout::{VertexAttribute, VertexLayout};
+
+//Shader imported models are drawn with, since the formats don't say
+pub const DEFAULT_SHADER: &str = "shader";
+
+//Model formats besides JSON meshes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModelFormat {
+    Gltf,
+    Obj
+}
+
+impl ModelFormat {
+    //The format a file's extension says it is, if it's imported instead of read as JSON
+    pub fn from_path(file: &str) -> Option<Self> {
+        let extension = file.rsplit_once('.')?.1.to_lowercase();
+        return match extension.as_str() {
+            "gltf" | "glb" => Some(Self::Gltf),
+            "obj" => Some(Self::Obj),
+            _ => None
+        };
+    }
+}
+
+//Files a model needs besides itself, like glTF buffers and OBJ materials, by their path from the model's folder
+pub type ReadFile<'a> = &'a dyn Fn(&str) -> Result<Vec<u8>, Error>;
+
+//Whether the file is a model in assets/models, by its extension. Other files, like OBJ materials and glTF buffers,
+//are only read by the models using them.
+pub fn is_model(file: &str) -> bool {
+    return file.ends_with(".json") || ModelFormat::from_path(file).is_some();
+}
+
+//Imports a glTF or OBJ model as one mesh, with a part for each primitive or material
+pub fn import(file: &str, bytes: &[u8], read: ReadFile) -> Result<Mesh, Error> {
+    let format = ModelFormat::from_path(file)
+        .ok_or_else(|| Error::msg(format!("{} isn't a glTF or OBJ model", file)))?;
+    let mesh = match format {
+        ModelFormat::Gltf => import_gltf(bytes, read),
+        ModelFormat::Obj => import_obj(bytes, read)
+    }.map_err(|error| Error::msg(format!("{} → {}", file, error)))?;
+    return Ok(mesh);
+}
+
+//Texture names for images the model points to, named like textures are: by their path from a textures folder,
+//without the extension. Images outside one are named by their file name.
+pub fn texture_name(uri: &str) -> String {
+    let uri = uri.replace('\\', "/");
+    let path = match uri.rfind("textures/") {
+        Some(start) => &uri[start + "textures/".len()..],
+        None => uri.rsplit('/').next().unwrap()
+    };
+    return path.split('.').next().unwrap().to_string();
+}
+
+fn import_gltf(bytes: &[u8], read: ReadFile) -> Result<Mesh, Error> {
+    let gltf = Gltf::from_slice(bytes)?;
+    let mut buffers = Vec::new();
+    for buffer in gltf.buffers() {
+        buffers.push(match buffer.source() {
+            gltf::buffer::Source::Bin => gltf.blob.clone().ok_or_else(|| Error::msg("Missing the GLB binary chunk"))?,
+            gltf::buffer::Source::Uri(uri) => match uri.strip_prefix("data:") {
+                Some(data) => STANDARD.decode(data.split_once(";base64,")
+                    .ok_or_else(|| Error::msg("Only base64 data URIs are supported"))?.1)?,
+                None => read(uri)?
+            }
+        });
+    }
+
+    let mut mesh = Mesh::new(DEFAULT_SHADER.to_string());
+    mesh.layout = VertexLayout::new(vec!(VertexAttribute::Position));
+    //Meshes are placed by the nodes using them, or as they are if there's no scene
+    let mut placed = Vec::new();
+    match gltf.default_scene().or_else(|| gltf.scenes().next()) {
+        Some(scene) => for node in scene.nodes() {
+            place_nodes(node, Matrix4::identity(), &mut placed);
+        }
+        None => placed.extend(gltf.meshes().map(|found| (found, Matrix4::identity())))
+    }
+    for (found, transform) in placed {
+        for primitive in found.primitives() {
+            if primitive.mode() != Mode::Triangles {
+                return Err(Error::msg(format!("Mesh {} has {:?}, only triangles are supported",
+                                              found.name().unwrap_or("unnamed"), primitive.mode())));
+            }
+            let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| data.as_slice()));
+            let positions = reader.read_positions()
+                .ok_or_else(|| Error::msg("Primitive has no positions"))?.collect::<Vec<_>>();
+            let mut vertexes = positions.iter().map(|position|
+                Vertex::new(transform.transform_point((*position).into()).into(), [0.0; 2])).collect::<Vec<_>>();
+            //Attributes any primitive has are in the mesh's layout, and zero in primitives without them
+            if let Some(tex_coords) = reader.read_tex_coords(0) {
+                mesh.layout.add(VertexAttribute::TexCoords);
+                vertexes.iter_mut().zip(tex_coords.into_f32()).for_each(|(vertex, uv)| vertex.set_tex_coords(uv));
+            }
+            if let Some(normals) = reader.read_normals() {
+                mesh.layout.add(VertexAttribute::Normal);
+                vertexes.iter_mut().zip(normals).for_each(|(vertex, normal)|
+                    *vertex = Vertex::with_normal(vertex.position(), vertex.tex_coords(),
+                                                  transform.transform_vector(normal.into()).normalize().into()));
+            }
+            if let Some(tangents) = reader.read_tangents() {
+                mesh.layout.add(VertexAttribute::Tangent);
+                vertexes.iter_mut().zip(tangents).for_each(|(vertex, [x, y, z, w])| {
+                    let [x, y, z]: [f32; 3] = transform.transform_vector([x, y, z].into()).normalize().into();
+                    vertex.set_tangent([x, y, z, w]);
+                });
+            }
+            if let Some(colors) = reader.read_colors(0) {
+                mesh.layout.add(VertexAttribute::Color);
+                vertexes.iter_mut().zip(colors.into_rgba_f32()).for_each(|(vertex, color)| vertex.set_color(color));
+            }
+            if let Some(tex_coords) = reader.read_tex_coords(1) {
+                mesh.layout.add(VertexAttribute::TexCoords2);
+                vertexes.iter_mut().zip(tex_coords.into_f32()).for_each(|(vertex, uv)| vertex.set_tex_coords2(uv));
+            }
+            if let (Some(joints), Some(weights)) = (reader.read_joints(0), reader.read_weights(0)) {
+                mesh.layout.add(VertexAttribute::Joints);
+                mesh.layout.add(VertexAttribute::Weights);
+                vertexes.iter_mut().zip(joints.into_u16().zip(weights.into_f32())).for_each(|(vertex, (joints, weights))|
+                    vertex.set_skin(joints.map(u32::from), weights));
+            }
+            let indices = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..positions.len() as u32).collect::<Vec<_>>()
+            };
+
+            let material = primitive.material();
+            let texture = match material.pbr_metallic_roughness().base_color_texture().map(|info| info.texture().source().source()) {
+                Some(gltf::image::Source::Uri { uri, .. }) => texture_name(uri),
+                _ => material.name().unwrap_or_default().to_string()
+            };
+            add_part(&mut mesh, texture, vertexes, &indices)?;
+        }
+    }
+    return Ok(mesh);
+}
+
+//Finds the meshes under a node, with the transform from its parents
+fn place_nodes<'a>(node: gltf::Node<'a>, parent: Matrix4<f32>, placed: &mut Vec<(gltf::Mesh<'a>, Matrix4<f32>)>) {
+    let columns = node.transform().matrix();
+    let transform = parent * Matrix4::from_cols(Vector4::from(columns[0]), Vector4::from(columns[1]),
+                                                Vector4::from(columns[2]), Vector4::from(columns[3]));
+    if let Some(found) = node.mesh() {
+        placed.push((found, transform));
+    }
+    for child in node.children() {
+        place_nodes(child, transform, placed);
+    }
+}
+
+fn import_obj(bytes: &[u8], read: ReadFile) -> Result<Mesh, Error> {
+    let (models, materials) = tobj::load_obj_buf(&mut BufReader::new(Cursor::new(bytes)), &tobj::GPU_LOAD_OPTIONS, |path| {
+        let file = read(&path.to_string_lossy()).map_err(|_| tobj::LoadError::OpenFileFailed)?;
+        return tobj::load_mtl_buf(&mut BufReader::new(Cursor::new(file)));
+    }).map_err(|error| Error::msg(format!("Invalid OBJ: {}", error)))?;
+    let materials = materials.map_err(|error| Error::msg(format!("Invalid OBJ materials: {}", error)))?;
+
+    let mut mesh = Mesh::new(DEFAULT_SHADER.to_string());
+    mesh.layout = VertexLayout::new(vec!(VertexAttribute::Position));
+    for model in models {
+        let found = &model.mesh;
+        let texture = match found.material_id.and_then(|id| materials.get(id)) {
+            Some(material) => match &material.diffuse_texture {
+                Some(texture) => texture_name(texture),
+                None => material.name.clone()
+            },
+            None => String::new()
+        };
+        if !found.texcoords.is_empty() {
+            mesh.layout.add(VertexAttribute::TexCoords);
+        }
+        if !found.normals.is_empty() {
+            mesh.layout.add(VertexAttribute::Normal);
+        }
+        let vertexes = (0..found.positions.len() / 3).map(|index| {
+            let normal = found.normals.get(index * 3..index * 3 + 3).map(|normal| [normal[0], normal[1], normal[2]]);
+            //OBJ texture coordinates start at the bottom
+            let tex_coords = found.texcoords.get(index * 2..index * 2 + 2).map(|uv| [uv[0], 1.0 - uv[1]]);
+            Vertex::with_normal([found.positions[index * 3], found.positions[index * 3 + 1], found.positions[index * 3 + 2]],
+                                tex_coords.unwrap_or_default(), normal.unwrap_or_default())
+        }).collect();
+        add_part(&mut mesh, texture, vertexes, &found.indices)?;
+    }
+    return Ok(mesh);
+}
+
+//Adds vertexes indexed from 0 to the mesh as a part drawn with the texture. Parts with the same texture
+//next to each other are merged.
+fn add_part(mesh: &mut Mesh, texture: String, vertexes: Vec<Vertex>, indices: &[u32]) -> Result<(), Error> {
+    if let Some(index) = indices.iter().find(|index| **index as usize >= vertexes.len()) {
+        return Err(Error::msg(format!("Index {} is past the {} vertexes", index, vertexes.len())));
+    }
+    let offset = mesh.vertexes.len() as u32;
+    let start = mesh.indices.len() as u32;
+    mesh.vertexes.extend(vertexes);
+    mesh.indices.extend(indices.iter().map(|index| index + offset));
+    match mesh.parts.last_mut() {
+        Some(last) if last.texture == texture => last.count += indices.len() as u32,
+        _ => mesh.parts.push(MeshPart {
+            start,
+            count: indices.len() as u32,
+            texture
+        })
+    }
+    return Ok(());
+}
+
+//Where a file a model points to is, from the model's own path
+pub fn relative_to(model: &str, file: &str) -> String {
+    return match Path::new(model).parent() {
+        Some(folder) => folder.join(file).display().to_string(),
+        None => file.to_string()
+    };
+}
@@ -1,6 +1,91 @@
 use std::fmt::{Display, Formatter};
+use std::time::Duration;
+use anyhow::Error;
+use macros::JsonLoadable;
 use crate::rendering::GameTexture;
 
+//How an animated texture plays, from the *.png.json next to its image.
+//Frames are cut from the image left to right, then top to bottom, so a vertical strip of square frames
+//only needs its frame_time. Times are in milliseconds.
+#[derive(JsonLoadable, Clone, Debug, PartialEq)]
+pub struct Animation {
+    //Size of each frame. The width defaults to the image's, and the height to the width.
+    pub frame_width: u32,
+    pub frame_height: u32,
+    //How long each frame shows
+    pub frame_time: u64,
+    //How long each frame shows, in order, for frames that don't use frame_time
+    pub durations: Vec<u64>,
+    //Blends each frame into the next one instead of switching at once
+    pub interpolate: bool
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        return Self {
+            frame_width: 0,
+            frame_height: 0,
+            frame_time: 100,
+            durations: Vec::new(),
+            interpolate: false
+        };
+    }
+}
+
+impl Animation {
+    //Size of each frame of an image this wide
+    pub fn frame_size(&self, width: u32) -> (u32, u32) {
+        let frame_width = if self.frame_width == 0 { width } else { self.frame_width };
+        let frame_height = if self.frame_height == 0 { frame_width } else { self.frame_height };
+        return (frame_width, frame_height);
+    }
+
+    //How many frames an image this size has, or why it can't be cut into them
+    pub fn frame_count(&self, width: u32, height: u32) -> Result<usize, Error> {
+        let (frame_width, frame_height) = self.frame_size(width);
+        if frame_width == 0 || frame_height == 0 || !width.is_multiple_of(frame_width) || !height.is_multiple_of(frame_height) {
+            return Err(Error::msg(format!("A {}x{} image can't be cut into {}x{} frames",
+                                          width, height, frame_width, frame_height)));
+        }
+        if self.durations.len() > ((width / frame_width) * (height / frame_height)) as usize {
+            return Err(Error::msg(format!("There are {} durations for {} frames", self.durations.len(),
+                                          (width / frame_width) * (height / frame_height))));
+        }
+        return Ok(((width / frame_width) * (height / frame_height)) as usize);
+    }
+
+    //How long the frame shows, in milliseconds
+    pub fn duration(&self, frame: usize) -> u64 {
+        return self.durations.get(frame).copied().unwrap_or(self.frame_time);
+    }
+
+    //The frame showing after playing for elapsed, looping, the frame after it, and how far
+    //it's blended into that one from 0 to 1. Without interpolating, it's never blended.
+    pub fn frame_at(&self, frames: usize, elapsed: Duration) -> (usize, usize, f32) {
+        let total = (0..frames).map(|frame| self.duration(frame)).sum::<u64>();
+        if total == 0 {
+            return (0, 0, 0.0);
+        }
+        let mut time = (elapsed.as_millis() % total as u128) as u64;
+        for frame in 0..frames {
+            let duration = self.duration(frame);
+            if time < duration {
+                let next = (frame + 1) % frames;
+                let blend = if self.interpolate { time as f32 / duration as f32 } else { 0.0 };
+                return (frame, next, blend);
+            }
+            time -= duration;
+        }
+        return (0, 0, 0.0);
+    }
+
+    //Pixels amount of the way from first to second, for interpolating between frames
+    pub fn blend(first: &[u8], second: &[u8], amount: f32) -> Vec<u8> {
+        return first.iter().zip(second)
+            .map(|(first, second)| (*first as f32 + (*second as f32 - *first as f32) * amount).round() as u8).collect();
+    }
+}
+
 #[derive(Debug)]
 pub struct ColorTexture {
     color: [u8; 4]
@@ -32,4 +117,54 @@ impl ColorTexture {
             color
         };
     }
-}
\ No newline at end of file
+}
+
+//A magenta and black checkerboard drawn instead of textures that are missing or failed to load
+#[derive(Debug)]
+pub struct MissingTexture {
+    data: Vec<u8>
+}
+
+impl MissingTexture {
+    pub const NAME: &'static str = "builtin:missing";
+    const SIZE: u32 = 16;
+
+    pub fn new() -> Self {
+        let mut data = Vec::new();
+        for y in 0..Self::SIZE {
+            for x in 0..Self::SIZE {
+                let magenta = (x < Self::SIZE / 2) == (y < Self::SIZE / 2);
+                data.extend_from_slice(if magenta { &[255, 0, 255, 255] } else { &[0, 0, 0, 255] });
+            }
+        }
+        return Self {
+            data
+        };
+    }
+}
+
+impl Default for MissingTexture {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl Display for MissingTexture {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Missing texture")
+    }
+}
+
+impl GameTexture for MissingTexture {
+    fn dimensions(&self) -> (u32, u32) {
+        return (Self::SIZE, Self::SIZE);
+    }
+
+    fn name(&self) -> String {
+        return Self::NAME.to_string();
+    }
+
+    fn data(&self) -> &[u8] {
+        return &self.data;
+    }
+}
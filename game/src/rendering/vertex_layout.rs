@@ -0,0 +1,154 @@
+use std::hash::{Hash, Hasher};
+use anyhow::Error;
+use json::JsonValue;
+use macros::{JsonLoadable, JsonSaveable};
+use interfaces::loading::JsonLoadable;
+use crate::rendering::mesh::Vertex;
+
+//Data a vertex can have. Shaders read each at its own location, in the order they're declared here.
+#[derive(JsonLoadable, JsonSaveable, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum VertexAttribute {
+    #[default]
+    Position,
+    TexCoords,
+    Normal,
+    //The direction texture coordinates' U goes in, with the bitangent's sign in W
+    Tangent,
+    Color,
+    //A second set of texture coordinates, like for lightmaps
+    TexCoords2,
+    //Indices of the four bones moving the vertex, as u32s
+    Joints,
+    //How much each of the joints moves it
+    Weights
+}
+
+impl VertexAttribute {
+    pub const ALL: [VertexAttribute; 8] = [Self::Position, Self::TexCoords, Self::Normal, Self::Tangent, Self::Color,
+        Self::TexCoords2, Self::Joints, Self::Weights];
+
+    //The @location shaders read it at
+    pub fn location(&self) -> u32 {
+        return *self as u32;
+    }
+
+    //How many f32s, or u32s for joints, it is
+    pub fn components(&self) -> usize {
+        return match self {
+            Self::TexCoords | Self::TexCoords2 => 2,
+            Self::Position | Self::Normal => 3,
+            Self::Tangent | Self::Color | Self::Joints | Self::Weights => 4
+        };
+    }
+
+    //Bytes it takes in a vertex buffer
+    pub fn size(&self) -> usize {
+        return self.components() * 4;
+    }
+}
+
+//Which attributes a mesh's vertexes have, or a shader reads. Vertex buffers hold only the mesh's attributes,
+//in VertexAttribute's order, so the order they're listed in doesn't matter.
+#[derive(JsonLoadable, JsonSaveable, Clone, Debug)]
+pub struct VertexLayout(Vec<VertexAttribute>);
+
+//Meshes and shaders from before layouts have positions and texture coordinates
+impl Default for VertexLayout {
+    fn default() -> Self {
+        return Self(vec!(VertexAttribute::Position, VertexAttribute::TexCoords));
+    }
+}
+
+impl PartialEq for VertexLayout {
+    fn eq(&self, other: &Self) -> bool {
+        return self.attributes() == other.attributes();
+    }
+}
+
+impl Eq for VertexLayout {}
+
+impl Hash for VertexLayout {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.attributes().hash(state);
+    }
+}
+
+impl VertexLayout {
+    pub fn new(attributes: Vec<VertexAttribute>) -> Self {
+        return Self(attributes);
+    }
+
+    //The attributes, in the order they're in vertex buffers
+    pub fn attributes(&self) -> Vec<VertexAttribute> {
+        return VertexAttribute::ALL.into_iter().filter(|attribute| self.0.contains(attribute)).collect();
+    }
+
+    pub fn contains(&self, attribute: VertexAttribute) -> bool {
+        return self.0.contains(&attribute);
+    }
+
+    pub fn add(&mut self, attribute: VertexAttribute) {
+        if !self.contains(attribute) {
+            self.0.push(attribute);
+        }
+    }
+
+    //Bytes each vertex takes
+    pub fn stride(&self) -> usize {
+        return self.attributes().iter().map(VertexAttribute::size).sum();
+    }
+
+    //Where the attribute is in each vertex, if it's in the layout
+    pub fn offset(&self, attribute: VertexAttribute) -> Option<usize> {
+        if !self.contains(attribute) {
+            return None;
+        }
+        return Some(self.attributes().iter().take_while(|other| **other != attribute).map(VertexAttribute::size).sum());
+    }
+
+    //Checks a mesh with this layout has everything a shader with the other one reads
+    pub fn check_shader(&self, shader: &str, reads: &VertexLayout) -> Result<(), Error> {
+        let missing = reads.attributes().into_iter().filter(|attribute| !self.contains(*attribute)).collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        return Err(Error::msg(format!("Shader {} reads {:?}, which the mesh's {:?} vertexes don't have",
+                                      shader, missing, self.attributes())));
+    }
+
+    //The vertexes as a vertex buffer with only this layout's attributes
+    pub fn pack(&self, vertexes: &[Vertex]) -> Vec<u8> {
+        let attributes = self.attributes();
+        let mut output = Vec::with_capacity(vertexes.len() * self.stride());
+        for vertex in vertexes {
+            for attribute in &attributes {
+                match attribute {
+                    VertexAttribute::Position => output.extend_from_slice(bytemuck::bytes_of(&vertex.position())),
+                    VertexAttribute::TexCoords => output.extend_from_slice(bytemuck::bytes_of(&vertex.tex_coords())),
+                    VertexAttribute::Normal => output.extend_from_slice(bytemuck::bytes_of(&vertex.normal())),
+                    VertexAttribute::Tangent => output.extend_from_slice(bytemuck::bytes_of(&vertex.tangent())),
+                    VertexAttribute::Color => output.extend_from_slice(bytemuck::bytes_of(&vertex.color())),
+                    VertexAttribute::TexCoords2 => output.extend_from_slice(bytemuck::bytes_of(&vertex.tex_coords2())),
+                    VertexAttribute::Joints => output.extend_from_slice(bytemuck::bytes_of(&vertex.joints())),
+                    VertexAttribute::Weights => output.extend_from_slice(bytemuck::bytes_of(&vertex.weights()))
+                }
+            }
+        }
+        return output;
+    }
+
+    //The layout a shader reads, from a "//layout: Position, TexCoords, Normal" line in its source.
+    //Shaders without one read positions and texture coordinates.
+    pub fn from_shader(source: &str) -> Result<Self, Error> {
+        let line = match source.lines().find_map(|line| line.trim().strip_prefix("//layout:")) {
+            Some(line) => line,
+            None => return Ok(Self::default())
+        };
+        let mut attributes = Vec::new();
+        for name in line.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            attributes.push(VertexAttribute::load(&JsonValue::from(name))
+                .map_err(|error| Error::msg(format!("Invalid shader layout: {}", error)))?);
+        }
+        return Ok(Self(attributes));
+    }
+}
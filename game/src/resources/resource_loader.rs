@@ -1,129 +1,352 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::ops::DerefMut;
 use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll, Waker};
 use anyhow::Error;
+use json::JsonValue;
 use json::object::Object;
+use interfaces::json_path;
 use crate::ResourceManager;
-use crate::util::alloc_handle::AllocHandle;
+use crate::util::json_util;
 
 pub struct ResourceLoader {
     total_tasks: u32,
-    sleeping: u32,
+    //Tasks waiting to be woken. Woken tasks are taken out before they're polled, so they count as awake.
+    sleeping: HashSet<u64>,
+    next_task: u64,
     deadlocked: bool,
     reference: Arc<Mutex<ResourceManager>>,
-    wakers: HashMap<String, Vec<Waker>>,
+    wakers: HashMap<String, Vec<(u64, Waker)>>,
+    //JSON of every loaded type with its parents merged in, for children to inherit from
+    loaded: HashMap<String, Object>,
+    //Which parent each sleeping type is waiting for, used to find inheritance cycles
+    waiting: HashMap<String, String>,
+    //Types found in an inheritance cycle, with the cycle to print
+    cycles: HashMap<String, String>,
+    //JSON of every loaded type as it was written, before inheriting, for reloading it and its children
+    sources: HashMap<String, Object>,
 }
 
 impl ResourceLoader {
     pub fn new(reference: Arc<Mutex<ResourceManager>>) -> Self {
         return Self {
             total_tasks: 0,
-            sleeping: 0,
+            sleeping: HashSet::new(),
+            next_task: 0,
             deadlocked: false,
             reference,
             wakers: HashMap::new(),
+            loaded: HashMap::new(),
+            waiting: HashMap::new(),
+            cycles: HashMap::new(),
+            sources: HashMap::new(),
         };
     }
 
     pub fn spawn(reference: Arc<Mutex<ResourceLoader>>, object: Object) -> impl Future<Output=Result<(), Error>> {
+        reference.lock().unwrap().total_tasks += 1;
         return ResourceLoadTask::new(object, reference);
     }
+
+    //Counts a file that's still being read as an awake task, so types waiting on something
+    //in it aren't treated as deadlocked. Call release once its types are spawned.
+    pub fn reserve(&mut self) {
+        self.total_tasks += 1;
+    }
+
+    pub fn release(&mut self) {
+        self.finish();
+    }
+
+    //The JSON a type was loaded from, after inheriting from its parents
+    pub fn get_json(&self, name: &str) -> Option<&Object> {
+        return self.loaded.get(name);
+    }
+
+    //Every loaded type's name and JSON
+    pub fn loaded_types(&self) -> impl Iterator<Item=(&String, &Object)> {
+        return self.loaded.iter();
+    }
+
+    //Instantiates a type again from new JSON once loading's done, then every type inheriting from it.
+    //New names are added too. Anything failing keeps its old version, as does a type changing its $type,
+    //since code may already hold onto it. Returns every type tried and whether it reloaded.
+    pub fn reload(&mut self, object: Object) -> Vec<(String, Result<(), Error>)> {
+        let name = match object.get("$name").and_then(JsonValue::as_str) {
+            Some(name) => name.to_string(),
+            None => return vec!((String::new(), Err(Error::msg("No name ($name)"))))
+        };
+        let result = self.instantiate_now(&name, &object);
+        let reloaded = result.is_ok();
+        let mut output = vec!((name.clone(), result));
+        if !reloaded {
+            return output;
+        }
+        self.sources.insert(name.clone(), object);
+
+        let mut children = self.sources.iter()
+            .filter(|(_, source)| source.get("$parent").and_then(JsonValue::as_str) == Some(name.as_str()))
+            .map(|(child, source)| (child.clone(), source.clone())).collect::<Vec<_>>();
+        children.sort_by(|first, second| first.0.cmp(&second.0));
+        for (_, source) in children {
+            output.extend(self.reload(source));
+        }
+        return output;
+    }
+
+    //Loads a type whose parent and dependencies are already loaded, without waiting for anything
+    fn instantiate_now(&mut self, name: &str, object: &Object) -> Result<(), Error> {
+        let mut object = object.clone();
+        if let Some(parent) = object.remove("$parent") {
+            let parent = match parent.as_str() {
+                Some(parent) => parent,
+                None => return Err(json_path::at_key(Error::msg(format!("Expected string, found {}", parent)), "$parent"))
+            };
+            //Loaded types can't have cycles, so one would have to go back through this type
+            let mut chain = vec!(name);
+            let mut ancestor = Some(parent);
+            while let Some(current) = ancestor {
+                chain.push(current);
+                if current == name {
+                    return Err(json_path::at_key(Error::msg(format!("Inheritance cycle {}", chain.join(" -> "))), "$parent"));
+                }
+                ancestor = self.sources.get(current).and_then(|source| source["$parent"].as_str());
+            }
+            let mut merged = match self.loaded.get(parent) {
+                Some(parent) => parent.clone(),
+                None => return Err(json_path::at_key(Error::msg(format!("Failed to find NamedType {}", parent)), "$parent"))
+            };
+            json_util::deep_merge(&mut merged, &object);
+            object = merged;
+        }
+
+        let obj_type = match object.get("$type").and_then(JsonValue::as_str) {
+            Some(obj_type) => obj_type.to_string(),
+            None => return Err(Error::msg("No type ($type) or parent ($parent)"))
+        };
+        let mut manager = self.reference.lock().unwrap();
+        let (id, named_type) = match manager.instantiator(obj_type.as_str()) {
+            Some(instantiator) => match instantiator(manager.deref_mut(), &object)? {
+                Ok(creator) => creator,
+                Err(blocked) => return Err(Error::msg(format!("Failed to find NamedType {}", blocked)))
+            },
+            None => return Err(json_path::at_key(
+                Error::msg(format!("No parent type ($type) registered as {}", obj_type)), "$type"))
+        };
+        manager.insert_type(id, named_type)?;
+        drop(manager);
+        self.loaded.insert(name.to_string(), object);
+        return Ok(());
+    }
+
+    fn wake(&mut self, name: &str) {
+        if let Some(wakers) = self.wakers.remove(name) {
+            for (task, waker) in wakers {
+                self.sleeping.remove(&task);
+                waker.wake();
+            }
+        }
+    }
+
+    fn wake_all(&mut self) {
+        for (_, wakers) in self.wakers.drain() {
+            for (task, waker) in wakers {
+                self.sleeping.remove(&task);
+                waker.wake();
+            }
+        }
+    }
+
+    //Follows the types waiting on each other from parent, returns the cycle if it leads back to name
+    fn find_cycle(&self, name: &str, parent: &str) -> Option<Vec<String>> {
+        let mut chain = vec!(name.to_string());
+        let mut current = parent;
+        while current != name {
+            if chain.iter().any(|found| found == current) {
+                return None;
+            }
+            chain.push(current.to_string());
+            current = self.waiting.get(current)?;
+        }
+        chain.push(name.to_string());
+        return Some(chain);
+    }
+
+    //Called whenever a task finishes, loaded or not. If everyone left is asleep, nothing can wake them.
+    fn finish(&mut self) {
+        self.total_tasks -= 1;
+        if self.total_tasks > 0 && self.sleeping.len() as u32 == self.total_tasks {
+            self.deadlocked = true;
+            self.wake_all();
+        }
+    }
 }
 
 pub struct ResourceLoadTask {
     object: Object,
+    //The object before its parent is merged in
+    source: Object,
     loader: Arc<Mutex<ResourceLoader>>,
+    //Which task this is to the loader, for keeping track of it sleeping
+    id: u64,
 }
 
 impl ResourceLoadTask {
     pub fn new(object: Object, loader: Arc<Mutex<ResourceLoader>>) -> Self {
+        let id = {
+            let mut locked = loader.lock().unwrap();
+            locked.next_task += 1;
+            locked.next_task
+        };
         return Self {
+            source: object.clone(),
             object,
             loader,
+            id,
         };
     }
+
+    fn get_string(&self, key: &str) -> Option<String> {
+        return self.object.get(key).and_then(JsonValue::as_str).map(str::to_string);
+    }
+
+    //Waits until blocked is loaded, or fails if nothing is left to load it
+    fn sleep(&mut self, loader: &mut ResourceLoader, blocked: String, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        //Nothing else is awake to load it, so it'll never come
+        if loader.deadlocked || loader.sleeping.len() as u32 + 1 == loader.total_tasks {
+            loader.deadlocked = true;
+            loader.finish();
+            loader.wake_all();
+            let error = Error::msg(format!("Failed to find NamedType {}", blocked));
+            //Missing parents are pointed out at $parent, missing dependencies at the whole type
+            if self.object.get("$parent").and_then(JsonValue::as_str) == Some(blocked.as_str()) {
+                return Poll::Ready(Err(json_path::at_key(error, "$parent")));
+            }
+            return Poll::Ready(Err(error));
+        }
+        loader.sleeping.insert(self.id);
+        loader.wakers.entry(blocked).or_default().push((self.id, cx.waker().clone()));
+        return Poll::Pending;
+    }
+
+    //Merges the parent's JSON under this one. Returns None once the parent is merged in (or there isn't one).
+    fn inherit(&mut self, loader: &mut ResourceLoader, name: &str, cx: &mut Context<'_>) -> Option<Poll<Result<(), Error>>> {
+        let parent = self.object.get("$parent")?;
+        let parent = match parent.as_str() {
+            Some(parent) => parent.to_string(),
+            None => {
+                loader.finish();
+                return Some(Poll::Ready(Err(json_path::at_key(
+                    Error::msg(format!("Expected string, found {}", parent)), "$parent"))));
+            }
+        };
+
+        if let Some(parent_json) = loader.loaded.get(&parent) {
+            let mut merged = parent_json.clone();
+            self.object.remove("$parent");
+            json_util::deep_merge(&mut merged, &self.object);
+            self.object = merged;
+            loader.waiting.remove(name);
+            return None;
+        }
+
+        if let Some(cycle) = loader.cycles.get(name) {
+            let error = json_path::at_key(Error::msg(format!("Inheritance cycle {}", cycle)), "$parent");
+            loader.waiting.remove(name);
+            loader.finish();
+            return Some(Poll::Ready(Err(error)));
+        }
+        if let Some(cycle) = loader.find_cycle(name, &parent) {
+            let description = cycle.join(" -> ");
+            //Wake the rest of the cycle so they fail too, instead of waiting for a deadlock
+            for found in &cycle[1..cycle.len() - 1] {
+                loader.cycles.insert(found.clone(), description.clone());
+                if let Some(waiting_for) = loader.waiting.get(found).cloned() {
+                    loader.wake(&waiting_for);
+                }
+            }
+            loader.finish();
+            return Some(Poll::Ready(Err(json_path::at_key(
+                Error::msg(format!("Inheritance cycle {}", description)), "$parent"))));
+        }
+
+        loader.waiting.insert(name.to_string(), parent.clone());
+        return Some(self.sleep(loader, parent, cx));
+    }
 }
 
 impl Future for ResourceLoadTask {
     type Output = Result<(), Error>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        let obj_type = match self.object.get("$type") {
+        let this = self.get_mut();
+        let loader_reference = this.loader.clone();
+        let mut loader = loader_reference.lock().unwrap();
+        //In case it was polled without being woken, it's awake now
+        loader.sleeping.remove(&this.id);
+
+        let name = match this.get_string("$name") {
             Some(name) => name,
-            None => return Poll::Ready(Err(Error::msg(
-                format!("No name for parent type in JSON\n{:?}", self.object))))
-        }.dump();
-        let obj_type = obj_type.as_str();
+            None => {
+                loader.finish();
+                return Poll::Ready(Err(Error::msg("No name ($name)")));
+            }
+        };
+
+        //Parents are merged in before the instantiator sees the JSON
+        if let Some(result) = this.inherit(&mut loader, &name, cx) {
+            return result;
+        }
+
+        let obj_type = match this.get_string("$type") {
+            Some(obj_type) => obj_type,
+            None => {
+                loader.finish();
+                return Poll::Ready(Err(Error::msg("No type ($type) or parent ($parent)")));
+            }
+        };
 
-        let mut loader = self.loader.lock().unwrap();
-        let mut manager = loader.reference.lock().unwrap();
+        let reference = loader.reference.clone();
+        let mut manager = reference.lock().unwrap();
 
-        let (id, named_type) = match manager.instantiators.get(obj_type) {
-            Some(instantiator) => match instantiator(manager.deref_mut(), &self.object) {
+        let (id, named_type) = match manager.instantiator(obj_type.as_str()) {
+            Some(instantiator) => match instantiator(manager.deref_mut(), &this.object) {
                 Ok(value) => match value {
                     Ok(creator) => creator,
                     Err(blocked) => {
-                        if loader.deadlocked {
-                            return Poll::Ready(Err(Error::msg(format!(
-                                "Failed to find NamedType {} for JSON:\n{:?}", blocked, self.object))));
-                        }
                         drop(manager);
-                        loader.sleeping += 1;
-                        if loader.sleeping == loader.total_tasks {
-                            loader.deadlocked = true;
-                            for wakers in loader.wakers.values() {
-                                for waker in wakers {
-                                    waker.wake_by_ref();
-                                }
-                            }
-                            return Poll::Ready(Err(Error::msg(format!(
-                                "Failed to find NamedType {} for JSON:\n{:?}", blocked, self.object))));
-                        }
-                        match loader.wakers.get_mut(&blocked) {
-                            Some(vec) => vec.push(cx.waker().clone()),
-                            None => {
-                                loader.wakers.insert(blocked, vec!(cx.waker().clone()));
-                            }
-                        }
-                        return Poll::Pending;
+                        return this.sleep(&mut loader, blocked, cx);
                     }
                 },
-                Err(error) => return Poll::Ready(Err(error))
+                Err(error) => {
+                    drop(manager);
+                    loader.finish();
+                    return Poll::Ready(Err(error));
+                }
             },
             None => {
-                return Poll::Ready(Err(Error::msg(
-                    format!("No parent type ($type) in JSON\n{:?}", self.object))));
+                drop(manager);
+                loader.finish();
+                return Poll::Ready(Err(json_path::at_key(
+                    Error::msg(format!("No parent type ($type) registered as {}", obj_type)), "$type")));
             }
         };
 
-        let name = match self.object.get("$name") {
-            Some(name) => name.dump(),
-            None => return Poll::Ready(Err(Error::msg(
-                format!("No name ($name( in JSON\n{:?}", self.object))))
-        };
-
-        let index = manager.all_types.len();
-        manager.named_types.insert(named_type.name().clone(), index);
-        manager.all_types.push(Arc::new(AllocHandle::new(named_type)));
-        match manager.types.get_mut(&id) {
-            Some(found) => found.push(index),
-            None => {
-                manager.types.insert(id, vec!(index));
-            }
+        if let Err(error) = manager.insert_type(id, named_type) {
+            drop(manager);
+            loader.finish();
+            return Poll::Ready(Err(json_path::at_key(error, "$type")));
         }
 
         drop(manager);
-        //Reduce the tasks and wake everyone up.
-        loader.total_tasks -= 1;
-        match loader.wakers.get(&name) {
-            Some(wakers) => for waker in wakers {
-                waker.wake_by_ref()
-            },
-            None => {}
-        }
+        loader.loaded.insert(name.clone(), this.object.clone());
+        loader.sources.insert(name.clone(), this.source.clone());
+        //Something loaded, so anything still waiting might load too. Reduce the tasks and wake everyone up.
+        loader.deadlocked = false;
+        loader.wake(&name);
+        loader.finish();
         return Poll::Ready(Ok(()));
     }
-}
\ No newline at end of file
+}
@@ -1,23 +1,37 @@
 use std::collections::HashMap;
-use std::any::TypeId;
+use std::any::{Any, TypeId};
 use std::sync::Arc;
 use anyhow::Error;
+use json::JsonValue;
+use interfaces::json_path::JsonPath;
 use json::object::Object;
 use tokio::task::JoinSet;
 use crate::mods::mod_manager::ModManager;
 use crate::mods::mods::GameMod;
+#[cfg(feature = "renderer")]
 use crate::rendering::assets::AssetReference;
+#[cfg(feature = "renderer")]
 use crate::rendering::renderer::Renderer;
 use crate::util::alloc_handle::AllocHandle;
+use crate::world::cubes::cube_type::CubeType;
 
 //An arc mutex of functions to return the created type.
 //It's a mouthful, but the best solution I can think of given the circumstance.
-pub type TypeInstantiator = HashMap<String,
-    fn(&mut ResourceManager, &Object) -> Result<Result<(TypeId, Box<dyn NamedType>), String>, Error>>;
+pub type TypeInstantiator = HashMap<String, fn(&mut ResourceManager, &Object) -> Instantiated>;
+
+//The created type, or the name of a type it's waiting for
+pub type Instantiated = Result<Result<(TypeId, Box<dyn NamedType>), String>, Error>;
+
+//Returns the assets a type's JSON refers to
+pub type AssetFinder = fn(&Object) -> Vec<AssetUse>;
 
 pub struct ResourceManager {
-    //Instantiators
-    pub(crate) instantiators: TypeInstantiator,
+    //Instantiators, only added to by register_type
+    instantiators: TypeInstantiator,
+    //Finds the assets each registered type refers to, for validating content
+    asset_finders: HashMap<String, AssetFinder>,
+    //JSON Schema of each registered type, without the $ fields
+    schemas: HashMap<String, fn() -> JsonValue>,
     //Map of types and named types of that type
     pub(crate) types: HashMap<TypeId, Vec<usize>>,
     //Map of types to their name
@@ -25,6 +39,7 @@ pub struct ResourceManager {
     pub(crate) all_types: Vec<Arc<AllocHandle>>,
     #[cfg(feature = "renderer")]
     pub asset_manager: Box<dyn AssetReference>,
+    #[cfg(feature = "renderer")]
     pub renderer: Arc<dyn Renderer>,
     pub _mods: ModManager,
 }
@@ -33,8 +48,10 @@ impl ResourceManager {
     pub fn new(mods: JoinSet<Result<GameMod, Error>>,
                #[cfg(feature = "renderer")]asset_manager: Box<dyn AssetReference>,
                #[cfg(feature = "renderer")]renderer: Arc<dyn Renderer>) -> Self {
-        return ResourceManager {
+        let mut output = ResourceManager {
             instantiators: HashMap::new(),
+            asset_finders: HashMap::new(),
+            schemas: HashMap::new(),
             types: HashMap::new(),
             named_types: HashMap::new(),
             all_types: Vec::new(),
@@ -44,24 +61,176 @@ impl ResourceManager {
             renderer,
             _mods: ModManager::new(mods),
         };
+        output.register_defaults();
+        return output;
     }
 
-    pub fn get_type<T>(&self, name: &str) -> Option<&T> where T: NamedType + 'static {
-        return match self.named_types.get(name) {
-            Some(value) => Some(self.all_types[*value].read()),
-            None => None
+    //Types implemented by the game itself
+    fn register_defaults(&mut self) {
+        self.register_type::<CubeType>("cube").unwrap();
+    }
+
+    //Lets "$type": name in JSON create a T. Names are unique, mods should prefix them with the mod name.
+    pub fn register_type<T>(&mut self, name: &str) -> Result<(), Error> where T: LoadableType {
+        if self.instantiators.contains_key(name) {
+            return Err(Error::msg(format!("Type {} is already registered", name)));
+        }
+        self.instantiators.insert(name.to_string(), Self::instantiate::<T>);
+        self.asset_finders.insert(name.to_string(), T::assets);
+        self.schemas.insert(name.to_string(), T::schema);
+        return Ok(());
+    }
+
+    //The instantiator registered as name
+    pub(crate) fn instantiator(&self, name: &str) -> Option<fn(&mut ResourceManager, &Object) -> Instantiated> {
+        return self.instantiators.get(name).copied();
+    }
+
+    pub fn is_registered(&self, name: &str) -> bool {
+        return self.instantiators.contains_key(name);
+    }
+
+    //Names of every registered type, sorted
+    pub fn registered_types(&self) -> Vec<String> {
+        let mut output = self.instantiators.keys().cloned().collect::<Vec<_>>();
+        output.sort();
+        return output;
+    }
+
+    //JSON Schema for one type in a types file, including $name, $type and $parent
+    pub fn type_schema(&self, name: &str) -> Option<JsonValue> {
+        let mut schema = self.schemas.get(name)?();
+        let mut required = vec!(JsonValue::from("$type"));
+        if let JsonValue::Array(fields) = schema.remove("required") {
+            required.extend(fields);
+        }
+        schema["properties"]["$name"] = json::object! { "type": "string" };
+        schema["properties"]["$type"] = json::object! { "const": name };
+        schema["properties"]["$parent"] = json::object! { "type": "string" };
+        schema["required"] = json::array!["$name"];
+        //Children inherit everything else from their parent
+        let mut own = JsonValue::new_object();
+        own["required"] = JsonValue::Array(required);
+        schema["anyOf"] = json::array![json::object! { "required": ["$parent"] }, own];
+        return Some(schema);
+    }
+
+    //JSON Schema for a types file, one type or an array of them
+    pub fn types_schema(&self) -> JsonValue {
+        let types = self.registered_types().iter().filter_map(|name| self.type_schema(name)).collect::<Vec<_>>();
+        let mut named_type = JsonValue::new_object();
+        named_type["anyOf"] = JsonValue::Array(types);
+        let mut array = json::object! { "type": "array" };
+        array["items"] = named_type.clone();
+        return json::object! { "anyOf": [named_type, array] };
+    }
+
+    //Assets the JSON of a type_name type refers to, empty if the type isn't registered
+    pub fn asset_references(&self, type_name: &str, object: &Object) -> Vec<AssetUse> {
+        return match self.asset_finders.get(type_name) {
+            Some(finder) => finder(object),
+            None => Vec::new()
         };
     }
 
-    pub fn get_all_of_type<T>(&self) -> Vec<&T> where T: 'static {
+    //The instantiator for every registered type, waits for dependencies before loading
+    fn instantiate<T>(resources: &mut ResourceManager, object: &Object) -> Instantiated where T: LoadableType {
+        for dependency in T::dependencies(object) {
+            if !resources.named_types.contains_key(&dependency) {
+                return Ok(Err(dependency));
+            }
+        }
+        return Ok(Ok((TypeId::of::<T>(), Box::new(T::instantiate(resources, object)?))));
+    }
+
+    //Adds a loaded type, or replaces the one with the same name. Replacing fails if it would change the type's type.
+    pub(crate) fn insert_type(&mut self, id: TypeId, named_type: Box<dyn NamedType>) -> Result<(), Error> {
+        let name = named_type.name();
+        if let Some(index) = self.named_types.get(&name) {
+            if !self.types.get(&id).is_some_and(|found| found.contains(index)) {
+                return Err(Error::msg(format!("{} changed to a different $type, restart to load it", name)));
+            }
+            self.all_types[*index] = Arc::new(AllocHandle::new(named_type));
+            return Ok(());
+        }
+
+        let index = self.all_types.len();
+        self.named_types.insert(name, index);
+        self.all_types.push(Arc::new(AllocHandle::new(named_type)));
+        self.types.entry(id).or_default().push(index);
+        return Ok(());
+    }
+
+    pub fn get_type<T>(&self, name: &str) -> Option<&T> where T: NamedType + 'static {
+        let found: &dyn Any = self.all_types[*self.named_types.get(name)?].read::<Box<dyn NamedType>>().as_ref();
+        return found.downcast_ref();
+    }
+
+    pub fn get_all_of_type<T>(&self) -> Vec<&T> where T: NamedType + 'static {
         let mut output = Vec::new();
-        for value in self.types.get(&TypeId::of::<T>()).unwrap() {
-            output.push(self.all_types[*value].read());
+        for value in self.types.get(&TypeId::of::<T>()).into_iter().flatten() {
+            let found: &dyn Any = self.all_types[*value].read::<Box<dyn NamedType>>().as_ref();
+            output.extend(found.downcast_ref::<T>());
         }
         return output;
     }
 }
 
-pub trait NamedType: Send {
+pub trait NamedType: Send + Any {
     fn name(&self) -> String;
-}
\ No newline at end of file
+}
+
+//A NamedType that can be registered with ResourceManager::register_type. Usually derived with #[derive(NamedType)].
+pub trait LoadableType: NamedType + Sized {
+    //Names of other NamedTypes that have to load before this one
+    fn dependencies(_object: &Object) -> Vec<String> {
+        return Vec::new();
+    }
+
+    //Textures, models, shaders and translations the type refers to by name
+    fn assets(_object: &Object) -> Vec<AssetUse> {
+        return Vec::new();
+    }
+
+    //JSON Schema of the type's fields, accepts anything by default
+    fn schema() -> JsonValue {
+        return JsonValue::new_object();
+    }
+
+    fn instantiate(resources: &mut ResourceManager, object: &Object) -> Result<Self, Error>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AssetKind {
+    Texture,
+    Model,
+    Shader,
+    Translation
+}
+
+//An asset named in a type's JSON, and where
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssetUse {
+    pub kind: AssetKind,
+    pub name: String,
+    pub path: JsonPath,
+}
+
+//Type names in a dependency field, which can be one name or an array of them
+pub fn dependency_names(value: &JsonValue) -> Vec<String> {
+    return match value {
+        JsonValue::Array(values) => values.iter().filter_map(JsonValue::as_str).map(str::to_string).collect(),
+        _ => value.as_str().map(str::to_string).into_iter().collect()
+    };
+}
+
+//Asset names in an asset field, which can be one name, an array of them or an object with them as values
+pub fn asset_names(kind: AssetKind, value: &JsonValue, path: JsonPath) -> Vec<AssetUse> {
+    return match value {
+        JsonValue::Array(values) => values.iter().enumerate()
+            .flat_map(|(i, value)| asset_names(kind, value, path.clone().index(i))).collect(),
+        JsonValue::Object(object) => object.iter()
+            .flat_map(|(key, value)| asset_names(kind, value, path.clone().key(key))).collect(),
+        _ => value.as_str().map(|name| AssetUse { kind, name: name.to_string(), path }).into_iter().collect()
+    };
+}
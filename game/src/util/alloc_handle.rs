@@ -1,22 +1,21 @@
-use std::alloc::{dealloc, Layout};
-use std::{mem, ptr};
+use std::ptr;
 use std::any::TypeId;
 
 pub struct AllocHandle {
     pointer: u64,
     type_id: TypeId,
-    size: usize,
+    //Drops the value with its real type, None if there's nothing to drop
+    dropper: Option<unsafe fn(u64)>,
 }
 
 impl AllocHandle {
     pub fn new<T>(allocing: T) -> Self where T: 'static {
-        let size = mem::size_of_val(&allocing);
         let reference = Box::leak(Box::new(allocing));
 
         return Self {
             pointer: reference as *mut T as u64,
             type_id: TypeId::of::<T>(),
-            size,
+            dropper: Some(Self::drop_value::<T>),
         };
     }
 
@@ -24,10 +23,14 @@ impl AllocHandle {
         return Self {
             pointer: 0,
             type_id: TypeId::of::<AllocHandle>(),
-            size: 0,
+            dropper: None,
         };
     }
 
+    unsafe fn drop_value<T>(pointer: u64) {
+        drop(Box::from_raw(pointer as *mut T));
+    }
+
     pub fn read<T>(&self) -> &T where T: 'static {
         //Must save and read the same thing, but generics can't be kept in every situation, so the ID is checked
         assert_eq!(TypeId::of::<T>(), self.type_id);
@@ -60,12 +63,10 @@ impl AllocHandle {
 
 impl Drop for AllocHandle {
     fn drop(&mut self) {
-        if self.size == 0 {
-            return;
-        }
-        unsafe {
-            dealloc(self.pointer as *mut u64 as *mut u8,
-                    Layout::from_size_align_unchecked(self.size, 0));
+        if let Some(dropper) = self.dropper {
+            unsafe {
+                dropper(self.pointer);
+            }
         }
     }
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 use json::JsonValue;
+use json::object::Object;
 use crate::error;
 
 pub fn stringify(array: &JsonValue) -> Vec<String> {
@@ -13,4 +14,14 @@ pub fn stringify(array: &JsonValue) -> Vec<String> {
         _ => error!("Expected Array, found other value:\n{}", array)
     }
     return Vec::new();
-}
\ No newline at end of file
+}
+
+//Merges overrides into base. Objects are merged key by key, anything else in overrides replaces what's in base.
+pub fn deep_merge(base: &mut Object, overrides: &Object) {
+    for (key, value) in overrides.iter() {
+        match (base.get_mut(key), value) {
+            (Some(JsonValue::Object(base)), JsonValue::Object(value)) => deep_merge(base, value),
+            _ => base.insert(key, value.clone())
+        }
+    }
+}
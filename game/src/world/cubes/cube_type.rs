@@ -0,0 +1,15 @@
+use std::collections::HashMap;
+use macros::{JsonLoadable, NamedType};
+
+//The "cube" named type, what a kind of cube looks like
+//Unknown keys are errors, so typos in resources/types get reported
+#[derive(JsonLoadable, NamedType, Clone, Debug, Default)]
+#[json(deny_unknown_keys)]
+pub struct CubeType {
+    #[ignore_field]
+    pub name: String,
+    //Texture of each face (top, bottom, side), by texture name
+    #[require_field]
+    #[texture]
+    pub faces: HashMap<String, String>,
+}
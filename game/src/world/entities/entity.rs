@@ -1,28 +1,95 @@
-use std::sync::atomic::AtomicU64;
+use std::sync::atomic::{AtomicU64, Ordering};
+use anyhow::Error;
+use cgmath::Vector3;
+use interfaces::json_path;
+use interfaces::loading::JsonLoadable;
+use interfaces::saving::JsonSaveable;
+use json::JsonValue;
+use json::object::Object;
+use crate::error;
 use crate::world::world::WorldData;
 
-static mut NEXT_ID: AtomicU64 = AtomicU64::new(0);
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
 
 pub struct Entity {
-    _id: u64
+    id: u64,
+    pub entity_type: String,
+    //Index of the room this entity is in
+    pub room: usize,
+    pub position: Vector3<f32>,
+    //Movement per update
+    pub velocity: Vector3<f32>,
+    pub rotation: f32,
 }
 
 impl Entity {
     //Creates a new entity. These aren't in a world yet, and must be spawned
-    pub fn new() -> Self {
+    pub fn new(entity_type: String, room: usize, position: Vector3<f32>) -> Self {
         return Self {
-            _id: Self::get_next_id()
+            id: Self::get_next_id(),
+            entity_type,
+            room,
+            position,
+            velocity: Vector3::new(0.0, 0.0, 0.0),
+            rotation: 0.0,
         }
     }
 
-    pub fn spawn(self, _world: &mut WorldData) {
-        todo!();
+    //Creates a local copy of an entity that exists on the server
+    pub fn replica(id: u64, entity_type: String, room: usize) -> Self {
+        return Self {
+            id,
+            entity_type,
+            room,
+            position: Vector3::new(0.0, 0.0, 0.0),
+            velocity: Vector3::new(0.0, 0.0, 0.0),
+            rotation: 0.0,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        return self.id;
+    }
+
+    pub fn spawn(self, world: &mut WorldData) {
+        match world.rooms_mut().get_mut(self.room) {
+            Some(room) => room.add_entity(self),
+            None => error!("Tried to spawn entity {} in missing room {}", self.id, self.room)
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.position += self.velocity;
     }
 
     fn get_next_id() -> u64 {
-        //Unsafe due to race conditions, but atomic u64 fixes that
-        let id = unsafe { *NEXT_ID.get_mut() };
-        unsafe { NEXT_ID = AtomicU64::new(id + 1) }
-        return id;
+        return NEXT_ID.fetch_add(1, Ordering::Relaxed);
     }
-}
\ No newline at end of file
+}
+
+//Ids aren't saved, loaded entities get new ones
+impl JsonSaveable for Entity {
+    fn save(&self) -> JsonValue {
+        let mut output = Object::new();
+        output.insert("type", self.entity_type.save());
+        output.insert("room", (self.room as u64).save());
+        output.insert("position", self.position.save());
+        output.insert("velocity", self.velocity.save());
+        output.insert("rotation", self.rotation.save());
+        return JsonValue::Object(output);
+    }
+}
+
+impl JsonLoadable for Entity {
+    fn load(value: &JsonValue) -> Result<Self, Error> {
+        let mut entity = Entity::new(load_key(value, "type")?, load_key::<u64>(value, "room")? as usize,
+                                     load_key(value, "position")?);
+        entity.velocity = load_key(value, "velocity")?;
+        entity.rotation = load_key(value, "rotation")?;
+        return Ok(entity);
+    }
+}
+
+fn load_key<T: JsonLoadable>(value: &JsonValue, key: &str) -> Result<T, Error> {
+    return T::load(&value[key]).map_err(|error| json_path::at_key(error, key));
+}
@@ -1 +1,2 @@
-pub mod entity;
\ No newline at end of file
+pub mod entity;
+pub mod player;
\ No newline at end of file
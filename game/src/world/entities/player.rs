@@ -0,0 +1,42 @@
+use std::f32::consts::TAU;
+use cgmath::{InnerSpace, Vector3};
+use crate::network::quantize::quantize_rotation;
+use crate::world::entities::entity::Entity;
+
+//Units moved per update at full input
+pub const PLAYER_SPEED: f32 = 0.1;
+
+//One update's worth of player input. The client predicts with these and the server
+//applies them, so both must go through apply.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PlayerInput {
+    pub sequence: u32,
+    //Direction, each axis from -127 to 127
+    pub movement: [i8; 3],
+    //Fraction of a full turn
+    pub rotation: u16,
+}
+
+impl PlayerInput {
+    pub fn new(sequence: u32, movement: Vector3<f32>, rotation: f32) -> Self {
+        //Diagonals shouldn't be faster
+        let movement = if movement.magnitude2() > 1.0 {
+            movement.normalize()
+        } else {
+            movement
+        };
+        return Self {
+            sequence,
+            movement: [(movement.x * 127.0).round() as i8,
+                (movement.y * 127.0).round() as i8,
+                (movement.z * 127.0).round() as i8],
+            rotation: quantize_rotation(rotation),
+        };
+    }
+
+    pub fn apply(&self, entity: &mut Entity) {
+        entity.velocity = Vector3::new(self.movement[0] as f32, self.movement[1] as f32,
+                                       self.movement[2] as f32) / 127.0 * PLAYER_SPEED;
+        entity.rotation = self.rotation as f32 / (u16::MAX as f32 + 1.0) * TAU;
+    }
+}
@@ -1,8 +1,16 @@
+use anyhow::Error;
+use interfaces::json_path;
+use interfaces::loading::JsonLoadable;
+use interfaces::saving::JsonSaveable;
+use json::JsonValue;
+use json::object::Object;
 use crate::resources::resource_manager::ResourceManager;
 use crate::world::blocks::block::Block;
+use crate::world::entities::entity::Entity;
 
 pub struct Room {
     _blocks: Vec<Block>,
+    entities: Vec<Entity>,
 }
 
 impl Room {
@@ -10,9 +18,59 @@ impl Room {
         let blocks = vec!(Block::new(resources));
 
         return Self {
-            _blocks: blocks
+            _blocks: blocks,
+            entities: Vec::new(),
         };
     }
 
-    pub fn update(&mut self) {}
-}
\ No newline at end of file
+    pub fn empty() -> Self {
+        return Self {
+            _blocks: Vec::new(),
+            entities: Vec::new(),
+        };
+    }
+
+    pub fn update(&mut self) {
+        for entity in &mut self.entities {
+            entity.update();
+        }
+    }
+
+    pub fn entities(&self) -> &Vec<Entity> {
+        return &self.entities;
+    }
+
+    pub fn get_entity_mut(&mut self, id: u64) -> Option<&mut Entity> {
+        return self.entities.iter_mut().find(|entity| entity.id() == id);
+    }
+
+    pub fn add_entity(&mut self, entity: Entity) {
+        self.entities.push(entity);
+    }
+
+    pub fn remove_entity(&mut self, id: u64) -> Option<Entity> {
+        return match self.entities.iter().position(|entity| entity.id() == id) {
+            Some(index) => Some(self.entities.remove(index)),
+            None => None
+        };
+    }
+}
+
+//Only entities are saved, blocks come from the room's resources
+impl JsonSaveable for Room {
+    fn save(&self) -> JsonValue {
+        let mut output = Object::new();
+        output.insert("entities", self.entities.save());
+        return JsonValue::Object(output);
+    }
+}
+
+impl JsonLoadable for Room {
+    fn load(value: &JsonValue) -> Result<Self, Error> {
+        let mut room = Room::empty();
+        if value.has_key("entities") {
+            room.entities = Vec::load(&value["entities"]).map_err(|error| json_path::at_key(error, "entities"))?;
+        }
+        return Ok(room);
+    }
+}
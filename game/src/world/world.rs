@@ -2,6 +2,11 @@ use std::ops::Deref;
 use std::sync::{Arc, mpsc, Mutex};
 use std::sync::mpsc::{Receiver, Sender};
 use anyhow::Error;
+use interfaces::json_path::{self, JsonError, JsonPath};
+use interfaces::loading::JsonLoadable;
+use interfaces::saving::JsonSaveable;
+use json::JsonValue;
+use json::object::Object;
 use tokio::runtime::Handle;
 use crate::resources::resource_manager::ResourceManager;
 use crate::world::attachments::WorldAttachment;
@@ -16,6 +21,7 @@ pub struct World {
 }
 
 pub struct WorldData {
+    tick: u64,
     rooms: Vec<Room>,
     input_receiver: Receiver<WorldInput>
 }
@@ -60,9 +66,7 @@ impl World {
                 Err(_error) => return
             }
 
-            for room in &mut world_data.rooms {
-                room.update();
-            }
+            world_data.update();
 
             for attachment in &mut attachments {
                 attachment.update(&mut world_data);
@@ -74,8 +78,70 @@ impl World {
 impl WorldData {
     pub fn new(input_receiver: Receiver<WorldInput>) -> Self {
         return Self {
+            tick: 0,
             rooms: Vec::new(),
             input_receiver
         };
     }
-}
\ No newline at end of file
+
+    pub fn update(&mut self) {
+        self.tick += 1;
+        for room in &mut self.rooms {
+            room.update();
+        }
+    }
+
+    pub fn tick(&self) -> u64 {
+        return self.tick;
+    }
+
+    pub fn rooms(&self) -> &Vec<Room> {
+        return &self.rooms;
+    }
+
+    pub fn rooms_mut(&mut self) -> &mut Vec<Room> {
+        return &mut self.rooms;
+    }
+
+    //Replaces the rooms with ones from a saved world
+    pub fn load(&mut self, value: &JsonValue) -> Result<(), Error> {
+        self.tick = u64::load(&value["tick"]).map_err(|error| json_path::at_key(error, "tick"))?;
+        self.rooms = Vec::load(&value["rooms"]).map_err(|error| json_path::at_key(error, "rooms"))?;
+        for (index, room) in self.rooms.iter().enumerate() {
+            if let Some((i, entity)) = room.entities().iter().enumerate().find(|(_, entity)| entity.room != index) {
+                return Err(Error::new(JsonError {
+                    path: JsonPath::new().key("rooms").index(index).key("entities").index(i).key("room"),
+                    message: format!("Entity saved in room {} is in room {}", entity.room, index)
+                }));
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn get_entity_mut(&mut self, id: u64) -> Option<&mut Entity> {
+        for room in &mut self.rooms {
+            if let Some(entity) = room.get_entity_mut(id) {
+                return Some(entity);
+            }
+        }
+        return None;
+    }
+
+    pub fn remove_entity(&mut self, id: u64) -> Option<Entity> {
+        for room in &mut self.rooms {
+            if let Some(entity) = room.remove_entity(id) {
+                return Some(entity);
+            }
+        }
+        return None;
+    }
+}
+
+impl JsonSaveable for WorldData {
+    fn save(&self) -> JsonValue {
+        let mut output = Object::new();
+        output.insert("tick", self.tick.save());
+        output.insert("rooms", self.rooms.save());
+        return JsonValue::Object(output);
+    }
+}
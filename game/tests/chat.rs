@@ -0,0 +1,124 @@
+use std::time::Duration;
+use game::language::language::{LanguageManager, LanguagePack};
+use game::network::chat::{ChatChannel, ChatContent, ChatLimiter, ChatMessage};
+use game::network::client::NetworkClient;
+use game::network::packets::ServerPacket;
+use game::network::replication::ReplicationSettings;
+use game::network::server::NetworkServer;
+use game::world::world::WorldData;
+use common::network;
+
+mod common;
+
+fn join(world: &mut WorldData, server: &mut NetworkServer, name: &str) -> NetworkClient {
+    let mut client = network::connect(server, name, Duration::ZERO);
+    network::step(world, server, &mut [&mut client]);
+    return client;
+}
+
+fn texts(client: &mut NetworkClient) -> Vec<String> {
+    return client.chat.drain(..).map(|message| match message.content {
+        ChatContent::Text(text) => text,
+        ChatContent::Translated { key, arguments } => format!("{} {:?}", key, arguments)
+    }).collect();
+}
+
+fn english() -> LanguageManager {
+    let mut language = LanguageManager::new();
+    language.load_packs(vec!(LanguagePack::Translations(vec!(json::object! {
+        "chat.joined": "{0} joined the game",
+        "chat.whisper_sent": "You whisper to {0}: {1}"
+    }))));
+    return language;
+}
+
+#[test]
+fn channels_reach_the_right_players() {
+    let mut world = network::create_world(2);
+    let mut server = NetworkServer::new(ReplicationSettings::default());
+    let mut alice = join(&mut world, &mut server, "alice");
+    let mut bob = join(&mut world, &mut server, "bob");
+    let mut carol = join(&mut world, &mut server, "carol");
+    server.set_room(carol.client_id.unwrap(), 1, &mut world);
+    network::step(&mut world, &mut server, &mut [&mut alice, &mut bob, &mut carol]);
+    alice.chat.clear();
+    bob.chat.clear();
+    carol.chat.clear();
+
+    alice.send_chat(ChatChannel::Global, "hello everyone".to_string()).unwrap();
+    alice.send_chat(ChatChannel::Room, "hello room".to_string()).unwrap();
+    alice.send_chat(ChatChannel::Whisper("carol".to_string()), "psst".to_string()).unwrap();
+    alice.send_chat(ChatChannel::Whisper("nobody".to_string()), "hello?".to_string()).unwrap();
+    network::step(&mut world, &mut server, &mut [&mut alice, &mut bob, &mut carol]);
+
+    assert_eq!(texts(&mut bob), vec!("hello everyone", "hello room"));
+    let whisper = carol.chat[1].clone();
+    assert_eq!(whisper.channel, ChatChannel::Whisper("alice".to_string()));
+    assert_eq!(texts(&mut carol), vec!("hello everyone", "psst"));
+    assert_eq!(texts(&mut alice), vec!("hello everyone", "hello room",
+                                       "chat.whisper_sent [\"carol\", \"psst\"]",
+                                       "chat.player_not_found [\"nobody\"]"));
+}
+
+#[test]
+fn system_messages_announce_joins_and_leaves() {
+    let mut world = network::create_world(2);
+    let mut server = NetworkServer::new(ReplicationSettings::default());
+    let mut alice = join(&mut world, &mut server, "alice");
+    let mut bob = join(&mut world, &mut server, "bob");
+    network::step(&mut world, &mut server, &mut [&mut alice]);
+
+    assert_eq!(alice.chat[1], ChatMessage::system("chat.joined", vec!("bob".to_string())));
+    assert_eq!(alice.chat[1].render(&english()), "bob joined the game");
+
+    server.disconnect(bob.client_id.unwrap(), "Leaving");
+    network::step(&mut world, &mut server, &mut [&mut alice, &mut bob]);
+    assert_eq!(alice.chat.last().unwrap(), &ChatMessage::system("chat.left", vec!("bob".to_string())));
+}
+
+#[test]
+fn chat_is_rate_limited() {
+    let mut world = network::create_world(2);
+    let mut server = NetworkServer::new(ReplicationSettings::default());
+    server.chat_limit = ChatLimiter::new(3, 10);
+    let mut alice = join(&mut world, &mut server, "alice");
+    alice.chat.clear();
+
+    for i in 0..5 {
+        alice.send_chat(ChatChannel::Global, format!("spam {}", i)).unwrap();
+    }
+    network::step(&mut world, &mut server, &mut [&mut alice]);
+    assert_eq!(texts(&mut alice), vec!("spam 0", "spam 1", "spam 2", "chat.rate_limited []", "chat.rate_limited []"));
+
+    //One more message is earned every 10 updates
+    for _ in 0..10 {
+        network::step(&mut world, &mut server, &mut [&mut alice]);
+    }
+    alice.send_chat(ChatChannel::Global, "again".to_string()).unwrap();
+    alice.send_chat(ChatChannel::Global, "too soon".to_string()).unwrap();
+    network::step(&mut world, &mut server, &mut [&mut alice]);
+    assert_eq!(texts(&mut alice), vec!("again", "chat.rate_limited []"));
+}
+
+#[test]
+fn long_and_empty_messages_are_rejected() {
+    let mut world = network::create_world(2);
+    let mut server = NetworkServer::new(ReplicationSettings::default());
+    let mut alice = join(&mut world, &mut server, "alice");
+    alice.chat.clear();
+
+    alice.send_chat(ChatChannel::Global, "   ".to_string()).unwrap();
+    alice.send_chat(ChatChannel::Global, "a".repeat(300)).unwrap();
+    network::step(&mut world, &mut server, &mut [&mut alice]);
+    assert_eq!(texts(&mut alice), vec!("chat.too_long [\"256\"]"));
+}
+
+#[test]
+fn translations_fill_in_arguments_once() {
+    let language = english();
+    let message = ChatMessage::system("chat.whisper_sent", vec!("{1}".to_string(), "hi {0}".to_string()));
+    assert_eq!(message.render(&language), "You whisper to {1}: hi {0}");
+
+    let packet = ServerPacket::Chat(message.clone());
+    assert_eq!(ServerPacket::decode(&packet.encode()).unwrap(), packet);
+}
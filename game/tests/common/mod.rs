@@ -0,0 +1,73 @@
+use std::sync::{Arc, Mutex};
+use anyhow::Error;
+use json::object::Object;
+use json::JsonValue;
+use macros::{JsonLoadable, NamedType};
+use tokio::runtime::Builder;
+use tokio::task::JoinSet;
+use game::mods::mods::GameMod;
+#[cfg(feature = "renderer")]
+use game::rendering::headless::{NoAssets, NoRenderer};
+use game::resources::resource_loader::ResourceLoader;
+use game::resources::resource_manager::ResourceManager;
+
+pub mod network;
+
+//A type mods could add, depending on the types it makes and is made from
+#[derive(JsonLoadable, NamedType, Default)]
+#[allow(dead_code)]
+pub struct Recipe {
+    #[ignore_field]
+    pub name: String,
+    #[dependency]
+    pub result: String,
+    #[dependency]
+    pub ingredients: Vec<String>,
+    pub count: u32,
+}
+
+//A ResourceManager without a window or mods
+#[allow(dead_code)]
+pub fn create_manager() -> ResourceManager {
+    return create_manager_with(JoinSet::new());
+}
+
+//A ResourceManager without a window, loading mods
+#[allow(dead_code)]
+pub fn create_manager_with(mods: JoinSet<Result<GameMod, Error>>) -> ResourceManager {
+    #[cfg(feature = "renderer")]
+    return ResourceManager::new(mods, Box::new(NoAssets), Arc::new(NoRenderer));
+    #[cfg(not(feature = "renderer"))]
+    return ResourceManager::new(mods);
+}
+
+pub fn object(value: JsonValue) -> Object {
+    return match value {
+        JsonValue::Object(object) => object,
+        _ => panic!("Expected an object")
+    };
+}
+
+//Loads every type at once into the manager, returning each one's result in order
+#[allow(dead_code)]
+pub fn load(manager: Arc<Mutex<ResourceManager>>, types: Vec<JsonValue>) -> Vec<Result<(), Error>> {
+    return load_with(Arc::new(Mutex::new(ResourceLoader::new(manager))), types);
+}
+
+//Like load, with a loader that can be kept for reloading types
+pub fn load_with(loader: Arc<Mutex<ResourceLoader>>, types: Vec<JsonValue>) -> Vec<Result<(), Error>> {
+    let runtime = Builder::new_current_thread().build().unwrap();
+    return runtime.block_on(async {
+        let mut join_set = JoinSet::new();
+        for (index, found) in types.into_iter().enumerate() {
+            let task = ResourceLoader::spawn(loader.clone(), object(found));
+            join_set.spawn(async move { (index, task.await) });
+        }
+        let mut results = Vec::new();
+        while let Some(result) = join_set.join_next().await {
+            results.push(result.unwrap());
+        }
+        results.sort_by_key(|(index, _)| *index);
+        return results.into_iter().map(|(_, result)| result).collect();
+    });
+}
@@ -0,0 +1,39 @@
+use std::sync::mpsc;
+use std::time::Duration;
+use game::network::client::NetworkClient;
+use game::network::server::NetworkServer;
+use game::network::transport::LoopbackTransport;
+use game::world::rooms::room::Room;
+use game::world::world::WorldData;
+
+//Only uses game, so the client's tests can include this file too
+
+//A world with empty rooms
+#[allow(dead_code)]
+pub fn create_world(rooms: usize) -> WorldData {
+    //The sender is dropped, the world is driven directly instead of through its input channel
+    let (_, receiver) = mpsc::channel();
+    let mut world = WorldData::new(receiver);
+    for _ in 0..rooms {
+        world.rooms_mut().push(Room::empty());
+    }
+    return world;
+}
+
+//Connects a client to the server, with every packet taking latency to arrive
+#[allow(dead_code)]
+pub fn connect(server: &mut NetworkServer, name: &str, latency: Duration) -> NetworkClient {
+    let (server_end, client_end) = LoopbackTransport::pair_with_latency(latency);
+    server.connect(Box::new(server_end));
+    return NetworkClient::connect(Box::new(client_end), name.to_string()).unwrap();
+}
+
+//Updates the world, sends it to the clients and has them read it
+#[allow(dead_code)]
+pub fn step(world: &mut WorldData, server: &mut NetworkServer, clients: &mut [&mut NetworkClient]) {
+    world.update();
+    server.tick(world);
+    for client in clients {
+        client.poll().unwrap();
+    }
+}
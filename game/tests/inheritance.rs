@@ -0,0 +1,85 @@
+use std::sync::{Arc, Mutex};
+use anyhow::Error;
+use json::JsonValue;
+use game::resources::resource_loader::ResourceLoader;
+use game::util::json_util;
+use interfaces::json_path::{self, JsonPath};
+use common::object;
+
+mod common;
+
+fn load(types: Vec<JsonValue>) -> Vec<Result<(), Error>> {
+    return common::load(Arc::new(Mutex::new(common::create_manager())), types);
+}
+
+#[test]
+fn deep_merge_overrides_leaves() {
+    let mut base = object(json::object! {
+        "$type": "cube",
+        "faces": { "top": "grass", "side": "dirt" },
+        "tags": ["natural", "solid"],
+        "hardness": 1
+    });
+    json_util::deep_merge(&mut base, &object(json::object! {
+        "$name": "snowy_grass",
+        "faces": { "top": "snow" },
+        "tags": ["cold"]
+    }));
+
+    assert_eq!(JsonValue::Object(base), json::object! {
+        "$type": "cube",
+        "faces": { "top": "snow", "side": "dirt" },
+        "tags": ["cold"],
+        "hardness": 1,
+        "$name": "snowy_grass"
+    });
+}
+
+#[test]
+fn long_chains_load() {
+    //Which sleeping task wakes first changes between runs, so try a few times
+    for _ in 0..50 {
+        let manager = Arc::new(Mutex::new(common::create_manager()));
+        let loader = Arc::new(Mutex::new(ResourceLoader::new(manager)));
+        let results = common::load_with(loader.clone(), vec!(
+            json::object! { "$name": "test:e", "$parent": "test:d", "faces": { "bottom": "e" } },
+            json::object! { "$name": "test:d", "$parent": "test:c", "faces": { "side": "d" } },
+            json::object! { "$name": "test:c", "$parent": "test:b" },
+            json::object! { "$name": "test:b", "$parent": "test:a", "faces": { "top": "b" } },
+            json::object! { "$name": "test:a", "$type": "cube", "faces": { "top": "a", "side": "a" } }
+        ));
+        for result in results {
+            result.unwrap();
+        }
+        let loader = loader.lock().unwrap();
+        assert_eq!(loader.get_json("test:e").unwrap()["faces"], json::object! { "top": "b", "side": "d", "bottom": "e" });
+    }
+}
+
+#[test]
+fn inheritance_cycles_fail() {
+    let results = load(vec!(
+        json::object! { "$name": "first", "$parent": "second" },
+        json::object! { "$name": "second", "$parent": "third" },
+        json::object! { "$name": "third", "$parent": "first" },
+        json::object! { "$name": "itself", "$parent": "itself" }
+    ));
+    for result in results {
+        let error = result.unwrap_err();
+        assert_eq!(json_path::error_path(&error), JsonPath::new().key("$parent"));
+        assert!(json_path::error_message(&error).starts_with("Inheritance cycle"), "{}", error);
+    }
+}
+
+#[test]
+fn missing_parent_fails() {
+    let results = load(vec!(
+        json::object! { "$name": "orphan", "$parent": "nobody" },
+        json::object! { "$name": "grandchild", "$parent": "orphan" }
+    ));
+    for result in results {
+        let error = result.unwrap_err();
+        assert_eq!(json_path::error_path(&error), JsonPath::new().key("$parent"));
+        assert!(json_path::error_message(&error).starts_with("Failed to find NamedType"), "{}", error);
+    }
+}
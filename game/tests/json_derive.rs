@@ -0,0 +1,291 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use json::JsonValue;
+use macros::{JsonLoadable, JsonSaveable};
+use interfaces::format::{Binary, DataFormat, Json};
+use interfaces::json_path::{self, JsonPath};
+use interfaces::loading::JsonLoadable;
+use interfaces::saving::JsonSaveable;
+
+#[derive(JsonLoadable, JsonSaveable, Debug, PartialEq)]
+pub enum Shape {
+    Empty,
+    Circle(f32),
+    Rectangle(f32, f32),
+    Polygon {
+        #[require_field]
+        points: Vec<[f32; 2]>,
+        closed: bool,
+    },
+}
+
+#[derive(JsonLoadable, JsonSaveable, Debug, PartialEq)]
+#[json_tag = "kind"]
+pub enum Action {
+    Jump,
+    Move {
+        #[require_field]
+        speed: f32,
+        #[ignore_field]
+        elapsed: u32,
+    },
+    Say(Message),
+}
+
+//Single field variants of internally tagged enums can hold anything that saves as an object
+#[derive(JsonLoadable, JsonSaveable, Debug, PartialEq)]
+#[json_tag = "type"]
+pub enum Event {
+    Act(Action),
+    Count(BTreeMap<String, u32>),
+    Note(Box<Message>),
+}
+
+#[derive(JsonLoadable, JsonSaveable, Default, Debug, PartialEq)]
+pub struct Message {
+    pub text: String,
+}
+
+#[derive(JsonLoadable, JsonSaveable, Debug, PartialEq)]
+pub struct Meters(f64);
+
+#[derive(JsonLoadable, JsonSaveable, Clone, Default, Debug, PartialEq)]
+pub struct Position(i32, i32, i32);
+
+#[derive(JsonLoadable, JsonSaveable, Default, Debug, PartialEq)]
+pub struct Named<T> where T: Clone {
+    pub name: String,
+    pub value: T,
+}
+
+#[derive(JsonLoadable, JsonSaveable, Debug, PartialEq)]
+#[json(deny_unknown_keys)]
+pub struct Sign {
+    #[json(rename = "text", alias = "message")]
+    pub contents: String,
+    #[json(default = 12, skip_serializing_if_default)]
+    pub size: u32,
+    #[json(skip_serializing_if_default)]
+    pub glowing: bool,
+    #[json(flatten)]
+    pub position: Placement,
+    #[ignore_field]
+    pub cached: Vec<u8>,
+}
+
+impl Default for Sign {
+    fn default() -> Self {
+        return Self {
+            contents: String::new(),
+            size: 12,
+            glowing: true,
+            position: Placement::default(),
+            cached: vec!(1, 2, 3),
+        };
+    }
+}
+
+#[derive(JsonLoadable, JsonSaveable, Default, Debug, PartialEq)]
+#[json(deny_unknown_keys)]
+pub struct Placement {
+    #[require_field]
+    pub x: i32,
+    #[require_field]
+    pub y: i32,
+}
+
+#[derive(JsonLoadable, JsonSaveable, Default, Debug, PartialEq)]
+pub struct Size {
+    pub width: u32,
+    pub height: u32,
+}
+
+//Denies unknown keys itself, with flattened fields that do and don't
+#[derive(JsonLoadable, JsonSaveable, Default, Debug, PartialEq)]
+#[json(deny_unknown_keys)]
+pub struct Frame {
+    pub name: String,
+    #[json(flatten)]
+    pub position: Placement,
+    #[json(flatten)]
+    pub size: Size,
+}
+
+#[derive(JsonLoadable, JsonSaveable, Debug, PartialEq)]
+#[json(deny_unknown_keys)]
+pub enum Decoration {
+    Banner {
+        #[json(rename = "colour", default = String::from("red"), skip_serializing_if_default)]
+        color: String,
+    },
+}
+
+//Checks value saves as json and loads from it, and reads back what it writes in binary
+fn round_trip<T: JsonLoadable + JsonSaveable + PartialEq + Debug>(value: T, json: &str) {
+    let json = json::parse(json).unwrap();
+    assert_eq!(value.save(), json);
+    assert_eq!(T::load(&json).unwrap(), value);
+    assert_eq!(T::load_from::<Binary>(&value.save_to::<Binary>()).unwrap(), value);
+}
+
+fn load_error<T: JsonLoadable + Debug>(json: &str) -> anyhow::Error {
+    return T::load(&json::parse(json).unwrap()).unwrap_err();
+}
+
+#[test]
+fn externally_tagged_enums() {
+    round_trip(Shape::Empty, r#""Empty""#);
+    round_trip(Shape::Circle(2.0), r#"{"Circle": 2}"#);
+    round_trip(Shape::Rectangle(1.0, 3.0), r#"{"Rectangle": [1, 3]}"#);
+    round_trip(Shape::Polygon { points: vec!([0.0, 0.0], [1.0, 0.0], [0.0, 1.0]), closed: true },
+               r#"{"Polygon": {"points": [[0, 0], [1, 0], [0, 1]], "closed": true}}"#);
+    //Missing fields are defaulted and unit variants can be objects too
+    assert_eq!(Shape::load(&json::parse(r#"{"Polygon": {"points": []}}"#).unwrap()).unwrap(),
+               Shape::Polygon { points: vec!(), closed: false });
+    assert_eq!(Shape::load(&json::parse(r#"{"Empty": null}"#).unwrap()).unwrap(), Shape::Empty);
+    //Map values don't need a default
+    round_trip(HashMap::from([(String::from("ring"), Shape::Circle(2.0))]), r#"{"ring": {"Circle": 2}}"#);
+}
+
+#[test]
+fn internally_tagged_enums() {
+    round_trip(Action::Jump, r#"{"kind": "Jump"}"#);
+    round_trip(Action::Say(Message { text: String::from("Hi") }), r#"{"text": "Hi", "kind": "Say"}"#);
+    assert_eq!(Action::load(&json::parse(r#"{"kind": "Move", "speed": 2}"#).unwrap()).unwrap(),
+               Action::Move { speed: 2.0, elapsed: 0 });
+    //Ignored fields aren't saved
+    assert_eq!(Action::Move { speed: 2.0, elapsed: 5 }.save(),
+               json::parse(r#"{"kind": "Move", "speed": 2}"#).unwrap());
+
+    round_trip(Event::Act(Action::Jump), r#"{"kind": "Jump", "type": "Act"}"#);
+    round_trip(Event::Count(BTreeMap::from([(String::from("apples"), 3)])), r#"{"apples": 3, "type": "Count"}"#);
+    round_trip(Event::Note(Box::new(Message { text: String::from("Hi") })), r#"{"text": "Hi", "type": "Note"}"#);
+}
+
+#[test]
+fn tuple_and_generic_structs() {
+    round_trip(Meters(1.5), "1.5");
+    round_trip(Position(1, -2, 3), "[1, -2, 3]");
+    round_trip(Named { name: String::from("origin"), value: Position(0, 0, 0) },
+               r#"{"name": "origin", "value": [0, 0, 0]}"#);
+    round_trip(Named { name: String::from("tags"), value: vec!(String::from("a")) },
+               r#"{"name": "tags", "value": ["a"]}"#);
+}
+
+#[test]
+fn enum_errors_have_paths() {
+    let error = load_error::<Shape>(r#""Square""#);
+    assert_eq!(error.to_string(), "Unknown variant Square, expected one of Empty, Circle, Rectangle, Polygon");
+
+    let error = load_error::<Shape>(r#"{"Polygon": {"points": [[0, "a"]]}}"#);
+    assert_eq!(json_path::error_path(&error), JsonPath::new().key("Polygon").key("points").index(0).index(1));
+
+    let error = load_error::<Shape>(r#"{"Polygon": {}}"#);
+    assert_eq!(error.to_string(), "Polygon → Missing required field points");
+
+    let error = load_error::<Shape>(r#"{"Rectangle": [1]}"#);
+    assert_eq!(error.to_string(), "Rectangle → Expected array of 2 values, found [1]");
+
+    let error = load_error::<Action>(r#"{"kind": "Run"}"#);
+    assert_eq!(error.to_string(), "kind → Unknown variant Run, expected one of Jump, Move, Say");
+
+    let error = load_error::<Action>(r#"{"speed": 1}"#);
+    assert_eq!(error.to_string(), "Missing required field kind");
+
+    let error = load_error::<Position>("[1, 2, true]");
+    assert_eq!(json_path::error_path(&error), JsonPath::new().index(2));
+}
+
+#[test]
+fn enum_schemas() {
+    let schema = Shape::schema();
+    let variants: Vec<&JsonValue> = schema["oneOf"].members().collect();
+    assert_eq!(variants.len(), 4);
+    assert_eq!(variants[0]["const"], "Empty");
+    assert_eq!(variants[2]["properties"]["Rectangle"]["minItems"], 2);
+    assert_eq!(variants[3]["properties"]["Polygon"]["required"][0], "points");
+
+    let schema = Action::schema();
+    assert_eq!(schema["oneOf"][1]["properties"]["kind"]["const"], "Move");
+    assert!(!schema["oneOf"][1]["properties"].has_key("elapsed"));
+    assert_eq!(schema["oneOf"][2]["allOf"][1]["required"][0], "kind");
+}
+
+#[test]
+fn field_attributes() {
+    let sign = Sign { contents: String::from("Hi"), size: 12, glowing: true, position: Placement { x: 1, y: 2 }, cached: vec!() };
+    //Skipped fields are compared to Self::default() or their own default
+    assert_eq!(sign.save(), json::parse(r#"{"text": "Hi", "x": 1, "y": 2}"#).unwrap());
+
+    let loaded = Sign::load(&json::parse(r#"{"message": "Hi", "x": 1, "y": 2}"#).unwrap()).unwrap();
+    assert_eq!(loaded.contents, "Hi");
+    assert_eq!(loaded.size, 12);
+    assert!(loaded.glowing);
+    assert_eq!(loaded.position, Placement { x: 1, y: 2 });
+    assert_eq!(loaded.cached, vec!(1, 2, 3));
+
+    round_trip(Sign { contents: String::new(), size: 20, glowing: false, position: Placement { x: 0, y: 0 }, cached: vec!(1, 2, 3) },
+               r#"{"text": "", "size": 20, "glowing": false, "x": 0, "y": 0}"#);
+
+    round_trip(Decoration::Banner { color: String::from("red") }, r#"{"Banner": {}}"#);
+    round_trip(Decoration::Banner { color: String::from("blue") }, r#"{"Banner": {"colour": "blue"}}"#);
+}
+
+#[test]
+fn unknown_keys_are_rejected() {
+    //The flattened struct gets the keys Sign doesn't use
+    let error = load_error::<Sign>(r#"{"text": "Hi", "x": 1, "y": 2, "sise": 3}"#);
+    assert_eq!(error.to_string(), "sise → Unknown field sise, expected one of text, message, size, glowing, x, y");
+
+    //Flattened fields don't see each other's keys, and what none of them read is rejected
+    let frame = Frame { name: String::from("Door"), position: Placement { x: 1, y: 2 }, size: Size { width: 3, height: 4 } };
+    assert_eq!(Frame::load(&frame.save()).unwrap(), frame);
+    let error = load_error::<Frame>(r#"{"name": "Door", "x": 1, "y": 2, "depth": 5}"#);
+    assert_eq!(error.to_string(), "depth → Unknown field depth, expected one of name, x, y, width, height");
+    assert_eq!(Frame::object_keys().unwrap(), vec!("name", "x", "y", "width", "height"));
+
+    let error = load_error::<Placement>(r#"{"x": 1, "y": 2, "cached": []}"#);
+    assert_eq!(json_path::error_path(&error), JsonPath::new().key("cached"));
+
+    let error = load_error::<Decoration>(r#"{"Banner": {"color": "red"}}"#);
+    assert_eq!(error.to_string(), "Banner.color → Unknown field color, expected one of colour");
+
+    let schema = Sign::schema();
+    assert_eq!(schema["additionalProperties"], false);
+    assert!(schema["properties"].has_key("message"));
+    assert!(schema["properties"].has_key("x"));
+    assert!(!schema["properties"].has_key("cached"));
+    assert_eq!(schema["required"], json::array!["x", "y"]);
+}
+
+#[test]
+fn binary_has_no_keys() {
+    let sign = Sign { contents: String::from("Hi"), size: 12, glowing: true, position: Placement { x: 1, y: 2 }, cached: vec!(4) };
+    let binary = sign.save_to::<Binary>();
+    assert!(!binary.windows(4).any(|window| window == b"text"));
+    assert!(binary.len() < sign.save_to::<Json>().len());
+    //Ignored fields aren't written, so they keep their default
+    assert_eq!(Sign::load_from::<Binary>(&binary).unwrap(), Sign { cached: vec!(1, 2, 3), ..sign });
+
+    let shape = Shape::Polygon { points: vec!([0.0, 1.0]), closed: true };
+    let binary = shape.save_to::<Binary>();
+    assert!(binary.len() < Binary::encode(&shape.save()).len());
+    assert_eq!(Action::load_from::<Binary>(&Action::Move { speed: 2.0, elapsed: 5 }.save_to::<Binary>()).unwrap(),
+               Action::Move { speed: 2.0, elapsed: 0 });
+}
+
+#[test]
+fn binary_errors_have_paths() {
+    let mut binary = Shape::Polygon { points: vec!([0.0, 1.0]), closed: true }.save_to::<Binary>();
+    //The last byte is closed's tag, true is 2
+    *binary.last_mut().unwrap() = 6;
+    let error = Shape::load_from::<Binary>(&binary).unwrap_err();
+    assert_eq!(json_path::error_path(&error), JsonPath::new().key("closed"));
+
+    let error = Position::load_from::<Binary>(&Meters(1.0).save_to::<Binary>()).unwrap_err();
+    assert_eq!(error.to_string(), "Expected struct, found number at byte 0");
+    let error = Shape::load_from::<Binary>(&[10, 4]).unwrap_err();
+    assert_eq!(error.to_string(), "Unknown variant 4 of Shape at byte 0, expected one of Empty, Circle, Rectangle, Polygon");
+    let error = Placement::load_from::<Binary>(&[9, 3]).unwrap_err();
+    assert_eq!(error.to_string(), "Expected Placement with 2 fields, found 3 at byte 0");
+}
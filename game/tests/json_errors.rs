@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use json::JsonValue;
+use macros::JsonLoadable;
+use interfaces::json_path::{self, JsonPath};
+use interfaces::loading::JsonLoadable;
+use interfaces::resource;
+
+#[derive(JsonLoadable, Default)]
+pub struct Face {
+    #[require_field]
+    pub texture: String,
+    pub rotation: u16,
+}
+
+#[derive(JsonLoadable, Default)]
+pub struct Block {
+    pub faces: HashMap<String, Face>,
+    pub tags: Vec<String>,
+}
+
+const SOURCE: &str = r#"[
+    {"faces": {"top": {"texture": "grass"}}},
+    {
+        "tags": ["a \"quoted\" {tag}", "b"],
+        "faces": {
+            "side": {"texture": "dirt", "rotation": 90},
+            "top": {"rotation": -1, "texture": "grass"}
+        }
+    }
+]"#;
+
+fn load_error(source: &str) -> anyhow::Error {
+    return Vec::<Block>::load(&json::parse(source).unwrap()).err().unwrap();
+}
+
+#[test]
+fn derived_fields_are_added_to_the_path() {
+    let error = load_error(SOURCE);
+    assert_eq!(json_path::error_path(&error), JsonPath::new().index(1).key("faces").key("top").key("rotation"));
+    assert_eq!(json_path::error_message(&error), "Expected integer in 0..=65535, found -1");
+    assert_eq!(error.to_string(), "[1].faces.top.rotation → Expected integer in 0..=65535, found -1");
+    assert_eq!(json_path::describe_error("types/blocks.json", SOURCE, &JsonPath::new(), &error),
+               "types/blocks.json:7:33 → [1].faces.top.rotation → Expected integer in 0..=65535, found -1");
+}
+
+#[test]
+fn missing_fields_point_at_their_object() {
+    let source = SOURCE.replace(r#"{"texture": "dirt", "rotation": 90}"#, r#"{"rotation": 90}"#);
+    let error = load_error(&source);
+    assert_eq!(error.to_string(), "[1].faces.side → Missing required field texture");
+    assert_eq!(json_path::locate(&source, &json_path::error_path(&error)), Some((6, 21)));
+    //Paths that don't exist stop at the deepest value that does
+    assert_eq!(json_path::locate(&source, &JsonPath::new().index(1).key("faces").key("bottom")), Some((5, 18)));
+    assert_eq!(json_path::locate(&source, &JsonPath::new().index(1).key("tags").index(1)), Some((4, 40)));
+}
+
+#[test]
+fn wrong_types_are_reported() {
+    let error = Block::load(&json::parse(r#"{"tags": "solid"}"#).unwrap()).err().unwrap();
+    assert_eq!(error.to_string(), "tags → Expected array, found solid");
+    let error = Block::load(&JsonValue::from(3)).err().unwrap();
+    assert_eq!(error.to_string(), "Expected object, found 3");
+}
+
+#[test]
+fn json5_sources_are_located() {
+    let source = r#"[
+    // The first block
+    {faces: {top: {texture: 'grass'}},},
+    {
+        tags: ['a \'quoted\' {tag}', "b", /* the end */],
+        faces: {
+            /* side: {texture: "stone"}, */
+            side: {texture: "dirt", rotation: 90},
+            'top': {rotation: -1, texture: "grass"},
+        },
+    },
+]"#;
+    let error = Vec::<Block>::load(&resource::parse(source).unwrap()).err().unwrap();
+    assert_eq!(json_path::describe_error("types/blocks.json", source, &JsonPath::new(), &error),
+               "types/blocks.json:9:31 → [1].faces.top.rotation → Expected integer in 0..=65535, found -1");
+    assert_eq!(json_path::locate(source, &JsonPath::new().index(1).key("faces").key("side")), Some((8, 19)));
+    assert_eq!(json_path::locate(source, &JsonPath::new().index(1).key("tags").index(1)), Some((5, 38)));
+    //Trailing commas don't start another value
+    assert_eq!(json_path::locate(source, &JsonPath::new().index(1).key("tags").index(2)), Some((5, 15)));
+}
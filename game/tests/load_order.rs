@@ -0,0 +1,40 @@
+use game::mods::mods::{load_order, ModManifest};
+
+fn manifest(name: &str, hard: &[&str], soft: &[&str]) -> ModManifest {
+    return ModManifest {
+        name: name.to_string(),
+        hard_dependencies: hard.iter().map(|dependency| dependency.to_string()).collect(),
+        soft_dependencies: soft.iter().map(|dependency| dependency.to_string()).collect(),
+        ..Default::default()
+    };
+}
+
+fn names(manifests: &[ModManifest]) -> Vec<&str> {
+    return load_order(manifests).unwrap().into_iter().map(|index| manifests[index].name.as_str()).collect();
+}
+
+#[test]
+fn mods_load_after_their_dependencies() {
+    let manifests = vec!(
+        manifest("addon", &["library"], &[]),
+        manifest("library", &[], &["core"]),
+        manifest("core", &[], &[]),
+        manifest("cosmetics", &[], &[]));
+    assert_eq!(names(&manifests), vec!("core", "cosmetics", "library", "addon"));
+}
+
+#[test]
+fn missing_dependencies_are_ignored() {
+    //Missing hard dependencies stop the mod loading elsewhere, the order doesn't care
+    let manifests = vec!(manifest("b", &["missing"], &["also_missing"]), manifest("a", &[], &[]));
+    assert_eq!(names(&manifests), vec!("a", "b"));
+}
+
+#[test]
+fn cycles_are_errors() {
+    let manifests = vec!(
+        manifest("first", &["second"], &[]),
+        manifest("second", &[], &["first"]),
+        manifest("fine", &[], &[]));
+    assert_eq!(load_order(&manifests).unwrap_err().to_string(), "Mods depend on each other in a cycle: first, second");
+}
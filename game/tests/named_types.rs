@@ -0,0 +1,185 @@
+use std::sync::{Arc, Mutex};
+use anyhow::Error;
+use json::JsonValue;
+use tokio::runtime::Builder;
+use tokio::task::JoinSet;
+use macros::{JsonLoadable, NamedType};
+use game::mods::mod_trait::ModMain;
+use game::mods::mods::{GameMod, ModManifest};
+use game::resources::resource_manager::{AssetKind, LoadableType, ResourceManager};
+use game::util::register::ThingRegister;
+use game::world::cubes::cube_type::CubeType;
+use common::Recipe;
+
+mod common;
+
+#[derive(JsonLoadable, NamedType, Default)]
+pub struct Variant<T> {
+    #[ignore_field]
+    pub name: String,
+    #[dependency]
+    #[json(rename = "base", alias = "parent_type")]
+    pub base: String,
+    #[texture]
+    #[json(alias = "image")]
+    pub texture: String,
+    pub value: T,
+}
+
+fn create_manager() -> Arc<Mutex<ResourceManager>> {
+    let mut manager = common::create_manager();
+    manager.register_type::<Recipe>("test:recipe").unwrap();
+    return Arc::new(Mutex::new(manager));
+}
+
+struct RecipeMod;
+
+impl ModMain for RecipeMod {
+    fn register_types(&mut self, resources: &mut ResourceManager) -> Result<(), Error> {
+        return resources.register_type::<Recipe>("test:recipe");
+    }
+
+    fn finish_loading(&mut self) {}
+
+    fn handle_event(&mut self, _event: Arc<dyn ThingRegister>) {}
+}
+
+#[test]
+fn mods_register_types() {
+    let runtime = Builder::new_current_thread().build().unwrap();
+    let mut manager = runtime.block_on(async {
+        let mut mods = JoinSet::new();
+        mods.spawn(async { Ok(GameMod::new(ModManifest { name: "test".to_string(), ..ModManifest::default() },
+                                           Box::new(RecipeMod))) });
+        return common::create_manager_with(mods);
+    });
+
+    //What the client's mod loader does before loading types
+    let mut mods = runtime.block_on(manager._mods.take_mods());
+    for found in &mut mods {
+        found.register_types(&mut manager).unwrap();
+    }
+    manager._mods.restore(mods);
+    assert!(manager.is_registered("test:recipe"));
+    assert_eq!(runtime.block_on(manager._mods.get_mods()).len(), 1);
+}
+
+#[test]
+fn registered_types_load() {
+    let manager = create_manager();
+    let results = common::load(manager.clone(), vec!(
+        json::object! { "$name": "test:dirt", "$type": "cube", "faces": { "top": "testing/dirt" } },
+        json::object! { "$name": "test:grass", "$parent": "test:dirt", "faces": { "side": "testing/grass_block_side" } }
+    ));
+    for result in results {
+        result.unwrap();
+    }
+
+    let manager = manager.lock().unwrap();
+    let grass = manager.get_type::<CubeType>("test:grass").unwrap();
+    assert_eq!(grass.name, "test:grass");
+    assert_eq!(grass.faces["top"], "testing/dirt");
+    assert_eq!(grass.faces["side"], "testing/grass_block_side");
+    assert_eq!(manager.get_all_of_type::<CubeType>().len(), 2);
+    assert!(manager.get_type::<Recipe>("test:grass").is_none());
+}
+
+#[test]
+fn dependencies_load_first() {
+    let manager = create_manager();
+    let results = common::load(manager.clone(), vec!(
+        json::object! { "$name": "test:bread", "$type": "test:recipe", "result": "test:bread_cube",
+            "ingredients": ["test:wheat", "test:water"], "count": 2 },
+        json::object! { "$name": "test:bread_cube", "$type": "cube", "faces": {} },
+        json::object! { "$name": "test:wheat", "$type": "cube", "faces": {} },
+        json::object! { "$name": "test:water", "$type": "cube", "faces": {} },
+        json::object! { "$name": "test:cake", "$type": "test:recipe", "result": "test:missing" }
+    ));
+    assert!(results[..4].iter().all(Result::is_ok));
+    let error = results[4].as_ref().unwrap_err().to_string();
+    assert!(error.starts_with("Failed to find NamedType test:missing"), "{}", error);
+
+    let manager = manager.lock().unwrap();
+    let bread = manager.get_type::<Recipe>("test:bread").unwrap();
+    assert_eq!(bread.ingredients, vec!("test:wheat", "test:water"));
+    assert_eq!(bread.count, 2);
+}
+
+#[test]
+fn generic_types_and_aliases() {
+    let manager = create_manager();
+    manager.lock().unwrap().register_type::<Variant<u32>>("test:variant").unwrap();
+    let object = common::object(json::object! {
+        "$name": "test:heavy", "$type": "test:variant", "parent_type": "test:stone", "image": "heavy", "value": 3
+    });
+    assert_eq!(Variant::<u32>::dependencies(&object), vec!("test:stone"));
+    let assets = Variant::<u32>::assets(&object);
+    assert_eq!(assets.len(), 1);
+    assert_eq!((assets[0].kind, assets[0].name.as_str()), (AssetKind::Texture, "heavy"));
+    assert_eq!(assets[0].path.to_string(), "image");
+
+    let results = common::load(manager.clone(), vec!(
+        JsonValue::Object(object),
+        json::object! { "$name": "test:stone", "$type": "cube", "faces": {} }
+    ));
+    for result in results {
+        result.unwrap();
+    }
+    let manager = manager.lock().unwrap();
+    let heavy = manager.get_type::<Variant<u32>>("test:heavy").unwrap();
+    assert_eq!((heavy.base.as_str(), heavy.value), ("test:stone", 3));
+}
+
+#[test]
+fn missing_names_fail() {
+    let mut manager = common::create_manager();
+    let error = Recipe::instantiate(&mut manager, &common::object(json::object! { "$type": "test:recipe" }))
+        .err().unwrap();
+    assert_eq!(error.to_string(), "$name → No name ($name)");
+}
+
+#[test]
+fn registering_twice_fails() {
+    let mut manager = common::create_manager();
+    assert!(manager.register_type::<Recipe>("cube").is_err());
+    manager.register_type::<Recipe>("test:recipe").unwrap();
+    assert!(manager.register_type::<CubeType>("test:recipe").is_err());
+
+    let results = common::load(Arc::new(Mutex::new(manager)), vec!(
+        json::object! { "$name": "test:stone", "$type": "test:unknown" },
+        json::object! { "$name": "test:sand", "$type": "cube" }
+    ));
+    assert_eq!(results[0].as_ref().unwrap_err().to_string(), "$type → No parent type ($type) registered as test:unknown");
+    assert!(results[1].as_ref().unwrap_err().to_string().starts_with("Missing required field faces"));
+}
+
+#[test]
+fn schemas_describe_registered_types() {
+    let manager = create_manager();
+    let manager = manager.lock().unwrap();
+    assert_eq!(manager.registered_types(), vec!("cube", "test:recipe"));
+    assert!(manager.type_schema("test:unknown").is_none());
+
+    let schema = manager.type_schema("test:recipe").unwrap();
+    assert_eq!(schema["properties"]["count"], json::object! { "type": "integer", "minimum": 0, "maximum": u32::MAX });
+    assert_eq!(schema["properties"]["ingredients"], json::object! { "type": "array", "items": { "type": "string" } });
+    assert_eq!(schema["properties"]["$type"], json::object! { "const": "test:recipe" });
+    //The name comes from $name, so it isn't a field
+    assert!(!schema["properties"].has_key("name"));
+    assert_eq!(schema["required"], json::array!["$name"]);
+    assert_eq!(schema["anyOf"], json::array![{ "required": ["$parent"] }, { "required": ["$type"] }]);
+
+    let cube = manager.type_schema("cube").unwrap();
+    assert_eq!(cube["anyOf"][1], json::object! { "required": ["$type", "faces"] });
+    assert_eq!(manager.types_schema()["anyOf"][0]["anyOf"].len(), 2);
+}
+
+#[test]
+fn unknown_keys_are_reported() {
+    let manager = create_manager();
+    let results = common::load(manager, vec!(
+        json::object! { "$name": "test:dirt", "$type": "cube", "faces": {}, "face": {} }
+    ));
+    let error = results[0].as_ref().unwrap_err();
+    assert_eq!(error.to_string(), "face → Unknown field face, expected one of faces");
+}
@@ -0,0 +1,170 @@
+use std::thread;
+use std::time::Duration;
+use cgmath::{InnerSpace, Vector3};
+use game::network::client::NetworkClient;
+use game::network::interpolation::Interpolator;
+use game::network::packets::{EntitySpawn, Snapshot};
+use game::network::quantize::{quantize_position, QuantizedState};
+use game::network::replication::{ReplicatedWorld, ReplicationSettings};
+use game::network::server::NetworkServer;
+use game::network::transport::{LoopbackTransport, Transport};
+use game::world::world::WorldData;
+use common::network;
+
+mod common;
+
+//Sends the player's movement, then runs an update
+fn step(world: &mut WorldData, server: &mut NetworkServer, client: &mut NetworkClient, movement: Vector3<f32>) {
+    client.send_input(movement, 0.0).unwrap();
+    network::step(world, server, &mut [client]);
+    thread::sleep(Duration::from_millis(1));
+}
+
+//Stops the player, then runs updates until the server has acked every input
+fn settle(world: &mut WorldData, server: &mut NetworkServer, client: &mut NetworkClient) {
+    for _ in 0..5 {
+        step(world, server, client, Vector3::new(0.0, 0.0, 0.0));
+    }
+    for _ in 0..500 {
+        network::step(world, server, &mut [client]);
+        if client.prediction.as_ref().unwrap().unacknowledged() == 0 {
+            return;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    panic!("Server never acknowledged every input");
+}
+
+fn wait_for_welcome(world: &mut WorldData, server: &mut NetworkServer, client: &mut NetworkClient) {
+    for _ in 0..500 {
+        network::step(world, server, &mut [client]);
+        if client.prediction.is_some() {
+            return;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    panic!("Never joined the server");
+}
+
+fn server_player(world: &mut WorldData, client: &NetworkClient) -> Vector3<f32> {
+    return world.get_entity_mut(client.player().unwrap().id()).unwrap().position;
+}
+
+#[test]
+fn prediction_matches_server() {
+    let mut world = network::create_world(1);
+    let mut server = NetworkServer::new(ReplicationSettings::default());
+    let mut client = network::connect(&mut server, "player", Duration::from_millis(10));
+    wait_for_welcome(&mut world, &mut server, &mut client);
+
+    //The player moves on the first input, long before the server could answer
+    step(&mut world, &mut server, &mut client, Vector3::new(1.0, 0.0, 0.0));
+    assert!(client.player().unwrap().velocity.x > 0.0);
+    step(&mut world, &mut server, &mut client, Vector3::new(1.0, 0.0, 0.0));
+    assert!(client.player().unwrap().position.x > 0.0);
+    assert!(client.prediction.as_ref().unwrap().unacknowledged() > 0);
+
+    for i in 0..60 {
+        let movement = if i < 30 { Vector3::new(1.0, 0.0, 0.5) } else { Vector3::new(-0.5, 0.0, 1.0) };
+        step(&mut world, &mut server, &mut client, movement);
+    }
+    settle(&mut world, &mut server, &mut client);
+
+    let predicted = client.player().unwrap().position;
+    let authoritative = server_player(&mut world, &client);
+    assert!((predicted - authoritative).magnitude() < 0.01,
+            "Predicted {:?}, server has {:?}", predicted, authoritative);
+}
+
+#[test]
+fn server_keeps_up_with_faster_client() {
+    let mut world = network::create_world(1);
+    let mut server = NetworkServer::new(ReplicationSettings::default());
+    let mut client = network::connect(&mut server, "player", Duration::ZERO);
+    wait_for_welcome(&mut world, &mut server, &mut client);
+
+    //Six client updates for every server update
+    for i in 0..30 {
+        let movement = if i < 15 { Vector3::new(1.0, 0.0, 0.0) } else { Vector3::new(0.0, 0.0, 1.0) };
+        for _ in 0..6 {
+            client.send_input(movement, 0.0).unwrap();
+        }
+        network::step(&mut world, &mut server, &mut [&mut client]);
+        //Every input is applied as it arrives, so none wait for a later update
+        assert_eq!(client.prediction.as_ref().unwrap().unacknowledged(), 0);
+    }
+    settle(&mut world, &mut server, &mut client);
+
+    let predicted = client.player().unwrap().position;
+    let authoritative = server_player(&mut world, &client);
+    assert!((predicted - authoritative).magnitude() < 0.01,
+            "Predicted {:?}, server has {:?}", predicted, authoritative);
+    assert!(predicted.x > 0.0 && predicted.z > 0.0);
+}
+
+#[test]
+fn reconciliation_corrects_misprediction() {
+    let mut world = network::create_world(1);
+    let mut server = NetworkServer::new(ReplicationSettings::default());
+    let mut client = network::connect(&mut server, "player", Duration::ZERO);
+    wait_for_welcome(&mut world, &mut server, &mut client);
+
+    for _ in 0..10 {
+        step(&mut world, &mut server, &mut client, Vector3::new(0.0, 0.0, 1.0));
+    }
+
+    //The server teleports the player, which the client can't predict
+    let id = client.player().unwrap().id();
+    world.get_entity_mut(id).unwrap().position = Vector3::new(50.0, 0.0, 0.0);
+    let mut largest_correction: f32 = 0.0;
+    for _ in 0..5 {
+        step(&mut world, &mut server, &mut client, Vector3::new(0.0, 0.0, 1.0));
+        largest_correction = largest_correction.max(client.prediction.as_ref().unwrap().last_correction);
+    }
+    settle(&mut world, &mut server, &mut client);
+
+    assert!(largest_correction > 40.0);
+    let predicted = client.player().unwrap().position;
+    assert!((predicted - server_player(&mut world, &client)).magnitude() < 0.01);
+    assert!(predicted.x > 49.0);
+}
+
+#[test]
+fn interpolates_between_snapshots() {
+    let mut world = ReplicatedWorld::new();
+    let mut interpolator = Interpolator::new(2.0);
+    let mut state = QuantizedState::default();
+
+    world.apply(&Snapshot {
+        tick: 10,
+        despawns: Vec::new(),
+        spawns: vec!(EntitySpawn { id: 1, entity_type: "testing".to_string(), state }),
+        deltas: Vec::new(),
+    }).unwrap();
+    interpolator.record(10, &world, None);
+
+    state.position = quantize_position(&Vector3::new(2.0, 0.0, -4.0));
+    world.apply(&Snapshot {
+        tick: 12,
+        despawns: Vec::new(),
+        spawns: vec!(EntitySpawn { id: 1, entity_type: "testing".to_string(), state }),
+        deltas: Vec::new(),
+    }).unwrap();
+    interpolator.record(12, &world, None);
+
+    let halfway = interpolator.sample(1, 11.0).unwrap();
+    assert!((halfway.position - Vector3::new(1.0, 0.0, -2.0)).magnitude() < 0.001);
+    //Rendering two updates behind the newest snapshot, a quarter of the way into this update
+    assert_eq!(interpolator.render_tick(0.25), 10.25);
+    //Never extrapolates past the newest snapshot
+    assert_eq!(interpolator.sample(1, 20.0).unwrap().position, Vector3::new(2.0, 0.0, -4.0));
+}
+
+#[test]
+fn loopback_latency_delays_packets() {
+    let (mut first, mut second) = LoopbackTransport::pair_with_latency(Duration::from_millis(20));
+    first.send(vec!(1, 2, 3)).unwrap();
+    assert_eq!(second.receive().unwrap(), None);
+    thread::sleep(Duration::from_millis(30));
+    assert_eq!(second.receive().unwrap(), Some(vec!(1, 2, 3)));
+}
@@ -0,0 +1,102 @@
+use std::sync::{Arc, Mutex};
+use game::resources::resource_loader::ResourceLoader;
+use game::resources::resource_manager::ResourceManager;
+use game::world::cubes::cube_type::CubeType;
+use interfaces::json_path::{self, JsonPath};
+use common::{object, Recipe};
+
+mod common;
+
+//A manager with dirt, grass inheriting from it and a recipe for dirt, and the loader that loaded them
+fn load() -> (Arc<Mutex<ResourceManager>>, Arc<Mutex<ResourceLoader>>) {
+    let mut manager = common::create_manager();
+    manager.register_type::<Recipe>("test:recipe").unwrap();
+    let manager = Arc::new(Mutex::new(manager));
+    let loader = Arc::new(Mutex::new(ResourceLoader::new(manager.clone())));
+    let results = common::load_with(loader.clone(), vec!(
+        json::object! { "$name": "test:dirt", "$type": "cube", "faces": { "top": "dirt", "side": "dirt" } },
+        json::object! { "$name": "test:grass", "$parent": "test:dirt", "faces": { "top": "grass" } },
+        json::object! { "$name": "test:dig", "$type": "test:recipe", "result": "test:dirt", "count": 1 }
+    ));
+    for result in results {
+        result.unwrap();
+    }
+    return (manager, loader);
+}
+
+fn names(results: &[(String, Result<(), anyhow::Error>)]) -> Vec<&str> {
+    return results.iter().map(|(name, _)| name.as_str()).collect();
+}
+
+#[test]
+fn reloading_updates_children() {
+    let (manager, loader) = load();
+    let results = loader.lock().unwrap().reload(object(
+        json::object! { "$name": "test:dirt", "$type": "cube", "faces": { "top": "dirt", "side": "mud" } }));
+    assert_eq!(names(&results), vec!("test:dirt", "test:grass"));
+    assert!(results.iter().all(|(_, result)| result.is_ok()));
+
+    let manager = manager.lock().unwrap();
+    assert_eq!(manager.get_type::<CubeType>("test:dirt").unwrap().faces["side"], "mud");
+    let grass = manager.get_type::<CubeType>("test:grass").unwrap();
+    assert_eq!(grass.faces["top"], "grass");
+    assert_eq!(grass.faces["side"], "mud");
+    assert_eq!(manager.get_all_of_type::<CubeType>().len(), 2);
+}
+
+#[test]
+fn new_types_are_added() {
+    let (manager, loader) = load();
+    let results = loader.lock().unwrap().reload(object(
+        json::object! { "$name": "test:podzol", "$parent": "test:grass", "faces": { "top": "podzol" } }));
+    assert!(results[0].1.is_ok());
+
+    let manager = manager.lock().unwrap();
+    let podzol = manager.get_type::<CubeType>("test:podzol").unwrap();
+    assert_eq!(podzol.faces["top"], "podzol");
+    assert_eq!(podzol.faces["side"], "dirt");
+    assert_eq!(manager.get_all_of_type::<CubeType>().len(), 3);
+}
+
+#[test]
+fn names_declared_with_two_types_fail() {
+    let mut manager = common::create_manager();
+    manager.register_type::<Recipe>("test:recipe").unwrap();
+    let results = common::load(Arc::new(Mutex::new(manager)), vec!(
+        json::object! { "$name": "test:stone", "$type": "cube", "faces": {} },
+        json::object! { "$name": "test:dirt", "$type": "cube", "faces": {} },
+        json::object! { "$name": "test:dirt", "$type": "test:recipe", "result": "test:stone", "count": 1 }
+    ));
+    assert!(results[0].is_ok());
+    let error = match &results[1..] {
+        [Ok(()), Err(error)] | [Err(error), Ok(())] => error,
+        _ => panic!("Expected one test:dirt to fail")
+    };
+    assert_eq!(json_path::error_path(error), JsonPath::new().key("$type"));
+}
+
+#[test]
+fn failed_reloads_keep_the_old_version() {
+    let (manager, loader) = load();
+    let mut loader = loader.lock().unwrap();
+    let results = loader.reload(object(json::object! { "$name": "test:dirt", "$type": "cube", "faces": 5 }));
+    assert_eq!(names(&results), vec!("test:dirt"));
+    assert_eq!(results[0].1.as_ref().unwrap_err().to_string(), "faces → Expected object, found 5");
+
+    let results = loader.reload(object(json::object! { "$name": "test:dirt", "$type": "test:recipe", "result": "test:grass" }));
+    assert_eq!(results[0].1.as_ref().unwrap_err().to_string(), "test:dirt changed to a different $type, restart to load it");
+
+    let results = loader.reload(object(json::object! { "$name": "test:dig", "$type": "test:recipe", "result": "test:nothing" }));
+    assert_eq!(results[0].1.as_ref().unwrap_err().to_string(), "Failed to find NamedType test:nothing");
+
+    let results = loader.reload(object(json::object! { "$name": "test:dirt", "$parent": "test:grass" }));
+    let error = results[0].1.as_ref().unwrap_err();
+    assert_eq!(json_path::error_path(error), JsonPath::new().key("$parent"));
+    assert_eq!(json_path::error_message(error), "Inheritance cycle test:dirt -> test:grass -> test:dirt");
+    drop(loader);
+
+    let manager = manager.lock().unwrap();
+    assert_eq!(manager.get_type::<CubeType>("test:dirt").unwrap().faces["side"], "dirt");
+    assert_eq!(manager.get_type::<CubeType>("test:grass").unwrap().faces["top"], "grass");
+    assert_eq!(manager.get_type::<Recipe>("test:dig").unwrap().result, "test:dirt");
+}
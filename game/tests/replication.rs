@@ -0,0 +1,192 @@
+use std::time::Duration;
+use cgmath::{InnerSpace, Vector3};
+use game::network::client::NetworkClient;
+use game::network::packets::{ClientPacket, ServerPacket};
+use game::network::quantize::QuantizedState;
+use game::network::replication::{ReplicationServer, ReplicationSettings};
+use game::network::server::NetworkServer;
+use game::world::entities::entity::Entity;
+use game::world::world::WorldData;
+use common::network::{connect, create_world, step};
+
+mod common;
+
+fn spawn_moving(world: &mut WorldData, room: usize, count: usize) -> Vec<u64> {
+    let mut ids = Vec::new();
+    for i in 0..count {
+        let mut entity = Entity::new("testing".to_string(), room,
+                                     Vector3::new(i as f32, 0.5 * i as f32, -(i as f32)));
+        entity.velocity = Vector3::new(0.01 * i as f32, -0.02, 0.003 * i as f32);
+        entity.rotation = i as f32 * 0.1;
+        ids.push(entity.id());
+        entity.spawn(world);
+    }
+    return ids;
+}
+
+fn stop_all(world: &mut WorldData) {
+    for room in world.rooms_mut() {
+        let ids: Vec<u64> = room.entities().iter().map(|entity| entity.id()).collect();
+        for id in ids {
+            room.get_entity_mut(id).unwrap().velocity = Vector3::new(0.0, 0.0, 0.0);
+        }
+    }
+}
+
+//Whether the entity is in the room and thinks it is
+fn in_room(world: &WorldData, room: usize, id: u64) -> bool {
+    return world.rooms()[room].entities().iter().any(|entity| entity.id() == id && entity.room == room);
+}
+
+fn assert_matches_room(client: &NetworkClient, world: &WorldData, room: usize) {
+    let entities = world.rooms()[room].entities();
+    assert_eq!(client.world.entities().len(), entities.len());
+    for entity in entities {
+        let replicated = client.world.get(entity.id())
+            .unwrap_or_else(|| panic!("Client is missing entity {}", entity.id()));
+        assert_eq!(replicated.entity_type, entity.entity_type);
+        assert_eq!(replicated.state, QuantizedState::of(entity));
+        assert!((replicated.state.position() - entity.position).magnitude2() < 0.0001);
+    }
+}
+
+#[test]
+fn two_clients_converge() {
+    let mut world = create_world(2);
+    let moving = spawn_moving(&mut world, 0, 20);
+    spawn_moving(&mut world, 1, 5);
+
+    let mut server = NetworkServer::new(ReplicationSettings::default());
+    let mut first = connect(&mut server, "first", Duration::ZERO);
+    let mut second = connect(&mut server, "second", Duration::ZERO);
+
+    for tick in 0..60 {
+        if tick == 20 {
+            //Despawns and changes halfway through
+            world.rooms_mut()[0].remove_entity(moving[3]).unwrap();
+            world.rooms_mut()[0].remove_entity(moving[7]).unwrap();
+            world.rooms_mut()[0].get_entity_mut(moving[5]).unwrap().rotation = 2.0;
+        }
+        if tick == 30 {
+            spawn_moving(&mut world, 0, 3);
+        }
+        step(&mut world, &mut server, &mut [&mut first, &mut second]);
+    }
+
+    assert!(first.client_id.is_some() && second.client_id.is_some());
+    assert_ne!(first.client_id, second.client_id);
+    assert_eq!(first.world, second.world);
+    assert_eq!(first.world.tick, world.tick());
+    assert_matches_room(&first, &world, 0);
+    assert_matches_room(&second, &world, 0);
+}
+
+#[test]
+fn tight_budget_converges() {
+    let mut world = create_world(1);
+    spawn_moving(&mut world, 0, 30);
+
+    let mut server = NetworkServer::new(ReplicationSettings { bytes_per_update: 96 });
+    let mut first = connect(&mut server, "first", Duration::ZERO);
+    let mut second = connect(&mut server, "second", Duration::ZERO);
+
+    for _ in 0..10 {
+        step(&mut world, &mut server, &mut [&mut first, &mut second]);
+    }
+    stop_all(&mut world);
+    for _ in 0..100 {
+        step(&mut world, &mut server, &mut [&mut first, &mut second]);
+    }
+
+    assert_eq!(first.world.entities(), second.world.entities());
+    assert_matches_room(&first, &world, 0);
+}
+
+#[test]
+fn snapshots_respect_budget() {
+    let mut world = create_world(1);
+    spawn_moving(&mut world, 0, 50);
+
+    let mut replication = ReplicationServer::new(ReplicationSettings { bytes_per_update: 128 });
+    replication.add_client(0, 0);
+    let mut sent = 0;
+    for _ in 0..40 {
+        world.update();
+        if let Some(snapshot) = replication.snapshot(0, &world) {
+            sent += snapshot.spawns.len();
+            assert!(ServerPacket::Snapshot(snapshot).encode().len() <= 128);
+        }
+    }
+    assert_eq!(sent, 50);
+}
+
+#[test]
+fn changing_rooms_swaps_entities() {
+    let mut world = create_world(2);
+    spawn_moving(&mut world, 0, 10);
+    spawn_moving(&mut world, 1, 4);
+
+    let mut server = NetworkServer::new(ReplicationSettings::default());
+    let mut first = connect(&mut server, "first", Duration::ZERO);
+    let mut second = connect(&mut server, "second", Duration::ZERO);
+    for _ in 0..5 {
+        step(&mut world, &mut server, &mut [&mut first, &mut second]);
+    }
+    assert_matches_room(&second, &world, 0);
+
+    server.set_room(second.client_id.unwrap(), 1, &mut world);
+    for _ in 0..5 {
+        step(&mut world, &mut server, &mut [&mut first, &mut second]);
+    }
+    assert_matches_room(&first, &world, 0);
+    assert_matches_room(&second, &world, 1);
+}
+
+#[test]
+fn clients_only_see_their_room() {
+    let mut world = create_world(2);
+    spawn_moving(&mut world, 0, 6);
+    spawn_moving(&mut world, 1, 3);
+
+    let mut server = NetworkServer::new(ReplicationSettings::default());
+    let mut first = connect(&mut server, "first", Duration::ZERO);
+    step(&mut world, &mut server, &mut [&mut first]);
+    server.set_spawn_room(1);
+    let mut second = connect(&mut server, "second", Duration::ZERO);
+    for _ in 0..5 {
+        step(&mut world, &mut server, &mut [&mut first, &mut second]);
+    }
+
+    let first_player = first.player().unwrap().id();
+    let second_player = second.player().unwrap().id();
+    assert_eq!(second.player().unwrap().room, 1);
+    assert!(in_room(&world, 0, first_player));
+    assert!(in_room(&world, 1, second_player));
+    assert_matches_room(&first, &world, 0);
+    assert_matches_room(&second, &world, 1);
+    assert!(first.world.get(second_player).is_none());
+    assert!(second.world.get(first_player).is_none());
+
+    //Moving the client takes its player along
+    server.set_room(second.client_id.unwrap(), 0, &mut world);
+    for _ in 0..5 {
+        step(&mut world, &mut server, &mut [&mut first, &mut second]);
+    }
+    assert!(in_room(&world, 0, second_player));
+    assert!(!in_room(&world, 1, second_player));
+    assert_matches_room(&first, &world, 0);
+    assert_matches_room(&second, &world, 0);
+}
+
+#[test]
+fn huge_lengths_are_errors() {
+    //A Join whose name claims to be u64::MAX bytes long
+    let packet = [0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01];
+    assert!(ClientPacket::decode(&packet).is_err());
+}
+
+#[test]
+fn welcome_sends_spawn_room() {
+    let packet = ServerPacket::Welcome { client_id: 1, tick: 20, player: 5, room: 3, motd: "Hi".to_string() };
+    assert_eq!(ServerPacket::decode(&packet.encode()).unwrap(), packet);
+}
@@ -1,4 +1,5 @@
 // Vertex shader
+//layout: Position, TexCoords
 
 struct VertexInput {
     @location(0) position: vec3<f32>,
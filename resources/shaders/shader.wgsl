@@ -1,4 +1,5 @@
 // Vertex shader
+//layout: Position, TexCoords
 struct CameraUniform {
     view_proj: mat4x4<f32>,
 };
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use anyhow::Error;
+use game::network::server::JoinFilter;
+use interfaces::json_path::{self, JsonPath};
+use interfaces::loading::JsonLoadable;
+use interfaces::resource;
+use interfaces::saving::JsonSaveable;
+use macros::{JsonLoadable, JsonSaveable};
+
+//Banned player names, with the reason they were banned
+#[derive(Default, JsonLoadable, JsonSaveable)]
+pub struct BanList {
+    pub players: HashMap<String, String>,
+}
+
+#[derive(Default, JsonLoadable, JsonSaveable)]
+pub struct PlayerList {
+    pub players: Vec<String>,
+}
+
+//Only players on the list can join while it's enabled
+#[derive(Default, JsonLoadable, JsonSaveable)]
+pub struct Whitelist {
+    pub enabled: bool,
+    pub players: Vec<String>,
+}
+
+//The bans, ops and whitelist, kept as bans.json, ops.json and whitelist.json in the world directory
+pub struct AdminLists {
+    directory: PathBuf,
+    pub bans: BanList,
+    pub ops: PlayerList,
+    pub whitelist: Whitelist,
+    pub max_players: usize,
+}
+
+impl AdminLists {
+    //Missing files start out empty
+    pub fn load(directory: PathBuf, max_players: usize) -> Result<Self, Error> {
+        return Ok(Self {
+            bans: Self::load_file(&directory.join("bans.json"))?,
+            ops: Self::load_file(&directory.join("ops.json"))?,
+            whitelist: Self::load_file(&directory.join("whitelist.json"))?,
+            directory,
+            max_players,
+        });
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        fs::create_dir_all(&self.directory)?;
+        fs::write(self.directory.join("bans.json"), self.bans.save().pretty(4))?;
+        fs::write(self.directory.join("ops.json"), self.ops.save().pretty(4))?;
+        fs::write(self.directory.join("whitelist.json"), self.whitelist.save().pretty(4))?;
+        return Ok(());
+    }
+
+    pub fn is_op(&self, name: &str) -> bool {
+        return self.ops.players.iter().any(|op| op == name);
+    }
+
+    //Returns the reason a player can't join
+    pub fn check(&self, name: &str, players: usize) -> Result<(), String> {
+        if let Some(reason) = self.bans.players.get(name) {
+            return Err(format!("Banned: {}", reason));
+        }
+        if self.whitelist.enabled && !self.whitelist.players.iter().any(|player| player == name) {
+            return Err("Not whitelisted".to_string());
+        }
+        //Ops can join a full server
+        if players >= self.max_players && !self.is_op(name) {
+            return Err("Server is full".to_string());
+        }
+        return Ok(());
+    }
+
+    fn load_file<T: JsonLoadable + Default>(path: &Path) -> Result<T, Error> {
+        if !path.exists() {
+            return Ok(T::default());
+        }
+        let (source, value) = resource::read(path)?;
+        return T::load(&value)
+            .map_err(|error| Error::msg(json_path::describe_error(&path.display().to_string(), &source, &JsonPath::new(), &error)));
+    }
+}
+
+//Checks joining players against the shared admin lists
+pub struct AdminFilter {
+    pub lists: Arc<Mutex<AdminLists>>,
+}
+
+impl JoinFilter for AdminFilter {
+    fn allow(&mut self, name: &str, players: usize) -> Result<(), String> {
+        return self.lists.lock().unwrap().check(name, players);
+    }
+}
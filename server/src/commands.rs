@@ -0,0 +1,99 @@
+use anyhow::Error;
+
+pub const HELP: &str = "Commands:
+    list                            Lists connected players
+    kick <player> [reason]          Disconnects a player
+    ban <player> [reason]           Disconnects a player and stops them from joining
+    pardon <player>                 Removes a ban
+    op <player>                     Makes a player an operator
+    deop <player>                   Removes an operator
+    whitelist <on|off|list>         Turns the whitelist on or off, or lists it
+    whitelist <add|remove> <player> Edits the whitelist
+    say <message>                   Sends a chat message to everyone
+    save                            Saves the world and admin lists
+    stop                            Saves and shuts down the server";
+
+//Admin commands typed into the server console
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Help,
+    List,
+    Kick { player: String, reason: String },
+    Ban { player: String, reason: String },
+    Pardon { player: String },
+    Op { player: String },
+    Deop { player: String },
+    Whitelist(WhitelistCommand),
+    Say(String),
+    Save,
+    Stop,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WhitelistCommand {
+    On,
+    Off,
+    List,
+    Add(String),
+    Remove(String),
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Self, Error> {
+        let mut words = line.split_whitespace();
+        let name = match words.next() {
+            Some(name) => name,
+            None => return Err(Error::msg("Empty command"))
+        };
+        return Ok(match name {
+            "help" => Command::Help,
+            "list" => Command::List,
+            "kick" | "ban" => {
+                let player = Self::player(words.next(), name)?;
+                //Everything after the player name
+                let reason = Self::rest(line, 2);
+                let reason = if reason.is_empty() { "No reason given".to_string() } else { reason };
+                if name == "kick" {
+                    Command::Kick { player, reason }
+                } else {
+                    Command::Ban { player, reason }
+                }
+            }
+            "pardon" => Command::Pardon { player: Self::player(words.next(), name)? },
+            "op" => Command::Op { player: Self::player(words.next(), name)? },
+            "deop" => Command::Deop { player: Self::player(words.next(), name)? },
+            "whitelist" => Command::Whitelist(match words.next() {
+                Some("on") => WhitelistCommand::On,
+                Some("off") => WhitelistCommand::Off,
+                Some("list") => WhitelistCommand::List,
+                Some("add") => WhitelistCommand::Add(Self::player(words.next(), "whitelist add")?),
+                Some("remove") => WhitelistCommand::Remove(Self::player(words.next(), "whitelist remove")?),
+                _ => return Err(Error::msg("Usage: whitelist <on|off|list|add|remove>"))
+            }),
+            "say" => match Self::rest(line, 1) {
+                message if message.is_empty() => return Err(Error::msg("Usage: say <message>")),
+                message => Command::Say(message)
+            },
+            "save" => Command::Save,
+            "stop" => Command::Stop,
+            _ => return Err(Error::msg(format!("Unknown command {}, try help", name)))
+        });
+    }
+
+    fn player(word: Option<&str>, usage: &str) -> Result<String, Error> {
+        return word.map(|player| player.to_string())
+            .ok_or_else(|| Error::msg(format!("Usage: {} <player>", usage)));
+    }
+
+    //The text after the first skip words
+    fn rest(line: &str, skip: usize) -> String {
+        let mut rest = line.trim_start();
+        for _ in 0..skip {
+            rest = match rest.find(char::is_whitespace) {
+                Some(end) => rest[end..].trim_start(),
+                None => ""
+            };
+        }
+        return rest.trim_end().to_string();
+    }
+}
@@ -0,0 +1,150 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Error;
+use interfaces::json_path::{self, JsonPath};
+use interfaces::loading::JsonLoadable;
+use interfaces::resource;
+use interfaces::saving::JsonSaveable;
+use macros::{JsonLoadable, JsonSaveable};
+
+pub const DEFAULT_CONFIG: &str = "server.json";
+
+//Flags that take a value
+const FLAGS: [&str; 7] = ["--config", "--bind", "--port", "--tick-rate", "--world", "--max-players", "--motd"];
+
+pub const USAGE: &str = "Usage: server [options]
+    --config <file>         Config file to load, created with the defaults if missing (default server.json)
+    --bind <address>        Address to listen on
+    --port <port>           Port to listen on
+    --tick-rate <updates>   World updates per second
+    --world <directory>     Directory holding the world and the admin lists
+    --max-players <count>   Players allowed at once
+    --motd <message>        Message shown to players when they join
+    --help                  Prints this message";
+
+//Loaded from server.json, missing fields keep their defaults
+#[derive(Clone, Debug, PartialEq, JsonLoadable, JsonSaveable)]
+pub struct ServerConfig {
+    pub bind_address: String,
+    pub port: u16,
+    //World updates per second
+    pub tick_rate: u32,
+    //Holds the saved world and the admin lists
+    pub world_directory: String,
+    pub max_players: u32,
+    //Shown to players when they join
+    pub motd: String,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        return Self {
+            bind_address: "0.0.0.0".to_string(),
+            port: 24680,
+            tick_rate: 30,
+            world_directory: "world".to_string(),
+            max_players: 16,
+            motd: "Welcome!".to_string(),
+        };
+    }
+}
+
+impl ServerConfig {
+    //Loads the config, or writes the defaults to path if there isn't one yet.
+    //It's JSON5 like the resource files, so operators can leave comments in it.
+    pub fn load_or_create(path: &Path) -> Result<Self, Error> {
+        if !path.exists() {
+            let config = Self::default();
+            fs::write(path, config.save().pretty(4))?;
+            return Ok(config);
+        }
+        let (source, value) = resource::read(path)?;
+        let config = Self::load(&value).map_err(|error|
+            Error::msg(json_path::describe_error(&path.display().to_string(), &source, &JsonPath::new(), &error)))?;
+        if config.tick_rate == 0 {
+            return Err(Error::msg("tick_rate must be at least 1"));
+        }
+        return Ok(config);
+    }
+
+    pub fn world_directory(&self) -> PathBuf {
+        return PathBuf::from(&self.world_directory);
+    }
+}
+
+//Command line flags, each one overrides the matching config field
+#[derive(Debug, Default, PartialEq)]
+pub struct Arguments {
+    pub config: Option<PathBuf>,
+    pub bind_address: Option<String>,
+    pub port: Option<u16>,
+    pub tick_rate: Option<u32>,
+    pub world_directory: Option<String>,
+    pub max_players: Option<u32>,
+    pub motd: Option<String>,
+    pub help: bool,
+}
+
+impl Arguments {
+    //Parses the arguments after the program name
+    pub fn parse(arguments: impl IntoIterator<Item=String>) -> Result<Self, Error> {
+        let mut output = Self::default();
+        let mut arguments = arguments.into_iter();
+        while let Some(flag) = arguments.next() {
+            if flag == "--help" {
+                output.help = true;
+                continue;
+            }
+            if !FLAGS.contains(&flag.as_str()) {
+                return Err(Error::msg(format!("Unknown flag {}", flag)));
+            }
+            let value = match arguments.next() {
+                Some(value) => value,
+                None => return Err(Error::msg(format!("Missing value for {}", flag)))
+            };
+            match flag.as_str() {
+                "--config" => output.config = Some(PathBuf::from(value)),
+                "--bind" => output.bind_address = Some(value),
+                "--port" => output.port = Some(Self::number(&flag, &value)?),
+                "--tick-rate" => output.tick_rate = Some(Self::number(&flag, &value)?),
+                "--world" => output.world_directory = Some(value),
+                "--max-players" => output.max_players = Some(Self::number(&flag, &value)?),
+                "--motd" => output.motd = Some(value),
+                _ => unreachable!()
+            }
+        }
+        if output.tick_rate == Some(0) {
+            return Err(Error::msg("--tick-rate must be at least 1"));
+        }
+        return Ok(output);
+    }
+
+    pub fn config_path(&self) -> PathBuf {
+        return self.config.clone().unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG));
+    }
+
+    pub fn apply(&self, config: &mut ServerConfig) {
+        if let Some(bind_address) = &self.bind_address {
+            config.bind_address = bind_address.clone();
+        }
+        if let Some(port) = self.port {
+            config.port = port;
+        }
+        if let Some(tick_rate) = self.tick_rate {
+            config.tick_rate = tick_rate;
+        }
+        if let Some(world_directory) = &self.world_directory {
+            config.world_directory = world_directory.clone();
+        }
+        if let Some(max_players) = self.max_players {
+            config.max_players = max_players;
+        }
+        if let Some(motd) = &self.motd {
+            config.motd = motd.clone();
+        }
+    }
+
+    fn number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, Error> {
+        return value.parse().map_err(|_| Error::msg(format!("Expected a number for {}, found {}", flag, value)));
+    }
+}
@@ -0,0 +1,272 @@
+use std::collections::HashSet;
+use std::fs;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use anyhow::Error;
+use game::error;
+use game::network::chat::ChatMessage;
+use game::network::packets::ServerPacket;
+use game::network::replication::ReplicationSettings;
+use game::network::server::NetworkServer;
+use game::network::transport::{TcpTransport, Transport};
+use game::world::rooms::room::Room;
+use game::world::world::WorldData;
+use interfaces::json_path::{self, JsonPath};
+use interfaces::resource;
+use interfaces::saving::JsonSaveable;
+use json::JsonValue;
+use crate::admin::{AdminFilter, AdminLists};
+use crate::commands::{Command, HELP, WhitelistCommand};
+use crate::config::ServerConfig;
+
+//A headless server running one world. The world and admin lists are loaded from the
+//config's world directory, and saved back there on shutdown.
+pub struct DedicatedServer {
+    pub config: ServerConfig,
+    pub world: WorldData,
+    pub network: NetworkServer,
+    pub admin: Arc<Mutex<AdminLists>>,
+    //Set by the stop command or a signal, the server saves and exits at the end of the update
+    pub stopping: Arc<AtomicBool>,
+}
+
+impl DedicatedServer {
+    pub fn new(config: ServerConfig) -> Result<Self, Error> {
+        let directory = config.world_directory();
+        fs::create_dir_all(&directory)?;
+
+        let (_, receiver) = mpsc::channel();
+        let mut world = WorldData::new(receiver);
+        let world_file = directory.join("world.json");
+        if world_file.exists() {
+            let (source, value) = resource::read(&world_file)?;
+            world.load(&value).map_err(|error|
+                Error::msg(json_path::describe_error(&world_file.display().to_string(), &source, &JsonPath::new(), &error)))?;
+        } else {
+            world.rooms_mut().push(Room::empty());
+        }
+
+        let admin = Arc::new(Mutex::new(AdminLists::load(directory, config.max_players as usize)?));
+        let mut network = NetworkServer::new(ReplicationSettings::default());
+        network.motd = config.motd.clone();
+        network.set_filter(Box::new(AdminFilter { lists: admin.clone() }));
+
+        return Ok(Self {
+            config,
+            world,
+            network,
+            admin,
+            stopping: Arc::new(AtomicBool::new(false)),
+        });
+    }
+
+    //Accepts TCP connections on a background thread, returns the address actually bound
+    pub fn listen(&self) -> Result<SocketAddr, Error> {
+        let listener = TcpListener::bind((self.config.bind_address.as_str(), self.config.port))?;
+        let address = listener.local_addr()?;
+        let connector = self.network.connector();
+        thread::Builder::new().name("ctl-listener".to_string()).spawn(move || {
+            for stream in listener.incoming() {
+                let transport = match stream.map_err(Error::from).and_then(TcpTransport::new) {
+                    Ok(transport) => transport,
+                    Err(error) => {
+                        error!("Failed to accept connection: {}", error);
+                        continue;
+                    }
+                };
+                //The server shut down
+                if connector.send(Box::new(transport) as Box<dyn Transport>).is_err() {
+                    return;
+                }
+            }
+        })?;
+        return Ok(address);
+    }
+
+    //Updates at the tick rate until stopped, running console lines between updates
+    pub fn run(&mut self, console: Receiver<String>) -> Result<(), Error> {
+        let delay = Duration::from_secs_f64(1.0 / self.config.tick_rate as f64);
+        while !self.stopping.load(Ordering::Relaxed) {
+            let start = Instant::now();
+            while let Ok(line) = console.try_recv() {
+                if !line.trim().is_empty() {
+                    println!("{}", self.execute(&line));
+                }
+            }
+            self.update();
+            thread::sleep(delay.saturating_sub(start.elapsed()));
+        }
+        return self.shutdown();
+    }
+
+    pub fn update(&mut self) {
+        self.world.update();
+        self.network.tick(&mut self.world);
+        self.run_player_commands();
+    }
+
+    //Runs commands players sent in chat if they're an operator, and sends them what the console would print
+    fn run_player_commands(&mut self) {
+        for (client, line) in std::mem::take(&mut self.network.commands) {
+            let name = match self.network.connections().iter().find(|connection| connection.id == client)
+                .and_then(|connection| connection.name.clone()) {
+                Some(name) => name,
+                None => continue
+            };
+            let op = self.admin.lock().unwrap().is_op(&name);
+            let reply = match op {
+                true => ChatMessage::system("chat.command", vec!(self.execute(&line))),
+                false => ChatMessage::system("chat.not_operator", Vec::new())
+            };
+            self.network.send(client, &ServerPacket::Chat(reply));
+        }
+    }
+
+    //Runs a console command and returns what to print
+    pub fn execute(&mut self, line: &str) -> String {
+        let command = match Command::parse(line) {
+            Ok(command) => command,
+            Err(error) => return error.to_string()
+        };
+        return match self.run_command(command) {
+            Ok(output) => output,
+            Err(error) => format!("Command failed: {}", error)
+        };
+    }
+
+    fn run_command(&mut self, command: Command) -> Result<String, Error> {
+        let mut admin = self.admin.lock().unwrap();
+        let output = match command {
+            Command::Help => return Ok(HELP.to_string()),
+            Command::List => {
+                let players: Vec<&str> = self.network.connections().iter()
+                    .filter_map(|connection| connection.name.as_deref()).collect();
+                return Ok(format!("{}/{} players: {}", players.len(), self.config.max_players, players.join(", ")));
+            }
+            Command::Kick { player, reason } => {
+                return match self.network.get_client(&player) {
+                    Some(client) => {
+                        self.network.disconnect(client, &format!("Kicked: {}", reason));
+                        Ok(format!("Kicked {}", player))
+                    }
+                    None => Ok(format!("{} isn't online", player))
+                };
+            }
+            Command::Ban { player, reason } => {
+                if let Some(client) = self.network.get_client(&player) {
+                    self.network.disconnect(client, &format!("Banned: {}", reason));
+                }
+                admin.bans.players.insert(player.clone(), reason);
+                format!("Banned {}", player)
+            }
+            Command::Pardon { player } => match admin.bans.players.remove(&player) {
+                Some(_) => format!("Unbanned {}", player),
+                None => return Ok(format!("{} isn't banned", player))
+            }
+            Command::Op { player } => {
+                if admin.is_op(&player) {
+                    return Ok(format!("{} is already an operator", player));
+                }
+                admin.ops.players.push(player.clone());
+                format!("Made {} an operator", player)
+            }
+            Command::Deop { player } => {
+                if !admin.is_op(&player) {
+                    return Ok(format!("{} isn't an operator", player));
+                }
+                admin.ops.players.retain(|op| op != &player);
+                format!("{} is no longer an operator", player)
+            }
+            Command::Whitelist(command) => match command {
+                WhitelistCommand::On => {
+                    admin.whitelist.enabled = true;
+                    Self::remove_unlisted(&mut self.network, &admin);
+                    "Whitelist enabled".to_string()
+                }
+                WhitelistCommand::Off => {
+                    admin.whitelist.enabled = false;
+                    "Whitelist disabled".to_string()
+                }
+                WhitelistCommand::List => return Ok(format!("Whitelist ({}): {}",
+                    if admin.whitelist.enabled { "on" } else { "off" }, admin.whitelist.players.join(", "))),
+                WhitelistCommand::Add(player) => {
+                    if !admin.whitelist.players.contains(&player) {
+                        admin.whitelist.players.push(player.clone());
+                    }
+                    format!("Added {} to the whitelist", player)
+                }
+                WhitelistCommand::Remove(player) => {
+                    admin.whitelist.players.retain(|found| found != &player);
+                    Self::remove_unlisted(&mut self.network, &admin);
+                    format!("Removed {} from the whitelist", player)
+                }
+            }
+            Command::Say(message) => {
+                self.network.broadcast(&ChatMessage::system("chat.server", vec!(message)));
+                return Ok("Sent".to_string());
+            }
+            Command::Save => {
+                drop(admin);
+                self.save()?;
+                return Ok("Saved".to_string());
+            }
+            Command::Stop => {
+                self.stopping.store(true, Ordering::Relaxed);
+                return Ok("Stopping".to_string());
+            }
+        };
+        //Every command that reaches here changed the admin lists
+        admin.save()?;
+        return Ok(output);
+    }
+
+    //Disconnects online players the whitelist wouldn't let join, like bans do
+    fn remove_unlisted(network: &mut NetworkServer, admin: &AdminLists) {
+        if !admin.whitelist.enabled {
+            return;
+        }
+        let unlisted: Vec<u64> = network.connections().iter()
+            .filter(|connection| connection.name.as_ref().is_some_and(|name| !admin.whitelist.players.contains(name)))
+            .map(|connection| connection.id).collect();
+        for client in unlisted {
+            network.disconnect(client, "Not whitelisted");
+        }
+    }
+
+    //Writes the world to a temporary file first, so a crash mid-save keeps the old world
+    pub fn save(&self) -> Result<(), Error> {
+        let directory = self.config.world_directory();
+        fs::create_dir_all(&directory)?;
+        let temporary = directory.join("world.json.tmp");
+        fs::write(&temporary, self.save_world().pretty(4))?;
+        fs::rename(temporary, directory.join("world.json"))?;
+        return self.admin.lock().unwrap().save();
+    }
+
+    //The world without online players' entities, since they're spawned again when their player joins
+    fn save_world(&self) -> JsonValue {
+        let players: HashSet<u64> = self.network.connections().iter()
+            .filter_map(|connection| connection.player).collect();
+        let mut saved = self.world.save();
+        for (index, room) in self.world.rooms().iter().enumerate() {
+            let entities = &mut saved["rooms"][index]["entities"];
+            for (position, entity) in room.entities().iter().enumerate().rev() {
+                if players.contains(&entity.id()) {
+                    entities.array_remove(position);
+                }
+            }
+        }
+        return saved;
+    }
+
+    //Disconnects everyone, then saves
+    pub fn shutdown(&mut self) -> Result<(), Error> {
+        self.network.disconnect_all("Server closed");
+        self.network.tick(&mut self.world);
+        return self.save();
+    }
+}
@@ -0,0 +1,4 @@
+pub mod admin;
+pub mod commands;
+pub mod config;
+pub mod dedicated;
@@ -1,3 +1,66 @@
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::{env, io, process, thread};
+use server::config::{Arguments, ServerConfig, USAGE};
+use server::dedicated::DedicatedServer;
+
 fn main() {
-    //TODO server
-}
\ No newline at end of file
+    let arguments = match Arguments::parse(env::args().skip(1)) {
+        Ok(arguments) => arguments,
+        Err(error) => {
+            eprintln!("{}\n{}", error, USAGE);
+            process::exit(2);
+        }
+    };
+    if arguments.help {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let mut config = match ServerConfig::load_or_create(&arguments.config_path()) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("Failed to load {:?}: {}", arguments.config_path(), error);
+            process::exit(1);
+        }
+    };
+    arguments.apply(&mut config);
+
+    let mut server = match DedicatedServer::new(config) {
+        Ok(server) => server,
+        Err(error) => {
+            eprintln!("Failed to start server: {}", error);
+            process::exit(1);
+        }
+    };
+
+    //SIGINT and SIGTERM stop the server at the end of the current update, which saves everything
+    let stopping = server.stopping.clone();
+    ctrlc::set_handler(move || stopping.store(true, Ordering::Relaxed)).expect("Couldn't set signal handler");
+
+    match server.listen() {
+        Ok(address) => println!("Listening on {}", address),
+        Err(error) => {
+            eprintln!("Failed to bind {}:{}: {}", server.config.bind_address, server.config.port, error);
+            process::exit(1);
+        }
+    }
+
+    let (sender, console) = mpsc::channel();
+    thread::Builder::new().name("ctl-console".to_string()).spawn(move || {
+        for line in io::stdin().lines() {
+            match line {
+                Ok(line) => if sender.send(line).is_err() {
+                    return;
+                },
+                Err(_) => return
+            }
+        }
+    }).unwrap();
+
+    if let Err(error) = server.run(console) {
+        eprintln!("Failed to save while shutting down: {}", error);
+        process::exit(1);
+    }
+    println!("Server stopped");
+}
@@ -0,0 +1,270 @@
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+use cgmath::Vector3;
+use game::network::chat::{ChatChannel, ChatContent, ChatMessage};
+use game::network::client::NetworkClient;
+use game::network::transport::{LoopbackTransport, TcpTransport};
+use game::world::entities::entity::Entity;
+use server::config::{Arguments, ServerConfig};
+use server::dedicated::DedicatedServer;
+
+#[path = "../../client/tests/common/mod.rs"]
+mod common;
+
+fn create_server(directory: &Path) -> DedicatedServer {
+    return DedicatedServer::new(ServerConfig {
+        world_directory: directory.join("world").to_str().unwrap().to_string(),
+        max_players: 2,
+        motd: "Hello there".to_string(),
+        ..ServerConfig::default()
+    }).unwrap();
+}
+
+fn join(server: &mut DedicatedServer, name: &str) -> NetworkClient {
+    let (server_end, client_end) = LoopbackTransport::pair();
+    server.network.connect(Box::new(server_end));
+    let mut client = NetworkClient::connect(Box::new(client_end), name.to_string()).unwrap();
+    for _ in 0..10 {
+        server.update();
+        client.poll().unwrap();
+        if client.client_id.is_some() || client.disconnected.is_some() {
+            break;
+        }
+    }
+    return client;
+}
+
+#[test]
+fn arguments_override_config() {
+    let directory = common::directory("server");
+    let path = directory.join("server.json");
+    fs::write(&path, r#"{"port": 4000, "motd": "From the file"}"#).unwrap();
+
+    let arguments = Arguments::parse(["--config", path.to_str().unwrap(), "--port", "5000", "--max-players", "3"]
+        .iter().map(|argument| argument.to_string())).unwrap();
+    let mut config = ServerConfig::load_or_create(&arguments.config_path()).unwrap();
+    assert_eq!(config.port, 4000);
+    arguments.apply(&mut config);
+
+    assert_eq!(config.port, 5000);
+    assert_eq!(config.max_players, 3);
+    assert_eq!(config.motd, "From the file");
+    assert_eq!(config.tick_rate, ServerConfig::default().tick_rate);
+
+    assert!(Arguments::parse(vec!("--port".to_string(), "lots".to_string())).is_err());
+    assert!(Arguments::parse(vec!("--unknown".to_string(), "1".to_string())).is_err());
+}
+
+#[test]
+fn config_is_json5() {
+    let directory = common::directory("server");
+    let path = directory.join("server.json");
+    fs::write(&path, "{\n    //Testing\n    port: 4000,\n    motd: 'Hi',\n}").unwrap();
+    let config = ServerConfig::load_or_create(&path).unwrap();
+    assert_eq!(config.port, 4000);
+    assert_eq!(config.motd, "Hi");
+
+    fs::write(&path, "{\n    port: 4000,\n    motd: 5,\n}").unwrap();
+    let error = ServerConfig::load_or_create(&path).unwrap_err().to_string();
+    assert!(error.contains("server.json:3:11 → motd"), "{}", error);
+
+    let whitelist = directory.join("world").join("whitelist.json");
+    fs::create_dir_all(whitelist.parent().unwrap()).unwrap();
+    fs::write(&whitelist, "{\n    enabled: true, //Testing\n    players: ['alice',],\n}").unwrap();
+    let mut server = create_server(&directory);
+    assert!(server.admin.lock().unwrap().whitelist.enabled);
+    assert!(join(&mut server, "alice").disconnected.is_none());
+}
+
+#[test]
+fn missing_config_is_created() {
+    let directory = common::directory("server");
+    let path = directory.join("server.json");
+    let config = ServerConfig::load_or_create(&path).unwrap();
+    assert_eq!(config, ServerConfig::default());
+    assert_eq!(ServerConfig::load_or_create(&path).unwrap(), config);
+}
+
+#[test]
+fn admin_commands_persist_and_filter_joins() {
+    let directory = common::directory("server");
+    let mut server = create_server(&directory);
+
+    let mut alice = join(&mut server, "alice");
+    assert_eq!(alice.motd.as_deref(), Some("Hello there"));
+    assert!(server.execute("list").contains("alice"));
+
+    server.execute("ban alice Being rude");
+    server.update();
+    alice.poll().unwrap();
+    assert_eq!(alice.disconnected.as_deref(), Some("Banned: Being rude"));
+    let rejoined = join(&mut server, "alice");
+    assert_eq!(rejoined.disconnected.as_deref(), Some("Banned: Being rude"));
+
+    server.execute("op bob");
+    server.execute("whitelist on");
+    server.execute("whitelist add carol");
+    assert_eq!(join(&mut server, "dave").disconnected.as_deref(), Some("Not whitelisted"));
+
+    //The lists are saved as soon as they change, and loaded by the next server
+    let mut restarted = create_server(&directory);
+    assert!(restarted.admin.lock().unwrap().is_op("bob"));
+    assert_eq!(join(&mut restarted, "alice").disconnected.as_deref(), Some("Banned: Being rude"));
+    restarted.execute("pardon alice");
+    restarted.execute("whitelist off");
+    assert!(join(&mut restarted, "alice").disconnected.is_none());
+}
+
+fn command(server: &mut DedicatedServer, client: &mut NetworkClient, line: &str) -> Vec<ChatMessage> {
+    client.send_chat(ChatChannel::Global, line.to_string()).unwrap();
+    server.update();
+    client.poll().unwrap();
+    return client.chat.drain(..).collect();
+}
+
+fn translated(messages: &[ChatMessage]) -> Vec<(&str, &[String])> {
+    return messages.iter().filter_map(|message| match &message.content {
+        ChatContent::Translated { key, arguments } => Some((key.as_str(), arguments.as_slice())),
+        ChatContent::Text(_) => None
+    }).collect();
+}
+
+#[test]
+fn ops_run_commands_from_chat() {
+    let directory = common::directory("server");
+    let mut server = create_server(&directory);
+    let mut admin = join(&mut server, "admin");
+    let mut guest = join(&mut server, "guest");
+    server.execute("op admin");
+    admin.poll().unwrap();
+    admin.chat.clear();
+    guest.chat.clear();
+
+    let replies = command(&mut server, &mut guest, "/kick admin");
+    assert_eq!(translated(&replies), vec!(("chat.not_operator", &[][..])));
+    assert!(server.network.get_client("admin").is_some());
+
+    let replies = command(&mut server, &mut admin, "/whitelist add admin");
+    assert_eq!(translated(&replies), vec!(("chat.command", &["Added admin to the whitelist".to_string()][..])));
+    //Commands aren't sent to anyone else
+    guest.poll().unwrap();
+    assert!(guest.chat.is_empty());
+
+    //Turning the whitelist on removes players who aren't on it
+    command(&mut server, &mut admin, "/whitelist on");
+    server.update();
+    guest.poll().unwrap();
+    assert_eq!(guest.disconnected.as_deref(), Some("Not whitelisted"));
+    assert!(admin.disconnected.is_none());
+}
+
+#[test]
+fn full_server_rejects_players() {
+    let directory = common::directory("server");
+    let mut server = create_server(&directory);
+    let _first = join(&mut server, "first");
+    let _second = join(&mut server, "second");
+    assert_eq!(join(&mut server, "third").disconnected.as_deref(), Some("Server is full"));
+    assert_eq!(join(&mut server, "first").disconnected.as_deref(), Some("Name already in use"));
+
+    //Ops can still join
+    server.execute("op boss");
+    assert!(join(&mut server, "boss").disconnected.is_none());
+}
+
+#[test]
+fn shutdown_saves_world_without_players() {
+    let directory = common::directory("server");
+    let mut server = create_server(&directory);
+    let mut client = join(&mut server, "player");
+    Entity::new("rock".to_string(), 0, Vector3::new(1.0, 2.0, 3.0)).spawn(&mut server.world);
+    server.update();
+
+    server.shutdown().unwrap();
+    client.poll().unwrap();
+    assert_eq!(client.disconnected.as_deref(), Some("Server closed"));
+
+    let restarted = create_server(&directory);
+    let entities = restarted.world.rooms()[0].entities();
+    assert_eq!(entities.len(), 1);
+    assert_eq!(entities[0].entity_type, "rock");
+    assert_eq!(entities[0].position, Vector3::new(1.0, 2.0, 3.0));
+    assert_eq!(restarted.world.tick(), server.world.tick());
+}
+
+#[test]
+fn world_errors_have_locations() {
+    let directory = common::directory("server");
+    let world = directory.join("world");
+    fs::create_dir_all(&world).unwrap();
+    let config = ServerConfig {
+        world_directory: world.to_str().unwrap().to_string(),
+        ..ServerConfig::default()
+    };
+
+    fs::write(world.join("world.json"), "{\n    tick: 3,\n    rooms: [{entities: [\n        \
+        {type: 'rock', room: 0, position: 'up', velocity: [0, 0, 0], rotation: 0},\n    ]}],\n}").unwrap();
+    let error = DedicatedServer::new(config.clone()).err().unwrap().to_string();
+    assert!(error.contains("world.json:4:43 → rooms[0].entities[0].position →"), "{}", error);
+
+    fs::write(world.join("world.json"), "{\n    tick: 3,\n    rooms: [{entities: [\n        \
+        {type: 'rock', room: 1, position: [0, 0, 0], velocity: [0, 0, 0], rotation: 0},\n    ]}],\n}").unwrap();
+    let error = DedicatedServer::new(config).err().unwrap().to_string();
+    assert!(error.ends_with("world.json:4:30 → rooms[0].entities[0].room → Entity saved in room 1 is in room 0"), "{}", error);
+}
+
+#[test]
+fn saving_skips_online_players() {
+    let directory = common::directory("server");
+    let mut server = create_server(&directory);
+    let mut client = join(&mut server, "player");
+    Entity::new("rock".to_string(), 0, Vector3::new(1.0, 2.0, 3.0)).spawn(&mut server.world);
+    server.update();
+    assert_eq!(server.world.rooms()[0].entities().len(), 2);
+
+    assert_eq!(server.execute("save"), "Saved");
+    //Saving doesn't touch the live world
+    assert_eq!(server.world.rooms()[0].entities().len(), 2);
+    client.poll().unwrap();
+    assert!(client.disconnected.is_none());
+
+    let restarted = create_server(&directory);
+    let entities = restarted.world.rooms()[0].entities();
+    assert_eq!(entities.len(), 1);
+    assert_eq!(entities[0].entity_type, "rock");
+}
+
+#[test]
+fn players_join_over_tcp() {
+    let directory = common::directory("server");
+    let mut server = create_server(&directory);
+    server.config.bind_address = "127.0.0.1".to_string();
+    server.config.port = 0;
+    let address = server.listen().unwrap();
+
+    let transport = TcpTransport::connect(address).unwrap();
+    let mut client = NetworkClient::connect(Box::new(transport), "remote".to_string()).unwrap();
+    for _ in 0..500 {
+        server.update();
+        client.poll().unwrap();
+        if client.client_id.is_some() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(2));
+    }
+    assert_eq!(client.motd.as_deref(), Some("Hello there"));
+    assert!(server.execute("list").contains("remote"));
+
+    server.execute("kick remote Testing");
+    for _ in 0..500 {
+        server.update();
+        client.poll().unwrap();
+        if client.disconnected.is_some() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(2));
+    }
+    assert_eq!(client.disconnected.as_deref(), Some("Kicked: Testing"));
+}
@@ -0,0 +1,3 @@
+pub mod pack;
+pub mod report;
+pub mod validator;
@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+use std::{env, process};
+use ctl_validate::validator::Validator;
+
+const USAGE: &str = "Usage: ctl-validate [resources directory] [--mods <directory>]
+Checks every type, asset, translation and mod manifest the way the client loads them.
+The resources directory defaults to ./resources, mods default to <resources>/mods.
+Exits with 1 if anything is broken.";
+
+fn main() {
+    let mut resources = None;
+    let mut mods = None;
+    let mut arguments = env::args().skip(1);
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return;
+            }
+            "--mods" => match arguments.next() {
+                Some(directory) => mods = Some(PathBuf::from(directory)),
+                None => usage_error("Missing value for --mods")
+            },
+            _ if argument.starts_with("--") => usage_error(&format!("Unknown flag {}", argument)),
+            _ if resources.is_none() => resources = Some(PathBuf::from(argument)),
+            _ => usage_error(&format!("Unexpected argument {}", argument))
+        }
+    }
+
+    let resources = resources.unwrap_or_else(|| PathBuf::from("resources"));
+    if !resources.is_dir() {
+        eprintln!("Couldn't find resources directory {}", resources.display());
+        process::exit(2);
+    }
+    let mut validator = Validator::new(resources);
+    if let Some(mods) = mods {
+        validator.mods = mods;
+    }
+
+    let report = validator.validate();
+    println!("{}", report);
+    if report.errors() > 0 {
+        process::exit(1);
+    }
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    process::exit(2);
+}
@@ -0,0 +1,91 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Error;
+use client::resources::archive_pack::ArchivePack;
+
+//A folder laid out like the resources folder: types, assets, language and shaders.
+//The base game and every mod are one each. Zipped mods are read through their archive.
+pub struct Pack {
+    //The folder, or the archive's path. Files in an archive have paths through it, like mods/example.zip/types/items.json
+    pub root: PathBuf,
+    //Name from the mod manifest, None for the base resources
+    pub mod_name: Option<String>,
+    archive: Option<ArchivePack>,
+}
+
+impl Pack {
+    pub fn new(root: PathBuf, mod_name: Option<String>) -> Self {
+        return Self {
+            root,
+            mod_name,
+            archive: None
+        };
+    }
+
+    //A .zip or .ctlpak mod
+    pub fn open_archive(path: PathBuf) -> Result<Self, Error> {
+        let archive = ArchivePack::open(path.clone())?;
+        return Ok(Self {
+            root: path,
+            mod_name: None,
+            archive: Some(archive)
+        });
+    }
+
+    //What the client calls the pack in its content stack
+    pub fn name(&self) -> &str {
+        return self.mod_name.as_deref().unwrap_or("base");
+    }
+
+    //Every file under folder, sorted so reports come out in the same order. Empty if it doesn't exist.
+    pub fn files(&self, folder: &str) -> Result<Vec<PathBuf>, Error> {
+        if let Some(archive) = &self.archive {
+            return Ok(archive.list(folder));
+        }
+        let mut output = Vec::new();
+        let directory = self.root.join(folder);
+        if directory.exists() {
+            Self::find_files(&directory, &mut output)?;
+        }
+        output.sort();
+        return Ok(output);
+    }
+
+    //Reads a file listed by files, or any other path under root
+    pub fn read(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        return match &self.archive {
+            Some(archive) => archive.read_file(&self.entry(path)?),
+            None => Ok(fs::read(path)?)
+        };
+    }
+
+    pub fn read_string(&self, path: &Path) -> Result<String, Error> {
+        return Ok(String::from_utf8(self.read(path)?)?);
+    }
+
+    pub fn is_file(&self, path: &Path) -> bool {
+        return match &self.archive {
+            Some(archive) => self.entry(path).is_ok_and(|entry| archive.contains(&entry)),
+            None => path.is_file()
+        };
+    }
+
+    //The path of a file in the archive, like types/items.json
+    fn entry(&self, path: &Path) -> Result<String, Error> {
+        let relative = path.strip_prefix(&self.root)
+            .map_err(|_| Error::msg(format!("{} isn't in {}", path.display(), self.root.display())))?;
+        return Ok(relative.components().map(|component| component.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"));
+    }
+
+    fn find_files(directory: &Path, output: &mut Vec<PathBuf>) -> Result<(), Error> {
+        for file in fs::read_dir(directory)? {
+            let file = file?;
+            if file.file_type()?.is_file() {
+                output.push(file.path());
+            } else {
+                Self::find_files(&file.path(), output)?;
+            }
+        }
+        return Ok(());
+    }
+}
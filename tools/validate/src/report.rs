@@ -0,0 +1,96 @@
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use anyhow::Error;
+use interfaces::format::json5::ParseError;
+use interfaces::json_path::{self, JsonPath};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning
+}
+
+#[derive(Clone, Debug)]
+pub struct Issue {
+    pub severity: Severity,
+    //File the problem is in
+    pub path: PathBuf,
+    //Line and column in the file, if it's known
+    pub location: Option<(usize, usize)>,
+    //Value in the file's JSON the problem is at, empty for the whole file
+    pub json_path: JsonPath,
+    pub message: String,
+}
+
+//Everything wrong with the validated content, in the order it was found
+#[derive(Default)]
+pub struct Report {
+    pub issues: Vec<Issue>,
+}
+
+impl Report {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn error(&mut self, path: &Path, message: String) {
+        self.push(Severity::Error, path, None, JsonPath::new(), message);
+    }
+
+    pub fn warning(&mut self, path: &Path, message: String) {
+        self.push(Severity::Warning, path, None, JsonPath::new(), message);
+    }
+
+    //An error at json_path in the file, found in its source
+    pub fn error_at(&mut self, path: &Path, source: &str, json_path: JsonPath, message: String) {
+        let location = json_path::locate(source, &json_path);
+        self.push(Severity::Error, path, location, json_path, message);
+    }
+
+    //A warning at json_path in the file, found in its source
+    pub fn warning_at(&mut self, path: &Path, source: &str, json_path: JsonPath, message: String) {
+        let location = json_path::locate(source, &json_path);
+        self.push(Severity::Warning, path, location, json_path, message);
+    }
+
+    //A file that isn't valid JSON5, at the line and column parsing failed at if it's known
+    pub fn parse_error(&mut self, path: &Path, error: &Error) {
+        match error.downcast_ref::<ParseError>() {
+            Some(error) => self.push(Severity::Error, path, Some((error.line, error.column)), JsonPath::new(),
+                                     format!("Invalid JSON: {}", error.message)),
+            None => self.error(path, format!("Invalid JSON: {}", error))
+        }
+    }
+
+    fn push(&mut self, severity: Severity, path: &Path, location: Option<(usize, usize)>, json_path: JsonPath, message: String) {
+        self.issues.push(Issue { severity, path: path.to_path_buf(), location, json_path, message });
+    }
+
+    pub fn errors(&self) -> usize {
+        return self.issues.iter().filter(|issue| issue.severity == Severity::Error).count();
+    }
+
+    pub fn warnings(&self) -> usize {
+        return self.issues.len() - self.errors();
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning"
+        };
+        return write!(f, "{}: {}", severity, json_path::describe(&self.path.display().to_string(), self.location,
+                                                                &self.json_path, &self.message));
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "{}", issue)?;
+        }
+        return write!(f, "{} error(s), {} warning(s)", self.errors(), self.warnings());
+    }
+}
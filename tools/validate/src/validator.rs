@@ -0,0 +1,398 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use anyhow::Error;
+use json::JsonValue;
+use json::object::Object;
+use tokio::runtime::Builder;
+use tokio::task::JoinSet;
+use client::resources::archive_pack::ArchivePack;
+use client::resources::content_pack;
+use client::resources::desktop_loader::DesktopLoader;
+use game::mods::mods::{load_order, ModManifest};
+use game::rendering::image_format;
+use game::rendering::headless::{NoAssets, NoRenderer};
+use game::rendering::mesh::Mesh;
+use game::rendering::model_import;
+use game::rendering::texture::Animation;
+use game::rendering::vertex_layout::VertexLayout;
+use game::resources::resource_loader::ResourceLoader;
+use game::resources::resource_manager::{AssetKind, ResourceManager};
+use interfaces::json_path::{self, JsonPath};
+use interfaces::loading::JsonLoadable;
+use interfaces::resource;
+use crate::pack::Pack;
+use crate::report::Report;
+
+//Checks a resources folder and its mods the same way the client loads them, without a window
+pub struct Validator {
+    pub resources: PathBuf,
+    pub mods: PathBuf,
+}
+
+type LoadedTypes = (Arc<Mutex<ResourceManager>>, Arc<Mutex<ResourceLoader>>, TypeFiles);
+
+//Where every type came from
+#[derive(Default)]
+struct TypeFiles {
+    //Text of each types file
+    sources: HashMap<PathBuf, String>,
+    //File and path in it of each type name
+    names: HashMap<String, (PathBuf, JsonPath)>,
+}
+
+//Names of every asset found, by kind
+#[derive(Default)]
+struct KnownAssets {
+    names: HashMap<AssetKind, HashSet<String>>,
+    //Translations of each language, with the file each key came from
+    languages: HashMap<String, HashMap<String, PathBuf>>,
+    //The vertex attributes each shader reads
+    shader_layouts: HashMap<String, VertexLayout>,
+}
+
+impl KnownAssets {
+    fn add(&mut self, kind: AssetKind, name: String) {
+        self.names.entry(kind).or_default().insert(name);
+    }
+
+    fn contains(&self, kind: AssetKind, name: &str) -> bool {
+        if kind == AssetKind::Translation {
+            return self.languages.values().any(|keys| keys.contains_key(name));
+        }
+        return self.names.get(&kind).is_some_and(|names| names.contains(name));
+    }
+}
+
+impl Validator {
+    pub fn new(resources: PathBuf) -> Self {
+        return Self {
+            mods: resources.join("mods"),
+            resources
+        };
+    }
+
+    pub fn validate(&self) -> Report {
+        let mut report = Report::new();
+        let mut packs = vec!(Pack::new(self.resources.clone(), None));
+        packs.extend(self.find_mods(&mut report));
+
+        let mut assets = KnownAssets::default();
+        let mut models = Vec::new();
+        for pack in &packs {
+            Self::find_assets(pack, &mut assets, &mut models, &mut report);
+        }
+        for (path, mesh) in models {
+            match assets.shader_layouts.get(&mesh.shader) {
+                Some(reads) => if let Err(error) = mesh.layout.check_shader(&mesh.shader, reads) {
+                    report.error(&path, error.to_string());
+                }
+                None => if !assets.contains(AssetKind::Shader, &mesh.shader) {
+                    report.error(&path, format!("Missing shader {}", mesh.shader));
+                }
+            }
+            for part in mesh.parts.iter().filter(|part| !part.texture.is_empty()) {
+                if !assets.contains(AssetKind::Texture, &part.texture) {
+                    report.error(&path, format!("Missing texture {}", part.texture));
+                }
+            }
+        }
+        Self::check_languages(&assets, &mut report);
+
+        let (manager, loader, files) = Self::load_types(&packs, &mut report);
+        let manager = manager.lock().unwrap();
+        let loader = loader.lock().unwrap();
+        let mut loaded = loader.loaded_types().collect::<Vec<_>>();
+        loaded.sort_by_key(|(name, _)| files.names.get(*name));
+        for (name, object) in loaded {
+            let (path, parent) = &files.names[name];
+            let type_name = object["$type"].as_str().unwrap_or_default();
+            for asset in manager.asset_references(type_name, object) {
+                if !assets.contains(asset.kind, &asset.name) {
+                    report.error_at(path, &files.sources[path], asset.path.inside(parent),
+                                    format!("Missing {} {}", Self::kind_name(asset.kind), asset.name));
+                }
+            }
+        }
+        return report;
+    }
+
+    //Every mod folder or archive with a valid manifest, in the order the client loads them
+    fn find_mods(&self, report: &mut Report) -> Vec<Pack> {
+        let mut folders = match fs::read_dir(&self.mods) {
+            Ok(folders) => folders.filter_map(Result::ok).map(|folder| folder.path())
+                .filter(|folder| folder.is_dir() || ArchivePack::is_archive(folder)).collect::<Vec<_>>(),
+            Err(_) => return Vec::new()
+        };
+        folders.sort();
+
+        let mut manifests = Vec::new();
+        for folder in folders {
+            let pack = match folder.is_dir() {
+                true => Pack::new(folder, None),
+                false => match Pack::open_archive(folder.clone()) {
+                    Ok(pack) => pack,
+                    Err(error) => {
+                        report.error(&folder, error.to_string());
+                        continue;
+                    }
+                }
+            };
+            let path = pack.root.join("manifest.json");
+            match Self::read_json(&pack, &path).and_then(|value| ModManifest::load(&value)) {
+                Ok(manifest) => manifests.push((pack, path, manifest)),
+                Err(error) => report.error(&path, format!("Invalid mod manifest: {}", error))
+            }
+        }
+
+        let names = manifests.iter().map(|(_, _, manifest)| manifest.name.clone()).collect::<Vec<_>>();
+        let mut checked = Vec::new();
+        for (pack, path, manifest) in manifests {
+            if names.iter().filter(|name| **name == manifest.name).count() > 1 {
+                report.error(&path, format!("Another mod is also named {}", manifest.name));
+            }
+            if manifest.main.is_empty() {
+                report.error(&path, "No main function set".to_string());
+            }
+            for dependency in &manifest.hard_dependencies {
+                if !names.contains(dependency) {
+                    report.error(&path, format!("Missing required mod {}", dependency));
+                }
+            }
+            for dependency in &manifest.soft_dependencies {
+                if !names.contains(dependency) {
+                    report.warning(&path, format!("Missing optional mod {}", dependency));
+                }
+            }
+            if !pack.is_file(&pack.root.join("assemblies").join(format!("{}.rlib", env::consts::ARCH))) {
+                report.warning(&path, format!("No assembly for {}, the client won't load it here", env::consts::ARCH));
+            }
+            checked.push((pack, manifest));
+        }
+
+        //Later mods override earlier ones, so they're checked in the same order
+        let (packs, manifests): (Vec<Pack>, Vec<ModManifest>) = checked.into_iter().unzip();
+        let order = match load_order(&manifests) {
+            Ok(order) => order,
+            Err(error) => {
+                report.error(&self.mods, error.to_string());
+                (0..manifests.len()).collect()
+            }
+        };
+        let mut packs = packs.into_iter().map(Some).collect::<Vec<_>>();
+        return order.into_iter().map(|index| {
+            let mut pack = packs[index].take().unwrap();
+            pack.mod_name = Some(manifests[index].name.clone());
+            pack
+        }).collect();
+    }
+
+    fn find_assets(pack: &Pack, assets: &mut KnownAssets, models: &mut Vec<(PathBuf, Mesh)>, report: &mut Report) {
+        for (folder, kind) in [("assets/textures", AssetKind::Texture), ("assets/models", AssetKind::Model),
+            ("shaders", AssetKind::Shader), ("language/translations", AssetKind::Translation)] {
+            let base = pack.root.join(folder);
+            let files = match pack.files(folder) {
+                Ok(files) => files,
+                Err(error) => {
+                    report.error(&base, format!("Failed to read folder: {}", error));
+                    continue;
+                }
+            };
+
+            for path in files {
+                //Animation files are next to the texture they animate, with the same name
+                if matches!(kind, AssetKind::Texture) && path.extension().is_some_and(|extension| extension == "json") {
+                    if let Err(error) = Self::check_animation(pack, &path) {
+                        report.error(&path, format!("Failed to load animation: {}", error));
+                    }
+                    continue;
+                }
+                //The client skips anything else in textures that isn't an image
+                if matches!(kind, AssetKind::Texture) && !image_format::is_texture(&path.to_string_lossy()) {
+                    report.warning(&path, "Not a PNG, JPEG, TGA or KTX2 texture, so it's never loaded".to_string());
+                    continue;
+                }
+                //glTF buffers, OBJ materials and anything else models point to are only read by them
+                if matches!(kind, AssetKind::Model) && !model_import::is_model(&path.to_string_lossy()) {
+                    continue;
+                }
+                let name = content_pack::asset_name(&base, &path);
+                let checked = match kind {
+                    AssetKind::Texture => pack.read(&path)
+                        .and_then(|bytes| image_format::read(&path.to_string_lossy(), &bytes)).map(|_| ()),
+                    AssetKind::Model => DesktopLoader::read_model(&path, &|file| pack.read(Path::new(file))).map(|mesh| models.push((path.clone(), mesh))),
+                    AssetKind::Shader => pack.read_string(&path)
+                        .and_then(|source| VertexLayout::from_shader(&source))
+                        .map(|layout| { assets.shader_layouts.insert(name.clone(), layout); }),
+                    AssetKind::Translation => Self::read_translations(pack, &path)
+                        .map(|keys| Self::add_translations(assets, name.clone(), keys, &path, report))
+                };
+                match checked {
+                    Ok(()) => assets.add(kind, name),
+                    Err(error) => report.error(&path, format!("Failed to load {}: {}", Self::kind_name(kind), error))
+                }
+            }
+        }
+    }
+
+    //Animations have to fit the texture they're next to
+    fn check_animation(pack: &Pack, path: &Path) -> Result<(), Error> {
+        let animation = Animation::load(&Self::read_json(pack, path)?)?;
+        let texture = path.with_extension("");
+        if !pack.is_file(&texture) {
+            return Err(Error::msg(format!("There's no texture {} to animate", texture.display())));
+        }
+        let (width, height) = image_format::decode(&texture.to_string_lossy(), &pack.read(&texture)?)?.dimensions();
+        animation.frame_count(width, height)?;
+        return Ok(());
+    }
+
+    fn kind_name(kind: AssetKind) -> &'static str {
+        return match kind {
+            AssetKind::Texture => "texture",
+            AssetKind::Model => "model",
+            AssetKind::Shader => "shader",
+            AssetKind::Translation => "translation"
+        };
+    }
+
+    fn read_json(pack: &Pack, path: &Path) -> Result<JsonValue, Error> {
+        return resource::parse(&pack.read_string(path)?);
+    }
+
+    fn read_translations(pack: &Pack, path: &Path) -> Result<Vec<String>, Error> {
+        let value = Self::read_json(pack, path)?;
+        if !value.is_object() {
+            return Err(Error::msg("Translations must be an object"));
+        }
+        let mut keys = Vec::new();
+        for (key, value) in value.entries() {
+            if value.as_str().is_none() {
+                return Err(Error::msg(format!("Translation {} isn't a string", key)));
+            }
+            keys.push(key.to_string());
+        }
+        return Ok(keys);
+    }
+
+    fn add_translations(assets: &mut KnownAssets, language: String, keys: Vec<String>, path: &Path, report: &mut Report) {
+        let language = assets.languages.entry(language).or_default();
+        for key in keys {
+            if let Some(previous) = language.insert(key.clone(), path.to_path_buf()) {
+                if previous != path {
+                    report.warning(path, format!("Translation {} overrides the one in {}", key, previous.display()));
+                }
+            }
+        }
+    }
+
+    //Every language should translate every key another language has
+    fn check_languages(assets: &KnownAssets, report: &mut Report) {
+        let mut languages = assets.languages.iter().collect::<Vec<_>>();
+        languages.sort_by_key(|(name, _)| *name);
+        for (name, keys) in &languages {
+            let mut missing = languages.iter().flat_map(|(_, other)| other.keys())
+                .filter(|key| !keys.contains_key(*key)).collect::<Vec<_>>();
+            missing.sort();
+            missing.dedup();
+            if missing.is_empty() {
+                continue;
+            }
+            //Reported on the file the language's first key came from
+            let path = keys.values().min().unwrap();
+            report.warning(path, format!("Language {} is missing {} translation(s): {}", name, missing.len(),
+                                         missing.iter().map(|key| key.as_str()).collect::<Vec<_>>().join(", ")));
+        }
+    }
+
+    //Runs every type through the ResourceLoader, like the client does. Returns the loaded
+    //types and where each one came from. A type named the same as one in an earlier pack overrides it,
+    //so only the last one is loaded.
+    fn load_types(packs: &[Pack], report: &mut Report) -> LoadedTypes {
+        let manager = Arc::new(Mutex::new(ResourceManager::new(JoinSet::new(), Box::new(NoAssets), Arc::new(NoRenderer))));
+        let loader = Arc::new(Mutex::new(ResourceLoader::new(manager.clone())));
+        let mut files = TypeFiles::default();
+        let mut types = Vec::new();
+        //Pack and index in types of each name's latest type
+        let mut owners: HashMap<String, (usize, usize)> = HashMap::new();
+        let mut overridden = HashSet::new();
+
+        for (pack_index, pack) in packs.iter().enumerate() {
+            let paths = match pack.files("types") {
+                Ok(paths) => paths,
+                Err(error) => {
+                    report.error(&pack.root.join("types"), format!("Failed to read folder: {}", error));
+                    continue;
+                }
+            };
+            for path in paths {
+                let (source, found) = match pack.read_string(&path)
+                    .and_then(|source| Ok((resource::parse(&source)?, source))) {
+                    Ok((JsonValue::Array(values), source)) => (source, values.into_iter().enumerate()
+                        .map(|(index, value)| (JsonPath::new().index(index), value)).collect::<Vec<_>>()),
+                    Ok((JsonValue::Object(object), source)) => (source, vec!((JsonPath::new(), JsonValue::Object(object)))),
+                    Ok(_) => {
+                        report.error(&path, "Expected array or object".to_string());
+                        continue;
+                    }
+                    Err(error) => {
+                        report.parse_error(&path, &error);
+                        continue;
+                    }
+                };
+                for (json_path, value) in found {
+                    match value {
+                        JsonValue::Object(object) => {
+                            if let Some(name) = object["$name"].as_str() {
+                                if let Some((previous, _)) = files.names.insert(name.to_string(), (path.clone(), json_path.clone())) {
+                                    let (previous_pack, previous_type) = owners[name];
+                                    if previous_pack == pack_index {
+                                        report.error_at(&path, &source, json_path.clone().key("$name"),
+                                                        format!("Name {} is also used in {}", name, previous.display()));
+                                    } else {
+                                        report.warning_at(&path, &source, json_path.clone().key("$name"),
+                                                          format!("Overrides {} in {}, {}'s version is loaded", name,
+                                                                  previous.display(), pack.name()));
+                                        overridden.insert(previous_type);
+                                    }
+                                }
+                                owners.insert(name.to_string(), (pack_index, types.len()));
+                            }
+                            types.push((path.clone(), json_path, object));
+                        }
+                        _ => report.error_at(&path, &source, json_path, format!("Expected object, found {}", value))
+                    }
+                }
+                files.sources.insert(path, source);
+            }
+        }
+
+        let types = types.into_iter().enumerate().filter(|(index, _)| !overridden.contains(index))
+            .map(|(_, found)| found).collect::<Vec<_>>();
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let results = runtime.block_on(Self::spawn_types(loader.clone(), &types));
+        for ((path, parent, _), result) in types.iter().zip(results) {
+            if let Err(error) = result {
+                report.error_at(path, &files.sources[path], json_path::error_path(&error).inside(parent),
+                                json_path::error_message(&error));
+            }
+        }
+        return (manager, loader, files);
+    }
+
+    async fn spawn_types(loader: Arc<Mutex<ResourceLoader>>, types: &[(PathBuf, JsonPath, Object)]) -> Vec<Result<(), Error>> {
+        let mut join_set = JoinSet::new();
+        for (index, (_, _, object)) in types.iter().enumerate() {
+            let task = ResourceLoader::spawn(loader.clone(), object.clone());
+            join_set.spawn(async move { (index, task.await) });
+        }
+        let mut results = Vec::new();
+        while let Some(result) = join_set.join_next().await {
+            results.push(result.unwrap());
+        }
+        results.sort_by_key(|(index, _)| *index);
+        return results.into_iter().map(|(_, result)| result).collect();
+    }
+}
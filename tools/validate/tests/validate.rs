@@ -0,0 +1,224 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use ctl_validate::report::{Report, Severity};
+use ctl_validate::validator::Validator;
+use zip::ZipWriter;
+use zip::write::FileOptions;
+
+#[path = "../../../client/tests/common/mod.rs"]
+mod common;
+
+fn archive(path: &Path, files: &[(&str, &[u8])]) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let mut writer = ZipWriter::new(File::create(path).unwrap());
+    for (name, contents) in files {
+        writer.start_file(*name, FileOptions::default()).unwrap();
+        writer.write_all(contents).unwrap();
+    }
+    writer.finish().unwrap();
+}
+
+//A resources folder with one of everything, all valid
+fn create_resources() -> PathBuf {
+    let root = common::directory("validate");
+    let texture = fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("../../resources/assets/textures/testing/dirt.png")).unwrap();
+    common::write(&root, "assets/textures/testing/dirt.png", &texture);
+    common::write(&root, "shaders/shader.wgsl", b"");
+    common::write(&root, "assets/models/block.json", br#"{"shader": "shader", "vertexes": [], "indices": []}"#);
+    common::write(&root, "language/translations/en_us.json", br#"{"menu.play": "Play"}"#);
+    common::write(&root, "types/cubes.json", br#"[
+        {"$name": "test:dirt", "$type": "cube", "faces": {"top": "testing/dirt"}},
+        {"$name": "test:grass", "$parent": "test:dirt", "faces": {"side": "testing/dirt"}}
+    ]"#);
+    return root;
+}
+
+fn errors(report: &Report) -> Vec<String> {
+    return report.issues.iter().filter(|issue| issue.severity == Severity::Error).map(|issue| issue.to_string()).collect();
+}
+
+#[test]
+fn valid_resources_pass() {
+    let root = create_resources();
+    let report = Validator::new(root.clone()).validate();
+    assert!(report.issues.is_empty(), "{}", report);
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn broken_types_and_assets_are_reported() {
+    let root = create_resources();
+    common::write(&root, "types/broken.json", br#"[
+        {"$name": "test:stone", "$type": "cube", "faces": {"top": "testing/stone"}},
+        {"$name": "test:sand", "$type": "unknown"},
+        {"$name": "test:dirt", "$type": "cube", "faces": {}},
+        {"$name": "test:clay", "$parent": "test:nothing"}
+    ]"#);
+    common::write(&root, "types/invalid.json", b"[{");
+    common::write(&root, "assets/textures/broken.png", b"not a png");
+    common::write(&root, "assets/textures/notes.txt", b"Not a texture");
+    common::write(&root, "assets/models/missing_shader.json", br#"{"shader": "nope"}"#);
+
+    let report = Validator::new(root.clone()).validate();
+    let broken = root.join("types/broken.json");
+    let expected = vec!(
+        format!("error: {} → Failed to load texture: Format error decoding Png: Invalid PNG signature.", root.join("assets/textures/broken.png").display()),
+        format!("error: {} → Missing shader nope", root.join("assets/models/missing_shader.json").display()),
+        format!("error: {}:2:19 → [0].$name → Name test:dirt is also used in {}", root.join("types/cubes.json").display(), broken.display()),
+        format!("error: {}:3:41 → [1].$type → No parent type ($type) registered as unknown", broken.display()),
+        format!("error: {}:5:43 → [3].$parent → Failed to find NamedType test:nothing", broken.display()),
+        format!("error: {}:2:67 → [0].faces.top → Missing texture testing/stone", broken.display())
+    );
+    let mut found = errors(&report);
+    found.retain(|error| !error.contains("invalid.json"));
+    assert_eq!(found, expected);
+    assert_eq!(report.errors(), expected.len() + 1, "{}", report);
+    assert!(report.issues.iter().any(|issue| issue.severity == Severity::Warning
+        && issue.message == "Not a PNG, JPEG, TGA or KTX2 texture, so it's never loaded"), "{}", report);
+    assert!(errors(&report).contains(&format!("error: {}:1:3 → Invalid JSON: Expected a key or '}}', found end of file",
+                                              root.join("types/invalid.json").display())), "{}", report);
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn resources_can_be_json5() {
+    let root = create_resources();
+    common::write(&root, "types/json5.json", br#"[
+        // Comments and trailing commas are fine
+        {$name: 'test:stone', $type: "cube", faces: {top: "testing/dirt",},},
+        /* So are unquoted keys */ {$name: "test:sand", $type: 'cube', faces: {side: 'testing/sand'}},
+    ]"#);
+    common::write(&root, "language/translations/en_us.json", b"{'menu.play': 'Play', /* Unused */}");
+
+    let report = Validator::new(root.clone()).validate();
+    assert_eq!(errors(&report), vec!(
+        format!("error: {}:4:86 → [1].faces.side → Missing texture testing/sand", root.join("types/json5.json").display())
+    ));
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn mods_and_translations_are_checked() {
+    let root = create_resources();
+    common::write(&root, "mods/first/manifest.json", br#"{"name": "first", "main": "first_main", "platforms": {},
+        "hard_dependencies": ["second"], "soft_dependencies": ["third"]}"#);
+    common::write(&root, "mods/first/language/translations/fr_fr.json", br#"{"menu.play": "Jouer", "menu.quit": "Quitter"}"#);
+    common::write(&root, "mods/first/types/items.json", br#"{"$name": "first:dirt", "$parent": "test:dirt", "faces": {"bottom": "first/dirt"}}"#);
+    common::write(&root, "mods/broken/manifest.json", br#"{"name": "broken"}"#);
+
+    let report = Validator::new(root.clone()).validate();
+    let manifest = root.join("mods/first/manifest.json");
+    assert_eq!(errors(&report), vec!(
+        format!("error: {} → Invalid mod manifest: Missing required field main", root.join("mods/broken/manifest.json").display()),
+        format!("error: {} → Missing required mod second", manifest.display()),
+        format!("error: {}:1:69 → faces.bottom → Missing texture first/dirt", root.join("mods/first/types/items.json").display())
+    ));
+    let warnings = report.issues.iter().filter(|issue| issue.severity == Severity::Warning)
+        .map(|issue| issue.message.clone()).collect::<Vec<_>>();
+    assert!(warnings.contains(&"Missing optional mod third".to_string()), "{}", report);
+    assert!(warnings.contains(&"Language en_us is missing 1 translation(s): menu.quit".to_string()), "{}", report);
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn mods_override_types() {
+    let root = create_resources();
+    common::write(&root, "mods/second/manifest.json", br#"{"name": "second", "main": "second_main", "platforms": {}}"#);
+    common::write(&root, "mods/second/types/cubes.json", br#"{"$name": "test:dirt", "$type": "cube", "faces": {"top": "second/dirt"}}"#);
+    //Loaded after second, since it depends on it, even though it comes first by name
+    common::write(&root, "mods/first/manifest.json", br#"{"name": "first", "main": "first_main", "platforms": {},
+        "hard_dependencies": ["second"]}"#);
+    common::write(&root, "mods/first/types/cubes.json", br#"{"$name": "test:dirt", "$type": "cube", "faces": {"top": "testing/dirt"}}"#);
+
+    let report = Validator::new(root.clone()).validate();
+    assert!(errors(&report).is_empty(), "{}", report);
+    let warnings = report.issues.iter().filter(|issue| issue.severity == Severity::Warning
+        && issue.message.starts_with("Overrides")).map(|issue| issue.to_string()).collect::<Vec<_>>();
+    assert_eq!(warnings, vec!(
+        format!("warning: {}:1:11 → $name → Overrides test:dirt in {}, second's version is loaded",
+                root.join("mods/second/types/cubes.json").display(), root.join("types/cubes.json").display()),
+        format!("warning: {}:1:11 → $name → Overrides test:dirt in {}, first's version is loaded",
+                root.join("mods/first/types/cubes.json").display(), root.join("mods/second/types/cubes.json").display())
+    ));
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn zipped_mods_are_checked() {
+    let root = create_resources();
+    let path = root.join("mods/zipped.zip");
+    archive(&path, &[
+        ("zipped/manifest.json", br#"{"name": "zipped", "main": "zipped_main", "platforms": {}}"#),
+        ("zipped/assets/textures/zipped/broken.png", b"not a png"),
+        ("zipped/types/items.json", br#"[
+            {"$name": "zipped:dirt", "$parent": "test:dirt", "faces": {"bottom": "zipped/missing"}}
+        ]"#),
+    ]);
+    archive(&root.join("mods/empty.ctlpak"), &[("types/items.json", b"[]")]);
+
+    let report = Validator::new(root.clone()).validate();
+    assert_eq!(errors(&report), vec!(
+        format!("error: {0} → Invalid mod manifest: {0}: specified file not found in archive",
+                root.join("mods/empty.ctlpak/manifest.json").display()),
+        format!("error: {} → Failed to load texture: Format error decoding Png: Invalid PNG signature.",
+                path.join("assets/textures/zipped/broken.png").display()),
+        format!("error: {}:2:82 → [0].faces.bottom → Missing texture zipped/missing", path.join("types/items.json").display())
+    ));
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn animations_have_to_fit_their_texture() {
+    let root = create_resources();
+    common::write(&root, "assets/textures/testing/dirt.png.json", b"{frame_time: 50}");
+    assert!(Validator::new(root.clone()).validate().issues.is_empty());
+
+    common::write(&root, "assets/textures/testing/dirt.png.json", b"{frame_width: 5}");
+    common::write(&root, "assets/textures/testing/stone.png.json", b"{}");
+    let report = Validator::new(root.clone()).validate();
+    let textures = root.join("assets/textures/testing");
+    assert_eq!(errors(&report), vec!(
+        format!("error: {} → Failed to load animation: A 16x16 image can't be cut into 5x5 frames", textures.join("dirt.png.json").display()),
+        format!("error: {} → Failed to load animation: There's no texture {} to animate",
+                textures.join("stone.png.json").display(), textures.join("stone.png").display())
+    ));
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn imported_models_need_their_textures() {
+    let root = create_resources();
+    common::write(&root, "assets/models/crate.obj", b"mtllib crate.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nusemtl wood\nf 1/1 2/1 3/1\n");
+    common::write(&root, "assets/models/crate.mtl", b"newmtl wood\nmap_Kd ../textures/testing/dirt.png\n");
+    assert!(Validator::new(root.clone()).validate().issues.is_empty());
+
+    common::write(&root, "assets/models/crate.mtl", b"newmtl wood\nmap_Kd ../textures/testing/planks.png\n");
+    common::write(&root, "assets/models/broken.obj", b"v 0 0 0\nf 1 2 3\n");
+    let report = Validator::new(root.clone()).validate();
+    let models = root.join("assets/models");
+    let errors = errors(&report);
+    assert_eq!(errors.len(), 2, "{}", report);
+    assert!(errors[0].starts_with(&format!("error: {} → Failed to load model: ", models.join("broken.obj").display())), "{}", report);
+    assert_eq!(errors[1], format!("error: {} → Missing texture testing/planks", models.join("crate.obj").display()));
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn shaders_have_to_be_able_to_draw_their_models() {
+    let root = create_resources();
+    common::write(&root, "shaders/lit.wgsl", b"//layout: Position, Normal\n");
+    common::write(&root, "shaders/broken.wgsl", b"//layout: Position, Normals\n");
+    common::write(&root, "assets/models/lit.json", br#"{"shader": "lit", "layout": ["Position", "Normal", "Color"], "vertexes": [], "indices": []}"#);
+    assert!(errors(&Validator::new(root.clone()).validate()).iter().all(|error| !error.contains("lit.json")));
+
+    common::write(&root, "assets/models/flat.json", br#"{"shader": "lit", "vertexes": [], "indices": []}"#);
+    let report = Validator::new(root.clone()).validate();
+    assert_eq!(errors(&report), vec!(
+        format!("error: {} → Failed to load shader: Invalid shader layout: Unknown variant Normals, expected one of \
+                 Position, TexCoords, Normal, Tangent, Color, TexCoords2, Joints, Weights", root.join("shaders/broken.wgsl").display()),
+        format!("error: {} → Shader lit reads [Normal], which the mesh's [Position, TexCoords] vertexes don't have",
+                root.join("assets/models/flat.json").display())
+    ));
+    fs::remove_dir_all(root).unwrap();
+}
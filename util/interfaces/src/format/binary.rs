@@ -0,0 +1,312 @@
+use anyhow::Error;
+use json::JsonValue;
+use json::number::Number;
+use json::object::Object;
+use crate::format::{DataFormat, Deserializer, Serializer};
+use crate::loading::JsonLoadable;
+use crate::saving::JsonSaveable;
+
+//A compact binary encoding for saves and bundles. Derived types are written with their Serializer impls,
+//so structs are their fields in order without keys and enums are their variant's index.
+//Every value starts with one of these tags. Lengths, counts, indices and mantissas are LEB128 varints,
+//exponents are zigzag varints, and strings are their length followed by UTF-8.
+const NULL: u8 = 0;
+const FALSE: u8 = 1;
+const TRUE: u8 = 2;
+const POSITIVE: u8 = 3;
+const NEGATIVE: u8 = 4;
+const NAN: u8 = 5;
+const STRING: u8 = 6;
+const ARRAY: u8 = 7;
+const OBJECT: u8 = 8;
+//The number of fields, then each field
+const STRUCT: u8 = 9;
+//The variant's index, then its fields
+const VARIANT: u8 = 10;
+
+const TAG_NAMES: [&str; 11] = ["null", "false", "true", "number", "number", "NaN", "string", "array", "object", "struct", "variant"];
+
+pub struct Binary;
+
+impl DataFormat for Binary {
+    fn encode(value: &JsonValue) -> Vec<u8> {
+        let mut writer = BinaryWriter::new();
+        writer.value(value);
+        return writer.output;
+    }
+
+    fn decode(data: &[u8]) -> Result<JsonValue, Error> {
+        let mut reader = BinaryReader::new(data);
+        let value = reader.read_value(0)?;
+        reader.finish()?;
+        return Ok(value);
+    }
+
+    fn write<T: JsonSaveable>(value: &T) -> Vec<u8> {
+        let mut writer = BinaryWriter::new();
+        value.serialize(&mut writer);
+        return writer.output;
+    }
+
+    fn read<T: JsonLoadable>(data: &[u8]) -> Result<T, Error> {
+        let mut reader = BinaryReader::new(data);
+        let value = T::deserialize(&mut reader)?;
+        reader.finish()?;
+        return Ok(value);
+    }
+}
+
+fn write_varint(output: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        output.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+fn write_string(output: &mut Vec<u8>, value: &str) {
+    write_varint(output, value.len() as u64);
+    output.extend_from_slice(value.as_bytes());
+}
+
+pub struct BinaryWriter {
+    pub output: Vec<u8>,
+}
+
+impl BinaryWriter {
+    pub fn new() -> Self {
+        return Self {
+            output: Vec::new()
+        };
+    }
+}
+
+impl Default for BinaryWriter {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl Serializer for BinaryWriter {
+    fn null(&mut self) {
+        self.output.push(NULL);
+    }
+
+    fn boolean(&mut self, value: bool) {
+        self.output.push(if value { TRUE } else { FALSE });
+    }
+
+    fn number(&mut self, value: Number) {
+        if value.is_nan() {
+            self.output.push(NAN);
+            return;
+        }
+        let (positive, mantissa, exponent) = value.as_parts();
+        self.output.push(if positive { POSITIVE } else { NEGATIVE });
+        write_varint(&mut self.output, mantissa);
+        write_varint(&mut self.output, ((exponent << 1) ^ (exponent >> 15)) as u16 as u64);
+    }
+
+    fn string(&mut self, value: &str) {
+        self.output.push(STRING);
+        write_string(&mut self.output, value);
+    }
+
+    fn array(&mut self, length: usize) {
+        self.output.push(ARRAY);
+        write_varint(&mut self.output, length as u64);
+    }
+
+    fn object(&mut self, length: usize) {
+        self.output.push(OBJECT);
+        write_varint(&mut self.output, length as u64);
+    }
+
+    fn key(&mut self, key: &str) {
+        write_string(&mut self.output, key);
+    }
+
+    fn structure(&mut self, _name: &str, fields: usize) {
+        self.output.push(STRUCT);
+        write_varint(&mut self.output, fields as u64);
+    }
+
+    //Both sides know the fields' order, so their keys aren't written
+    fn field(&mut self, _key: &str) {}
+
+    fn variant(&mut self, _name: &str, index: u32) {
+        self.output.push(VARIANT);
+        write_varint(&mut self.output, index as u64);
+    }
+}
+
+//Deeper values are rejected instead of overflowing the stack
+const MAX_DEPTH: usize = 256;
+
+pub struct BinaryReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BinaryReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        return Self {
+            data,
+            position: 0
+        };
+    }
+
+    //Checks everything was read
+    pub fn finish(&self) -> Result<(), Error> {
+        if self.position != self.data.len() {
+            return Err(Error::msg(format!("Unexpected data after the value at byte {}", self.position)));
+        }
+        return Ok(());
+    }
+
+    //Reads a tag, which has to be the expected one
+    fn expect(&mut self, expected: u8) -> Result<(), Error> {
+        let start = self.position;
+        let tag = self.byte()?;
+        if tag != expected {
+            let found = TAG_NAMES.get(tag as usize).map_or_else(|| format!("unknown tag {}", tag), |name| name.to_string());
+            return Err(Error::msg(format!("Expected {}, found {} at byte {}", TAG_NAMES[expected as usize], found, start)));
+        }
+        return Ok(());
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        let output = *self.data.get(self.position)
+            .ok_or_else(|| Error::msg(format!("Unexpected end of data at byte {}", self.position)))?;
+        self.position += 1;
+        return Ok(output);
+    }
+
+    fn varint(&mut self) -> Result<u64, Error> {
+        let start = self.position;
+        let mut output = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift > 63 {
+                return Err(Error::msg(format!("Varint too long at byte {}", start)));
+            }
+            output |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(output);
+            }
+            shift += 7;
+        }
+    }
+
+    //Counts are checked against what's left, so bad data can't make huge allocations
+    fn length(&mut self) -> Result<usize, Error> {
+        let start = self.position;
+        let length = self.varint()?;
+        if length > (self.data.len() - self.position) as u64 {
+            return Err(Error::msg(format!("Length {} at byte {} is longer than the data", length, start)));
+        }
+        return Ok(length as usize);
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let length = self.length()?;
+        let bytes = &self.data[self.position..self.position + length];
+        let output = String::from_utf8(bytes.to_vec())
+            .map_err(|_| Error::msg(format!("Invalid UTF-8 in string at byte {}", self.position)))?;
+        self.position += length;
+        return Ok(output);
+    }
+
+    fn read_value(&mut self, depth: usize) -> Result<JsonValue, Error> {
+        let start = self.position;
+        if depth > MAX_DEPTH {
+            return Err(Error::msg(format!("Values nested deeper than {} at byte {}", MAX_DEPTH, start)));
+        }
+        return Ok(match self.byte()? {
+            NULL => JsonValue::Null,
+            FALSE => JsonValue::Boolean(false),
+            TRUE => JsonValue::Boolean(true),
+            NAN => JsonValue::Number(json::number::NAN),
+            tag @ (POSITIVE | NEGATIVE) => {
+                let mantissa = self.varint()?;
+                let zigzag = self.varint()?;
+                if zigzag > u16::MAX as u64 {
+                    return Err(Error::msg(format!("Exponent out of range at byte {}", start)));
+                }
+                let exponent = ((zigzag >> 1) as i16) ^ -((zigzag & 1) as i16);
+                JsonValue::Number(Number::from_parts(tag == POSITIVE, mantissa, exponent))
+            }
+            STRING => JsonValue::String(self.string()?),
+            ARRAY => {
+                let length = self.length()?;
+                let mut values = Vec::with_capacity(length);
+                for _ in 0..length {
+                    values.push(self.read_value(depth + 1)?);
+                }
+                JsonValue::Array(values)
+            }
+            OBJECT => {
+                let length = self.length()?;
+                let mut object = Object::with_capacity(length);
+                for _ in 0..length {
+                    let key = self.string()?;
+                    object.insert(&key, self.read_value(depth + 1)?);
+                }
+                JsonValue::Object(object)
+            }
+            STRUCT | VARIANT => return Err(Error::msg(format!("Found a {} at byte {}, which can only be read as its type",
+                                                              TAG_NAMES[self.data[start] as usize], start))),
+            tag => return Err(Error::msg(format!("Unknown tag {} at byte {}", tag, start)))
+        });
+    }
+}
+
+impl Deserializer for BinaryReader<'_> {
+    fn value(&mut self) -> Result<JsonValue, Error> {
+        return self.read_value(0);
+    }
+
+    fn null(&mut self) -> Result<bool, Error> {
+        if self.data.get(self.position) != Some(&NULL) {
+            return Ok(false);
+        }
+        self.position += 1;
+        return Ok(true);
+    }
+
+    fn array(&mut self) -> Result<usize, Error> {
+        self.expect(ARRAY)?;
+        return self.length();
+    }
+
+    fn object(&mut self) -> Result<usize, Error> {
+        self.expect(OBJECT)?;
+        return self.length();
+    }
+
+    fn key(&mut self) -> Result<String, Error> {
+        return self.string();
+    }
+
+    fn structure(&mut self, name: &str, fields: usize) -> Result<(), Error> {
+        let start = self.position;
+        self.expect(STRUCT)?;
+        let found = self.varint()?;
+        if found != fields as u64 {
+            return Err(Error::msg(format!("Expected {} with {} fields, found {} at byte {}", name, fields, found, start)));
+        }
+        return Ok(());
+    }
+
+    fn variant(&mut self, name: &str, variants: &[&str]) -> Result<u32, Error> {
+        let start = self.position;
+        self.expect(VARIANT)?;
+        let index = self.varint()?;
+        if index >= variants.len() as u64 {
+            return Err(Error::msg(format!("Unknown variant {} of {} at byte {}, expected one of {}",
+                                          index, name, start, variants.join(", "))));
+        }
+        return Ok(index as u32);
+    }
+}
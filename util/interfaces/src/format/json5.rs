@@ -0,0 +1,494 @@
+use std::fmt::{Display, Formatter};
+use anyhow::Error;
+use json::JsonValue;
+use json::number::Number;
+use json::object::Object;
+use crate::format::DataFormat;
+
+//JSON5: JSON with comments, trailing commas, unquoted keys, single quoted strings, hex numbers and more.
+//See https://spec.json5.org. It's written with unquoted keys, for people to edit.
+pub struct Json5;
+
+impl DataFormat for Json5 {
+    fn encode(value: &JsonValue) -> Vec<u8> {
+        let mut output = String::new();
+        write_value(&mut output, value, 0);
+        output.push('\n');
+        return output.into_bytes();
+    }
+
+    fn decode(data: &[u8]) -> Result<JsonValue, Error> {
+        return parse(std::str::from_utf8(data)?);
+    }
+}
+
+//Where parsing failed, lines and columns start at 1
+#[derive(Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}:{} → {}", self.line, self.column, self.message);
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+//Parses JSON5 into the same tree json::parse makes. Errors are ParseErrors.
+//NaN and Infinity become NaN, because JsonValue numbers can't be infinite.
+pub fn parse(source: &str) -> Result<JsonValue, Error> {
+    let mut parser = Parser {
+        source,
+        chars: source.char_indices().collect(),
+        position: 0
+    };
+    parser.skip_ignored()?;
+    let value = parser.value(0)?;
+    parser.skip_ignored()?;
+    if parser.position < parser.chars.len() {
+        return Err(parser.error(format!("Unexpected {} after the value", parser.describe())));
+    }
+    return Ok(value);
+}
+
+//Deeper values are rejected instead of overflowing the stack
+const MAX_DEPTH: usize = 256;
+
+struct Parser<'a> {
+    source: &'a str,
+    chars: Vec<(usize, char)>,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        return self.chars.get(self.position).map(|(_, character)| *character);
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        return self.chars.get(self.position + offset).map(|(_, character)| *character);
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let output = self.peek();
+        self.position += 1;
+        return output;
+    }
+
+    fn error(&self, message: String) -> Error {
+        let offset = self.chars.get(self.position).map_or(self.source.len(), |(offset, _)| *offset);
+        let before = &self.source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        return Error::new(ParseError {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            message
+        });
+    }
+
+    //The next character for errors
+    fn describe(&self) -> String {
+        return match self.peek() {
+            Some(character) => format!("'{}'", character),
+            None => String::from("end of file")
+        };
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Error> {
+        if self.peek() != Some(expected) {
+            return Err(self.error(format!("Expected '{}', found {}", expected, self.describe())));
+        }
+        self.position += 1;
+        return Ok(());
+    }
+
+    //Skips whitespace and comments
+    fn skip_ignored(&mut self) -> Result<(), Error> {
+        loop {
+            match (self.peek(), self.peek_at(1)) {
+                (Some(character), _) if character.is_whitespace() || character == '\u{feff}' => self.position += 1,
+                (Some('/'), Some('/')) => {
+                    while !matches!(self.peek(), None | Some('\n' | '\r' | '\u{2028}' | '\u{2029}')) {
+                        self.position += 1;
+                    }
+                }
+                (Some('/'), Some('*')) => {
+                    let start = self.position;
+                    self.position += 2;
+                    loop {
+                        match (self.peek(), self.peek_at(1)) {
+                            (Some('*'), Some('/')) => {
+                                self.position += 2;
+                                break;
+                            }
+                            (Some(_), _) => self.position += 1,
+                            (None, _) => {
+                                self.position = start;
+                                return Err(self.error(String::from("Unterminated comment")));
+                            }
+                        }
+                    }
+                }
+                _ => return Ok(())
+            }
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<JsonValue, Error> {
+        if depth > MAX_DEPTH {
+            return Err(self.error(format!("Values nested deeper than {}", MAX_DEPTH)));
+        }
+        return match self.peek() {
+            Some('{') => self.object(depth),
+            Some('[') => self.array(depth),
+            Some('"' | '\'') => Ok(JsonValue::String(self.string()?)),
+            Some(character) if character.is_ascii_digit() || matches!(character, '-' | '+' | '.') => self.number(),
+            Some(character) if is_identifier_start(character) => {
+                let start = self.position;
+                let word = self.identifier()?;
+                match word.as_str() {
+                    "null" => Ok(JsonValue::Null),
+                    "true" => Ok(JsonValue::Boolean(true)),
+                    "false" => Ok(JsonValue::Boolean(false)),
+                    "NaN" | "Infinity" => Ok(JsonValue::Number(json::number::NAN)),
+                    _ => {
+                        self.position = start;
+                        Err(self.error(format!("Unexpected {}, strings need quotes", word)))
+                    }
+                }
+            }
+            _ => Err(self.error(format!("Expected a value, found {}", self.describe())))
+        };
+    }
+
+    fn object(&mut self, depth: usize) -> Result<JsonValue, Error> {
+        self.expect('{')?;
+        let mut object = Object::new();
+        loop {
+            self.skip_ignored()?;
+            if self.peek() == Some('}') {
+                self.position += 1;
+                return Ok(JsonValue::Object(object));
+            }
+            let key = match self.peek() {
+                Some('"' | '\'') => self.string()?,
+                Some(character) if is_identifier_start(character) => self.identifier()?,
+                _ => return Err(self.error(format!("Expected a key or '}}', found {}", self.describe())))
+            };
+            self.skip_ignored()?;
+            self.expect(':')?;
+            self.skip_ignored()?;
+            let value = self.value(depth + 1)?;
+            object.insert(&key, value);
+            self.skip_ignored()?;
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some('}') => {}
+                _ => return Err(self.error(format!("Expected ',' or '}}', found {}", self.describe())))
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<JsonValue, Error> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        loop {
+            self.skip_ignored()?;
+            if self.peek() == Some(']') {
+                self.position += 1;
+                return Ok(JsonValue::Array(values));
+            }
+            values.push(self.value(depth + 1)?);
+            self.skip_ignored()?;
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(']') => {}
+                _ => return Err(self.error(format!("Expected ',' or ']', found {}", self.describe())))
+            }
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, Error> {
+        let mut output = String::new();
+        while let Some(character) = self.peek() {
+            if character == '\\' {
+                //Identifiers can have \uXXXX escapes too
+                self.position += 1;
+                if self.next() != Some('u') {
+                    self.position -= 1;
+                    return Err(self.error(String::from("Expected \\u escape in key")));
+                }
+                output.push(self.unicode_escape()?);
+            } else if is_identifier_part(character) {
+                output.push(character);
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+        return Ok(output);
+    }
+
+    fn hex_digits(&mut self, count: usize) -> Result<u32, Error> {
+        let mut output = 0;
+        for _ in 0..count {
+            match self.peek().and_then(|character| character.to_digit(16)) {
+                Some(digit) => output = output * 16 + digit,
+                None => return Err(self.error(format!("Expected hex digit, found {}", self.describe())))
+            }
+            self.position += 1;
+        }
+        return Ok(output);
+    }
+
+    //The XXXX of \uXXXX, including the second half of surrogate pairs
+    fn unicode_escape(&mut self) -> Result<char, Error> {
+        let code = self.hex_digits(4)?;
+        if (0xd800..0xdc00).contains(&code) && self.peek() == Some('\\') && self.peek_at(1) == Some('u') {
+            let start = self.position;
+            self.position += 2;
+            let low = self.hex_digits(4)?;
+            if (0xdc00..0xe000).contains(&low) {
+                return Ok(char::from_u32(0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00)).unwrap_or('\u{fffd}'));
+            }
+            self.position = start;
+        }
+        return Ok(char::from_u32(code).unwrap_or('\u{fffd}'));
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let start = self.position;
+        let quote = self.next().unwrap();
+        let mut output = String::new();
+        loop {
+            match self.next() {
+                None => {
+                    self.position = start;
+                    return Err(self.error(String::from("Unterminated string")));
+                }
+                Some(character) if character == quote => return Ok(output),
+                Some('\n' | '\r') => {
+                    self.position -= 1;
+                    return Err(self.error(String::from("Unescaped line break in string")));
+                }
+                Some('\\') => match self.next() {
+                    Some('n') => output.push('\n'),
+                    Some('t') => output.push('\t'),
+                    Some('r') => output.push('\r'),
+                    Some('b') => output.push('\u{8}'),
+                    Some('f') => output.push('\u{c}'),
+                    Some('v') => output.push('\u{b}'),
+                    Some('0') if !self.peek().is_some_and(|character| character.is_ascii_digit()) => output.push('\0'),
+                    Some('x') => {
+                        let code = self.hex_digits(2)?;
+                        output.push(char::from_u32(code).unwrap());
+                    }
+                    Some('u') => output.push(self.unicode_escape()?),
+                    //Escaped line breaks continue the string on the next line
+                    Some('\r') => {
+                        if self.peek() == Some('\n') {
+                            self.position += 1;
+                        }
+                    }
+                    Some('\n' | '\u{2028}' | '\u{2029}') => {}
+                    Some(character) if character.is_ascii_digit() => {
+                        self.position -= 1;
+                        return Err(self.error(String::from("Digits can't be escaped")));
+                    }
+                    Some(character) => output.push(character),
+                    None => {
+                        self.position = start;
+                        return Err(self.error(String::from("Unterminated string")));
+                    }
+                },
+                Some(character) => output.push(character)
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<JsonValue, Error> {
+        let start = self.position;
+        let positive = match self.peek() {
+            Some('-') => {
+                self.position += 1;
+                false
+            }
+            Some('+') => {
+                self.position += 1;
+                true
+            }
+            _ => true
+        };
+
+        //NaN, Infinity and hex numbers
+        if self.peek().is_some_and(is_identifier_start) {
+            return match self.identifier()?.as_str() {
+                "NaN" | "Infinity" => Ok(JsonValue::Number(json::number::NAN)),
+                _ => {
+                    self.position = start;
+                    Err(self.error(String::from("Invalid number")))
+                }
+            };
+        }
+        if self.peek() == Some('0') && matches!(self.peek_at(1), Some('x' | 'X')) {
+            self.position += 2;
+            let digits_start = self.position;
+            let mut mantissa: u64 = 0;
+            while let Some(digit) = self.peek().and_then(|character| character.to_digit(16)) {
+                mantissa = match mantissa.checked_mul(16).and_then(|mantissa| mantissa.checked_add(digit as u64)) {
+                    Some(mantissa) => mantissa,
+                    None => {
+                        self.position = start;
+                        return Err(self.error(String::from("Hex number too large")));
+                    }
+                };
+                self.position += 1;
+            }
+            if self.position == digits_start {
+                return Err(self.error(format!("Expected hex digit, found {}", self.describe())));
+            }
+            return Ok(JsonValue::Number(Number::from_parts(positive, mantissa, 0)));
+        }
+
+        //Rewritten as plain JSON so json's exact number parsing can be used
+        let mut text = String::from(if positive { "" } else { "-" });
+        let mut digits = 0;
+        while let Some(character) = self.peek().filter(char::is_ascii_digit) {
+            text.push(character);
+            digits += 1;
+            self.position += 1;
+        }
+        if digits == 0 {
+            text.push('0');
+        }
+        if self.peek() == Some('.') {
+            self.position += 1;
+            let mut fraction = String::new();
+            while let Some(character) = self.peek().filter(char::is_ascii_digit) {
+                fraction.push(character);
+                self.position += 1;
+            }
+            if digits == 0 && fraction.is_empty() {
+                self.position = start;
+                return Err(self.error(String::from("Invalid number")));
+            }
+            if !fraction.is_empty() {
+                text += ".";
+                text += &fraction;
+            }
+        } else if digits == 0 {
+            self.position = start;
+            return Err(self.error(String::from("Invalid number")));
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            text.push('e');
+            self.position += 1;
+            if let Some(sign @ ('+' | '-')) = self.peek() {
+                text.push(sign);
+                self.position += 1;
+            }
+            let exponent_start = self.position;
+            while let Some(character) = self.peek().filter(char::is_ascii_digit) {
+                text.push(character);
+                self.position += 1;
+            }
+            if self.position == exponent_start {
+                return Err(self.error(format!("Expected exponent digits, found {}", self.describe())));
+            }
+        }
+
+        //Leading zeros aren't allowed
+        let integer = text.trim_start_matches('-');
+        if integer.len() > 1 && integer.starts_with('0') && integer.as_bytes()[1].is_ascii_digit() {
+            self.position = start;
+            return Err(self.error(String::from("Numbers can't have leading zeros")));
+        }
+        return match json::parse(&text) {
+            Ok(value) => Ok(value),
+            Err(_) => {
+                self.position = start;
+                Err(self.error(String::from("Invalid number")))
+            }
+        };
+    }
+}
+
+fn is_identifier_start(character: char) -> bool {
+    return character.is_alphabetic() || character == '$' || character == '_';
+}
+
+fn is_identifier_part(character: char) -> bool {
+    return character.is_alphanumeric() || character == '$' || character == '_' || character == '\u{200c}' || character == '\u{200d}';
+}
+
+//Keys that can be written without quotes
+fn is_identifier(key: &str) -> bool {
+    let mut characters = key.chars();
+    return characters.next().is_some_and(is_identifier_start) && characters.all(is_identifier_part);
+}
+
+fn write_string(output: &mut String, value: &str) {
+    output.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            '\u{2028}' => output.push_str("\\u2028"),
+            '\u{2029}' => output.push_str("\\u2029"),
+            character if character.is_control() => output.push_str(&format!("\\u{:04x}", character as u32)),
+            character => output.push(character)
+        }
+    }
+    output.push('"');
+}
+
+fn indent(output: &mut String, depth: usize) {
+    for _ in 0..depth {
+        output.push_str("    ");
+    }
+}
+
+fn write_value(output: &mut String, value: &JsonValue, depth: usize) {
+    match value {
+        JsonValue::Null => output.push_str("null"),
+        JsonValue::Boolean(value) => output.push_str(if *value { "true" } else { "false" }),
+        JsonValue::Number(number) if number.is_nan() => output.push_str("NaN"),
+        JsonValue::Number(number) => output.push_str(&number.to_string()),
+        JsonValue::Short(_) | JsonValue::String(_) => write_string(output, value.as_str().unwrap()),
+        JsonValue::Array(values) if values.is_empty() => output.push_str("[]"),
+        JsonValue::Array(values) => {
+            output.push_str("[\n");
+            for value in values {
+                indent(output, depth + 1);
+                write_value(output, value, depth + 1);
+                output.push_str(",\n");
+            }
+            indent(output, depth);
+            output.push(']');
+        }
+        JsonValue::Object(object) if object.is_empty() => output.push_str("{}"),
+        JsonValue::Object(object) => {
+            output.push_str("{\n");
+            for (key, value) in object.iter() {
+                indent(output, depth + 1);
+                if is_identifier(key) {
+                    output.push_str(key);
+                } else {
+                    write_string(output, key);
+                }
+                output.push_str(": ");
+                write_value(output, value, depth + 1);
+                output.push_str(",\n");
+            }
+            indent(output, depth);
+            output.push('}');
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use anyhow::Error;
+use json::JsonValue;
+use json::number::Number;
+use crate::loading::JsonLoadable;
+use crate::saving::JsonSaveable;
+
+pub mod binary;
+pub mod json5;
+
+pub use binary::Binary;
+pub use json5::Json5;
+
+//A way of writing the types JsonLoadable and JsonSaveable work with.
+//load_from and save_to use one to read and write any loadable type, JSON is the default.
+pub trait DataFormat {
+    //Converts a JsonValue to and from bytes
+    fn encode(value: &JsonValue) -> Vec<u8>;
+
+    fn decode(data: &[u8]) -> Result<JsonValue, Error>;
+
+    //Writes a value. Text formats write what it saves as, so its keys, defaults and tags are the same as JSON's.
+    //Formats with a Serializer override this to write it with JsonSaveable::serialize instead.
+    fn write<T: JsonSaveable>(value: &T) -> Vec<u8> {
+        return Self::encode(&value.save());
+    }
+
+    fn read<T: JsonLoadable>(data: &[u8]) -> Result<T, Error> {
+        return T::load(&Self::decode(data)?);
+    }
+}
+
+//The data model derived types write themselves to, without going through JsonValue.
+//Structs and enum variants say how many fields they have and name each one, so formats where both sides
+//know the type, like Binary, can write the fields in order without their keys.
+pub trait Serializer {
+    fn null(&mut self);
+
+    fn boolean(&mut self, value: bool);
+
+    fn number(&mut self, value: Number);
+
+    fn string(&mut self, value: &str);
+
+    //Followed by each element
+    fn array(&mut self, length: usize);
+
+    //Maps, followed by a key and a value for each entry
+    fn object(&mut self, length: usize);
+
+    fn key(&mut self, key: &str);
+
+    //Structs, tuple structs and the fields of enum variants, followed by each field's value.
+    //Named fields are preceded by field.
+    fn structure(&mut self, name: &str, fields: usize);
+
+    fn field(&mut self, key: &str);
+
+    //Which variant of an enum it is, followed by the variant's fields if it has any
+    fn variant(&mut self, name: &str, index: u32);
+
+    //Types that don't implement JsonSaveable::serialize are written as what they save as
+    fn value(&mut self, value: &JsonValue) {
+        match value {
+            JsonValue::Null => self.null(),
+            JsonValue::Boolean(value) => self.boolean(*value),
+            JsonValue::Number(number) => self.number(*number),
+            JsonValue::Short(_) | JsonValue::String(_) => self.string(value.as_str().unwrap()),
+            JsonValue::Array(values) => {
+                self.array(values.len());
+                for value in values {
+                    self.value(value);
+                }
+            }
+            JsonValue::Object(object) => {
+                self.object(object.len());
+                for (key, value) in object.iter() {
+                    self.key(key);
+                    self.value(value);
+                }
+            }
+        }
+    }
+}
+
+//Reads what a Serializer wrote, in the same order
+pub trait Deserializer {
+    //Reads a value of any kind, for types that load from JsonValue
+    fn value(&mut self) -> Result<JsonValue, Error>;
+
+    //Reads a null if that's what's next, for options
+    fn null(&mut self) -> Result<bool, Error>;
+
+    //The array's length
+    fn array(&mut self) -> Result<usize, Error>;
+
+    //The object's length
+    fn object(&mut self) -> Result<usize, Error>;
+
+    fn key(&mut self) -> Result<String, Error>;
+
+    //Checks the structure has the fields the type expects
+    fn structure(&mut self, name: &str, fields: usize) -> Result<(), Error>;
+
+    //The variant's index, checked against the enum's variants
+    fn variant(&mut self, name: &str, variants: &[&str]) -> Result<u32, Error>;
+}
+
+//Plain JSON, as written by json::stringify
+pub struct Json;
+
+impl DataFormat for Json {
+    fn encode(value: &JsonValue) -> Vec<u8> {
+        return json::stringify(value.clone()).into_bytes();
+    }
+
+    fn decode(data: &[u8]) -> Result<JsonValue, Error> {
+        return Ok(json::parse(std::str::from_utf8(data)?)?);
+    }
+}
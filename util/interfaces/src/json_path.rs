@@ -0,0 +1,321 @@
+use std::fmt::{Display, Formatter};
+use anyhow::Error;
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+//Where a value is inside a JSON document, like [3].faces.top
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct JsonPath {
+    pub segments: Vec<PathSegment>,
+}
+
+impl JsonPath {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn key(mut self, key: &str) -> Self {
+        self.segments.push(PathSegment::Key(key.to_string()));
+        return self;
+    }
+
+    pub fn index(mut self, index: usize) -> Self {
+        self.segments.push(PathSegment::Index(index));
+        return self;
+    }
+
+    //This path inside of parent
+    pub fn inside(mut self, parent: &JsonPath) -> Self {
+        self.segments.splice(0..0, parent.segments.iter().cloned());
+        return self;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.segments.is_empty();
+    }
+}
+
+impl Display for JsonPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                PathSegment::Key(key) if i == 0 => write!(f, "{}", key)?,
+                PathSegment::Key(key) => write!(f, ".{}", key)?,
+                PathSegment::Index(index) => write!(f, "[{}]", index)?
+            }
+        }
+        return Ok(());
+    }
+}
+
+//A loading error and the path to the value that caused it
+#[derive(Debug)]
+pub struct JsonError {
+    pub path: JsonPath,
+    pub message: String,
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            return write!(f, "{}", self.message);
+        }
+        return write!(f, "{} → {}", self.path, self.message);
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+//Marks error as coming from segment, inside whatever path it already has
+pub fn prepend(error: Error, segment: PathSegment) -> Error {
+    return match error.downcast::<JsonError>() {
+        Ok(mut error) => {
+            error.path.segments.insert(0, segment);
+            Error::new(error)
+        }
+        Err(error) => Error::new(JsonError {
+            path: JsonPath { segments: vec!(segment) },
+            message: format!("{:#}", error),
+        })
+    };
+}
+
+pub fn at_key(error: Error, key: &str) -> Error {
+    return prepend(error, PathSegment::Key(key.to_string()));
+}
+
+pub fn at_index(error: Error, index: usize) -> Error {
+    return prepend(error, PathSegment::Index(index));
+}
+
+//The path an error happened at, empty if it doesn't have one
+pub fn error_path(error: &Error) -> JsonPath {
+    return error.downcast_ref::<JsonError>().map(|error| error.path.clone()).unwrap_or_default();
+}
+
+//The error without its path
+pub fn error_message(error: &Error) -> String {
+    return match error.downcast_ref::<JsonError>() {
+        Some(error) => error.message.clone(),
+        None => format!("{:#}", error)
+    };
+}
+
+//Formats an error like types/cubes.json:12:17 → [3].faces.top → Expected string, leaving out anything unknown
+pub fn describe(file: &str, location: Option<(usize, usize)>, path: &JsonPath, message: &str) -> String {
+    let mut output = file.to_string();
+    if let Some((line, column)) = location {
+        output += &format!(":{}:{}", line, column);
+    }
+    if !path.is_empty() {
+        output += &format!(" → {}", path);
+    }
+    return format!("{} → {}", output, message);
+}
+
+//Describes an error loading the value at parent in the JSON source of file
+pub fn describe_error(file: &str, source: &str, parent: &JsonPath, error: &Error) -> String {
+    let path = error_path(error).inside(parent);
+    return describe(file, locate(source, &path), &path, &error_message(error));
+}
+
+//Finds the line and column (both starting at 1) of the value at path in the JSON or JSON5 source.
+//If part of the path doesn't exist, it's the deepest value that does.
+pub fn locate(source: &str, path: &JsonPath) -> Option<(usize, usize)> {
+    let bytes = source.as_bytes();
+    let mut position = skip_ignored(bytes, 0);
+    if position >= bytes.len() {
+        return None;
+    }
+    for segment in &path.segments {
+        let found = match segment {
+            PathSegment::Key(key) => find_key(bytes, position, key),
+            PathSegment::Index(index) => find_index(bytes, position, *index)
+        };
+        match found {
+            Some(found) => position = found,
+            None => break
+        }
+    }
+    return Some(line_column(source, position));
+}
+
+fn line_column(source: &str, position: usize) -> (usize, usize) {
+    let before = &source[..position];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    return (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1);
+}
+
+//Skips whitespace and comments
+fn skip_ignored(bytes: &[u8], mut position: usize) -> usize {
+    loop {
+        while position < bytes.len() && bytes[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        match skip_comment(bytes, position) {
+            Some(end) => position = end,
+            None => return position
+        }
+    }
+}
+
+//Returns the position after the comment starting at position, if there is one
+fn skip_comment(bytes: &[u8], position: usize) -> Option<usize> {
+    if bytes.get(position) != Some(&b'/') {
+        return None;
+    }
+    return match bytes.get(position + 1)? {
+        b'/' => Some(bytes[position..].iter().position(|byte| *byte == b'\n')
+            .map_or(bytes.len(), |end| position + end + 1)),
+        b'*' => Some(bytes[position + 2..].windows(2).position(|end| end == b"*/")
+            .map_or(bytes.len(), |end| position + end + 4)),
+        _ => None
+    };
+}
+
+//Start of the value for key in the object starting at position
+fn find_key(bytes: &[u8], position: usize, key: &str) -> Option<usize> {
+    if bytes.get(position) != Some(&b'{') {
+        return None;
+    }
+    let mut position = skip_ignored(bytes, position + 1);
+    loop {
+        let (found, end) = read_key(bytes, position)?;
+        position = skip_ignored(bytes, end);
+        if bytes.get(position) != Some(&b':') {
+            return None;
+        }
+        position = skip_ignored(bytes, position + 1);
+        if found == key {
+            return Some(position);
+        }
+        position = skip_ignored(bytes, skip_value(bytes, position)?);
+        if bytes.get(position) != Some(&b',') {
+            return None;
+        }
+        position = skip_ignored(bytes, position + 1);
+    }
+}
+
+//Start of the index-th value in the array starting at position
+fn find_index(bytes: &[u8], position: usize, index: usize) -> Option<usize> {
+    if bytes.get(position) != Some(&b'[') {
+        return None;
+    }
+    let mut position = skip_ignored(bytes, position + 1);
+    for _ in 0..index {
+        position = skip_ignored(bytes, skip_value(bytes, position)?);
+        if bytes.get(position) != Some(&b',') {
+            return None;
+        }
+        position = skip_ignored(bytes, position + 1);
+    }
+    if bytes.get(position) == Some(&b']') {
+        return None;
+    }
+    return Some(position);
+}
+
+//Returns the position after the value starting at position
+fn skip_value(bytes: &[u8], position: usize) -> Option<usize> {
+    return match bytes.get(position)? {
+        b'"' | b'\'' => read_string(bytes, position).map(|(_, end)| end),
+        b'{' | b'[' => {
+            let mut depth = 0;
+            let mut position = position;
+            while position < bytes.len() {
+                match bytes[position] {
+                    b'"' | b'\'' => {
+                        position = read_string(bytes, position)?.1;
+                        continue;
+                    }
+                    b'/' => if let Some(end) = skip_comment(bytes, position) {
+                        position = end;
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(position + 1);
+                        }
+                    }
+                    _ => {}
+                }
+                position += 1;
+            }
+            None
+        }
+        _ => {
+            let mut position = position;
+            while position < bytes.len() && !b",}]/ \t\r\n".contains(&bytes[position]) {
+                position += 1;
+            }
+            Some(position)
+        }
+    };
+}
+
+//Reads the key starting at position, quoted or a JSON5 identifier, returning it and the position after it
+fn read_key(bytes: &[u8], position: usize) -> Option<(String, usize)> {
+    if matches!(bytes.get(position)?, b'"' | b'\'') {
+        return read_string(bytes, position);
+    }
+    let end = bytes[position..].iter().position(|byte| !(byte.is_ascii_alphanumeric() || b"_$".contains(byte) || *byte >= 0x80))
+        .map_or(bytes.len(), |end| position + end);
+    if end == position {
+        return None;
+    }
+    return Some((String::from_utf8_lossy(&bytes[position..end]).to_string(), end));
+}
+
+//Reads the string starting at position, in either kind of quotes, returning it and the position after it
+fn read_string(bytes: &[u8], position: usize) -> Option<(String, usize)> {
+    let quote = *bytes.get(position)?;
+    if quote != b'"' && quote != b'\'' {
+        return None;
+    }
+    let mut output = Vec::new();
+    let mut position = position + 1;
+    while position < bytes.len() {
+        match bytes[position] {
+            byte if byte == quote => return Some((String::from_utf8_lossy(&output).to_string(), position + 1)),
+            b'\\' => {
+                position += 1;
+                match bytes.get(position)? {
+                    b'n' => output.push(b'\n'),
+                    b't' => output.push(b'\t'),
+                    b'r' => output.push(b'\r'),
+                    b'b' => output.push(8),
+                    b'f' => output.push(12),
+                    b'0' => output.push(0),
+                    b'x' => {
+                        let code = std::str::from_utf8(bytes.get(position + 1..position + 3)?).ok()?;
+                        let character = char::from_u32(u32::from_str_radix(code, 16).ok()?).unwrap_or('\u{fffd}');
+                        output.extend(character.to_string().as_bytes());
+                        position += 2;
+                    }
+                    b'u' => {
+                        let code = std::str::from_utf8(bytes.get(position + 1..position + 5)?).ok()?;
+                        let character = char::from_u32(u32::from_str_radix(code, 16).ok()?).unwrap_or('\u{fffd}');
+                        output.extend(character.to_string().as_bytes());
+                        position += 4;
+                    }
+                    //Line continuations aren't part of the string
+                    b'\n' => {}
+                    b'\r' => if bytes.get(position + 1) == Some(&b'\n') {
+                        position += 1;
+                    }
+                    other => output.push(*other)
+                }
+            }
+            other => output.push(other)
+        }
+        position += 1;
+    }
+    return None;
+}
@@ -1,2 +1,5 @@
+pub mod format;
+pub mod json_path;
 pub mod loading;
-pub mod saving;
\ No newline at end of file
+pub mod resource;
+pub mod saving;
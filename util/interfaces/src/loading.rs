@@ -1,14 +1,77 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::hash::Hash;
-use std::mem::MaybeUninit;
-use std::ptr;
+use std::sync::Arc;
 use std::time::Duration;
 use anyhow::Error;
+use cgmath::{Point2, Point3, Vector2, Vector3, Vector4};
 use json::JsonValue;
 use json::number::Number;
+use crate::format::{DataFormat, Deserializer};
+use crate::json_path;
 
 pub trait JsonLoadable: Sized {
     fn load(value: &JsonValue) -> Result<Self, Error>;
+
+    //JSON Schema of the JSON load accepts, for editors. Accepts anything unless overridden.
+    fn schema() -> JsonValue {
+        return JsonValue::new_object();
+    }
+
+    //Reads what JsonSaveable::serialize wrote. The derives implement it, anything else is loaded from a JsonValue.
+    fn deserialize<D: Deserializer>(deserializer: &mut D) -> Result<Self, Error> {
+        return Self::load(&deserializer.value()?);
+    }
+
+    //Loads from data in another format, like format::Binary
+    fn load_from<F: DataFormat>(data: &[u8]) -> Result<Self, Error> {
+        return F::read(data);
+    }
+
+    //Keys load reads from an object, so structs flattening this know which of their keys are its.
+    //None if it reads any key, the default.
+    fn object_keys() -> Option<Vec<&'static str>> {
+        return None;
+    }
+}
+
+//The keys a struct's own fields don't use, split between its flattened fields given the keys each reads.
+//Each one gets every key except those only its siblings read, so they don't reject each other's keys.
+pub fn split_flattened(rest: &JsonValue, flattened: &[Option<Vec<&'static str>>]) -> Vec<JsonValue> {
+    return flattened.iter().enumerate().map(|(i, own)| {
+        let mut output = rest.clone();
+        for (_, keys) in flattened.iter().enumerate().filter(|(j, _)| *j != i) {
+            for key in keys.iter().flatten() {
+                if !own.as_ref().is_some_and(|own| own.contains(key)) {
+                    output.remove(key);
+                }
+            }
+        }
+        output
+    }).collect();
+}
+
+//For structs that deny unknown keys, errors on the first key in rest none of the flattened fields read
+pub fn deny_flattened(rest: &JsonValue, known: &[&str], flattened: &[Option<Vec<&'static str>>]) -> Result<(), Error> {
+    if flattened.iter().any(Option::is_none) {
+        return Ok(());
+    }
+    let mut expected = known.to_vec();
+    expected.extend(flattened.iter().flatten().flatten());
+    for (key, _) in rest.entries() {
+        if !expected.contains(&key) {
+            return Err(json_path::at_key(Error::msg(format!("Unknown field {}, expected one of {}", key, expected.join(", "))), key));
+        }
+    }
+    return Ok(());
+}
+
+fn deserialize_all<T: JsonLoadable, D: Deserializer>(deserializer: &mut D) -> Result<Vec<T>, Error> {
+    let length = deserializer.array()?;
+    let mut output = Vec::with_capacity(length);
+    for i in 0..length {
+        output.push(T::deserialize(deserializer).map_err(|error| json_path::at_index(error, i))?);
+    }
+    return Ok(output);
 }
 
 impl<T> JsonLoadable for Vec<T> where T: JsonLoadable {
@@ -16,21 +79,47 @@ impl<T> JsonLoadable for Vec<T> where T: JsonLoadable {
         let mut result = Vec::new();
         match value {
             JsonValue::Array(values) => {
-                for value in values {
-                    result.push(T::load(&value)?);
+                for (i, value) in values.iter().enumerate() {
+                    result.push(T::load(value).map_err(|error| json_path::at_index(error, i))?);
                 }
             }
-            _ => return Err(Error::msg(format!("Non-array type in array! {:?}", value)))
+            _ => return Err(Error::msg(format!("Expected array, found {}", value)))
         }
         return Ok(result);
     }
+
+    fn schema() -> JsonValue {
+        return json::object! { "type": "array", "items": T::schema() };
+    }
+
+    fn deserialize<D: Deserializer>(deserializer: &mut D) -> Result<Self, Error> {
+        return deserialize_all(deserializer);
+    }
 }
 
-impl<T, V> JsonLoadable for HashMap<T, V> where T: From<String> + Eq + Hash, V: Default + JsonLoadable {
+impl<T, V> JsonLoadable for HashMap<T, V> where T: From<String> + Eq + Hash, V: JsonLoadable {
     fn load(value: &JsonValue) -> Result<HashMap<T, V>, Error> {
+        if !value.is_object() {
+            return Err(Error::msg(format!("Expected object, found {}", value)));
+        }
         let mut output = HashMap::new();
         for (key, value) in value.entries() {
-            output.insert(T::from(key.to_string()), V::load(value)?);
+            output.insert(T::from(key.to_string()), V::load(value).map_err(|error| json_path::at_key(error, key))?);
+        }
+        return Ok(output);
+    }
+
+    fn schema() -> JsonValue {
+        return json::object! { "type": "object", "additionalProperties": V::schema() };
+    }
+
+    fn deserialize<D: Deserializer>(deserializer: &mut D) -> Result<Self, Error> {
+        let length = deserializer.object()?;
+        let mut output = Self::new();
+        for _ in 0..length {
+            let key = deserializer.key()?;
+            let value = V::deserialize(deserializer).map_err(|error| json_path::at_key(error, &key))?;
+            output.insert(T::from(key), value);
         }
         return Ok(output);
     }
@@ -38,11 +127,29 @@ impl<T, V> JsonLoadable for HashMap<T, V> where T: From<String> + Eq + Hash, V:
 
 impl JsonLoadable for String {
     fn load(value: &JsonValue) -> Result<String, Error> {
+        //Short strings are parsed as JsonValue::Short instead of JsonValue::String
+        return match value.as_str() {
+            Some(found) => Ok(found.to_string()),
+            None => Err(Error::msg(format!("Expected string, found {}", value)))
+        }
+    }
+
+    fn schema() -> JsonValue {
+        return json::object! { "type": "string" };
+    }
+}
+
+impl JsonLoadable for bool {
+    fn load(value: &JsonValue) -> Result<Self, Error> {
         return match value {
-            JsonValue::String(found) => Ok(found.clone()),
-            _ => Err(Error::msg(format!("Expected string, found {:?}", value)))
+            JsonValue::Boolean(found) => Ok(*found),
+            _ => Err(Error::msg(format!("Expected boolean, found {}", value)))
         }
     }
+
+    fn schema() -> JsonValue {
+        return json::object! { "type": "boolean" };
+    }
 }
 
 impl JsonLoadable for Duration {
@@ -50,57 +157,319 @@ impl JsonLoadable for Duration {
         return match value {
             JsonValue::Number(value) => match value.as_fixed_point_u64(0) {
                 Some(number) => Ok(Duration::from_nanos(number)),
-                None => Err(Error::msg(format!("Expected positive number, got negative/NaN {:?}", value)))
+                None => Err(Error::msg(format!("Expected positive number, found {}", value)))
             }
-            _ => Err(Error::msg(format!("Expected number, found {:?}", value)))
+            _ => Err(Error::msg(format!("Expected number, found {}", value)))
         }
     }
+
+    //In nanoseconds
+    fn schema() -> JsonValue {
+        return json::object! { "type": "integer", "minimum": 0 };
+    }
 }
 
-impl<T, const LEN: usize> JsonLoadable for [T; LEN] where T: JsonLoadable + Default + Sized {
+impl<T, const LEN: usize> JsonLoadable for [T; LEN] where T: JsonLoadable {
     fn load(value: &JsonValue) -> Result<Self, Error> {
-        let mut array: [MaybeUninit<T>; LEN] = unsafe { MaybeUninit::uninit().assume_init() };
-
-        let mut i = 0;
-        match value {
-            JsonValue::Array(values) => {
-                if i != values.len() {
-                    return Err(Error::msg(format!("Bad length in array: {:?}", value)))
-                }
-                for value in values {
-                    array[i] = MaybeUninit::new(T::load(&value)?);
-                    i += 1;
+        return match value {
+            JsonValue::Array(values) if values.len() == LEN => {
+                let mut output = Vec::with_capacity(LEN);
+                for (i, value) in values.iter().enumerate() {
+                    output.push(T::load(value).map_err(|error| json_path::at_index(error, i))?);
                 }
+                //The length was checked, so this can't fail
+                Ok(output.try_into().unwrap_or_else(|_| unreachable!()))
             }
-            _ => return Err(Error::msg(format!("Non-array type in array! {:?}", value)))
+            JsonValue::Array(_) => Err(Error::msg(format!("Expected {} values, found {}", LEN, value))),
+            _ => Err(Error::msg(format!("Expected array, found {}", value)))
+        };
+    }
+
+    fn schema() -> JsonValue {
+        return json::object! { "type": "array", "items": T::schema(), "minItems": LEN, "maxItems": LEN };
+    }
+
+    fn deserialize<D: Deserializer>(deserializer: &mut D) -> Result<Self, Error> {
+        let values = deserialize_all(deserializer)?;
+        let length = values.len();
+        return values.try_into().map_err(|_| Error::msg(format!("Expected {} values, found {}", LEN, length)));
+    }
+}
+
+//Null is None
+impl<T> JsonLoadable for Option<T> where T: JsonLoadable {
+    fn load(value: &JsonValue) -> Result<Self, Error> {
+        if value.is_null() {
+            return Ok(None);
         }
-        //Do I know if this works? No. Will this break later? Yes. Does it make the red line go away? For now.
-        return Ok(unsafe {ptr::read(&array as *const [MaybeUninit<T>; LEN] as *const [T; LEN]) });
+        return Ok(Some(T::load(value)?));
+    }
+
+    fn schema() -> JsonValue {
+        return json::object! { "anyOf": [T::schema(), { "type": "null" }] };
+    }
+
+    fn deserialize<D: Deserializer>(deserializer: &mut D) -> Result<Self, Error> {
+        if deserializer.null()? {
+            return Ok(None);
+        }
+        return Ok(Some(T::deserialize(deserializer)?));
     }
 }
 
-//This is required or else TryFrom<T> could conflict with other impls.
-pub trait LoadableNumber: TryFrom<Number> + Into<Number> + Copy {}
+impl<T> JsonLoadable for Box<T> where T: JsonLoadable {
+    fn load(value: &JsonValue) -> Result<Self, Error> {
+        return Ok(Box::new(T::load(value)?));
+    }
+
+    fn schema() -> JsonValue {
+        return T::schema();
+    }
 
-impl<T> JsonLoadable for T where T: LoadableNumber {
+    fn deserialize<D: Deserializer>(deserializer: &mut D) -> Result<Self, Error> {
+        return Ok(Box::new(T::deserialize(deserializer)?));
+    }
+}
+
+impl<T> JsonLoadable for Arc<T> where T: JsonLoadable {
     fn load(value: &JsonValue) -> Result<Self, Error> {
-        return match value {
-            JsonValue::Number(number) => match TryFrom::try_from(*number) {
-                Ok(result) => Ok(result),
-                Err(_) => Err(Error::msg(format!("Expected number, found {:?}", value)))
-            },
-            _ => Err(Error::msg(format!("Expected number, found {:?}", value)))
+        return Ok(Arc::new(T::load(value)?));
+    }
+
+    fn schema() -> JsonValue {
+        return T::schema();
+    }
+
+    fn deserialize<D: Deserializer>(deserializer: &mut D) -> Result<Self, Error> {
+        return Ok(Arc::new(T::deserialize(deserializer)?));
+    }
+}
+
+//Sets are arrays, duplicates are merged
+impl<T> JsonLoadable for HashSet<T> where T: JsonLoadable + Eq + Hash {
+    fn load(value: &JsonValue) -> Result<Self, Error> {
+        return Ok(Vec::<T>::load(value)?.into_iter().collect());
+    }
+
+    fn schema() -> JsonValue {
+        return json::object! { "type": "array", "items": T::schema(), "uniqueItems": true };
+    }
+
+    fn deserialize<D: Deserializer>(deserializer: &mut D) -> Result<Self, Error> {
+        return Ok(deserialize_all::<T, D>(deserializer)?.into_iter().collect());
+    }
+}
+
+impl<T> JsonLoadable for BTreeSet<T> where T: JsonLoadable + Ord {
+    fn load(value: &JsonValue) -> Result<Self, Error> {
+        return Ok(Vec::<T>::load(value)?.into_iter().collect());
+    }
+
+    fn schema() -> JsonValue {
+        return json::object! { "type": "array", "items": T::schema(), "uniqueItems": true };
+    }
+
+    fn deserialize<D: Deserializer>(deserializer: &mut D) -> Result<Self, Error> {
+        return Ok(deserialize_all::<T, D>(deserializer)?.into_iter().collect());
+    }
+}
+
+impl<T, V> JsonLoadable for BTreeMap<T, V> where T: From<String> + Ord, V: JsonLoadable {
+    fn load(value: &JsonValue) -> Result<BTreeMap<T, V>, Error> {
+        if !value.is_object() {
+            return Err(Error::msg(format!("Expected object, found {}", value)));
+        }
+        let mut output = BTreeMap::new();
+        for (key, value) in value.entries() {
+            output.insert(T::from(key.to_string()), V::load(value).map_err(|error| json_path::at_key(error, key))?);
+        }
+        return Ok(output);
+    }
+
+    fn schema() -> JsonValue {
+        return json::object! { "type": "object", "additionalProperties": V::schema() };
+    }
+
+    fn deserialize<D: Deserializer>(deserializer: &mut D) -> Result<Self, Error> {
+        let length = deserializer.object()?;
+        let mut output = Self::new();
+        for _ in 0..length {
+            let key = deserializer.key()?;
+            let value = V::deserialize(deserializer).map_err(|error| json_path::at_key(error, &key))?;
+            output.insert(T::from(key), value);
+        }
+        return Ok(output);
+    }
+}
+
+//Tuples are arrays with a value for each element
+macro_rules! load_tuple {
+    ($length:literal, $($name:ident $index:tt),+) => {
+        impl<$($name),+> JsonLoadable for ($($name,)+) where $($name: JsonLoadable),+ {
+            fn load(value: &JsonValue) -> Result<Self, Error> {
+                return match value {
+                    JsonValue::Array(values) if values.len() == $length =>
+                        Ok(($($name::load(&values[$index]).map_err(|error| json_path::at_index(error, $index))?,)+)),
+                    _ => Err(Error::msg(format!("Expected array of {} values, found {}", $length, value)))
+                };
+            }
+
+            fn schema() -> JsonValue {
+                return json::object! { "type": "array", "items": [$($name::schema()),+], "minItems": $length, "maxItems": $length };
+            }
+
+            fn deserialize<Z: Deserializer>(deserializer: &mut Z) -> Result<Self, Error> {
+                let length = deserializer.array()?;
+                if length != $length {
+                    return Err(Error::msg(format!("Expected array of {} values, found {}", $length, length)));
+                }
+                return Ok(($($name::deserialize(deserializer).map_err(|error| json_path::at_index(error, $index))?,)+));
+            }
+        }
+    };
+}
+
+load_tuple!(1, A 0);
+load_tuple!(2, A 0, B 1);
+load_tuple!(3, A 0, B 1, C 2);
+load_tuple!(4, A 0, B 1, C 2, D 3);
+load_tuple!(5, A 0, B 1, C 2, D 3, E 4);
+load_tuple!(6, A 0, B 1, C 2, D 3, E 4, F 5);
+
+//cgmath vectors and points are arrays of their components, like [x, y, z]
+macro_rules! load_math {
+    ($type:ident, $length:literal, $($field:ident),+) => {
+        impl<S> JsonLoadable for $type<S> where S: JsonLoadable {
+            fn load(value: &JsonValue) -> Result<Self, Error> {
+                let [$($field),+] = <[S; $length]>::load(value)?;
+                return Ok($type { $($field),+ });
+            }
+
+            fn schema() -> JsonValue {
+                return <[S; $length]>::schema();
+            }
         }
+    };
+}
+
+load_math!(Vector2, 2, x, y);
+load_math!(Vector3, 3, x, y, z);
+load_math!(Vector4, 4, x, y, z, w);
+load_math!(Point2, 2, x, y);
+load_math!(Point3, 3, x, y, z);
+
+//Numbers JsonLoadable and JsonSaveable are implemented for, see number_impls.
+//They aren't a blanket impl over this trait, because that would conflict with Box<T>.
+pub trait LoadableNumber: TryFrom<Number> + Into<Number> + Copy {
+    //Integers get their range in the schema, floats don't.
+    //The range is in f64 because Number::from overflows negating the smallest signed integers.
+    const INTEGER: bool = true;
+    const MIN: f64;
+    const MAX: f64;
+}
+
+pub fn load_number<T: LoadableNumber>(value: &JsonValue) -> Result<T, Error> {
+    return match value {
+        //It's a number, just not a whole one in the type's range
+        JsonValue::Number(number) => match TryFrom::try_from(*number) {
+            Ok(result) => Ok(result),
+            Err(_) => Err(Error::msg(format!("Expected integer in {}..={}, found {}", T::MIN, T::MAX, value)))
+        },
+        _ => Err(Error::msg(format!("Expected number, found {}", value)))
     }
 }
 
-impl LoadableNumber for u8 {}
-impl LoadableNumber for u16 {}
-impl LoadableNumber for u32 {}
-impl LoadableNumber for u64 {}
-impl LoadableNumber for i8 {}
-impl LoadableNumber for i16 {}
-impl LoadableNumber for i32 {}
-impl LoadableNumber for i64 {}
-impl LoadableNumber for f32 {}
-impl LoadableNumber for f64 {}
+pub fn number_schema<T: LoadableNumber>() -> JsonValue {
+    if !T::INTEGER {
+        return json::object! { "type": "number" };
+    }
+    return json::object! { "type": "integer", "minimum": T::MIN, "maximum": T::MAX };
+}
+
+macro_rules! number_impls {
+    ($($type:ty),+) => {
+        $(impl JsonLoadable for $type {
+            fn load(value: &JsonValue) -> Result<Self, Error> {
+                return load_number(value);
+            }
+
+            fn schema() -> JsonValue {
+                return number_schema::<Self>();
+            }
+        })+
+    };
+}
+
+//json's float conversions can be off by a bit, so floats are parsed from the number's exact decimal text
+macro_rules! float_impls {
+    ($($type:ty),+) => {
+        $(impl JsonLoadable for $type {
+            fn load(value: &JsonValue) -> Result<Self, Error> {
+                return match value {
+                    JsonValue::Number(number) => number.to_string().parse()
+                        .map_err(|_| Error::msg(format!("Expected number, found {}", value))),
+                    _ => Err(Error::msg(format!("Expected number, found {}", value)))
+                };
+            }
+
+            fn schema() -> JsonValue {
+                return number_schema::<Self>();
+            }
+        })+
+    };
+}
+
+number_impls!(u8, u16, u32, u64, i8, i16, i32, i64);
+float_impls!(f32, f64);
+
+impl LoadableNumber for u8 {
+    const MIN: f64 = u8::MIN as f64;
+    const MAX: f64 = u8::MAX as f64;
+}
+
+impl LoadableNumber for u16 {
+    const MIN: f64 = u16::MIN as f64;
+    const MAX: f64 = u16::MAX as f64;
+}
+
+impl LoadableNumber for u32 {
+    const MIN: f64 = u32::MIN as f64;
+    const MAX: f64 = u32::MAX as f64;
+}
+
+impl LoadableNumber for u64 {
+    const MIN: f64 = u64::MIN as f64;
+    const MAX: f64 = u64::MAX as f64;
+}
+
+impl LoadableNumber for i8 {
+    const MIN: f64 = i8::MIN as f64;
+    const MAX: f64 = i8::MAX as f64;
+}
+
+impl LoadableNumber for i16 {
+    const MIN: f64 = i16::MIN as f64;
+    const MAX: f64 = i16::MAX as f64;
+}
+
+impl LoadableNumber for i32 {
+    const MIN: f64 = i32::MIN as f64;
+    const MAX: f64 = i32::MAX as f64;
+}
+
+impl LoadableNumber for i64 {
+    const MIN: f64 = i64::MIN as f64;
+    const MAX: f64 = i64::MAX as f64;
+}
+
+impl LoadableNumber for f32 {
+    const INTEGER: bool = false;
+    const MIN: f64 = f32::MIN as f64;
+    const MAX: f64 = f32::MAX as f64;
+}
+
+impl LoadableNumber for f64 {
+    const INTEGER: bool = false;
+    const MIN: f64 = f64::MIN;
+    const MAX: f64 = f64::MAX;
+}
@@ -0,0 +1,40 @@
+use std::fs;
+use std::path::Path;
+use anyhow::Error;
+use json::JsonValue;
+use crate::format::json5::{self, ParseError};
+use crate::json_path::{self, JsonPath};
+
+//Parses a resource file: types, models, translations, mod manifests and so on.
+//They're all JSON5, so they can have comments, trailing commas, unquoted keys and single quoted strings.
+//Errors are ParseErrors, with the line and column parsing failed at.
+pub fn parse(source: &str) -> Result<JsonValue, Error> {
+    return json5::parse(source);
+}
+
+//Reads and parses the resource file at path, returning its text too for locating loading errors.
+//Errors start with the file, like types/cubes.json:3:5 → Expected ',' or '}', found 'b'
+pub fn read(path: &Path) -> Result<(String, JsonValue), Error> {
+    let file = path.display().to_string();
+    let bytes = fs::read(path)
+        .map_err(|error| Error::msg(json_path::describe(&file, None, &JsonPath::new(), &error.to_string())))?;
+    return parse_file(&file, bytes);
+}
+
+//Like read, for a file that's already loaded, like one in an archive
+pub fn parse_file(file: &str, bytes: Vec<u8>) -> Result<(String, JsonValue), Error> {
+    let source = String::from_utf8(bytes)
+        .map_err(|error| Error::msg(json_path::describe(file, None, &JsonPath::new(), &error.to_string())))?;
+    return match parse(&source) {
+        Ok(value) => Ok((source, value)),
+        Err(error) => Err(Error::msg(describe_error(file, &error)))
+    };
+}
+
+//Describes an error from parse, with the file and where in it parsing failed if it's known
+pub fn describe_error(file: &str, error: &Error) -> String {
+    return match error.downcast_ref::<ParseError>() {
+        Some(error) => json_path::describe(file, Some((error.line, error.column)), &JsonPath::new(), &error.message),
+        None => json_path::describe(file, None, &JsonPath::new(), &format!("{:#}", error))
+    };
+}
@@ -1,13 +1,42 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::hash::Hash;
+use std::sync::Arc;
 use std::time::Duration;
+use cgmath::{Point2, Point3, Vector2, Vector3, Vector4};
 use json::JsonValue;
 use json::number::Number;
 use json::object::Object;
-use crate::loading::LoadableNumber;
+use crate::format::{DataFormat, Serializer};
 
 pub trait JsonSaveable {
     fn save(&self) -> JsonValue;
+
+    //Writes to a format's Serializer without building a JsonValue. The derives implement it,
+    //anything else is written as what it saves as.
+    fn serialize<S: Serializer>(&self, serializer: &mut S) where Self: Sized {
+        serializer.value(&self.save());
+    }
+
+    //Saves in another format, like format::Binary
+    fn save_to<F: DataFormat>(&self) -> Vec<u8> where Self: Sized {
+        return F::write(self);
+    }
+}
+
+//Types that always save as a JSON object, so a tag key can be added to them.
+//The derives implement it for structs with named fields and internally tagged enums, which require it of
+//their single field variants' fields.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` doesn't always save as a JSON object",
+    label = "internally tagged enums can only have single field variants holding objects, like structs with named fields"
+)]
+pub trait SavesAsObject: JsonSaveable {}
+
+fn serialize_all<'a, T: JsonSaveable + 'a, S: Serializer>(serializer: &mut S, values: impl ExactSizeIterator<Item=&'a T>) {
+    serializer.array(values.len());
+    for value in values {
+        value.serialize(serializer);
+    }
 }
 
 impl<T> JsonSaveable for Vec<T> where T: JsonSaveable {
@@ -18,9 +47,13 @@ impl<T> JsonSaveable for Vec<T> where T: JsonSaveable {
         }
         return JsonValue::Array(output);
     }
+
+    fn serialize<S: Serializer>(&self, serializer: &mut S) {
+        serialize_all(serializer, self.iter());
+    }
 }
 
-impl<T, V> JsonSaveable for HashMap<T, V> where T: ToString + Clone + Eq + Hash, V: Default + JsonSaveable {
+impl<T, V> JsonSaveable for HashMap<T, V> where T: ToString + Clone + Eq + Hash, V: JsonSaveable {
     fn save(&self) -> JsonValue {
         let mut output = Object::new();
         for (key, value) in self {
@@ -28,6 +61,14 @@ impl<T, V> JsonSaveable for HashMap<T, V> where T: ToString + Clone + Eq + Hash,
         }
         return JsonValue::Object(output);
     }
+
+    fn serialize<S: Serializer>(&self, serializer: &mut S) {
+        serializer.object(self.len());
+        for (key, value) in self {
+            serializer.key(&key.to_string());
+            value.serialize(serializer);
+        }
+    }
 }
 
 impl JsonSaveable for String {
@@ -36,6 +77,12 @@ impl JsonSaveable for String {
     }
 }
 
+impl JsonSaveable for bool {
+    fn save(&self) -> JsonValue {
+        return JsonValue::Boolean(*self);
+    }
+}
+
 impl JsonSaveable for Duration {
     fn save(&self) -> JsonValue {
         return JsonValue::Number(Number::from(self.as_nanos() as u64));
@@ -50,10 +97,153 @@ impl<T, const LEN: usize> JsonSaveable for [T; LEN] where T: JsonSaveable {
         }
         return JsonValue::Array(output);
     }
+
+    fn serialize<S: Serializer>(&self, serializer: &mut S) {
+        serialize_all(serializer, self.iter());
+    }
 }
 
-impl<T> JsonSaveable for T where T: LoadableNumber {
+//None is null
+impl<T> JsonSaveable for Option<T> where T: JsonSaveable {
     fn save(&self) -> JsonValue {
-        return JsonValue::Number(Into::<Number>::into(*self));
+        return match self {
+            Some(value) => value.save(),
+            None => JsonValue::Null
+        };
     }
-}
\ No newline at end of file
+
+    fn serialize<S: Serializer>(&self, serializer: &mut S) {
+        match self {
+            Some(value) => value.serialize(serializer),
+            None => serializer.null()
+        }
+    }
+}
+
+impl<T> JsonSaveable for Box<T> where T: JsonSaveable {
+    fn save(&self) -> JsonValue {
+        return T::save(self);
+    }
+
+    fn serialize<S: Serializer>(&self, serializer: &mut S) {
+        T::serialize(self, serializer);
+    }
+}
+
+impl<T> JsonSaveable for Arc<T> where T: JsonSaveable {
+    fn save(&self) -> JsonValue {
+        return T::save(self);
+    }
+
+    fn serialize<S: Serializer>(&self, serializer: &mut S) {
+        T::serialize(self, serializer);
+    }
+}
+
+impl<T> JsonSaveable for HashSet<T> where T: JsonSaveable {
+    fn save(&self) -> JsonValue {
+        return JsonValue::Array(self.iter().map(JsonSaveable::save).collect());
+    }
+
+    fn serialize<S: Serializer>(&self, serializer: &mut S) {
+        serialize_all(serializer, self.iter());
+    }
+}
+
+impl<T> JsonSaveable for BTreeSet<T> where T: JsonSaveable {
+    fn save(&self) -> JsonValue {
+        return JsonValue::Array(self.iter().map(JsonSaveable::save).collect());
+    }
+
+    fn serialize<S: Serializer>(&self, serializer: &mut S) {
+        serialize_all(serializer, self.iter());
+    }
+}
+
+impl<T, V> JsonSaveable for BTreeMap<T, V> where T: ToString, V: JsonSaveable {
+    fn save(&self) -> JsonValue {
+        let mut output = Object::new();
+        for (key, value) in self {
+            output.insert(key.to_string().as_str(), value.save());
+        }
+        return JsonValue::Object(output);
+    }
+
+    fn serialize<S: Serializer>(&self, serializer: &mut S) {
+        serializer.object(self.len());
+        for (key, value) in self {
+            serializer.key(&key.to_string());
+            value.serialize(serializer);
+        }
+    }
+}
+
+impl<T, V> SavesAsObject for HashMap<T, V> where T: ToString + Clone + Eq + Hash, V: JsonSaveable {}
+
+impl<T, V> SavesAsObject for BTreeMap<T, V> where T: ToString, V: JsonSaveable {}
+
+impl<T> SavesAsObject for Box<T> where T: SavesAsObject {}
+
+impl<T> SavesAsObject for Arc<T> where T: SavesAsObject {}
+
+macro_rules! save_tuple {
+    ($($name:ident $index:tt),+) => {
+        impl<$($name),+> JsonSaveable for ($($name,)+) where $($name: JsonSaveable),+ {
+            fn save(&self) -> JsonValue {
+                return JsonValue::Array(vec!($(self.$index.save()),+));
+            }
+
+            fn serialize<S: Serializer>(&self, serializer: &mut S) {
+                serializer.array([$($index),+].len());
+                $(self.$index.serialize(serializer);)+
+            }
+        }
+    };
+}
+
+save_tuple!(A 0);
+save_tuple!(A 0, B 1);
+save_tuple!(A 0, B 1, C 2);
+save_tuple!(A 0, B 1, C 2, D 3);
+save_tuple!(A 0, B 1, C 2, D 3, E 4);
+save_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+
+macro_rules! save_math {
+    ($type:ident, $($field:ident),+) => {
+        impl<S> JsonSaveable for $type<S> where S: JsonSaveable {
+            fn save(&self) -> JsonValue {
+                return JsonValue::Array(vec!($(self.$field.save()),+));
+            }
+        }
+    };
+}
+
+save_math!(Vector2, x, y);
+save_math!(Vector3, x, y, z);
+save_math!(Vector4, x, y, z, w);
+save_math!(Point2, x, y);
+save_math!(Point3, x, y, z);
+
+macro_rules! number_impls {
+    ($($type:ty),+) => {
+        $(impl JsonSaveable for $type {
+            fn save(&self) -> JsonValue {
+                return JsonValue::Number(Into::<Number>::into(*self));
+            }
+        })+
+    };
+}
+
+//Number::from overflows negating the smallest signed integers, so they're built from their parts
+macro_rules! signed_impls {
+    ($($type:ty),+) => {
+        $(impl JsonSaveable for $type {
+            fn save(&self) -> JsonValue {
+                return JsonValue::Number(Number::from_parts(*self >= 0, self.unsigned_abs() as u64, 0));
+            }
+        })+
+    };
+}
+
+number_impls!(u8, u16, u32, u64, f32, f64);
+signed_impls!(i8, i16, i32, i64);
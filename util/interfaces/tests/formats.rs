@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use json::JsonValue;
+use json::number::Number;
+use proptest::prelude::*;
+use interfaces::format::{Binary, DataFormat, Json, Json5};
+use interfaces::format::json5::{self, ParseError};
+use interfaces::loading::JsonLoadable;
+use interfaces::resource;
+use interfaces::saving::JsonSaveable;
+
+fn number() -> impl Strategy<Value = Number> {
+    return prop_oneof![
+        any::<i64>().prop_map(|value| JsonValue::from(value).as_number().unwrap()),
+        any::<f64>().prop_filter("JSON has no NaN or infinity", |value| value.is_finite()).prop_map(Number::from),
+        (any::<bool>(), any::<u64>(), -300i16..300).prop_map(|(positive, mantissa, exponent)| Number::from_parts(positive, mantissa, exponent)),
+    ];
+}
+
+fn value() -> impl Strategy<Value = JsonValue> {
+    let leaf = prop_oneof![
+        Just(JsonValue::Null),
+        any::<bool>().prop_map(JsonValue::Boolean),
+        number().prop_map(JsonValue::Number),
+        any::<String>().prop_map(JsonValue::from),
+    ];
+    return leaf.prop_recursive(4, 64, 8, |inner| prop_oneof![
+        prop::collection::vec(inner.clone(), 0..8).prop_map(JsonValue::Array),
+        prop::collection::vec((any::<String>(), inner), 0..8).prop_map(|entries| {
+            let mut object = json::object::Object::new();
+            for (key, value) in entries {
+                object.insert(&key, value);
+            }
+            JsonValue::Object(object)
+        }),
+    ]);
+}
+
+fn parse_error(source: &str) -> String {
+    let error = json5::parse(source).unwrap_err();
+    assert!(error.downcast_ref::<ParseError>().is_some());
+    return error.to_string();
+}
+
+proptest! {
+    #[test]
+    fn formats_round_trip(value in value()) {
+        prop_assert_eq!(Binary::decode(&Binary::encode(&value)).unwrap(), value.clone());
+        prop_assert_eq!(Json5::decode(&Json5::encode(&value)).unwrap(), value.clone());
+        prop_assert_eq!(Json::decode(&Json::encode(&value)).unwrap(), value.clone());
+        //Anything that's JSON is JSON5 too
+        prop_assert_eq!(json5::parse(&json::stringify_pretty(value.clone(), 2)).unwrap(), value);
+    }
+
+    #[test]
+    fn binary_never_panics(data: Vec<u8>) {
+        let _ = Binary::decode(&data);
+    }
+
+    #[test]
+    fn json5_never_panics(source: String) {
+        let _ = json5::parse(&source);
+    }
+}
+
+#[test]
+fn loadable_types_use_any_format() {
+    let value: HashMap<String, Vec<(u8, Option<f32>)>> = HashMap::from([
+        (String::from("a"), vec!((1, Some(0.5)), (2, None))),
+        (String::from("b"), vec!()),
+    ]);
+    let binary = value.save_to::<Binary>();
+    assert!(binary.len() < value.save_to::<Json>().len());
+    assert_eq!(HashMap::load_from::<Binary>(&binary).unwrap(), value);
+    assert_eq!(Vec::<u8>::load_from::<Binary>(&binary).unwrap_err().to_string(), "Expected array, found object at byte 0");
+    assert_eq!(Option::<Vec<u8>>::load_from::<Binary>(&[0]).unwrap(), None);
+    assert_eq!(HashMap::load_from::<Json5>(&value.save_to::<Json5>()).unwrap(), value);
+    assert_eq!(HashMap::load_from::<Json>(&value.save_to::<Json>()).unwrap(), value);
+}
+
+#[test]
+fn json5_syntax() {
+    let source = r#"
+        // Comments are allowed
+        {
+            unquoted: 'single quoted',
+            "trailing": [1, 2, 3,],
+            /* block
+               comments */
+            hex: 0xFF,
+            numbers: [+1, .5, 5., -0x10, 1e3],
+            escapes: 'it\'s \x41 é \
+continued',
+            $and_: NaN,
+        }
+    "#;
+    let value = json5::parse(source).unwrap();
+    assert_eq!(value["unquoted"], "single quoted");
+    assert_eq!(value["trailing"], json::array![1, 2, 3]);
+    assert_eq!(value["hex"], 255);
+    assert_eq!(value["numbers"], json::array![1, 0.5, 5, -16, 1000]);
+    assert_eq!(value["escapes"], "it's A é continued");
+    assert!(value["$and_"].as_number().unwrap().is_nan());
+
+    let written = String::from_utf8(Json5::encode(&json::object! { "key": [1, "two"], "not an identifier": {} })).unwrap();
+    assert_eq!(written, "{\n    key: [\n        1,\n        \"two\",\n    ],\n    \"not an identifier\": {},\n}\n");
+}
+
+#[test]
+fn json5_errors_have_lines_and_columns() {
+    assert_eq!(parse_error("{\n  a: 1\n  b: 2\n}"), "3:3 → Expected ',' or '}', found 'b'");
+    assert_eq!(parse_error("[1, 2"), "1:6 → Expected ',' or ']', found end of file");
+    assert_eq!(parse_error("{a: yes}"), "1:5 → Unexpected yes, strings need quotes");
+    assert_eq!(parse_error("/* open"), "1:1 → Unterminated comment");
+    assert_eq!(parse_error("[007]"), "1:2 → Numbers can't have leading zeros");
+    assert_eq!(parse_error("'line\nbreak'"), "1:6 → Unescaped line break in string");
+    assert_eq!(parse_error("{} {}"), "1:4 → Unexpected '{' after the value");
+}
+
+#[test]
+fn binary_errors() {
+    let data = Binary::encode(&json::object! { "key": "value" });
+    assert_eq!(Binary::decode(&data[..data.len() - 1]).unwrap_err().to_string(), "Length 5 at byte 7 is longer than the data");
+    assert_eq!(Binary::decode(&[11]).unwrap_err().to_string(), "Unknown tag 11 at byte 0");
+    assert_eq!(Binary::decode(&[9, 0]).unwrap_err().to_string(), "Found a struct at byte 0, which can only be read as its type");
+    assert_eq!(Binary::decode(&[0, 0]).unwrap_err().to_string(), "Unexpected data after the value at byte 1");
+    //Arrays holding arrays, a million deep
+    let deep: Vec<u8> = [7, 1].repeat(1_000_000);
+    assert_eq!(Binary::decode(&deep).unwrap_err().to_string(), "Values nested deeper than 256 at byte 514");
+}
+
+#[test]
+fn resource_files_are_json5() {
+    let path = std::env::temp_dir().join(format!("interfaces-resource-{}.json", std::process::id()));
+    std::fs::write(&path, "{\n  // Comment\n  key: 'value',\n}").unwrap();
+    let (source, value) = resource::read(&path).unwrap();
+    assert_eq!(value, json::object! { "key": "value" });
+    assert!(source.starts_with("{\n  // Comment"));
+
+    std::fs::write(&path, "{\n  key: 'value'\n  other: 1\n}").unwrap();
+    let error = resource::read(&path).unwrap_err();
+    assert_eq!(error.to_string(), format!("{}:3:3 → Expected ',' or '}}', found 'o'", path.display()));
+    std::fs::remove_file(&path).unwrap();
+    let error = resource::read(&path).unwrap_err();
+    assert!(error.to_string().starts_with(&format!("{} → ", path.display())));
+}
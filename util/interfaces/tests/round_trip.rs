@@ -0,0 +1,105 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+use cgmath::{Point2, Point3, Vector2, Vector3, Vector4};
+use proptest::prelude::*;
+use interfaces::format::Binary;
+use interfaces::json_path::{self, JsonPath};
+use interfaces::loading::JsonLoadable;
+use interfaces::saving::JsonSaveable;
+
+//Saves value, writes and parses it as text, then loads it again. Then does the same in binary.
+fn round_trip<T: JsonLoadable + JsonSaveable + PartialEq + Debug>(value: &T) -> Result<(), TestCaseError> {
+    let text = json::stringify(value.save());
+    let loaded = T::load(&json::parse(&text).unwrap()).map_err(|error| TestCaseError::fail(format!("{}: {:#}", text, error)))?;
+    prop_assert_eq!(&loaded, value, "{}", text);
+
+    let binary = value.save_to::<Binary>();
+    let loaded = T::load_from::<Binary>(&binary).map_err(|error| TestCaseError::fail(format!("{:?}: {:#}", binary, error)))?;
+    prop_assert_eq!(&loaded, value, "{:?}", binary);
+    return Ok(());
+}
+
+fn float() -> impl Strategy<Value = f32> {
+    return any::<f32>().prop_filter("JSON has no NaN or infinity", |value| value.is_finite());
+}
+
+proptest! {
+    #[test]
+    fn numbers(a: u8, b: u16, c: u32, d: u64, e: i8, f: i16, g: i32, h: i64, i in float()) {
+        round_trip(&a)?;
+        round_trip(&b)?;
+        round_trip(&c)?;
+        round_trip(&d)?;
+        round_trip(&e)?;
+        round_trip(&f)?;
+        round_trip(&g)?;
+        round_trip(&h)?;
+        round_trip(&i)?;
+        round_trip(&(i as f64))?;
+        round_trip(&(i as f64 / 3.0))?;
+    }
+
+    #[test]
+    fn strings_and_bools(value: String, flag: bool, nanos: u64) {
+        round_trip(&value)?;
+        round_trip(&flag)?;
+        round_trip(&Duration::from_nanos(nanos))?;
+    }
+
+    #[test]
+    fn options_and_pointers(value: Option<i32>, boxed: Box<String>, shared: Option<Arc<Vec<u8>>>) {
+        round_trip(&value)?;
+        round_trip(&boxed)?;
+        round_trip(&shared)?;
+    }
+
+    #[test]
+    fn arrays_and_tuples(array: [i16; 4], nested: [[u8; 2]; 3], pair: (String, bool), triple: (u8, Option<i64>, Vec<u16>)) {
+        round_trip(&array)?;
+        round_trip(&nested)?;
+        round_trip(&pair)?;
+        round_trip(&triple)?;
+        round_trip(&(array[0],))?;
+        round_trip(&(1u8, 2u16, 3u32, 4u64, 5i8, -6i16))?;
+    }
+
+    #[test]
+    fn collections(list: Vec<u32>, set: HashSet<String>, ordered: BTreeSet<i8>,
+                   map: HashMap<String, Vec<bool>>, tree: BTreeMap<String, u8>) {
+        round_trip(&list)?;
+        round_trip(&set)?;
+        round_trip(&ordered)?;
+        round_trip(&map)?;
+        round_trip(&tree)?;
+    }
+
+    #[test]
+    fn math(x in float(), y in float(), z in float(), w in float()) {
+        round_trip(&Vector2::new(x, y))?;
+        round_trip(&Vector3::new(x, y, z))?;
+        round_trip(&Vector4::new(x, y, z, w))?;
+        round_trip(&Point2::new(x, y))?;
+        round_trip(&Point3::new(x, y, z))?;
+        round_trip(&[Vector3::new(x, y, z), Vector3::new(z, y, x)])?;
+    }
+}
+
+#[test]
+fn arrays_need_the_right_length() {
+    assert_eq!(<[f32; 3]>::load(&json::array![1, 2, 3]).unwrap(), [1.0, 2.0, 3.0]);
+    let error = <[f32; 3]>::load(&json::array![1, 2]).unwrap_err();
+    assert_eq!(error.to_string(), "Expected 3 values, found [1,2]");
+    let error = <(u8, String)>::load(&json::array![1, 2]).unwrap_err();
+    assert_eq!(json_path::error_path(&error), JsonPath::new().index(1));
+    let error = Vector3::<f32>::load(&json::array![1, 2, "3"]).unwrap_err();
+    assert_eq!(error.to_string(), "[2] → Expected number, found 3");
+}
+
+#[test]
+fn null_is_none() {
+    assert_eq!(Option::<u8>::load(&json::JsonValue::Null).unwrap(), None);
+    assert_eq!(Option::<u8>::None.save(), json::JsonValue::Null);
+    assert!(Option::<u8>::load(&json::JsonValue::from("a")).is_err());
+}
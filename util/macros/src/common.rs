@@ -0,0 +1,194 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{Attribute, DeriveInput, Field, Ident, Lit, LitStr, Meta, PathSegment, Token};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::token::Colon2;
+
+pub(crate) fn combine(segments: &Punctuated<PathSegment, Colon2>) -> String {
+    let mut output = String::new();
+    for segment in segments {
+        output += (segment.ident.to_string() + "::").as_str();
+    }
+
+    return String::from(&output[0..output.len() - 2]);
+}
+
+//"impl<...> trait_path for Type<...> where ...", with every type parameter bound by trait_path
+pub(crate) fn impl_header(ast: &DeriveInput, trait_path: &str, extra_bounds: &[&str]) -> String {
+    return impl_header_bounded(ast, trait_path, trait_path, extra_bounds);
+}
+
+//Like impl_header, with every type parameter bound by parameter_bound instead
+pub(crate) fn impl_header_bounded(ast: &DeriveInput, trait_path: &str, parameter_bound: &str, extra_bounds: &[&str]) -> String {
+    let (impl_generics, type_generics, where_clause) = ast.generics.split_for_impl();
+    let mut predicates = Vec::new();
+    if let Some(where_clause) = where_clause {
+        for predicate in &where_clause.predicates {
+            predicates.push(predicate.to_token_stream().to_string());
+        }
+    }
+    for parameter in ast.generics.type_params() {
+        predicates.push(format!("{}: {}", parameter.ident, parameter_bound));
+    }
+    for bound in extra_bounds {
+        predicates.push(bound.to_string());
+    }
+
+    let mut output = format!("impl {} {} for {} {}", impl_generics.to_token_stream(), trait_path,
+                             ast.ident, type_generics.to_token_stream());
+    if !predicates.is_empty() {
+        output += format!(" where {}", predicates.join(", ")).as_str();
+    }
+    return output;
+}
+
+//Keys as a list of string literals, for an array expression
+pub(crate) fn quoted(keys: &[String]) -> String {
+    return keys.iter().map(|key| format!("\"{}\"", key)).collect::<Vec<_>>().join(", ");
+}
+
+//The key of #[json_tag = "key"], for internally tagged enums
+pub(crate) fn json_tag(attributes: &[Attribute]) -> Option<String> {
+    for attribute in attributes {
+        if combine(&attribute.path.segments) != "json_tag" {
+            continue;
+        }
+        return match attribute.parse_meta() {
+            Ok(Meta::NameValue(meta)) => match meta.lit {
+                Lit::Str(tag) => Some(tag.value()),
+                _ => panic!("json_tag must be a string, like #[json_tag = \"kind\"]")
+            },
+            _ => panic!("json_tag must be a string, like #[json_tag = \"kind\"]")
+        };
+    }
+    return None;
+}
+
+//One option inside #[json(...)]: a name, optionally followed by = and a value
+struct JsonOption {
+    name: Ident,
+    value: TokenStream,
+}
+
+impl Parse for JsonOption {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        let mut value = TokenStream::new();
+        if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            //Everything up to the next comma, commas inside brackets belong to their group
+            while !input.is_empty() && !input.peek(Token![,]) {
+                value.extend([input.parse::<TokenTree>()?]);
+            }
+        }
+        return Ok(Self {
+            name,
+            value
+        });
+    }
+}
+
+//Every option in the #[json(...)] attributes
+fn json_options(attributes: &[Attribute]) -> Vec<JsonOption> {
+    let mut output = Vec::new();
+    for attribute in attributes {
+        if combine(&attribute.path.segments) != "json" {
+            continue;
+        }
+        match attribute.parse_args_with(Punctuated::<JsonOption, Token![,]>::parse_terminated) {
+            Ok(options) => output.extend(options),
+            Err(error) => panic!("Invalid json attribute: {}", error)
+        }
+    }
+    return output;
+}
+
+fn string_option(option: &JsonOption) -> String {
+    return match syn::parse2::<LitStr>(option.value.clone()) {
+        Ok(value) => value.value(),
+        Err(_) => panic!("json({}) needs a string, like #[json({} = \"key\")]", option.name, option.name)
+    };
+}
+
+//Options of a type with #[json(...)]
+pub(crate) struct TypeOptions {
+    pub deny_unknown_keys: bool,
+}
+
+impl TypeOptions {
+    pub fn new(attributes: &[Attribute]) -> Self {
+        let mut output = Self {
+            deny_unknown_keys: false
+        };
+        for option in json_options(attributes) {
+            match option.name.to_string().as_str() {
+                "deny_unknown_keys" => output.deny_unknown_keys = true,
+                other => panic!("Unknown json option {} on a type", other)
+            }
+        }
+        return output;
+    }
+}
+
+//Field attributes used by JsonLoadable and JsonSaveable
+pub(crate) struct FieldOptions {
+    pub ignore: bool,
+    pub required: bool,
+    //Key in the JSON object, the field's name unless renamed
+    pub key: String,
+    //Other keys it can be loaded from
+    pub aliases: Vec<String>,
+    //Expression used when the field is missing
+    pub default: Option<String>,
+    pub skip_if_default: bool,
+    //Loads and saves the field's keys as if they were in this object
+    pub flatten: bool,
+}
+
+impl FieldOptions {
+    pub fn new(field: &Field) -> Self {
+        let mut output = Self {
+            ignore: false,
+            required: false,
+            key: field.ident.as_ref().map(|ident| ident.to_string()).unwrap_or_default(),
+            aliases: Vec::new(),
+            default: None,
+            skip_if_default: false,
+            flatten: false
+        };
+        for attribute in &field.attrs {
+            //Combine segments to get the attribute
+            match combine(&attribute.path.segments).as_str() {
+                "ignore_field" => output.ignore = true,
+                "require_field" => output.required = true,
+                _ => {}
+            }
+        }
+        for option in json_options(&field.attrs) {
+            match option.name.to_string().as_str() {
+                "rename" => output.key = string_option(&option),
+                "alias" => output.aliases.push(string_option(&option)),
+                "default" if option.value.is_empty() => output.default = Some(String::from("Default::default()")),
+                "default" => output.default = Some(option.value.to_string()),
+                "skip_serializing_if_default" => output.skip_if_default = true,
+                "flatten" => output.flatten = true,
+                other => panic!("Unknown json option {} on a field", other)
+            }
+        }
+        return output;
+    }
+
+    //Every key the field can be loaded from
+    pub fn keys(&self) -> Vec<String> {
+        let mut output = vec!(self.key.clone());
+        output.extend(self.aliases.iter().cloned());
+        return output;
+    }
+
+    //Expression for the field's default value, fallback if it doesn't have #[json(default)]
+    pub fn default_value(&self, field: &Field, fallback: &str) -> String {
+        return format!("{{ let default: {} = {}; default }}", field.ty.to_token_stream(),
+                       self.default.as_deref().unwrap_or(fallback));
+    }
+}
@@ -1,73 +1,466 @@
 use proc_macro::TokenStream;
-use syn::{DeriveInput, Fields, parse_macro_input, PathSegment};
-use syn::punctuated::Punctuated;
-use syn::token::Colon2;
+use quote::ToTokens;
+use syn::{Data, DataEnum, DeriveInput, Field, Fields, FieldsNamed, FieldsUnnamed, parse_macro_input};
+use crate::common::{FieldOptions, impl_header, json_tag, quoted, TypeOptions};
 
 pub fn json_loading(item: TokenStream) -> TokenStream {
     //Get abstract syntax tree
     let ast = parse_macro_input!(item as DeriveInput);
-    //Function header
-    let mut output = String::from(
-        format!("impl interfaces::loading::JsonLoadable for {} {{fn load(value: &json::JsonValue) -> Result<Self, anyhow::Error> {{let mut output = Self::default();",
-                ast.ident));
-
-    //Get all fields from the struct
-    let fields =
-        if let syn::Data::Struct(
-            syn::DataStruct {
-                fields: Fields::Named(ref fields),
-                ..
-            }) = ast.data
-        {
-            fields
+    let options = TypeOptions::new(&ast.attrs);
+
+    //Body of load, the schema expression, and bounds needed on the type
+    let (load, schema, bounds) = match &ast.data {
+        Data::Struct(data) => match &data.fields {
+            //Structs start from Self::default(), so missing fields keep their default values
+            Fields::Named(fields) =>
+                (load_struct(fields, &options), named_schema(&fields.named.iter().collect::<Vec<_>>(), &options, None), vec!("Self: Default")),
+            Fields::Unnamed(fields) =>
+                (format!("return Ok({});", load_tuple("Self", fields, &no_wrap)), tuple_schema(fields), vec!()),
+            Fields::Unit => (String::from("return Ok(Self);"), String::from("json::JsonValue::new_object()"), vec!())
+        },
+        Data::Enum(data) => match json_tag(&ast.attrs) {
+            Some(tag) => (load_internal(data, &options, &tag), internal_schema(data, &options, &tag), vec!()),
+            None => (load_external(data, &options), external_schema(data, &options), vec!())
+        },
+        Data::Union(_) => panic!("Derive macro doesn't support unions!")
+    };
+
+    //Structs say which keys they read, for structs flattening them
+    let keys = match &ast.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => format!("fn object_keys() -> Option<Vec<&'static str>> {{ {} }}", object_keys(fields)),
+            _ => String::new()
+        },
+        _ => String::new()
+    };
+
+    let deserialize = match &ast.data {
+        Data::Struct(data) => deserialize_struct(&ast.ident.to_string(), &data.fields),
+        Data::Enum(data) => deserialize_enum(&ast.ident.to_string(), data),
+        Data::Union(_) => unreachable!()
+    };
+
+    let output = format!("{} {{fn load(value: &json::JsonValue) -> Result<Self, anyhow::Error> {{{}}}\
+        fn schema() -> json::JsonValue {{ return {}; }}\
+        fn deserialize<__D: interfaces::format::Deserializer>(deserializer: &mut __D) -> Result<Self, anyhow::Error> {{{}}} {}}}",
+                         impl_header(&ast, "interfaces::loading::JsonLoadable", &bounds), load, schema, deserialize, keys);
+    output.parse().unwrap()
+}
+
+//Wraps an error expression with where it happened, for errors inside enum variants
+type Wrap<'a> = &'a dyn Fn(String) -> String;
+
+fn no_wrap(error: String) -> String {
+    return error;
+}
+
+fn expect_object(wrap: Wrap) -> String {
+    return format!("if !value.is_object() {{ return Err({}); }}",
+                   wrap(String::from("anyhow::Error::msg(format!(\"Expected object, found {}\", value))")));
+}
+
+//Expression loading the field from the first of its keys in value, or missing if there isn't one
+fn load_field(options: &FieldOptions, wrap: Wrap, missing: &str) -> String {
+    return format!("match [{}].into_iter().find(|key| value.has_key(key)) {{\
+        Some(key) => interfaces::loading::JsonLoadable::load(&value[key]).map_err(|error| {})?,\
+        None => {} }}", quoted(&options.keys()), wrap(String::from("interfaces::json_path::at_key(error, key)")), missing);
+}
+
+//Expression loading the index'th flattened field from its share of the keys no other field uses
+fn load_flattened(index: usize, wrap: Wrap) -> String {
+    return format!("interfaces::loading::JsonLoadable::load(&rest[{}]).map_err(|error| {})?", index, wrap(String::from("error")));
+}
+
+fn missing_field(options: &FieldOptions, wrap: Wrap) -> String {
+    return format!("return Err({})", wrap(format!("anyhow::Error::msg(\"Missing required field {}\")", options.key)));
+}
+
+//Checks that value is an object and has no unknown keys, then splits the keys no field uses in rest between flattened fields
+fn check_object(fields: &[&Field], type_options: &TypeOptions, tag: Option<&str>, wrap: Wrap) -> String {
+    let mut output = expect_object(wrap);
+    let mut known: Vec<String> = tag.iter().map(|tag| tag.to_string()).collect();
+    let flattened = flattened_keys(fields, &mut known);
+
+    //Flattened fields get the unknown keys. What none of them read is rejected here, or by the fields themselves.
+    if !flattened.is_empty() {
+        output += format!("let rest = {{ let mut rest = value.clone(); for key in [{}] {{ rest.remove(key); }} rest }};\
+            let flattened = [{}];", quoted(&known), flattened.join(", ")).as_str();
+        if type_options.deny_unknown_keys {
+            output += format!("interfaces::loading::deny_flattened(&rest, &[{}], &flattened).map_err(|error| {})?;",
+                              quoted(&known), wrap(String::from("error"))).as_str();
+        }
+        output += "let rest = interfaces::loading::split_flattened(&rest, &flattened);";
+    } else if type_options.deny_unknown_keys {
+        output += format!("for (key, _) in value.entries() {{ if ![{0}].contains(&key) {{ return Err({1}); }} }}", quoted(&known),
+                          wrap(format!("interfaces::json_path::at_key(anyhow::Error::msg(\
+                              format!(\"Unknown field {{}}, expected one of {}\", key)), key)", known.join(", ")))).as_str();
+    }
+    return output;
+}
+
+//Adds the keys of the fields that aren't flattened to known, and returns an expression for each flattened field's keys
+fn flattened_keys(fields: &[&Field], known: &mut Vec<String>) -> Vec<String> {
+    let mut flattened = Vec::new();
+    for field in fields {
+        let options = FieldOptions::new(field);
+        if options.flatten {
+            flattened.push(format!("<{} as interfaces::loading::JsonLoadable>::object_keys()", field.ty.to_token_stream()));
+        } else if !options.ignore {
+            known.extend(options.keys());
+        }
+    }
+    return flattened;
+}
+
+//The keys a struct reads, its own and its flattened fields'
+fn object_keys(fields: &FieldsNamed) -> String {
+    let mut known = Vec::new();
+    let flattened = flattened_keys(&fields.named.iter().collect::<Vec<_>>(), &mut known);
+    let mut output = format!("let mut keys = vec!({});", quoted(&known));
+    for keys in flattened {
+        output += format!("keys.extend({}?);", keys).as_str();
+    }
+    output += "return Some(keys);";
+    return output;
+}
+
+fn load_struct(fields: &FieldsNamed, type_options: &TypeOptions) -> String {
+    let fields: Vec<&Field> = fields.named.iter().collect();
+    let mut output = check_object(&fields, type_options, None, &no_wrap);
+    output += "let mut output = Self::default();";
+
+    let mut flattened = 0;
+    for field in fields {
+        let options = FieldOptions::new(field);
+        if options.ignore {
+            continue;
+        }
+        let field_name = field.ident.as_ref().unwrap().to_string();
+
+        //Missing fields keep their value from Self::default(), unless they're required or have their own default
+        if options.flatten {
+            output += format!("output.{} = {};", field_name, load_flattened(flattened, &no_wrap)).as_str();
+            flattened += 1;
+        } else if options.required {
+            output += format!("output.{} = {};", field_name, load_field(&options, &no_wrap, &missing_field(&options, &no_wrap))).as_str();
+        } else if options.default.is_some() {
+            output += format!("output.{} = {};", field_name, load_field(&options, &no_wrap, &options.default_value(field, ""))).as_str();
         } else {
-            panic!("Derive macro only supports Structs!")
+            output += format!("if let Some(key) = [{}].into_iter().find(|key| value.has_key(key)) {{\
+                output.{} = interfaces::loading::JsonLoadable::load(&value[key])\
+                .map_err(|error| interfaces::json_path::at_key(error, key))?; }}", quoted(&options.keys()), field_name).as_str();
+        }
+    }
+
+    output += "return Ok(output);";
+    return output;
+}
+
+//Expression building constructor { ... } from the object in value. Fields that aren't loaded are Default::default()
+fn load_named(constructor: &str, fields: &FieldsNamed, type_options: &TypeOptions, tag: Option<&str>, wrap: Wrap) -> String {
+    let fields: Vec<&Field> = fields.named.iter().collect();
+    let mut output = format!("{{ {} {} {{", check_object(&fields, type_options, tag, wrap), constructor);
+    let mut flattened = 0;
+    for field in fields {
+        let options = FieldOptions::new(field);
+        let field_name = field.ident.as_ref().unwrap().to_string();
+        let value = if options.ignore {
+            options.default_value(field, "Default::default()")
+        } else if options.flatten {
+            flattened += 1;
+            load_flattened(flattened - 1, wrap)
+        } else if options.required {
+            load_field(&options, wrap, &missing_field(&options, wrap))
+        } else {
+            load_field(&options, wrap, &options.default_value(field, "Default::default()"))
         };
+        output += format!("{}: {},", field_name, value).as_str();
+    }
+    output += "}}";
+    return output;
+}
 
-    for field in fields.named.iter() {
-        //Get attributes on field
-        let mut ignore = false;
-        let mut required = false;
-
-        for attribute in &field.attrs {
-            //Combine segments to get the attribute
-            match combine(&attribute.path.segments).as_str() {
-                "ignore_field" => ignore = true,
-                "require_field" => required = true,
-                _ => {}
-            }
+//Expression building constructor(...) from value. One field is loaded from value itself, more from an array
+fn load_tuple(constructor: &str, fields: &FieldsUnnamed, wrap: Wrap) -> String {
+    let length = fields.unnamed.len();
+    if length == 1 {
+        let error = wrap(String::from("error"));
+        if error == "error" {
+            return format!("{}(interfaces::loading::JsonLoadable::load(value)?)", constructor);
         }
+        return format!("{}(interfaces::loading::JsonLoadable::load(value).map_err(|error| {})?)", constructor, error);
+    }
 
-        if !ignore {
-            let field_name = field.ident.as_ref().unwrap().to_string();
+    let mut output = format!("{{ let values = match value {{ json::JsonValue::Array(values) if values.len() == {0} => values, \
+        _ => return Err({1}) }}; {2}(", length,
+                             wrap(format!("anyhow::Error::msg(format!(\"Expected array of {} values, found {{}}\", value))", length)), constructor);
+    for i in 0..length {
+        output += format!("interfaces::loading::JsonLoadable::load(&values[{0}]).map_err(|error| {1})?,",
+                          i, wrap(format!("interfaces::json_path::at_index(error, {})", i))).as_str();
+    }
+    output += ") }";
+    return output;
+}
 
-            //If it's not required, check if it exists
-            if !required {
-                output += format!("if value.has_key(\"{}\") {{", field_name).as_str();
-            } else {
-                output += format!("if !value.has_key(\"{}\") {{ return Err(anyhow::Error::msg(format!(\"Missing required field {}: {{:?}}\", value))) }}", field_name, field_name).as_str();
-            }
+//Names of the variants, for errors
+fn variant_names(data: &DataEnum) -> String {
+    return data.variants.iter().map(|variant| variant.ident.to_string()).collect::<Vec<_>>().join(", ");
+}
 
-            //Load field from json
-            output += format!("output.{} = interfaces::loading::JsonLoadable::load(&value[\"{}\"])?;", field_name.clone(),
-                              field_name.clone()).as_str();
+//Unit variants are strings, the others are objects with the variant's name as their only key: {"Variant": ...}
+fn load_external(data: &DataEnum, options: &TypeOptions) -> String {
+    let names = variant_names(data);
+    let mut strings = String::new();
+    let mut objects = String::new();
 
-            //Close if
-            if !required {
-                output += "}"
+    for variant in &data.variants {
+        let name = variant.ident.to_string();
+        let wrap = |error: String| format!("interfaces::json_path::at_key({}, \"{}\")", error, name);
+        match &variant.fields {
+            Fields::Unit => {
+                strings += format!("\"{0}\" => Ok(Self::{0}),", name).as_str();
+                objects += format!("\"{0}\" => Ok(Self::{0}),", name).as_str();
+            }
+            Fields::Named(fields) => {
+                strings += format!("\"{0}\" => Err(anyhow::Error::msg(\"Variant {0} needs a value\")),", name).as_str();
+                objects += format!("\"{}\" => Ok({}),", name, load_named(&format!("Self::{}", name), fields, options, None, &wrap)).as_str();
+            }
+            Fields::Unnamed(fields) => {
+                strings += format!("\"{0}\" => Err(anyhow::Error::msg(\"Variant {0} needs a value\")),", name).as_str();
+                objects += format!("\"{}\" => Ok({}),", name, load_tuple(&format!("Self::{}", name), fields, &wrap)).as_str();
             }
         }
     }
 
-    output += "return Ok(output);}}";
-    output.parse().unwrap()
+    return format!("match value {{\
+        json::JsonValue::String(_) | json::JsonValue::Short(_) => return match value.as_str().unwrap() {{ {0} \
+            other => Err(anyhow::Error::msg(format!(\"Unknown variant {{}}, expected one of {2}\", other))) }},\
+        json::JsonValue::Object(object) if object.len() == 1 => {{\
+            let (variant, value) = object.iter().next().unwrap();\
+            return match variant {{ {1} \
+                other => Err(interfaces::json_path::at_key(\
+                    anyhow::Error::msg(format!(\"Unknown variant {{}}, expected one of {2}\", other)), other)) }};\
+        }}\
+        _ => return Err(anyhow::Error::msg(format!(\"Expected variant name or object with one key, found {{}}\", value)))\
+        }}", strings, objects, names);
 }
 
-fn combine(segments: &Punctuated<PathSegment, Colon2>) -> String {
-    let mut output = String::new();
-    for segment in segments {
-        output += (segment.ident.to_string() + "::").as_str();
+//Objects with the variant's name under the tag key: {"kind": "Variant", ...}
+fn load_internal(data: &DataEnum, options: &TypeOptions, tag: &str) -> String {
+    let names = variant_names(data);
+    let mut variants = String::new();
+
+    for variant in &data.variants {
+        let name = variant.ident.to_string();
+        match &variant.fields {
+            Fields::Unit => variants += format!("\"{0}\" => Ok(Self::{0}),", name).as_str(),
+            Fields::Named(fields) =>
+                variants += format!("\"{}\" => Ok({}),", name, load_named(&format!("Self::{}", name), fields, options, Some(tag), &no_wrap)).as_str(),
+            //The tag is removed so the inner value only sees its own keys
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 =>
+                variants += format!("\"{0}\" => {{ let mut value = value.clone(); value.remove(\"{1}\");\
+                    Ok(Self::{0}(interfaces::loading::JsonLoadable::load(&value)?)) }}", name, tag).as_str(),
+            Fields::Unnamed(_) => panic!("Internally tagged enums can't have tuple variants with more than one field!")
+        }
+    }
+
+    return format!("{0}\
+        let variant = match &value[\"{1}\"] {{\
+            json::JsonValue::Null => return Err(anyhow::Error::msg(\"Missing required field {1}\")),\
+            tag => match tag.as_str() {{\
+                Some(variant) => variant,\
+                None => return Err(interfaces::json_path::at_key(\
+                    anyhow::Error::msg(format!(\"Expected string, found {{}}\", tag)), \"{1}\"))\
+            }}\
+        }};\
+        return match variant {{ {2} \
+            other => Err(interfaces::json_path::at_key(\
+                anyhow::Error::msg(format!(\"Unknown variant {{}}, expected one of {3}\", other)), \"{1}\")) }};",
+                   expect_object(&no_wrap), tag, variants, names);
+}
+
+fn schema_of(field: &Field) -> String {
+    return format!("<{} as interfaces::loading::JsonLoadable>::schema()", field.ty.to_token_stream());
+}
+
+//Schema of an object with the fields, plus a tag key holding the variant name for internally tagged enums
+fn named_schema(fields: &[&Field], type_options: &TypeOptions, tag: Option<(&str, &str)>) -> String {
+    let mut properties = String::new();
+    let mut required_fields = String::new();
+
+    if let Some((tag, variant)) = tag {
+        properties += format!("properties.insert(\"{}\", {});", tag, const_schema(variant)).as_str();
+        required_fields += format!("\"{}\".into(),", tag).as_str();
+    }
+    for field in fields {
+        let options = FieldOptions::new(field);
+        if options.ignore {
+            continue;
+        }
+        //Flattened fields add their own properties
+        if options.flatten {
+            properties += format!("{{ let inner = {};\
+                for (key, value) in inner[\"properties\"].entries() {{ properties.insert(key, value.clone()); }}\
+                required.extend(inner[\"required\"].members().cloned()); }}", schema_of(field)).as_str();
+            continue;
+        }
+        for key in options.keys() {
+            properties += format!("properties.insert(\"{}\", {});", key, schema_of(field)).as_str();
+        }
+        if options.required {
+            required_fields += format!("\"{}\".into(),", options.key).as_str();
+        }
+    }
+
+    let additional = if type_options.deny_unknown_keys {
+        "output.insert(\"additionalProperties\", false.into());"
+    } else {
+        ""
+    };
+    return format!("{{\
+        #[allow(unused_mut)] let mut required: Vec<json::JsonValue> = vec!({});\
+        #[allow(unused_mut)] let mut properties = json::object::Object::new();{}\
+        let mut output = json::object::Object::new();\
+        output.insert(\"type\", \"object\".into());\
+        output.insert(\"properties\", json::JsonValue::Object(properties));\
+        if !required.is_empty() {{ output.insert(\"required\", json::JsonValue::Array(required)); }}{}\
+        json::JsonValue::Object(output)}}", required_fields, properties, additional);
+}
+
+fn tuple_schema(fields: &FieldsUnnamed) -> String {
+    if fields.unnamed.len() == 1 {
+        return schema_of(&fields.unnamed[0]);
+    }
+
+    let items = fields.unnamed.iter().map(schema_of).collect::<Vec<_>>().join(",");
+    return format!("{{\
+        let mut output = json::object::Object::new();\
+        output.insert(\"type\", \"array\".into());\
+        output.insert(\"items\", json::JsonValue::Array(vec!({0})));\
+        output.insert(\"minItems\", {1}.into());\
+        output.insert(\"maxItems\", {1}.into());\
+        json::JsonValue::Object(output)}}", items, fields.unnamed.len());
+}
+
+fn const_schema(value: &str) -> String {
+    return format!("{{ let mut output = json::object::Object::new(); output.insert(\"const\", \"{}\".into());\
+        json::JsonValue::Object(output) }}", value);
+}
+
+fn one_of(variants: Vec<String>) -> String {
+    return format!("{{ let mut output = json::object::Object::new();\
+        output.insert(\"oneOf\", json::JsonValue::Array(vec!({})));\
+        json::JsonValue::Object(output) }}", variants.join(","));
+}
+
+fn external_schema(data: &DataEnum, options: &TypeOptions) -> String {
+    let mut variants = Vec::new();
+    for variant in &data.variants {
+        let name = variant.ident.to_string();
+        let inner = match &variant.fields {
+            Fields::Unit => {
+                variants.push(const_schema(&name));
+                continue;
+            }
+            Fields::Named(fields) => named_schema(&fields.named.iter().collect::<Vec<_>>(), options, None),
+            Fields::Unnamed(fields) => tuple_schema(fields)
+        };
+        variants.push(format!("{{\
+            let mut properties = json::object::Object::new();\
+            properties.insert(\"{0}\", {1});\
+            let mut output = json::object::Object::new();\
+            output.insert(\"type\", \"object\".into());\
+            output.insert(\"properties\", json::JsonValue::Object(properties));\
+            output.insert(\"required\", json::array![\"{0}\"]);\
+            output.insert(\"additionalProperties\", false.into());\
+            json::JsonValue::Object(output)}}", name, inner));
     }
+    return one_of(variants);
+}
+
+fn internal_schema(data: &DataEnum, options: &TypeOptions, tag: &str) -> String {
+    let mut variants = Vec::new();
+    for variant in &data.variants {
+        let name = variant.ident.to_string();
+        let fields = variant.fields.iter().collect::<Vec<_>>();
+        variants.push(match &variant.fields {
+            Fields::Unnamed(fields) => format!("{{\
+                let mut output = json::object::Object::new();\
+                output.insert(\"allOf\", json::JsonValue::Array(vec!({}, {})));\
+                json::JsonValue::Object(output)}}", schema_of(&fields.unnamed[0]), named_schema(&[], options, Some((tag, &name)))),
+            _ => named_schema(&fields, options, Some((tag, &name)))
+        });
+    }
+    return one_of(variants);
+}
 
-    return String::from(&output[0..output.len() - 2]);
-}
\ No newline at end of file
+//Reads the field from a Deserializer, with its key or index in errors
+fn deserialize_field(location: &str) -> String {
+    return format!("interfaces::loading::JsonLoadable::deserialize(deserializer).map_err(|error| {})?", location);
+}
+
+//Expression building constructor { ... } from the fields a Serializer wrote in order. Ignored fields weren't written.
+fn deserialize_named(name: &str, constructor: &str, fields: &FieldsNamed) -> String {
+    let written = fields.named.iter().filter(|field| !FieldOptions::new(field).ignore).count();
+    let mut output = format!("{{ deserializer.structure(\"{}\", {})?; {} {{", name, written, constructor);
+    for field in &fields.named {
+        let options = FieldOptions::new(field);
+        let field_name = field.ident.as_ref().unwrap().to_string();
+        let value = if options.ignore {
+            options.default_value(field, "Default::default()")
+        } else {
+            deserialize_field(&format!("interfaces::json_path::at_key(error, \"{}\")", options.key))
+        };
+        output += format!("{}: {},", field_name, value).as_str();
+    }
+    output += "}}";
+    return output;
+}
+
+//A single field is read as itself, more as a structure
+fn deserialize_tuple(name: &str, constructor: &str, fields: &FieldsUnnamed) -> String {
+    if fields.unnamed.len() == 1 {
+        return format!("{}(interfaces::loading::JsonLoadable::deserialize(deserializer)?)", constructor);
+    }
+    let mut output = format!("{{ deserializer.structure(\"{}\", {})?; {}(", name, fields.unnamed.len(), constructor);
+    for i in 0..fields.unnamed.len() {
+        output += format!("{},", deserialize_field(&format!("interfaces::json_path::at_index(error, {})", i))).as_str();
+    }
+    output += ") }";
+    return output;
+}
+
+fn deserialize_struct(name: &str, fields: &Fields) -> String {
+    return match fields {
+        //Like load, ignored fields keep their value from Self::default()
+        Fields::Named(fields) => {
+            let written: Vec<&Field> = fields.named.iter().filter(|field| !FieldOptions::new(field).ignore).collect();
+            let mut output = format!("deserializer.structure(\"{}\", {})?; let mut output = Self::default();", name, written.len());
+            for field in written {
+                let options = FieldOptions::new(field);
+                output += format!("output.{} = {};", field.ident.as_ref().unwrap(),
+                                  deserialize_field(&format!("interfaces::json_path::at_key(error, \"{}\")", options.key))).as_str();
+            }
+            output + "return Ok(output);"
+        }
+        Fields::Unnamed(fields) => format!("return Ok({});", deserialize_tuple(name, "Self", fields)),
+        Fields::Unit => format!("deserializer.structure(\"{}\", 0)?; return Ok(Self);", name)
+    };
+}
+
+//The variant's index, then its fields
+fn deserialize_enum(name: &str, data: &DataEnum) -> String {
+    let names = data.variants.iter().map(|variant| format!("\"{}\"", variant.ident)).collect::<Vec<_>>().join(", ");
+    let mut variants = String::new();
+    for (index, variant) in data.variants.iter().enumerate() {
+        let variant_name = variant.ident.to_string();
+        let constructor = format!("Self::{}", variant_name);
+        let value = match &variant.fields {
+            Fields::Unit => constructor,
+            Fields::Named(fields) => deserialize_named(&variant_name, &constructor, fields),
+            Fields::Unnamed(fields) => deserialize_tuple(&variant_name, &constructor, fields)
+        };
+        variants += format!("{} => Ok({}),", index, value).as_str();
+    }
+    return format!("return match deserializer.variant(\"{}\", &[{}])? {{ {} _ => unreachable!() }};", name, names, variants);
+}
@@ -1,32 +1,199 @@
 use proc_macro::TokenStream;
-use syn::{DeriveInput, Fields, parse_macro_input};
+use quote::ToTokens;
+use syn::{Data, DeriveInput, Field, Fields, parse_macro_input};
+use crate::common::{FieldOptions, impl_header, impl_header_bounded, json_tag};
 
 pub fn json_saving(item: TokenStream) -> TokenStream {
     //Get abstract syntax tree
     let ast = parse_macro_input!(item as DeriveInput);
-    //Function header
-    let mut output = String::from(
-        format!("impl interfaces::saving::JsonSaveable for {} {{fn save(&self) -> json::JsonValue {{let mut output = json::JsonValue::Object(json::object::Object::new());",
-                ast.ident));
-
-    //Get all fields from the struct
-    let fields =
-        if let syn::Data::Struct(
-            syn::DataStruct {
-                fields: Fields::Named(ref fields),
-                ..
-            }) = ast.data
-        {
-            fields
+
+    //Skipped struct fields are compared to their value in Self::default(), like when loading
+    let mut bounds = Vec::new();
+    let save = match &ast.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => {
+                let fields: Vec<(&Field, String)> = fields.named.iter()
+                    .map(|field| (field, format!("&self.{}", field.ident.as_ref().unwrap()))).collect();
+                if fields.iter().any(|(field, _)| {
+                    let options = FieldOptions::new(field);
+                    options.skip_if_default && options.default.is_none()
+                }) {
+                    bounds.push(String::from("Self: Default"));
+                }
+                save_named(&fields, "<Self as Default>::default().")
+            }
+            Fields::Unnamed(fields) => save_tuple((0..fields.unnamed.len()).map(|i| format!("&self.{}", i)).collect()),
+            Fields::Unit => String::from("json::JsonValue::Null")
+        },
+        Data::Enum(data) => {
+            let tag = json_tag(&ast.attrs);
+            let mut variants = String::new();
+            for variant in &data.variants {
+                let name = variant.ident.to_string();
+                //Pattern binding the variant's fields, and the JSON of its contents
+                let (pattern, inner) = match &variant.fields {
+                    Fields::Unit => (String::new(), None),
+                    Fields::Named(fields) => {
+                        //Only the saved fields are bound
+                        let fields: Vec<(&Field, String)> = fields.named.iter()
+                            .filter(|field| !FieldOptions::new(field).ignore)
+                            .map(|field| (field, field.ident.as_ref().unwrap().to_string())).collect();
+                        let names: Vec<&str> = fields.iter().map(|(_, name)| name.as_str()).collect();
+                        (format!("{{ {}, .. }}", names.join(", ")), Some(save_named(&fields, "")))
+                    }
+                    Fields::Unnamed(fields) => {
+                        //The tag is added to the field's object, so it has to save as one
+                        if tag.is_some() {
+                            if fields.unnamed.len() != 1 {
+                                panic!("Internally tagged enums can't have tuple variants with more than one field!");
+                            }
+                            bounds.push(format!("{}: interfaces::saving::SavesAsObject", fields.unnamed[0].ty.to_token_stream()));
+                        }
+                        let names: Vec<String> = (0..fields.unnamed.len()).map(|i| format!("field{}", i)).collect();
+                        (format!("({})", names.join(", ")), Some(save_tuple(names)))
+                    }
+                };
+                let json = match (&tag, inner) {
+                    (None, None) => format!("\"{}\".into()", name),
+                    (None, Some(inner)) => format!("{{ let mut output = json::JsonValue::new_object();\
+                        output.insert(\"{}\", {}).unwrap(); output }}", name, inner),
+                    (Some(tag), None) => format!("{{ let mut output = json::JsonValue::new_object();\
+                        output.insert(\"{}\", \"{}\").unwrap(); output }}", tag, name),
+                    //Named and single field variants both add the tag to the object they save as
+                    (Some(tag), Some(inner)) => format!("{{ let mut output = {};\
+                        output.insert(\"{}\", \"{}\").unwrap(); output }}", inner, tag, name)
+                };
+                variants += format!("Self::{}{} => {},", name, pattern, json).as_str();
+            }
+            if data.variants.is_empty() {
+                String::from("match *self {}")
+            } else {
+                format!("match self {{ {} }}", variants)
+            }
+        }
+        Data::Union(_) => panic!("Derive macro doesn't support unions!")
+    };
+
+    let serialize = match &ast.data {
+        Data::Struct(data) => serialize_struct(&ast.ident.to_string(), &data.fields),
+        Data::Enum(data) => {
+            let mut variants = String::new();
+            for (index, variant) in data.variants.iter().enumerate() {
+                let name = variant.ident.to_string();
+                let (pattern, fields) = match &variant.fields {
+                    Fields::Unit => (String::new(), String::new()),
+                    Fields::Named(fields) => {
+                        let fields: Vec<(&Field, String)> = fields.named.iter()
+                            .filter(|field| !FieldOptions::new(field).ignore)
+                            .map(|field| (field, field.ident.as_ref().unwrap().to_string())).collect();
+                        let names: Vec<&str> = fields.iter().map(|(_, name)| name.as_str()).collect();
+                        (format!("{{ {}, .. }}", names.join(", ")), serialize_named(&name, &fields))
+                    }
+                    Fields::Unnamed(fields) => {
+                        let names: Vec<String> = (0..fields.unnamed.len()).map(|i| format!("field{}", i)).collect();
+                        (format!("({})", names.join(", ")), serialize_tuple(&name, &names))
+                    }
+                };
+                variants += format!("Self::{}{} => {{ serializer.variant(\"{}\", {}); {} }}", name, pattern, name, index, fields).as_str();
+            }
+            if data.variants.is_empty() {
+                String::from("let _ = serializer; match *self {}")
+            } else {
+                format!("match self {{ {} }}", variants)
+            }
+        }
+        Data::Union(_) => unreachable!()
+    };
+
+    //Structs with named fields and internally tagged enums always save as objects
+    let object = match &ast.data {
+        Data::Struct(data) => matches!(data.fields, Fields::Named(_)),
+        Data::Enum(_) => json_tag(&ast.attrs).is_some(),
+        Data::Union(_) => false
+    };
+    let bounds: Vec<&str> = bounds.iter().map(String::as_str).collect();
+    let mut output = format!("{} {{fn save(&self) -> json::JsonValue {{ return {}; }}\
+        fn serialize<__S: interfaces::format::Serializer>(&self, serializer: &mut __S) {{ {} }}}}",
+                         impl_header(&ast, "interfaces::saving::JsonSaveable", &bounds), save, serialize);
+    if object {
+        output += format!("{} {{}}", impl_header_bounded(&ast, "interfaces::saving::SavesAsObject",
+                                                        "interfaces::saving::JsonSaveable", &bounds)).as_str();
+    }
+    output.parse().unwrap()
+}
+
+//Object of the fields, with references to their values.
+//Fields without their own default are compared to default_prefix + name, or Default::default() if it's empty
+fn save_named(fields: &[(&Field, String)], default_prefix: &str) -> String {
+    let mut output = String::from("{ let mut output = json::JsonValue::Object(json::object::Object::new());");
+    for (field, value) in fields {
+        let options = FieldOptions::new(field);
+        if options.ignore {
+            continue;
+        }
+
+        let save = if options.flatten {
+            format!("if let json::JsonValue::Object(inner) = interfaces::saving::JsonSaveable::save({}) {{\
+                for (key, value) in inner.iter() {{ output.insert(key, value.clone()).unwrap(); }} }}", value)
         } else {
-            panic!("Derive macro only supports Structs!")
+            format!("output.insert(\"{}\", interfaces::saving::JsonSaveable::save({})).unwrap();", options.key, value)
         };
+        if options.skip_if_default {
+            let fallback = if default_prefix.is_empty() {
+                String::from("Default::default()")
+            } else {
+                format!("{}{}", default_prefix, field.ident.as_ref().unwrap())
+            };
+            output += format!("if *{} != {} {{ {} }}", value, options.default_value(field, &fallback), save).as_str();
+        } else {
+            output += save.as_str();
+        }
+    }
+    output += "output }";
+    return output;
+}
 
-    for field in fields.named.iter() {
-        let field_name = field.ident.as_ref().unwrap().to_string();
-        output += format!("output.insert(\"{}\", interfaces::saving::JsonSaveable::save(&self.{})).unwrap();", field_name, field_name).as_str();
+//A single value is saved as itself, more as an array
+fn save_tuple(values: Vec<String>) -> String {
+    if values.len() == 1 {
+        return format!("interfaces::saving::JsonSaveable::save({})", values[0]);
     }
+    let values: Vec<String> = values.iter().map(|value| format!("interfaces::saving::JsonSaveable::save({})", value)).collect();
+    return format!("json::JsonValue::Array(vec!({}))", values.join(", "));
+}
 
-    output += "return output;}}";
-    output.parse().unwrap()
-}
\ No newline at end of file
+//Writes every field that isn't ignored in order, named so formats can write keys.
+//Unlike save, fields equal to their default and flattened fields are written like the others, so readers know what's next.
+fn serialize_named(name: &str, fields: &[(&Field, String)]) -> String {
+    let fields: Vec<&(&Field, String)> = fields.iter().filter(|(field, _)| !FieldOptions::new(field).ignore).collect();
+    let mut output = format!("serializer.structure(\"{}\", {});", name, fields.len());
+    for (field, value) in fields {
+        output += format!("serializer.field(\"{}\"); interfaces::saving::JsonSaveable::serialize({}, serializer);",
+                          FieldOptions::new(field).key, value).as_str();
+    }
+    return output;
+}
+
+//A single value is written as itself, more as a structure
+fn serialize_tuple(name: &str, values: &[String]) -> String {
+    if values.len() == 1 {
+        return format!("interfaces::saving::JsonSaveable::serialize({}, serializer);", values[0]);
+    }
+    let mut output = format!("serializer.structure(\"{}\", {});", name, values.len());
+    for value in values {
+        output += format!("interfaces::saving::JsonSaveable::serialize({}, serializer);", value).as_str();
+    }
+    return output;
+}
+
+fn serialize_struct(name: &str, fields: &Fields) -> String {
+    return match fields {
+        Fields::Named(fields) => {
+            let fields: Vec<(&Field, String)> = fields.named.iter()
+                .map(|field| (field, format!("&self.{}", field.ident.as_ref().unwrap()))).collect();
+            serialize_named(name, &fields)
+        }
+        Fields::Unnamed(fields) => serialize_tuple(name, &(0..fields.unnamed.len()).map(|i| format!("&self.{}", i)).collect::<Vec<_>>()),
+        Fields::Unit => format!("serializer.structure(\"{}\", 0);", name)
+    };
+}
@@ -2,15 +2,30 @@ extern crate proc_macro;
 
 use proc_macro::TokenStream;
 
+mod common;
 mod json_loading;
 mod json_saving;
+mod named_type;
 
-#[proc_macro_derive(JsonLoadable, attributes(ignore_field, require_field))]
+//Structs load from objects, tuple structs from arrays (or their only field's value), and enums from
+//"Variant" or {"Variant": ...}, or {"tag": "Variant", ...} with #[json_tag = "tag"].
+//Fields can have #[json(rename = "key", alias = "other", default = expression, skip_serializing_if_default, flatten)],
+//and #[json(deny_unknown_keys)] on the type makes unknown keys an error.
+//Both also implement deserialize and serialize, which read and write the fields in order for formats like Binary.
+#[proc_macro_derive(JsonLoadable, attributes(ignore_field, require_field, json_tag, json))]
 pub fn json_load(item: TokenStream) -> TokenStream {
     return json_loading::json_loading(item);
 }
 
-#[proc_macro_derive(JsonSaveable)]
+#[proc_macro_derive(JsonSaveable, attributes(ignore_field, require_field, json_tag, json))]
 pub fn json_save(item: TokenStream) -> TokenStream {
     return json_saving::json_saving(item);
 }
+
+//Implements NamedType and LoadableType for a JsonLoadable struct with a name field.
+//Fields marked #[dependency] hold names of types that have to load first.
+//Fields marked #[texture], #[model], #[shader] or #[translation] hold asset names, checked by ctl-validate.
+#[proc_macro_derive(NamedType, attributes(dependency, texture, model, shader, translation))]
+pub fn named_type(item: TokenStream) -> TokenStream {
+    return named_type::named_type(item);
+}
@@ -0,0 +1,79 @@
+use proc_macro::TokenStream;
+use syn::{DeriveInput, Fields, parse_macro_input};
+use crate::common::{combine, FieldOptions, impl_header_bounded, quoted};
+
+pub fn named_type(item: TokenStream) -> TokenStream {
+    //Get abstract syntax tree
+    let ast = parse_macro_input!(item as DeriveInput);
+
+    //Get all fields from the struct
+    let fields =
+        if let syn::Data::Struct(
+            syn::DataStruct {
+                fields: Fields::Named(ref fields),
+                ..
+            }) = ast.data
+        {
+            fields
+        } else {
+            panic!("Derive macro only supports Structs!")
+        };
+
+    let mut named = false;
+    let mut dependencies = String::new();
+    let mut assets = String::new();
+    for field in fields.named.iter() {
+        let field_name = field.ident.as_ref().unwrap().to_string();
+        if field_name == "name" {
+            named = true;
+        }
+        //The first of the field's keys in the JSON, like JsonLoadable loads it from
+        let keys = quoted(&FieldOptions::new(field).keys());
+        let find = format!("[{}].into_iter().find(|key| object.get(key).is_some())", keys);
+
+        for attribute in &field.attrs {
+            let kind = match combine(&attribute.path.segments).as_str() {
+                "dependency" => {
+                    dependencies += format!("if let Some(key) = {} {{\
+                        output.extend(game::resources::resource_manager::dependency_names(&object[key]));}}", find).as_str();
+                    continue;
+                }
+                "texture" => "Texture",
+                "model" => "Model",
+                "shader" => "Shader",
+                "translation" => "Translation",
+                _ => continue
+            };
+            assets += format!("if let Some(key) = {1} {{output.extend(game::resources::resource_manager::asset_names(\
+                game::resources::resource_manager::AssetKind::{0}, &object[key], interfaces::json_path::JsonPath::new().key(key)));}}",
+                              kind, find).as_str();
+        }
+    }
+
+    if !named {
+        panic!("NamedType needs a name: String field, it's set from $name");
+    }
+
+    //NamedTypes are stored as Box<dyn Any + Send>, so type parameters have to be too
+    let named_header = impl_header_bounded(&ast, "game::resources::resource_manager::NamedType", "Send + 'static", &[]);
+    let loadable_header = impl_header_bounded(&ast, "game::resources::resource_manager::LoadableType", "Send + 'static",
+                                              &["Self: interfaces::loading::JsonLoadable"]);
+
+    //The name comes from $name, everything else is loaded with JsonLoadable without the $ keys
+    let output = format!("{0} {{\
+        fn name(&self) -> String {{return self.name.clone();}}}}\
+        {3} {{\
+        fn dependencies(object: &json::object::Object) -> Vec<String> {{let mut output = Vec::new();{1}return output;}}\
+        fn assets(object: &json::object::Object) -> Vec<game::resources::resource_manager::AssetUse> {{\
+        let mut output = Vec::new();{2}return output;}}\
+        fn schema() -> json::JsonValue {{return <Self as interfaces::loading::JsonLoadable>::schema();}}\
+        fn instantiate(_resources: &mut game::resources::resource_manager::ResourceManager, object: &json::object::Object) -> Result<Self, anyhow::Error> {{\
+        let mut fields = object.clone();\
+        for key in [\"$name\", \"$type\", \"$parent\"] {{ fields.remove(key); }}\
+        let mut output: Self = interfaces::loading::JsonLoadable::load(&json::JsonValue::Object(fields))?;\
+        output.name = match object[\"$name\"].as_str() {{\
+            Some(name) => name.to_string(),\
+            None => return Err(interfaces::json_path::at_key(anyhow::Error::msg(\"No name ($name)\"), \"$name\"))}};\
+        return Ok(output);}}}}", named_header, dependencies, assets, loadable_header);
+    output.parse().unwrap()
+}